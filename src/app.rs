@@ -0,0 +1,131 @@
+//! What a running session reacts to, as one stream of `AppEvent`s fed by
+//! two threads: a clock and the keyboard. The clock ticks on every whole
+//! second since the session started, however late it wakes up, so nothing
+//! else going on can skew the countdown; between ticks it sends frames, for
+//! anything that wants redrawing more often than once a second. Commands
+//! from the FIFO, the daemon or stdin are picked up on each frame.
+//!
+//! Dropping `Events` stops both threads, waiting for the keyboard one so it
+//! can't swallow a key meant for whatever prompt comes next. There's no
+//! keyboard thread when stdin isn't a terminal, since there are no keys.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEvent};
+
+use crate::commands::Command;
+use crate::ui;
+
+pub const FRAME: Duration = Duration::from_millis(100);
+const FRAMES_PER_TICK: u32 = 10;
+// How often the keyboard thread looks up to see whether it should stop
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
+// Set while there's an `Events`, so a shutdown signal can tell a session
+// that will pick up a command from a prompt that won't
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub fn session_running() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AppEvent {
+    // A second of the session has gone by
+    Tick,
+    Frame,
+    Key(KeyEvent),
+    Resize,
+    Command(Command),
+}
+
+pub struct Events {
+    receiver: Receiver<AppEvent>,
+    stop: Arc<AtomicBool>,
+    input: Option<JoinHandle<()>>,
+}
+
+impl Events {
+    pub fn start() -> Events {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let clock = sender.clone();
+        thread::spawn(move || run_clock(&clock, Instant::now()));
+        let input = ui::reads_keys().then(|| {
+            let stop = stop.clone();
+            thread::spawn(move || read_input(&sender, &stop))
+        });
+        RUNNING.store(true, Ordering::Relaxed);
+        Events { receiver, stop, input }
+    }
+
+    // Waits for the next event; commands go ahead of anything queued
+    pub fn next(&self, commands: &Receiver<Command>) -> AppEvent {
+        if let Ok(command) = commands.try_recv() {
+            return AppEvent::Command(command);
+        }
+        // Neither thread stops before this is dropped
+        self.receiver.recv().unwrap_or(AppEvent::Frame)
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Relaxed);
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(input) = self.input.take() {
+            let _ = input.join();
+        }
+    }
+}
+
+// Runs until `Events` is dropped and there's no one left to send to
+fn run_clock(sender: &Sender<AppEvent>, started: Instant) {
+    for frame in 1.. {
+        thread::sleep((started + FRAME * frame).saturating_duration_since(Instant::now()));
+        let event = if frame % FRAMES_PER_TICK == 0 { AppEvent::Tick } else { AppEvent::Frame };
+        if sender.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+fn read_input(sender: &Sender<AppEvent>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let event = match event::poll(INPUT_POLL) {
+            Ok(true) => event::read(),
+            Ok(false) => continue,
+            Err(error) => Err(error),
+        };
+        let event = match event {
+            Ok(Event::Key(key)) => AppEvent::Key(key),
+            Ok(Event::Resize(..)) => AppEvent::Resize,
+            Ok(_) => continue,
+            // Nothing more is coming from a terminal that's gone
+            Err(_) => return,
+        };
+        if sender.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_ticks_every_tenth_frame() {
+        let (sender, receiver) = mpsc::channel();
+        // Started long enough ago that every event is already due
+        let started = Instant::now() - FRAME * 20;
+        thread::spawn(move || run_clock(&sender, started));
+        let events: Vec<_> = receiver.iter().take(20).collect();
+        let ticks: Vec<_> = events.iter().enumerate().filter(|(_, event)| **event == AppEvent::Tick).map(|(i, _)| i).collect();
+        assert_eq!(ticks, [9, 19]);
+    }
+}
@@ -0,0 +1,256 @@
+//! `timeadair history archive --before 2023`: moves the sessions started
+//! before then out of `history.jsonl` into a file under `archive/` in the
+//! data directory, gzipped when `gzip` is there to do it, so the live
+//! history stays small after years of use.
+//!
+//! What `timeadair stats` needs from them is kept in `rollups.json` next to
+//! the history: a tally for each month and the longest streak they held.
+//! The all-time figures and streak go on counting them; the day, week and
+//! task breakdowns only cover the live history.
+//!
+//! The same file keeps a tally for each day of the live history, added to
+//! as each session is recorded, so `timeadair stats --year` never has to
+//! read the history itself. It notes how long the history was when it was
+//! last brought up to date, and is rebuilt from scratch whenever that no
+//! longer matches, e.g. after an archive or an edit by hand.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::history::{self, History, Outcome, SessionRecord};
+use crate::paths;
+use crate::stats::{self, Tally};
+
+pub fn rollups_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("rollups.json"))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rollups {
+    // Work sessions by `YYYY-MM`
+    pub months: BTreeMap<String, Tally>,
+    pub longest_streak: u32,
+    // Work sessions by day, for the live history only
+    #[serde(default)]
+    pub days: BTreeMap<NaiveDate, Tally>,
+    // The size of `history.jsonl` that `days` covers
+    #[serde(default)]
+    pub history_len: u64,
+}
+
+impl Rollups {
+    pub fn load(path: &Path) -> io::Result<Rollups> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Rollups::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // The rollups `timeadair stats` counts, or none if there's nowhere for them
+    pub fn load_default() -> io::Result<Rollups> {
+        rollups_path().map_or(Ok(Rollups::default()), |path| Rollups::load(&path))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        paths::ensure_writable()?;
+        let staging = path.with_extension("tmp");
+        fs::write(&staging, serde_json::to_string_pretty(self)? + "\n")?;
+        fs::rename(&staging, path)
+    }
+
+    pub fn add<Tz: TimeZone>(&mut self, records: &[SessionRecord], tz: &Tz) {
+        for record in records.iter().filter(|record| record.session == "work") {
+            let Some(started) = tz.timestamp_opt(record.started, 0).single() else { continue };
+            self.months.entry(started.date_naive().format("%Y-%m").to_string()).or_default().add(Tally {
+                pomodoros: (record.outcome == Outcome::Completed) as u32,
+                focus: record.duration,
+                interruptions: record.interruptions.total(),
+            });
+        }
+        let productive: BTreeSet<NaiveDate> = records
+            .iter()
+            .filter(|record| record.session == "work" && record.outcome == Outcome::Completed)
+            .filter_map(|record| tz.timestamp_opt(record.started, 0).single().map(|start| start.date_naive()))
+            .collect();
+        let productive: Vec<_> = productive.into_iter().collect();
+        self.longest_streak = self.longest_streak.max(stats::longest_streak(&productive));
+    }
+
+    fn count_day<Tz: TimeZone>(&mut self, record: &SessionRecord, tz: &Tz) {
+        let Some(started) = tz.timestamp_opt(record.started, 0).single().filter(|_| record.session == "work") else { return };
+        self.days.entry(started.date_naive()).or_default().add(Tally {
+            pomodoros: (record.outcome == Outcome::Completed) as u32,
+            focus: record.duration,
+            interruptions: record.interruptions.total(),
+        });
+    }
+
+    // Counts the session `History::append` just wrote, taking the history
+    // from `before` bytes to `after`; a table that was already behind is
+    // left for `daily` to rebuild
+    pub fn recorded(path: &Path, record: &SessionRecord, before: u64, after: u64) -> io::Result<()> {
+        let mut rollups = Rollups::load(path)?;
+        if rollups.history_len != before {
+            return Ok(());
+        }
+        rollups.count_day(record, &Local);
+        rollups.history_len = after;
+        rollups.save(path)
+    }
+
+    pub fn rebuild<Tz: TimeZone>(&mut self, records: &[SessionRecord], history_len: u64, tz: &Tz) {
+        self.days.clear();
+        records.iter().for_each(|record| self.count_day(record, tz));
+        self.history_len = history_len;
+    }
+
+    pub fn total(&self) -> Tally {
+        let mut total = Tally::default();
+        self.months.values().for_each(|month| total.add(*month));
+        total
+    }
+}
+
+// The live history's tally for each day, read from the rollups unless
+// they've fallen behind the history
+pub fn daily(history: &History) -> io::Result<BTreeMap<NaiveDate, Tally>> {
+    let path = history.rollups_path();
+    let rollups = Rollups::load(&path)?;
+    if rollups.history_len == history.size()? {
+        return Ok(rollups.days);
+    }
+    // Under --read-only they're rebuilt for this once and not saved
+    let lock = history.lock().ok();
+    let mut rollups = Rollups::load(&path)?;
+    let len = history.size()?;
+    rollups.rebuild(&history.load()?, len, &Local);
+    if lock.is_some() {
+        let _ = rollups.save(&path);
+    }
+    Ok(rollups.days)
+}
+
+// `2023`, `2023-06` or `2023-06-15`, each meaning the start of it
+pub fn parse_before(text: &str) -> Result<NaiveDate, String> {
+    let date = match text.split('-').count() {
+        1 => format!("{}-01-01", text),
+        2 => format!("{}-01", text),
+        _ => text.to_string(),
+    };
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| format!("archive --before requires a year, month or day, e.g. 2023 or 2023-06, not '{}'", text))
+}
+
+// The sessions started before the day, and the rest
+pub fn split<Tz: TimeZone>(records: Vec<SessionRecord>, before: NaiveDate, tz: &Tz) -> (Vec<SessionRecord>, Vec<SessionRecord>) {
+    records.into_iter().partition(|record| tz.timestamp_opt(record.started, 0).single().is_some_and(|start| start.date_naive() < before))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Archived {
+    pub sessions: usize,
+    // None when there was nothing to archive
+    pub file: Option<PathBuf>,
+}
+
+pub fn archive(before: NaiveDate) -> io::Result<Archived> {
+    paths::ensure_writable()?;
+    let (Some(history_path), Some(rollups_path)) = (history::default_path(), rollups_path()) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no data directory"));
+    };
+    let history = History::new(history_path.clone());
+    let lock = history.lock()?;
+    let (old, kept) = split(history.load()?, before, &Local);
+    let (Some(first), Some(last)) = (old.first(), old.last()) else {
+        return Ok(Archived { sessions: 0, file: None });
+    };
+
+    let day = |record: &SessionRecord| Local.timestamp_opt(record.started, 0).single().map(|start| start.format("%Y-%m-%d").to_string());
+    let dir = history_path.with_file_name("archive");
+    fs::create_dir_all(&dir)?;
+    let name = format!("history-{}-to-{}.jsonl", day(first).unwrap_or_default(), day(last).unwrap_or_default());
+    let file = dir.join(name);
+    let lines: Vec<_> = old.iter().map(serde_json::to_string).collect::<Result<_, _>>()?;
+    fs::write(&file, lines.join("\n") + "\n")?;
+    let file = compress(&file).unwrap_or(file);
+
+    // Rolled up before the history shrinks, so a failure loses nothing
+    let mut rollups = Rollups::load(&rollups_path)?;
+    rollups.add(&old, &Local);
+    rollups.save(&rollups_path)?;
+    history.replace(&lock, &kept)?;
+    Ok(Archived { sessions: old.len(), file: Some(file) })
+}
+
+// None leaves the file as it is, e.g. without `gzip` on the PATH
+fn compress(file: &Path) -> Option<PathBuf> {
+    let status = Command::new("gzip").arg("-9").arg("-f").arg(file).stderr(Stdio::null()).status().ok()?;
+    let mut compressed = file.as_os_str().to_owned();
+    compressed.push(".gz");
+    status.success().then(|| PathBuf::from(compressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn work(started: i64, outcome: Outcome) -> SessionRecord {
+        SessionRecord {
+            session: "work".to_string(),
+            started,
+            duration: 1500,
+            outcome,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rolls_up_old_sessions_by_month() {
+        assert_eq!(parse_before("2023"), Ok(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()));
+        assert_eq!(parse_before("2023-06"), Ok(NaiveDate::from_ymd_opt(2023, 6, 1).unwrap()));
+        assert!(parse_before("last year").is_err());
+
+        // 2022-12-30 and 31, then 2023-01-01, all at noon
+        let records = vec![work(1672401600, Outcome::Completed), work(1672488000, Outcome::Completed), work(1672574400, Outcome::Quit)];
+        let (old, kept) = split(records.clone(), parse_before("2023").unwrap(), &Utc);
+        assert_eq!((old, kept), (records[..2].to_vec(), records[2..].to_vec()));
+
+        let mut rollups = Rollups::default();
+        rollups.add(&records, &Utc);
+        assert_eq!(rollups.months["2022-12"], Tally { pomodoros: 2, focus: 3000, interruptions: 0 });
+        assert_eq!(rollups.months["2023-01"], Tally { pomodoros: 0, focus: 1500, interruptions: 0 });
+        assert_eq!(rollups.total().focus, 4500);
+        assert_eq!(rollups.longest_streak, 2);
+
+        let path = std::env::temp_dir().join(format!("timeadair-rollups-{}.json", std::process::id()));
+        rollups.save(&path).unwrap();
+        assert_eq!(Rollups::load(&path).unwrap(), rollups);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Rollups::load(&path).unwrap(), Rollups::default());
+    }
+
+    #[test]
+    fn days_are_tallied_as_sessions_are_recorded() {
+        let dir = std::env::temp_dir().join(format!("timeadair-daily-{}", std::process::id()));
+        let history = History::new(dir.join("history.jsonl"));
+        history.append(&work(1714651200, Outcome::Completed)).unwrap();
+        history.append(&work(1714651200 + 1800, Outcome::Completed)).unwrap();
+        let rollups = Rollups::load(&history.rollups_path()).unwrap();
+        assert_eq!(rollups.history_len, history.size().unwrap());
+        let day = Local.timestamp_opt(1714651200, 0).unwrap().date_naive();
+        assert_eq!(rollups.days[&day].pomodoros, 2);
+
+        // An edit behind its back has the table rebuilt
+        history.replace(&history.lock().unwrap(), &[work(1714651200, Outcome::Quit)]).unwrap();
+        assert_eq!(daily(&history).unwrap()[&day], Tally { pomodoros: 0, focus: 1500, interruptions: 0 });
+        assert_eq!(Rollups::load(&history.rollups_path()).unwrap().history_len, history.size().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
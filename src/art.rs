@@ -0,0 +1,81 @@
+//! Art for the full screen's header in place of its one line of title, one
+//! file for work and another for breaks:
+//!
+//! ```toml
+//! work_art = "/home/me/art/tomato.txt"
+//! break_art = "/home/me/art/cup.txt"
+//! ```
+//!
+//! A file can hold the same picture at several sizes, largest first, each
+//! after a line with only `%` on it as in a fortune file. The first that
+//! fits the terminal is drawn, centred, with the title moving onto the
+//! header's border, and a terminal too small for any of them gets the
+//! usual line. Plain ASCII is safest, since widths are counted in
+//! characters.
+
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Art {
+    sizes: Vec<Vec<String>>,
+}
+
+impl Art {
+    pub fn load(path: &Path) -> io::Result<Art> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| io::Error::new(error.kind(), format!("unable to read header art {}: {}", path.display(), error)))?;
+        Ok(Art::parse(&text))
+    }
+
+    fn parse(text: &str) -> Art {
+        let mut sizes = Vec::new();
+        let mut size = Vec::new();
+        for line in text.lines().map(str::trim_end) {
+            if line == "%" {
+                sizes.push(mem::take(&mut size));
+            } else {
+                size.push(line.to_string());
+            }
+        }
+        sizes.push(size);
+        // Blank lines around a picture would only push the view down
+        for size in &mut sizes {
+            while size.last().is_some_and(|line| line.trim().is_empty()) {
+                size.pop();
+            }
+            let top = size.iter().take_while(|line| line.trim().is_empty()).count();
+            size.drain(..top);
+        }
+        sizes.retain(|size| !size.is_empty());
+        Art { sizes }
+    }
+
+    // The largest size no wider or taller than this, if any
+    pub fn fitting(&self, width: u16, height: u16) -> Option<&[String]> {
+        self.sizes
+            .iter()
+            .find(|size| size.len() <= usize::from(height) && width_of(size) <= usize::from(width))
+            .map(Vec::as_slice)
+    }
+}
+
+pub fn width_of(lines: &[String]) -> usize {
+    lines.iter().map(|line| line.chars().count()).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_largest_size_that_fits() {
+        let art = Art::parse("\n  _____\n /     \\\n|  (o)  |\n \\_____/\n\n%\n (o)\n%\n");
+        assert_eq!(art.fitting(40, 6).map(<[String]>::len), Some(4));
+        assert_eq!(art.fitting(40, 3), Some(&[" (o)".to_string()][..]));
+        assert_eq!(art.fitting(8, 6).map(width_of), Some(4));
+        assert_eq!(art.fitting(3, 6), None);
+    }
+}
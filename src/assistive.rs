@@ -0,0 +1,87 @@
+//! Completion events for assistive hardware, e.g. a braille display notifier.
+//!
+//! When a session runs to completion the configured program is started with
+//! the event as plain arguments, so that a notifier script needs no JSON
+//! parsing:
+//!
+//! ```text
+//! <program> work_completed work 1500 "Work session complete" ["<task>"]
+//! ```
+//!
+//! The arguments are the event, the session type, its length in seconds,
+//! a short message that fits on a 40 cell display and, if one was given,
+//! the task. A reminder from the daemon to start a session comes as
+//! `reminder work <seconds waited> <message>`. The program isn't waited
+//! for. It's the `command` backend in `notify`.
+
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::notify::{Completion, Notifier};
+use crate::scheduler::Phase;
+
+pub struct AssistiveCommand {
+    program: PathBuf,
+}
+
+impl AssistiveCommand {
+    pub fn new(program: PathBuf) -> AssistiveCommand {
+        AssistiveCommand { program }
+    }
+}
+
+impl Notifier for AssistiveCommand {
+    fn name(&self) -> &'static str {
+        "assistive"
+    }
+
+    fn completed(&mut self, completion: &Completion) -> io::Result<()> {
+        let mut child = Command::new(&self.program)
+            .args(arguments(completion.phase, completion.elapsed, completion.task))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+
+    fn remind(&mut self, waited: u64, message: &str) -> io::Result<()> {
+        let mut child = Command::new(&self.program)
+            .args(["reminder", "work", &waited.to_string(), message])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+fn arguments(phase: Phase, duration: u64, task: Option<&str>) -> Vec<String> {
+    let session = phase.name().to_lowercase();
+    let mut args = vec![
+        format!("{}_completed", session),
+        session,
+        duration.to_string(),
+        if phase == Phase::Work { "Work session complete" } else { "Break over" }.to_string(),
+    ];
+    args.extend(task.map(str::to_string));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_for_each_session() {
+        assert_eq!(
+            arguments(Phase::Work, 1500, Some("Write report")),
+            ["work_completed", "work", "1500", "Work session complete", "Write report"]
+        );
+        assert_eq!(arguments(Phase::Break, 300, None), ["break_completed", "break", "300", "Break over"]);
+    }
+}
@@ -0,0 +1,201 @@
+//! `timeadair bench`: times the parts of the timer that grow with use,
+//! against made-up data so runs compare like with like:
+//!
+//! - drawing the full-screen progress view, off-screen at 80x24
+//! - appending sessions to a history in a temp directory, locking and
+//!   rollups included
+//! - `timeadair stats` and `stats --year` over years of sessions
+//!
+//! Nothing in the real data directory is read or written. Each timing
+//! comes with how many allocations a run made, counted by the allocator
+//! below, since the render path is meant to make none of its own.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate};
+use serde_json::{json, Value};
+
+use crate::archive::Rollups;
+use crate::history::{History, Interruptions, Outcome, SessionRecord};
+use crate::locale::ENGLISH;
+use crate::schedule::Schedule;
+use crate::stats::{self, Tally};
+use crate::timer::Timer;
+use crate::ui::{Renderer, Screen};
+
+const FRAMES: u32 = 2_000;
+const WRITES: u32 = 500;
+const SESSIONS: usize = 100_000;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+// The system allocator, counting as it goes; a relaxed add is all it
+// costs the rest of the program
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[derive(Debug, PartialEq)]
+pub struct Timing {
+    pub name: &'static str,
+    pub runs: u32,
+    pub total: Duration,
+    pub allocations: u64,
+}
+
+impl Timing {
+    pub fn each(&self) -> Duration {
+        self.total / self.runs.max(1)
+    }
+
+    pub fn allocations_each(&self) -> u64 {
+        self.allocations / u64::from(self.runs.max(1))
+    }
+}
+
+fn time(name: &'static str, runs: u32, mut run: impl FnMut() -> io::Result<()>) -> io::Result<Timing> {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..runs {
+        run()?;
+    }
+    let total = start.elapsed();
+    Ok(Timing { name, runs, total, allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations })
+}
+
+// Ten work sessions a day with breaks between, going back from `end`
+pub fn sessions(count: usize, end: i64) -> Vec<SessionRecord> {
+    let tasks = ["report", "email", "slides", "review"];
+    (0..count)
+        .map(|i| {
+            let work = i % 2 == 0;
+            let day = (count - i) as i64 / 20;
+            SessionRecord {
+                session: if work { "work" } else { "break" }.to_string(),
+                started: end - day * 86400 - (i % 20) as i64 * 1800,
+                duration: if work { 1500 } else { 300 },
+                outcome: if i % 7 == 0 { Outcome::Quit } else { Outcome::Completed },
+                task: work.then(|| tasks[i / 2 % tasks.len()].to_string()),
+                planned: Some(if work { 1500 } else { 300 }),
+                rating: (i % 3 == 0).then_some((i % 5) as u8 + 1),
+                interruptions: Interruptions { internal: (i % 4 == 0) as u32, external: 0 },
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+pub fn run() -> io::Result<Vec<Timing>> {
+    let mut timings = Vec::new();
+
+    let mut screen = Screen::fixed(io::sink(), 80, 24)?;
+    let mut timer = Timer::new(1500);
+    timings.push(time("render progress frame", FRAMES, || {
+        timer.elapsed = (timer.elapsed + 1) % timer.duration;
+        screen.progress(&timer, "Current session: Work - report", false)
+    })?);
+
+    let dir = std::env::temp_dir().join(format!("timeadair-bench-{}", std::process::id()));
+    let history = History::new(dir.join("history.jsonl"));
+    let records = sessions(WRITES as usize, Local::now().timestamp());
+    let mut next = records.iter();
+    let writes = time("append to history", WRITES, || next.next().map_or(Ok(()), |record| history.append(record)));
+    let _ = fs::remove_dir_all(&dir);
+    timings.push(writes?);
+
+    let now = Local::now();
+    let records = sessions(SESSIONS, now.timestamp());
+    timings.push(time("stats over 100k sessions", 5, || {
+        stats::summarize(&records, &Rollups::default(), &Schedule::default(), now);
+        Ok(())
+    })?);
+    let mut rollups = Rollups::default();
+    timings.push(time("rebuild daily rollups", 5, || {
+        rollups.rebuild(&records, 0, &Local);
+        Ok(())
+    })?);
+    let days: BTreeMap<NaiveDate, Tally> = rollups.days;
+    let today = now.date_naive();
+    timings.push(time("stats --year from rollups", 100, || {
+        stats::year(&days, today, &ENGLISH, false);
+        Ok(())
+    })?);
+    Ok(timings)
+}
+
+pub fn report(timings: &[Timing]) -> String {
+    let width = timings.iter().map(|timing| timing.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for timing in timings {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>6} runs  {:>10.3?} each  {:>10.3?} total  {:>8} allocations each",
+            timing.name,
+            timing.runs,
+            timing.each(),
+            timing.total,
+            timing.allocations_each()
+        );
+    }
+    out
+}
+
+// The same for `timeadair bench --json`, in microseconds
+pub fn data(timings: &[Timing]) -> Value {
+    timings
+        .iter()
+        .map(|timing| {
+            json!({
+                "name": timing.name,
+                "runs": timing.runs,
+                "each_us": timing.each().as_micros() as u64,
+                "total_us": timing.total.as_micros() as u64,
+                "allocations_each": timing.allocations_each(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthetic_sessions_fill_the_days() {
+        let records = sessions(40, 1714651200);
+        assert_eq!(records.iter().filter(|record| record.session == "work").count(), 20);
+        assert_eq!(records[0].task.as_deref(), Some("report"));
+        let timing = Timing { name: "x", runs: 4, total: Duration::from_millis(10), allocations: 8 };
+        assert_eq!(timing.each(), Duration::from_micros(2500));
+        assert_eq!(timing.allocations_each(), 2);
+        assert!(report(&[timing]).starts_with("x       4 runs"));
+    }
+}
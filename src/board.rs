@@ -0,0 +1,220 @@
+//! The task board that `board = true` (or `--board`) shows in place of the
+//! task prompt: the task list in todo, doing and done columns. The arrow
+//! keys (or `hjkl`) move around, `<` and `>` move the selected card to the
+//! next column, Enter starts the work session on it and Esc starts one
+//! without a task. `/` narrows the cards down to those matching what's
+//! typed next, until Enter; Esc while typing clears it again.
+//!
+//! Starting on a card moves it to doing; once a session finishes off its
+//! estimate the timer asks whether it's done.
+
+use std::io;
+
+use chrono::{DateTime, TimeZone};
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+
+use crate::history::SessionRecord;
+use crate::search;
+use crate::tasks::{self, Task};
+use crate::ui::{RawMode, Renderer};
+
+const TODO: usize = 0;
+const DOING: usize = 1;
+const DONE: usize = 2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Board {
+    // Indices into the task list, in list order
+    pub columns: [Vec<usize>; 3],
+    pub column: usize,
+    pub row: usize,
+    // Cards shown have to match this
+    pub search: String,
+    typing: bool,
+}
+
+impl Board {
+    pub fn new<Tz: TimeZone>(tasks: &[Task], now: &DateTime<Tz>) -> Board {
+        let mut board = Board { columns: Default::default(), column: TODO, row: 0, search: String::new(), typing: false };
+        board.sort(tasks, now);
+        board
+    }
+
+    fn sort<Tz: TimeZone>(&mut self, tasks: &[Task], now: &DateTime<Tz>) {
+        self.columns = Default::default();
+        for (i, task) in tasks.iter().enumerate().filter(|(_, task)| search::matches(&task.title, &self.search)) {
+            self.columns[column_of(task, now)].push(i);
+        }
+    }
+
+    // What's being searched for, while it's shown
+    pub fn search(&self) -> Option<&str> {
+        (self.typing || !self.search.is_empty()).then_some(self.search.as_str())
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.columns[self.column].get(self.row).copied()
+    }
+
+    // Some once the board is closed, with the task picked if there is one
+    pub fn key<Tz: TimeZone>(&mut self, code: KeyCode, tasks: &mut [Task], now: &DateTime<Tz>) -> Option<Option<usize>> {
+        if self.typing {
+            match code {
+                KeyCode::Char(c) => self.search.push(c),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Enter => self.typing = false,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.typing = false;
+                }
+                _ => {}
+            }
+            self.sort(tasks, now);
+            self.row = self.row.min(self.columns[self.column].len().saturating_sub(1));
+            return None;
+        }
+        match code {
+            KeyCode::Char('/') => self.typing = true,
+            KeyCode::Left | KeyCode::Char('h') => self.column = self.column.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => self.column = (self.column + 1).min(DONE),
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.row += 1,
+            KeyCode::Char('<') if self.column > TODO => self.shift(self.column - 1, tasks, now),
+            KeyCode::Char('>') if self.column < DONE => self.shift(self.column + 1, tasks, now),
+            KeyCode::Enter => {
+                let picked = self.selected()?;
+                move_to(&mut tasks[picked], DOING, now);
+                return Some(Some(picked));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(None),
+            _ => {}
+        }
+        self.row = self.row.min(self.columns[self.column].len().saturating_sub(1));
+        None
+    }
+
+    // One label per card, for drawing
+    pub fn labels<Tz: TimeZone>(&self, tasks: &[Task], records: &[SessionRecord], now: &DateTime<Tz>) -> [Vec<String>; 3] {
+        self.columns
+            .clone()
+            .map(|column| column.into_iter().map(|i| card(&tasks[i], tasks::pomodoros(&tasks[i], records, now))).collect())
+    }
+
+    // Moves the selected card, and the selection with it
+    fn shift<Tz: TimeZone>(&mut self, column: usize, tasks: &mut [Task], now: &DateTime<Tz>) {
+        let Some(picked) = self.selected() else { return };
+        move_to(&mut tasks[picked], column, now);
+        self.sort(tasks, now);
+        self.column = column;
+        self.row = self.columns[column].iter().position(|i| *i == picked).unwrap_or_default();
+    }
+}
+
+fn column_of<Tz: TimeZone>(task: &Task, now: &DateTime<Tz>) -> usize {
+    if task.is_done(now) {
+        DONE
+    } else if task.doing {
+        DOING
+    } else {
+        TODO
+    }
+}
+
+fn move_to<Tz: TimeZone>(task: &mut Task, column: usize, now: &DateTime<Tz>) {
+    if column == DONE {
+        task.mark_done(now.timestamp());
+        return;
+    }
+    task.done = false;
+    task.done_at = None;
+    task.doing = column == DOING;
+}
+
+// `Report  2/8`, or the title alone for a task with nothing to show yet
+pub fn card(task: &Task, pomodoros: u32) -> String {
+    match task.estimate {
+        Some(estimate) => format!("{}  {}/{}", task.title, pomodoros, estimate),
+        None if pomodoros > 0 => format!("{}  {}", task.title, pomodoros),
+        None => task.title.clone(),
+    }
+}
+
+// The index of the task picked, or None to go ahead without one
+pub fn run<Tz: TimeZone>(screen: &mut dyn Renderer, tasks: &mut [Task], records: &[SessionRecord], now: &DateTime<Tz>) -> io::Result<Option<usize>> {
+    let mut board = Board::new(tasks, now);
+    let _raw = RawMode::enable()?;
+    screen.show_cursor(false)?;
+    let picked = loop {
+        screen.board(&board.labels(tasks, records, now), (board.column, board.row), board.search())?;
+        match event::read()? {
+            Event::Key(KeyEvent { code, .. }) => {
+                if let Some(picked) = board.key(code, tasks, now) {
+                    break picked;
+                }
+            }
+            Event::Resize(..) => screen.redraw()?,
+            _ => {}
+        }
+    };
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn cards_move_between_columns() {
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let mut tasks = vec![
+            Task { estimate: Some(4), ..Task::new("report".to_string()) },
+            Task { doing: true, ..Task::new("slides".to_string()) },
+            Task { done: true, ..Task::new("email".to_string()) },
+        ];
+        let mut board = Board::new(&tasks, &now);
+        assert_eq!(board.columns, [vec![0], vec![1], vec![2]]);
+        assert_eq!(board.labels(&tasks, &[], &now), [vec!["report  0/4".to_string()], vec!["slides".to_string()], vec!["email".to_string()]]);
+
+        // Slides on to done, then back to doing
+        for key in [KeyCode::Right, KeyCode::Char('>')] {
+            assert_eq!(board.key(key, &mut tasks, &now), None);
+        }
+        assert_eq!((board.columns.clone(), board.column, board.row), ([vec![0], vec![], vec![1, 2]], DONE, 0));
+        assert!(tasks[1].is_done(&now) && !tasks[1].doing);
+        board.key(KeyCode::Char('<'), &mut tasks, &now);
+        assert!(!tasks[1].is_done(&now) && tasks[1].doing);
+
+        // Down past the last card stays on it
+        board.key(KeyCode::Left, &mut tasks, &now);
+        board.key(KeyCode::Down, &mut tasks, &now);
+        assert_eq!(board.selected(), Some(0));
+        assert_eq!(board.key(KeyCode::Enter, &mut tasks, &now), Some(Some(0)));
+        assert!(tasks[0].doing);
+        assert_eq!(board.key(KeyCode::Esc, &mut tasks, &now), Some(None));
+    }
+
+    #[test]
+    fn searching_narrows_the_cards() {
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let mut tasks = vec![Task::new("Invoice ACME".to_string()), Task::new("Slides".to_string()), Task::new("acme call".to_string())];
+        let mut board = Board::new(&tasks, &now);
+        for key in [KeyCode::Char('/'), KeyCode::Char('a'), KeyCode::Char('c'), KeyCode::Char('m'), KeyCode::Char('e')] {
+            board.key(key, &mut tasks, &now);
+        }
+        assert_eq!(board.columns[TODO], [0, 2]);
+        // Typed keys don't move or pick anything until Enter
+        assert_eq!(board.key(KeyCode::Char('q'), &mut tasks, &now), None);
+        board.key(KeyCode::Backspace, &mut tasks, &now);
+        board.key(KeyCode::Enter, &mut tasks, &now);
+        assert_eq!(board.search(), Some("acme"));
+        board.key(KeyCode::Down, &mut tasks, &now);
+        assert_eq!(board.selected(), Some(2));
+
+        board.key(KeyCode::Char('/'), &mut tasks, &now);
+        board.key(KeyCode::Esc, &mut tasks, &now);
+        assert_eq!((board.search(), board.columns[TODO].len()), (None, 3));
+    }
+}
@@ -0,0 +1,184 @@
+//! Crash-safe resume. While a session runs, where it has got to is saved to
+//! `session.json` in the data directory every few seconds, and the file is
+//! removed once the session ends in any of the usual ways. Finding one at
+//! startup means the timer was killed mid-session (a closed terminal, a
+//! crash or a reboot), so the first prompt offers to carry on from there.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::history::Interruptions;
+use crate::paths;
+use crate::scheduler::{Phase, State};
+use crate::stopwatch::format_elapsed;
+use crate::timer::Timer;
+use crate::wait;
+
+// Seconds of running or paused time between saves
+const SAVE_EVERY: u64 = 5;
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("session.json"))
+}
+
+// For exit paths that skip destructors, like the Ctrl-C handler: quitting
+// on purpose isn't an interruption
+pub fn remove_default() {
+    if paths::ensure_writable().is_err() {
+        return;
+    }
+    if let Some(path) = default_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub pid: u32,
+    pub session: String,
+    pub duration: u64,
+    pub elapsed: u64,
+    pub paused_time: u64,
+    pub completed_work: u32,
+    // When the session started, for its history record
+    pub started: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    #[serde(default, skip_serializing_if = "Interruptions::is_empty")]
+    pub interruptions: Interruptions,
+}
+
+impl Checkpoint {
+    // None between sessions, when there's nothing to lose
+    pub fn of(state: &State, started: i64, task: Option<&str>, interruptions: Interruptions) -> Option<Checkpoint> {
+        let session = match state.phase {
+            Phase::Work => "work",
+            Phase::Break => "break",
+            _ => return None,
+        };
+        Some(Checkpoint {
+            pid: process::id(),
+            session: session.to_string(),
+            duration: state.timer.duration,
+            elapsed: state.timer.elapsed,
+            paused_time: state.paused_time,
+            completed_work: state.completed_work,
+            started,
+            task: task.map(str::to_string),
+            interruptions,
+        })
+    }
+
+    // The session running again, unpaused, with the rest of `state` as it is
+    pub fn restore(&self, state: State) -> Option<State> {
+        let phase = match self.session.as_str() {
+            "work" => Phase::Work,
+            "break" => Phase::Break,
+            _ => return None,
+        };
+        Some(State {
+            phase,
+            timer: Timer { duration: self.duration, elapsed: self.elapsed.min(self.duration) },
+            paused: false,
+            paused_time: self.paused_time,
+            completed_work: self.completed_work,
+            ..state
+        })
+    }
+
+    // For the resume prompt
+    pub fn summary(&self) -> String {
+        let started = Local.timestamp_opt(self.started, 0).single().map(|at| at.format("%H:%M").to_string());
+        let mut summary = format!("A {} session", self.session);
+        if let Some(started) = started {
+            summary.push_str(&format!(" started at {}", started));
+        }
+        if let Some(task) = &self.task {
+            summary.push_str(&format!(" on '{}'", task));
+        }
+        let left = format_elapsed(self.duration.saturating_sub(self.elapsed));
+        format!("{} was cut off with {} to go.", summary, left)
+    }
+}
+
+pub struct CheckpointFile {
+    path: PathBuf,
+}
+
+impl CheckpointFile {
+    pub fn new(path: PathBuf) -> CheckpointFile {
+        CheckpointFile { path }
+    }
+
+    // A session left behind by a timer that isn't running any more
+    pub fn interrupted(&self) -> io::Result<Option<Checkpoint>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let checkpoint: Checkpoint = serde_json::from_str(&text)?;
+        Ok((!wait::alive(checkpoint.pid)).then_some(checkpoint))
+    }
+
+    // Only every few seconds unless `now` is set, e.g. when pausing
+    pub fn save(&self, checkpoint: &Checkpoint, now: bool) -> io::Result<()> {
+        if !now && !(checkpoint.elapsed + checkpoint.paused_time).is_multiple_of(SAVE_EVERY) {
+            return Ok(());
+        }
+        paths::ensure_writable()?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let staging = self.path.with_extension("tmp");
+        fs::write(&staging, serde_json::to_string(checkpoint)? + "\n")?;
+        fs::rename(&staging, &self.path)
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{self, Durations, Event};
+
+    #[test]
+    fn saves_and_restores_a_running_session() {
+        let mut state = scheduler::step(State::new(Durations::default()), Event::Start);
+        (0..300).for_each(|_| state = scheduler::step(state, Event::Tick));
+        state.completed_work = 2;
+        let interruptions = Interruptions { internal: 1, external: 0 };
+        let mut checkpoint = Checkpoint::of(&state, 1714651200, Some("report"), interruptions).unwrap();
+        assert!(Checkpoint::of(&State::new(Durations::default()), 0, None, interruptions).is_none());
+
+        let restored = checkpoint.restore(State::new(Durations::default())).unwrap();
+        assert_eq!(restored, state);
+        assert!(checkpoint.summary().ends_with(" on 'report' was cut off with 20:00 to go."));
+
+        let path = std::env::temp_dir().join(format!("timeadair-session-{}.json", process::id()));
+        let file = CheckpointFile::new(path.clone());
+        file.save(&checkpoint, false).unwrap();
+        // Still running, in this very process
+        assert_eq!(file.interrupted().unwrap(), None);
+        checkpoint.pid = u32::MAX;
+        file.save(&checkpoint, true).unwrap();
+        assert_eq!(file.interrupted().unwrap(), Some(checkpoint.clone()));
+        // Between saves nothing is written
+        file.save(&Checkpoint { elapsed: 301, ..checkpoint.clone() }, false).unwrap();
+        assert_eq!(file.interrupted().unwrap().unwrap().elapsed, 300);
+        file.clear().unwrap();
+        assert_eq!(file.interrupted().unwrap(), None);
+        file.clear().unwrap();
+    }
+}
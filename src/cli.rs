@@ -0,0 +1,1277 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::archive;
+use crate::config::{Config, Desktops, Integrations};
+use crate::dnd::Desktop;
+use crate::dryrun::Integration;
+use crate::export::{Export, ExportFormat};
+use crate::leaderboard::Leaderboard;
+use crate::hooks::Hooks;
+use crate::media::Media;
+use crate::notify::Backend;
+use crate::hours::WorkingHours;
+use crate::locale::Locale;
+use crate::permissions::{Permission, PermissionsCommand};
+use crate::publish::{Mqtt, Targets};
+use crate::schedule::Schedule;
+use crate::scheduler::{Durations, Planned};
+use crate::search::Search;
+use crate::seed::{Pattern, Seed};
+use crate::shell::Shell;
+use crate::sound::{SoundEvent, SoundsCommand};
+use crate::statusbar::StatusFormat;
+use crate::tasks::{Repeat, Task, TaskCommand};
+use crate::theme::Theme;
+use crate::vacation;
+
+pub const USAGE: &str = "\
+Usage: timeadair [options]
+       timeadair shell-init <bash|zsh|fish>
+       timeadair countdown <length, e.g. 10m, 90s or 1h30m>
+       timeadair stopwatch
+       timeadair tutorial
+       timeadair bench
+       timeadair stats [--year] [--profile <name>]
+       timeadair task <add <title> [--due <YYYY-MM-DD>] [--estimate <pomodoros>] [--repeat <daily|weekly|cron>] [--after <number,...>]|list|done <number>>
+       timeadair export [--format <csv|json|ics>] [--since <YYYY-MM-DD>]
+       timeadair history archive --before <YYYY[-MM[-DD]]>
+       timeadair debug-bundle [--output <file>]
+       timeadair seed [--days <n>] [--profile <realistic|steady>] [--seed <n>]
+       timeadair search <text> [--since <YYYY-MM-DD>] [--until <YYYY-MM-DD>]
+       timeadair leaderboard [--anonymous] [<name>=<data dir or history file>...]
+       timeadair daemon
+       timeadair start
+       timeadair wait
+       timeadair ctl <start|pause|resume|skip|reset|quit|status|add-timer <name> <minutes>|...>
+       timeadair sounds <list|preview <pack> [sound]|devices>
+       timeadair permissions [list|grant <name>|revoke <name>]
+
+Options:
+  --work <minutes>               Length of work sessions (default 25)
+  --break <minutes>              Length of breaks (default 5)
+  --long-break <minutes>         Length of long breaks (default 15)
+  --long-break-every <count>     Work sessions per long break, 0 for none (default 4)
+  --plan <session:minutes,...>   Run these sessions in turn, e.g. work:50,break:10,long_break:30
+  --adjust-step <minutes>        How much '+' and '-' change the running session (default 1)
+  --snooze <minutes>             How much more work 'z' at the break prompt adds (default 5)
+  --auto                         Start every session after a short countdown, no prompts
+  --auto-advance <seconds>       Start the next session after a countdown
+  --false-start <seconds>        Leave sessions abandoned this early out of history
+  --idle-pause <minutes>         Pause work when the computer has been idle this long
+  --nudge <minutes>              In daemon mode, nudge after this long at the keyboard with no session
+  --remind <minutes>             In daemon mode, remind after this long in working hours with no session
+  --task <description>           What the work sessions are for, instead of asking
+  --board                        Pick tasks from a todo/doing/done board instead of a list
+  --rate                         Ask for a 1-5 energy rating after each work session
+  --notes                        Ask what each work session got done, to keep in history
+  --diary                        Keep a daily diary of work sessions, with their git branch and commits
+  --coach                        Suggest changes when sessions or breaks keep going awry
+  --strict                       Make giving up on work take a typed phrase, and breaks unskippable
+  --daily-goal <count>           Pomodoros to aim for each day, shown in the header
+  --serial <device>              Write progress to a serial/USB display
+  --title                        Show the time left in the terminal's title
+  --tmux                         Show it in tmux's @timeadair option too, for the status line
+  --event-log <file>             Append session events as JSON Lines
+  --webhook <url>                POST session starts and ends to this URL as JSON
+  --mqtt <url>                   Publish them to an MQTT topic, e.g. mqtt://broker.local/office/focus
+  --metrics-port <port>          Serve state and totals for Prometheus on 127.0.0.1:<port>/metrics
+  --dry-run                      Log webhook, MQTT and hook calls to dry-run.log in the data dir instead
+  --command-fifo                 Accept commands on a named pipe in the data dir
+  --stdio                        Read JSON commands on stdin and write events on stdout
+  --ui <screen|plain>            Full-screen display (default) or one line per update
+  --display <normal|big>         The time left as text or in block numerals; 'd' switches
+  --theme <name>                 Colours: default, light or high-contrast
+  --no-color                     No colours and plain ASCII, as does setting NO_COLOR
+  --accessible                   Plain lines now and then for screen readers, e.g. 'Work - 20 minutes remaining'
+  --announce-every <minutes>     How often --accessible says the time left (default 5)
+  --locale <en|gd>               English or Gàidhlig, instead of going by LANG
+  --status-format <json|plain>   Print a status bar line every second, no prompts
+  --mute                         Don't play a sound when a session completes
+  --visual-bell                  Flash the screen instead of ringing the terminal bell
+  --final-countdown <mode>       Count down the last ten seconds: tick, flash or both
+  --dnd                          Turn on Do Not Disturb during work sessions
+  --assistive-command <program>  Run this when a session completes, e.g. a braille notifier
+  --notify <backends>            Tell of a completed session with these: bell, desktop, sound, command
+  --sound-pack <name>            Play the sounds in ~/.config/timeadair/sounds/<name>
+  --volume <percent>             Sound volume, changed with '[' and ']' while running
+  --audio-device <name>          Play sounds here, see 'timeadair sounds devices'
+  --workspace <name>             Keep history and other data separate per workspace
+  --profile <name>               Use the config file's [profiles.<name>] settings; with stats, count only its sessions
+  --data-dir <dir>               Use another data directory, e.g. a backup
+  --read-only                    Never write to the data or runtime directories
+  --demo                         Play a scripted demo session (for recordings)
+  --json                         Print what a subcommand has to say as JSON, for scripts
+  --working-hours <HH:MM-HH:MM>  Warn when starting work outside these hours
+  --strict-hours                 Refuse work sessions outside working hours
+  -h, --help                     Show this help
+
+Defaults for most options can be set in ~/.config/timeadair/config.toml.";
+
+// Seconds of countdown under --auto when --auto-advance doesn't say
+const AUTO_COUNTDOWN: u64 = 5;
+const SNOOZE: u64 = 5 * 60;
+const ANNOUNCE_EVERY: u64 = 5 * 60;
+
+#[derive(Clone, Copy, Default)]
+pub enum UiMode {
+    #[default]
+    Screen,
+    Plain,
+}
+
+impl UiMode {
+    fn parse(mode: &str) -> Result<UiMode, String> {
+        match mode {
+            "screen" => Ok(UiMode::Screen),
+            "plain" => Ok(UiMode::Plain),
+            _ => Err("--ui must be 'screen' or 'plain'".to_string()),
+        }
+    }
+}
+
+// How the full-screen view shows the time left
+#[derive(Clone, Copy, Default)]
+pub enum Display {
+    #[default]
+    Normal,
+    Big,
+}
+
+impl Display {
+    fn parse(display: &str) -> Result<Display, String> {
+        match display {
+            "normal" => Ok(Display::Normal),
+            "big" => Ok(Display::Big),
+            _ => Err("--display must be 'normal' or 'big'".to_string()),
+        }
+    }
+}
+
+// How the last ten seconds of a session are counted down, if at all
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum FinalCountdown {
+    #[default]
+    Off,
+    Tick,
+    Flash,
+    Both,
+}
+
+impl FinalCountdown {
+    fn parse(countdown: &str) -> Result<FinalCountdown, String> {
+        match countdown {
+            "off" => Ok(FinalCountdown::Off),
+            "tick" => Ok(FinalCountdown::Tick),
+            "flash" => Ok(FinalCountdown::Flash),
+            "both" => Ok(FinalCountdown::Both),
+            _ => Err(format!("final countdown must be 'off', 'tick', 'flash' or 'both', not '{}'", countdown)),
+        }
+    }
+
+    pub fn ticks(self) -> bool {
+        matches!(self, FinalCountdown::Tick | FinalCountdown::Both)
+    }
+
+    pub fn flashes(self) -> bool {
+        matches!(self, FinalCountdown::Flash | FinalCountdown::Both)
+    }
+}
+
+// What SIGTERM does to a running session: end it there and then, recorded
+// as interrupted by the system, or leave it for the next run to pick up
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum OnShutdown {
+    #[default]
+    Record,
+    Resume,
+}
+
+impl OnShutdown {
+    fn parse(action: &str) -> Result<OnShutdown, String> {
+        match action {
+            "record" => Ok(OnShutdown::Record),
+            "resume" => Ok(OnShutdown::Resume),
+            _ => Err("on_shutdown must be 'record' or 'resume'".to_string()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Options {
+    pub durations: Durations,
+    pub auto_advance: Option<u64>,
+    // Seconds of overtime 'z' adds to a completed work session before its
+    // break
+    pub snooze: u64,
+    // Chain every session, including the first, rather than only hand over
+    // from one that completed
+    pub auto: bool,
+    pub serial: Option<PathBuf>,
+    // The time left in the terminal title, and in tmux's status line
+    pub title: bool,
+    pub tmux: bool,
+    pub event_log: Option<PathBuf>,
+    // Where session starts and ends are sent on to
+    pub publish: Targets,
+    pub metrics_port: Option<u16>,
+    pub command_fifo: bool,
+    pub stdio: bool,
+    pub working_hours: Option<WorkingHours>,
+    // Where meetings are read from, an ICS file or URL
+    pub calendar: Option<String>,
+    pub checklist: Vec<String>,
+    // None for the locale's own
+    pub break_suggestions: Option<Vec<String>>,
+    // Names and weights for the roulette, and whether picking one spins
+    pub break_activities: Vec<(String, u32)>,
+    pub spin: bool,
+    pub task: Option<String>,
+    // Pick tasks from the board rather than the numbered list
+    pub board: bool,
+    pub false_start: u64,
+    // Takes over from the work/break cycle when not empty
+    pub plan: Vec<Planned>,
+    // Seconds without keyboard or mouse before a work session pauses itself
+    pub idle_pause: Option<u64>,
+    pub on_shutdown: OnShutdown,
+    // Seconds at the keyboard between sessions before the daemon nudges,
+    // and seconds away that count as a break
+    pub nudge: Option<u64>,
+    pub nudge_idle: Option<u64>,
+    // Seconds without a session, in working hours, before the daemon
+    // reminds
+    pub remind: Option<u64>,
+    pub rate: bool,
+    pub notes: bool,
+    pub diary: bool,
+    pub coach: bool,
+    pub strict: bool,
+    // Completed work sessions to aim for each day
+    pub daily_goal: Option<u32>,
+    pub mute: bool,
+    pub visual_bell: bool,
+    pub final_countdown: FinalCountdown,
+    // The bar moves once a second rather than between ticks too
+    pub low_refresh: bool,
+    // The time left to a tenth of a second in the final minute
+    pub tenths: bool,
+    // Header art for each phase of the full screen
+    pub work_art: Option<PathBuf>,
+    pub break_art: Option<PathBuf>,
+    pub theme: Theme,
+    pub locale: Locale,
+    pub no_color: bool,
+    // Plain lines every `announce_every` seconds instead of every second
+    pub accessible: bool,
+    pub announce_every: u64,
+    pub assistive_command: Option<PathBuf>,
+    // How a completed session is told, see `notify`
+    pub notify: Vec<Backend>,
+    // Desktops to turn Do Not Disturb on for during work sessions
+    pub dnd: Vec<Desktop>,
+    pub focus_shortcuts: Option<(String, String)>,
+    pub hooks: Hooks,
+    // Integrations that log what they'd send instead of sending it
+    pub dry_run: Vec<Integration>,
+    pub sound_pack: Option<String>,
+    pub volume: Option<u8>,
+    pub audio_device: Option<String>,
+    pub work_end_sound: Option<PathBuf>,
+    pub break_end_sound: Option<PathBuf>,
+    // What each kind of session does to the music, from `[media]`
+    pub media: Option<Media>,
+    pub workspace: Option<String>,
+    // Recorded with each session, and what stats are narrowed to
+    pub profile: Option<String>,
+    // Each weekday's profile and goal, and the vacation days, for streaks
+    pub schedule: Schedule,
+    // Read for stats only, since it may mean a download
+    pub vacation_calendar: Option<String>,
+    pub data_dir: Option<PathBuf>,
+    pub read_only: bool,
+    pub demo: bool,
+    pub ui: UiMode,
+    pub display: Display,
+    pub status_format: Option<StatusFormat>,
+    pub shell_init: Option<Shell>,
+    pub countdown: Option<u64>,
+    pub stopwatch: bool,
+    pub tutorial: bool,
+    pub bench: bool,
+    pub stats: bool,
+    // The heatmap of the last 52 weeks instead of the summary
+    pub year: bool,
+    pub export: Option<Export>,
+    pub leaderboard: Option<Leaderboard>,
+    pub search: Option<Search>,
+    pub seed: Option<Seed>,
+    // `history archive`: sessions started before this day
+    pub archive: Option<NaiveDate>,
+    // `debug-bundle`, and where to write it if not where it's run
+    pub debug_bundle: Option<Option<PathBuf>>,
+    pub tasks: Option<TaskCommand>,
+    pub daemon: bool,
+    pub ctl: Option<String>,
+    pub wait: bool,
+    pub sounds: Option<SoundsCommand>,
+    pub permissions: Option<PermissionsCommand>,
+    pub json: bool,
+    pub help: bool,
+}
+
+impl Options {
+    // Flags are applied on top of whatever the config file set
+    pub fn parse<I: Iterator<Item = String>>(mut args: I, config: &Config) -> Result<Options, String> {
+        let mut options = Options::from_config(config).map_err(|e| format!("config.toml: {}", e))?;
+        let mut strict_hours = config.strict_hours.unwrap_or(false);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--work" => options.durations.work_time = parse_minutes("--work", args.next())?,
+                "--break" => options.durations.break_time = parse_minutes("--break", args.next())?,
+                "--long-break" => options.durations.long_break_time = parse_minutes("--long-break", args.next())?,
+                "--adjust-step" => options.durations.adjust_step = parse_minutes("--adjust-step", args.next())?,
+                "--snooze" => options.snooze = parse_minutes("--snooze", args.next())?,
+                "--long-break-every" => {
+                    let count = args.next().unwrap_or_default();
+                    let count = count.trim().parse().map_err(|_| long_break_every_error(&count))?;
+                    options.durations.long_break_every = check_long_break_every(count)?;
+                }
+                "--plan" => {
+                    let plan = args.next().ok_or("--plan requires sessions like work:50,break:10")?;
+                    let entries = plan.split(',').map(|entry| match entry.trim().split_once(':') {
+                        Some((name, minutes)) => (name.to_string(), minutes.trim().parse().unwrap_or(0)),
+                        None => (entry.trim().to_string(), 0),
+                    });
+                    options.plan = check_plan(entries)?;
+                }
+                "--auto" => options.auto = true,
+                "--auto-advance" => {
+                    let seconds = args.next().unwrap_or_default();
+                    let seconds = seconds.trim().parse().map_err(|_| auto_advance_error(&seconds))?;
+                    options.auto_advance = Some(check_auto_advance(seconds)?);
+                }
+                "--false-start" => {
+                    let seconds = args.next().unwrap_or_default();
+                    let seconds = seconds.trim().parse().map_err(|_| false_start_error(&seconds))?;
+                    options.false_start = check_false_start(seconds)?;
+                }
+                "--idle-pause" => options.idle_pause = Some(parse_minutes("--idle-pause", args.next())?),
+                "--nudge" => options.nudge = Some(parse_minutes("--nudge", args.next())?),
+                "--remind" => options.remind = Some(parse_minutes("--remind", args.next())?),
+                "--task" => {
+                    let task = args.next().filter(|task| !task.trim().is_empty());
+                    options.task = Some(task.ok_or("--task requires a description")?.trim().to_string());
+                }
+                "--title" => options.title = true,
+                "--tmux" => {
+                    options.title = true;
+                    options.tmux = true;
+                }
+                "--serial" => {
+                    let device = args.next().ok_or("--serial requires a device path")?;
+                    options.serial = Some(PathBuf::from(device));
+                }
+                "--event-log" => {
+                    let file = args.next().ok_or("--event-log requires a file path")?;
+                    options.event_log = Some(PathBuf::from(file));
+                }
+                "--webhook" => options.publish.webhook = Some(args.next().ok_or("--webhook requires a URL")?),
+                "--mqtt" => options.publish.mqtt = Some(Mqtt::parse(&args.next().ok_or("--mqtt requires a URL")?)?),
+                "--metrics-port" => {
+                    let port = args.next().unwrap_or_default();
+                    let port = port.trim().parse().map_err(|_| metrics_port_error(&port))?;
+                    options.metrics_port = Some(check_metrics_port(port)?);
+                }
+                "--command-fifo" => options.command_fifo = true,
+                "--stdio" => options.stdio = true,
+                "--working-hours" => {
+                    let range = args.next().ok_or("--working-hours requires a range like 09:00-17:30")?;
+                    options.working_hours = Some(WorkingHours::parse(&range)?);
+                }
+                "--strict-hours" => strict_hours = true,
+                "--workspace" => options.workspace = Some(parse_workspace(args.next())?),
+                "--profile" => options.profile = Some(parse_profile(args.next())?),
+                "--data-dir" => {
+                    let dir = args.next().ok_or("--data-dir requires a directory")?;
+                    options.data_dir = Some(PathBuf::from(dir));
+                }
+                "--read-only" => options.read_only = true,
+                "--demo" => options.demo = true,
+                "--json" => options.json = true,
+                "--board" => options.board = true,
+                "--rate" => options.rate = true,
+                "--notes" => options.notes = true,
+                "--diary" => options.diary = true,
+                "--coach" => options.coach = true,
+                "--strict" => options.strict = true,
+                "--daily-goal" => {
+                    let count = args.next().unwrap_or_default();
+                    let count = count.trim().parse().map_err(|_| daily_goal_error(&count))?;
+                    options.daily_goal = Some(check_daily_goal(count)?);
+                }
+                "--mute" => options.mute = true,
+                "--visual-bell" => options.visual_bell = true,
+                "--final-countdown" => options.final_countdown = FinalCountdown::parse(&args.next().unwrap_or_default())?,
+                "--dnd" => options.dnd = Desktop::ALL.to_vec(),
+                "--dry-run" => options.dry_run = Integration::ALL.to_vec(),
+                "--assistive-command" => {
+                    let program = args.next().filter(|program| !program.is_empty());
+                    options.assistive_command = Some(PathBuf::from(program.ok_or("--assistive-command requires a program")?));
+                }
+                "--notify" => {
+                    let names = args.next().unwrap_or_default();
+                    options.notify = names.split(',').filter(|name| !name.trim().is_empty()).map(Backend::parse).collect::<Result<_, _>>()?;
+                }
+                "--sound-pack" => {
+                    let pack = args.next().filter(|pack| !pack.is_empty());
+                    options.sound_pack = Some(pack.ok_or("--sound-pack requires a pack name")?);
+                }
+                "--volume" => {
+                    let volume = args.next().unwrap_or_default();
+                    let volume = volume.trim().parse().map_err(|_| volume_error(&volume))?;
+                    options.volume = Some(check_volume(volume)?);
+                }
+                "--audio-device" => {
+                    let device = args.next().filter(|device| !device.is_empty());
+                    options.audio_device = Some(device.ok_or("--audio-device requires a device name")?);
+                }
+                "--ui" => options.ui = UiMode::parse(&args.next().unwrap_or_default())?,
+                "--display" => options.display = Display::parse(&args.next().unwrap_or_default())?,
+                "--theme" => options.theme = Theme::parse(&args.next().unwrap_or_default())?,
+                "--locale" => options.locale = Locale::parse(&args.next().unwrap_or_default())?,
+                "--no-color" => options.no_color = true,
+                "--accessible" => options.accessible = true,
+                "--announce-every" => options.announce_every = parse_minutes("--announce-every", args.next())?,
+                "--status-format" => {
+                    options.status_format = Some(StatusFormat::parse(&args.next().unwrap_or_default())?);
+                }
+                "shell-init" => {
+                    let shell = args.next().unwrap_or_default();
+                    let shell = Shell::parse(&shell).ok_or("shell-init requires one of bash, zsh or fish")?;
+                    options.shell_init = Some(shell);
+                }
+                "countdown" => options.countdown = Some(parse_length(args.next())?),
+                "stopwatch" => options.stopwatch = true,
+                "tutorial" => options.tutorial = true,
+                "bench" => options.bench = true,
+                "stats" => options.stats = true,
+                "--year" => options.year = true,
+                // Everything after these belongs to them, bar --json
+                "export" => options.export = Some(parse_export(rest(args.by_ref(), &mut options.json))?),
+                "task" => options.tasks = Some(parse_task_command(rest(args.by_ref(), &mut options.json))?),
+                "history" => options.archive = Some(parse_history(rest(args.by_ref(), &mut options.json))?),
+                "debug-bundle" => options.debug_bundle = Some(parse_debug_bundle(rest(args.by_ref(), &mut options.json))?),
+                "search" => options.search = Some(parse_search(rest(args.by_ref(), &mut options.json))?),
+                "seed" => options.seed = Some(parse_seed(rest(args.by_ref(), &mut options.json))?),
+                "leaderboard" => {
+                    let (anonymous, participants): (Vec<_>, Vec<_>) =
+                        rest(args.by_ref(), &mut options.json).partition(|arg| arg == "--anonymous");
+                    options.leaderboard = Some(Leaderboard::parse(&participants, config.leaderboard.as_ref(), !anonymous.is_empty())?);
+                }
+                "daemon" => options.daemon = true,
+                "start" => options.ctl = Some("start".to_string()),
+                "wait" => options.wait = true,
+                "ctl" => {
+                    // The rest is one request, as `add-timer tea 4` is
+                    let request: Vec<_> = rest(args.by_ref(), &mut options.json).collect();
+                    if request.is_empty() {
+                        return Err("ctl requires a command, e.g. 'timeadair ctl pause'".to_string());
+                    }
+                    options.ctl = Some(request.join(" "));
+                }
+                "sounds" => {
+                    let mut rest = rest(args.by_ref(), &mut options.json);
+                    options.sounds = Some(parse_sounds(rest.next(), rest.next(), rest.next())?);
+                }
+                "permissions" => {
+                    let mut rest = rest(args.by_ref(), &mut options.json);
+                    options.permissions = Some(parse_permissions(rest.next(), rest.next(), rest.next())?);
+                }
+                "-h" | "--help" => options.help = true,
+                _ => return Err(format!("unrecognised argument '{}'\n\n{}", arg, USAGE)),
+            }
+        }
+        if options.auto && options.auto_advance.is_none() {
+            options.auto_advance = Some(AUTO_COUNTDOWN);
+        }
+        // Nothing full-screen, whatever --ui says
+        if options.accessible {
+            options.ui = UiMode::Plain;
+        }
+        if options.year && !options.stats {
+            return Err("--year goes with stats, e.g. 'timeadair stats --year'".to_string());
+        }
+        if strict_hours {
+            let hours = options.working_hours.as_mut().ok_or("--strict-hours requires --working-hours")?;
+            hours.strict = true;
+        }
+        Ok(options)
+    }
+
+    // The subcommand being run, if any, as named in --json output
+    pub fn command(&self) -> Option<&'static str> {
+        if self.shell_init.is_some() {
+            Some("shell-init")
+        } else if self.stats {
+            Some("stats")
+        } else if self.bench {
+            Some("bench")
+        } else if self.export.is_some() {
+            Some("export")
+        } else if self.archive.is_some() {
+            Some("history")
+        } else if self.debug_bundle.is_some() {
+            Some("debug-bundle")
+        } else if self.search.is_some() {
+            Some("search")
+        } else if self.seed.is_some() {
+            Some("seed")
+        } else if self.leaderboard.is_some() {
+            Some("leaderboard")
+        } else if self.tasks.is_some() {
+            Some("task")
+        } else if self.daemon {
+            Some("daemon")
+        } else if let Some(request) = &self.ctl {
+            Some(if request == "start" { "start" } else { "ctl" })
+        } else if self.wait {
+            Some("wait")
+        } else if self.sounds.is_some() {
+            Some("sounds")
+        } else if self.permissions.is_some() {
+            Some("permissions")
+        } else {
+            None
+        }
+    }
+
+    // Config values go through the same checks as the flags they mirror
+    fn from_config(config: &Config) -> Result<Options, String> {
+        let mut options = Options::default();
+        if let Some(minutes) = config.work {
+            options.durations.work_time = check_minutes("work", minutes)?;
+        }
+        if let Some(minutes) = config.break_time {
+            options.durations.break_time = check_minutes("break", minutes)?;
+        }
+        if let Some(minutes) = config.long_break {
+            options.durations.long_break_time = check_minutes("long_break", minutes)?;
+        }
+        if let Some(minutes) = config.adjust_step {
+            options.durations.adjust_step = check_minutes("adjust_step", minutes)?;
+        }
+        options.snooze = config.snooze.map_or(Ok(SNOOZE), |minutes| check_minutes("snooze", minutes))?;
+        if let Some(count) = config.long_break_every {
+            options.durations.long_break_every = check_long_break_every(count)?;
+        }
+        if let Some(seconds) = config.auto_advance {
+            options.auto_advance = Some(check_auto_advance(seconds)?);
+        }
+        if let Some(plan) = &config.plan {
+            options.plan = check_plan(plan.iter().cloned())?;
+        }
+        options.auto = config.auto.unwrap_or(false);
+        if let Some(mode) = &config.ui {
+            options.ui = UiMode::parse(mode)?;
+        }
+        if let Some(display) = &config.display {
+            options.display = Display::parse(display)?;
+        }
+        if let Some(range) = &config.working_hours {
+            options.working_hours = Some(WorkingHours::parse(range)?);
+        }
+        options.calendar = config.calendar.clone();
+        if let Some(items) = &config.checklist {
+            options.checklist = items.iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect();
+        }
+        if let Some(items) = &config.break_suggestions {
+            options.break_suggestions = Some(items.iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect());
+        }
+        if let Some(activities) = &config.break_activities {
+            options.break_activities =
+                activities.iter().map(|(name, weight)| (name.trim().to_string(), *weight)).filter(|(name, _)| !name.is_empty()).collect();
+        }
+        options.spin = config.spin.unwrap_or(false);
+        if let Some(action) = &config.on_shutdown {
+            options.on_shutdown = OnShutdown::parse(action)?;
+        }
+        if let Some(seconds) = config.false_start {
+            options.false_start = check_false_start(seconds)?;
+        }
+        if let Some(minutes) = config.idle_pause {
+            options.idle_pause = Some(check_minutes("idle_pause", minutes)?);
+        }
+        if let Some(minutes) = config.nudge {
+            options.nudge = Some(check_minutes("nudge", minutes)?);
+        }
+        if let Some(minutes) = config.nudge_idle {
+            options.nudge_idle = Some(check_minutes("nudge_idle", minutes)?);
+        }
+        if let Some(minutes) = config.remind {
+            options.remind = Some(check_minutes("remind", minutes)?);
+        }
+        options.board = config.board.unwrap_or(false);
+        options.tmux = config.tmux.unwrap_or(false);
+        options.title = config.title.unwrap_or(false) || options.tmux;
+        options.rate = config.rate.unwrap_or(false);
+        options.notes = config.notes.unwrap_or(false);
+        options.diary = config.diary.unwrap_or(false);
+        options.coach = config.coach.unwrap_or(false);
+        options.strict = config.strict.unwrap_or(false);
+        if let Some(count) = config.daily_goal {
+            options.daily_goal = Some(check_daily_goal(count)?);
+        }
+        if let Some(table) = &config.schedule {
+            options.schedule = Schedule::parse(table)?;
+            options.schedule.goals().try_for_each(|count| check_daily_goal(count).map(drop))?;
+            let today = options.schedule.today();
+            options.profile = today.and_then(|day| day.profile.clone());
+            options.daily_goal = today.and_then(|day| day.daily_goal).or(options.daily_goal);
+        }
+        for entry in config.vacation.iter().flatten() {
+            options.schedule.add_vacation(vacation::parse(entry)?);
+        }
+        options.vacation_calendar = config.vacation_calendar.clone();
+        options.mute = config.mute.unwrap_or(false);
+        options.visual_bell = config.visual_bell.unwrap_or(false);
+        if let Some(countdown) = &config.final_countdown {
+            options.final_countdown = FinalCountdown::parse(countdown)?;
+        }
+        options.low_refresh = !config.smooth_bar.unwrap_or(true);
+        options.tenths = config.tenths.unwrap_or(false);
+        options.work_art = config.work_art.clone();
+        options.break_art = config.break_art.clone();
+        if let Some(name) = &config.theme {
+            options.theme = Theme::parse(name)?;
+        }
+        options.locale = match &config.locale {
+            Some(name) => Locale::parse(name)?,
+            None => Locale::from_env(),
+        };
+        options.no_color = config.no_color.unwrap_or(false);
+        options.accessible = config.accessible.unwrap_or(false);
+        options.announce_every = config.announce_every.map_or(Ok(ANNOUNCE_EVERY), |minutes| check_minutes("announce_every", minutes))?;
+        options.assistive_command = config.assistive_command.clone();
+        options.notify = match &config.notify {
+            Some(names) => names.iter().map(|name| Backend::parse(name)).collect::<Result<_, _>>()?,
+            None => Backend::DEFAULT.to_vec(),
+        };
+        options.publish.webhook = config.webhook.clone();
+        if let Some(url) = &config.mqtt {
+            options.publish.mqtt = Some(Mqtt::parse(url)?);
+        }
+        options.metrics_port = config.metrics_port.map(check_metrics_port).transpose()?;
+        options.dnd = match &config.dnd {
+            Some(Desktops::All(true)) => Desktop::ALL.to_vec(),
+            Some(Desktops::Only(names)) => names.iter().map(|name| Desktop::parse(name)).collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+        options.dry_run = match &config.dry_run {
+            Some(Integrations::All(true)) => Integration::ALL.to_vec(),
+            Some(Integrations::Only(names)) => names.iter().map(|name| Integration::parse(name)).collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+        options.focus_shortcuts = config.macos_focus_shortcuts.clone();
+        options.hooks = Hooks {
+            on_work_start: config.on_work_start.clone(),
+            on_work_end: config.on_work_end.clone(),
+            on_break_start: config.on_break_start.clone(),
+            on_break_end: config.on_break_end.clone(),
+            on_nudge: config.on_nudge.clone(),
+            dry_run: None,
+        };
+        options.sound_pack = config.sound_pack.clone();
+        if let Some(volume) = config.volume {
+            options.volume = Some(check_volume(volume)?);
+        }
+        options.audio_device = config.audio_device.clone();
+        options.work_end_sound = config.work_end_sound.clone();
+        options.break_end_sound = config.break_end_sound.clone();
+        options.media = config.media.as_ref().map(Media::parse).transpose()?;
+        Ok(options)
+    }
+}
+
+// The workspace and profile are needed before the config file is read, so
+// they are picked out of the arguments ahead of the full parse
+pub fn workspace<I: Iterator<Item = String>>(args: I) -> Result<Option<String>, String> {
+    picked(args, "--workspace").map(parse_workspace).transpose()
+}
+
+pub fn profile<I: Iterator<Item = String>>(args: I) -> Result<Option<String>, String> {
+    picked(args, "--profile").map(parse_profile).transpose()
+}
+
+// The value after the first `flag`, if it's there; `seed` has a --profile
+// of its own, so nothing after it counts
+fn picked<I: Iterator<Item = String>>(mut args: I, flag: &str) -> Option<Option<String>> {
+    while let Some(arg) = args.next() {
+        if arg == "seed" {
+            return None;
+        }
+        if arg == flag {
+            return Some(args.next());
+        }
+    }
+    None
+}
+
+// Workspace names become directory names, so keep them to a safe set
+fn parse_workspace(value: Option<String>) -> Result<String, String> {
+    let name = value.ok_or("--workspace requires a name")?;
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!("invalid workspace name '{}', use letters, digits, '-', '_' and '.'", name));
+    }
+    Ok(name)
+}
+
+fn parse_profile(value: Option<String>) -> Result<String, String> {
+    let name = value.map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+    name.ok_or_else(|| "--profile requires a name".to_string())
+}
+
+// Session lengths are whole minutes, at least one and less than a day
+fn parse_minutes(flag: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} requires a number of minutes", flag))?;
+    let minutes: u64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} expects a whole number of minutes, got '{}'", flag, value))?;
+    check_minutes(flag, minutes)
+}
+
+// A countdown's length, as `10m`, `90s`, `1h30m` or a bare number of minutes
+fn parse_length(value: Option<String>) -> Result<u64, String> {
+    let error = || "countdown requires a length like 10m, 90s or 1h30m".to_string();
+    let value = value.ok_or_else(error)?;
+    let value = value.trim();
+    if let Ok(minutes) = value.parse::<u64>() {
+        return check_length(minutes.saturating_mul(60));
+    }
+    let (mut seconds, mut number) = (0u64, String::new());
+    for c in value.chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(error()),
+        };
+        let count: u64 = number.parse().map_err(|_| error())?;
+        seconds = seconds.saturating_add(count.saturating_mul(unit));
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(error());
+    }
+    check_length(seconds)
+}
+
+fn check_length(seconds: u64) -> Result<u64, String> {
+    if seconds == 0 {
+        return Err("countdown must be at least 1 second".to_string());
+    }
+    if seconds >= 24 * 60 * 60 {
+        return Err("countdown must be shorter than a day".to_string());
+    }
+    Ok(seconds)
+}
+
+fn check_minutes(flag: &str, minutes: u64) -> Result<u64, String> {
+    if minutes == 0 {
+        return Err(format!("{} must be at least 1 minute", flag));
+    }
+    if minutes >= 24 * 60 {
+        return Err(format!("{} must be shorter than a day", flag));
+    }
+    Ok(minutes * 60)
+}
+
+fn check_plan(entries: impl Iterator<Item = (String, u64)>) -> Result<Vec<Planned>, String> {
+    let mut plan = Vec::new();
+    for (name, minutes) in entries {
+        let seconds = check_minutes(&format!("plan's {} session", name), minutes)?;
+        plan.push(Planned::new(&name, seconds).ok_or_else(|| format!("plan sessions must be work, break or long_break, not '{}'", name))?);
+    }
+    if !plan.iter().any(|entry| matches!(entry, Planned::Work(_))) {
+        return Err("plan needs at least one work session".to_string());
+    }
+    Ok(plan)
+}
+
+fn check_long_break_every(count: u32) -> Result<u32, String> {
+    if count > 100 {
+        return Err(long_break_every_error(&count.to_string()));
+    }
+    Ok(count)
+}
+
+fn long_break_every_error(value: &str) -> String {
+    format!("long break interval must be 0-100 work sessions, not '{}'", value)
+}
+
+fn check_daily_goal(count: u32) -> Result<u32, String> {
+    if count == 0 || count > 100 {
+        return Err(daily_goal_error(&count.to_string()));
+    }
+    Ok(count)
+}
+
+fn daily_goal_error(value: &str) -> String {
+    format!("daily goal must be 1-100 pomodoros, not '{}'", value)
+}
+
+fn check_auto_advance(seconds: u64) -> Result<u64, String> {
+    if seconds > 600 {
+        return Err(auto_advance_error(&seconds.to_string()));
+    }
+    Ok(seconds)
+}
+
+fn auto_advance_error(value: &str) -> String {
+    format!("auto-advance grace period must be 0-600 seconds, not '{}'", value)
+}
+
+// The arguments left for a subcommand, with --json picked out of them
+fn rest<I: Iterator<Item = String>>(args: I, json: &mut bool) -> impl Iterator<Item = String> {
+    let rest: Vec<String> = args.collect();
+    *json |= rest.iter().any(|arg| arg == "--json");
+    rest.into_iter().filter(|arg| arg != "--json")
+}
+
+fn parse_sounds(action: Option<String>, pack: Option<String>, sound: Option<String>) -> Result<SoundsCommand, String> {
+    match action.as_deref() {
+        Some("list") => Ok(SoundsCommand::List),
+        Some("devices") => Ok(SoundsCommand::Devices),
+        Some("preview") => {
+            let pack = pack.ok_or("sounds preview requires a pack name")?;
+            let event = sound
+                .map(|name| {
+                    SoundEvent::parse(&name).ok_or_else(|| {
+                        let names: Vec<_> = SoundEvent::ALL.iter().map(|event| event.name()).collect();
+                        format!("unknown sound '{}', expected one of {}", name, names.join(", "))
+                    })
+                })
+                .transpose()?;
+            Ok(SoundsCommand::Preview { pack, event })
+        }
+        _ => Err("sounds requires 'list', 'preview <pack> [sound]' or 'devices'".to_string()),
+    }
+}
+
+fn parse_export<I: Iterator<Item = String>>(mut args: I) -> Result<Export, String> {
+    let mut export = Export::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                export.format = match args.next().as_deref() {
+                    Some("csv") => ExportFormat::Csv,
+                    Some("json") => ExportFormat::Json,
+                    Some("ics") => ExportFormat::Ics,
+                    _ => return Err("export --format must be 'csv', 'json' or 'ics'".to_string()),
+                }
+            }
+            "--since" => {
+                let date = args.next().unwrap_or_default();
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| "export --since requires a date like 2024-05-01")?;
+                export.since = Some(date);
+            }
+            _ => return Err(format!("unrecognised export argument '{}'", arg)),
+        }
+    }
+    Ok(export)
+}
+
+fn parse_permissions(action: Option<String>, name: Option<String>, extra: Option<String>) -> Result<PermissionsCommand, String> {
+    let usage = || "permissions takes 'list', 'grant <name>' or 'revoke <name>'".to_string();
+    match (action.as_deref(), name, extra) {
+        (None | Some("list"), None, None) => Ok(PermissionsCommand::List),
+        (Some("grant"), Some(name), None) => Ok(PermissionsCommand::Grant(Permission::parse(&name)?)),
+        (Some("revoke"), Some(name), None) => Ok(PermissionsCommand::Revoke(Permission::parse(&name)?)),
+        _ => Err(usage()),
+    }
+}
+
+fn parse_debug_bundle<I: Iterator<Item = String>>(mut args: I) -> Result<Option<PathBuf>, String> {
+    match (args.next().as_deref(), args.next(), args.next()) {
+        (None, _, _) => Ok(None),
+        (Some("--output"), Some(file), None) => Ok(Some(PathBuf::from(file))),
+        _ => Err("debug-bundle takes only '--output <file>'".to_string()),
+    }
+}
+
+fn parse_history<I: Iterator<Item = String>>(mut args: I) -> Result<NaiveDate, String> {
+    match (args.next().as_deref(), args.next().as_deref(), args.next(), args.next()) {
+        (Some("archive"), Some("--before"), Some(before), None) => archive::parse_before(&before),
+        _ => Err("history requires 'archive --before <YYYY[-MM[-DD]]>'".to_string()),
+    }
+}
+
+// Its --profile is the kind of history to make up, not a config profile
+fn parse_seed<I: Iterator<Item = String>>(mut args: I) -> Result<Seed, String> {
+    let mut seed = Seed::default();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_default();
+        match arg.as_str() {
+            "--days" => seed.days = value.parse().ok().filter(|days| *days > 0).ok_or("seed --days requires a number of days, e.g. 90")?,
+            "--profile" => seed.pattern = Pattern::parse(&value)?,
+            "--seed" => seed.seed = Some(value.parse().map_err(|_| "seed --seed requires a number")?),
+            _ => return Err(format!("seed takes --days, --profile and --seed, not '{}'", arg)),
+        }
+    }
+    Ok(seed)
+}
+
+fn parse_search<I: Iterator<Item = String>>(mut args: I) -> Result<Search, String> {
+    let mut search = Search::default();
+    let mut words = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--since" | "--until" => {
+                let date = args.next().unwrap_or_default();
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| format!("search {} requires a date like 2024-05-01", arg))?;
+                if arg == "--since" {
+                    search.since = Some(date);
+                } else {
+                    search.until = Some(date);
+                }
+            }
+            _ => words.push(arg),
+        }
+    }
+    search.query = words.join(" ");
+    if search.query.trim().is_empty() {
+        return Err("search requires something to look for, e.g. 'timeadair search invoice'".to_string());
+    }
+    Ok(search)
+}
+
+fn parse_task_command<I: Iterator<Item = String>>(mut args: I) -> Result<TaskCommand, String> {
+    match args.next().as_deref() {
+        Some("list") => Ok(TaskCommand::List),
+        Some("done") => {
+            let number = args.next().and_then(|number| number.trim().parse().ok()).filter(|number| *number > 0);
+            Ok(TaskCommand::Done(number.ok_or("task done requires a task number, see 'timeadair task list'")?))
+        }
+        Some("add") => {
+            let title = args.next().filter(|title| !title.trim().is_empty() && !title.starts_with("--"));
+            let title = title.ok_or("task add requires a title")?.trim().to_string();
+            let mut task = Task::new(title);
+            let mut after = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--due" => {
+                        let date = args.next().unwrap_or_default();
+                        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| "task add --due requires a date like 2024-05-01")?;
+                        task.due = Some(date);
+                    }
+                    "--estimate" => {
+                        let estimate = args.next().and_then(|count| count.trim().parse().ok()).filter(|count| *count > 0);
+                        task.estimate = Some(estimate.ok_or("task add --estimate requires a number of pomodoros")?);
+                    }
+                    "--repeat" => {
+                        let repeat = args.next().ok_or("task add --repeat requires daily, weekly or a cron expression")?;
+                        task.repeat = Some(Repeat::parse(repeat.trim()).map_err(|e| format!("task add --repeat: {}", e))?);
+                    }
+                    "--after" => {
+                        let numbers = args.next().unwrap_or_default();
+                        for number in numbers.split(',') {
+                            let number = number.trim().parse().ok().filter(|number| *number > 0);
+                            after.push(number.ok_or("task add --after requires task numbers, see 'timeadair task list'")?);
+                        }
+                    }
+                    _ => return Err(format!("unrecognised task argument '{}'", arg)),
+                }
+            }
+            Ok(TaskCommand::Add(task, after))
+        }
+        _ => Err("task requires 'add <title>', 'list' or 'done <number>'".to_string()),
+    }
+}
+
+fn check_volume(percent: u8) -> Result<u8, String> {
+    if percent > 100 {
+        return Err(volume_error(&percent.to_string()));
+    }
+    Ok(percent)
+}
+
+fn volume_error(value: &str) -> String {
+    format!("volume must be a percentage from 0 to 100, not '{}'", value)
+}
+
+// Anything longer than a few minutes is a real session, however it ended
+fn check_false_start(seconds: u64) -> Result<u64, String> {
+    if seconds > 300 {
+        return Err(false_start_error(&seconds.to_string()));
+    }
+    Ok(seconds)
+}
+
+fn false_start_error(value: &str) -> String {
+    format!("false start grace period must be 0-300 seconds, not '{}'", value)
+}
+
+fn check_metrics_port(port: u16) -> Result<u16, String> {
+    if port == 0 {
+        return Err(metrics_port_error(&port.to_string()));
+    }
+    Ok(port)
+}
+
+fn metrics_port_error(value: &str) -> String {
+    format!("metrics port must be a number from 1 to 65535, not '{}'", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|a| a.to_string()), &Config::default())
+    }
+
+    #[test]
+    fn defaults_to_25_5() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.durations, Durations::default());
+    }
+
+    #[test]
+    fn durations_from_flags() {
+        let options = parse(&["--work", "50", "--break", "10", "--adjust-step", "5"]).unwrap();
+        assert_eq!(options.durations.work_time, 50 * 60);
+        assert_eq!(options.durations.break_time, 10 * 60);
+        assert_eq!(options.durations.adjust_step, 5 * 60);
+        assert_eq!(options.snooze, SNOOZE);
+        assert_eq!(parse(&["--snooze", "10"]).unwrap().snooze, 10 * 60);
+        let accessible = parse(&["--ui", "screen", "--accessible", "--announce-every", "10"]).unwrap();
+        assert!(matches!(accessible.ui, UiMode::Plain));
+        assert_eq!(accessible.announce_every, 10 * 60);
+        assert!(parse(&["--final-countdown", "off"]).unwrap().final_countdown == FinalCountdown::Off);
+        assert_eq!(parse(&["--final-countdown", "loud"]).err().unwrap(), "final countdown must be 'off', 'tick', 'flash' or 'both', not 'loud'");
+    }
+
+    #[test]
+    fn long_breaks_from_flags() {
+        let options = parse(&["--long-break", "30", "--long-break-every", "0"]).unwrap();
+        assert_eq!(options.durations.long_break_time, 30 * 60);
+        assert_eq!(options.durations.long_break_every, 0);
+        assert!(parse(&["--long-break-every", "-1"]).is_err());
+    }
+
+    #[test]
+    fn range_errors_name_the_setting_and_value() {
+        let from_config = |text: &str| Options::parse(std::iter::empty(), &Config::parse(text).unwrap()).err().unwrap();
+        assert_eq!(from_config("long_break_every = 101"), "config.toml: long break interval must be 0-100 work sessions, not '101'");
+        assert_eq!(parse(&["--long-break-every", "many"]).err().unwrap(), "long break interval must be 0-100 work sessions, not 'many'");
+        assert_eq!(from_config("auto_advance = 601"), "config.toml: auto-advance grace period must be 0-600 seconds, not '601'");
+        assert_eq!(parse(&["--auto-advance", "soon"]).err().unwrap(), "auto-advance grace period must be 0-600 seconds, not 'soon'");
+        assert_eq!(from_config("false_start = 301"), "config.toml: false start grace period must be 0-300 seconds, not '301'");
+        assert_eq!(parse(&["--false-start", "quick"]).err().unwrap(), "false start grace period must be 0-300 seconds, not 'quick'");
+        assert_eq!(from_config("volume = 150"), "config.toml: volume must be a percentage from 0 to 100, not '150'");
+        assert_eq!(parse(&["--volume", "loud"]).err().unwrap(), "volume must be a percentage from 0 to 100, not 'loud'");
+        assert_eq!(from_config("daily_goal = 0"), "config.toml: daily goal must be 1-100 pomodoros, not '0'");
+        assert_eq!(parse(&["--daily-goal", "lots"]).err().unwrap(), "daily goal must be 1-100 pomodoros, not 'lots'");
+        assert_eq!(from_config("metrics_port = 0"), "config.toml: metrics port must be a number from 1 to 65535, not '0'");
+        assert_eq!(parse(&["--metrics-port", "http"]).err().unwrap(), "metrics port must be a number from 1 to 65535, not 'http'");
+    }
+
+    #[test]
+    fn flags_override_config() {
+        let config = Config::parse("work = 50\nbreak = 10\n").unwrap();
+        let options = Options::parse(["--work", "30"].iter().map(|a| a.to_string()), &config).unwrap();
+        assert_eq!(options.durations.work_time, 30 * 60);
+        assert_eq!(options.durations.break_time, 10 * 60);
+        assert!(Options::parse(std::iter::empty(), &Config::parse("work = 0\n").unwrap()).is_err());
+    }
+
+    #[test]
+    fn auto_uses_a_short_countdown() {
+        assert_eq!(parse(&["--auto"]).unwrap().auto_advance, Some(AUTO_COUNTDOWN));
+        assert_eq!(parse(&["--auto", "--auto-advance", "10"]).unwrap().auto_advance, Some(10));
+        assert!(!parse(&["--auto-advance", "10"]).unwrap().auto);
+    }
+
+    #[test]
+    fn sounds_subcommand() {
+        assert_eq!(parse(&["sounds", "list"]).unwrap().sounds, Some(SoundsCommand::List));
+        assert_eq!(
+            parse(&["sounds", "preview", "rain", "tick"]).unwrap().sounds,
+            Some(SoundsCommand::Preview { pack: "rain".to_string(), event: Some(SoundEvent::Tick) })
+        );
+        assert!(parse(&["sounds", "preview", "rain", "thunder"]).is_err());
+        assert!(parse(&["sounds"]).is_err());
+        assert_eq!(parse(&["--volume", "40"]).unwrap().volume, Some(40));
+        assert!(parse(&["--volume", "101"]).is_err());
+    }
+
+    #[test]
+    fn export_subcommand() {
+        assert_eq!(parse(&["export"]).unwrap().export, Some(Export::default()));
+        let export = parse(&["export", "--format", "json", "--since", "2024-05-01"]).unwrap().export.unwrap();
+        assert_eq!(export.format, ExportFormat::Json);
+        assert_eq!(export.since, NaiveDate::from_ymd_opt(2024, 5, 1));
+        assert!(parse(&["export", "--since", "May"]).is_err());
+        assert!(parse(&["export", "--work", "50"]).is_err());
+    }
+
+    #[test]
+    fn history_subcommand() {
+        let options = parse(&["history", "archive", "--before", "2023", "--json"]).unwrap();
+        assert_eq!((options.archive, options.command()), (NaiveDate::from_ymd_opt(2023, 1, 1), Some("history")));
+        assert!(parse(&["history", "archive"]).is_err());
+        assert!(parse(&["history", "archive", "--before", "2023", "now"]).is_err());
+    }
+
+    #[test]
+    fn search_subcommand() {
+        let search = parse(&["search", "acme", "invoice", "--until", "2024-05-01", "--json"]).unwrap();
+        assert!(search.json);
+        assert_eq!(search.search.unwrap(), Search { query: "acme invoice".to_string(), since: None, until: NaiveDate::from_ymd_opt(2024, 5, 1) });
+        assert!(parse(&["search"]).is_err());
+        assert!(parse(&["search", "x", "--since", "May"]).is_err());
+    }
+
+    #[test]
+    fn json_names_the_subcommand() {
+        let options = parse(&["stats", "--json"]).unwrap();
+        assert!(options.json);
+        assert!(parse(&["stats", "--year", "--json"]).unwrap().year);
+        assert_eq!(parse(&["bench"]).unwrap().command(), Some("bench"));
+        let seed = parse(&["seed", "--days", "90", "--profile", "steady"]).unwrap().seed.unwrap();
+        assert_eq!((seed.days, seed.pattern, seed.seed), (90, Pattern::Steady, None));
+        assert!(parse(&["seed", "--days", "0"]).is_err());
+        assert!(parse(&["--year"]).is_err());
+        assert_eq!(options.command(), Some("stats"));
+        assert_eq!(parse(&["--json", "ctl", "status"]).unwrap().command(), Some("ctl"));
+        assert_eq!(parse(&["ctl", "add-timer", "tea", "4"]).unwrap().ctl.as_deref(), Some("add-timer tea 4"));
+        assert_eq!(parse(&["start"]).unwrap().command(), Some("start"));
+        assert_eq!(parse(&["wait"]).unwrap().command(), Some("wait"));
+        assert!(parse(&["export", "--since", "2024-05-01", "--json"]).unwrap().json);
+        assert!(parse(&["sounds", "list", "--json"]).unwrap().json);
+        assert_eq!(parse(&[]).unwrap().command(), None);
+    }
+
+    #[test]
+    fn countdown_lengths() {
+        for (length, seconds) in [("10m", 600), ("90s", 90), ("1h30m", 5400), ("5", 300)] {
+            assert_eq!(parse(&["countdown", length]).unwrap().countdown, Some(seconds), "{}", length);
+        }
+        for bad in ["", "0s", "10x", "m", "24h", "10m5"] {
+            assert!(parse(&["countdown", bad]).is_err(), "accepted {:?}", bad);
+        }
+        assert!(parse(&["stopwatch"]).unwrap().stopwatch);
+    }
+
+    #[test]
+    fn task_subcommand() {
+        let task = parse(&["task", "add", "Report", "--due", "2024-05-10", "--estimate", "8", "--json"]).unwrap();
+        assert!(task.json);
+        assert_eq!(
+            task.tasks,
+            Some(TaskCommand::Add(Task { due: "2024-05-10".parse().ok(), estimate: Some(8), ..Task::new("Report".to_string()) }, Vec::new()))
+        );
+        let standup = parse(&["task", "add", "Standup", "--repeat", "0 9 * * 1-5", "--after", "1,3"]).unwrap();
+        assert_eq!(
+            standup.tasks,
+            Some(TaskCommand::Add(Task { repeat: Repeat::parse("0 9 * * 1-5").ok(), ..Task::new("Standup".to_string()) }, vec![1, 3]))
+        );
+        assert_eq!(parse(&["task", "done", "2"]).unwrap().tasks, Some(TaskCommand::Done(2)));
+        for bad in [&["task"][..], &["task", "add"], &["task", "done", "0"], &["task", "add", "x", "--estimate", "0"], &["task", "add", "x", "--repeat", "hourly"], &["task", "add", "x", "--after", "0"]] {
+            assert!(parse(bad).is_err(), "accepted {:?}", bad);
+        }
+    }
+
+    #[test]
+    fn false_start_from_config_or_flag() {
+        let config = Config::parse("false_start = 20\n").unwrap();
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().false_start, 20);
+        assert_eq!(parse(&["--false-start", "5"]).unwrap().false_start, 5);
+        assert!(parse(&["--false-start", "301"]).is_err());
+    }
+
+    #[test]
+    fn plans_from_config_or_flag() {
+        let config = Config::parse("plan = [[\"work\", 50], [\"break\", 10], [\"long_break\", 30]]\n").unwrap();
+        let plan = [Planned::Work(3000), Planned::Break(600), Planned::LongBreak(1800)];
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().plan, plan);
+        assert_eq!(parse(&["--plan", "work:50, break:10,long_break:30"]).unwrap().plan, plan);
+        for bad in ["break:10", "work:50,nap:20", "work:0", "work"] {
+            assert!(parse(&["--plan", bad]).is_err(), "accepted {:?}", bad);
+        }
+    }
+
+    #[test]
+    fn dnd_for_every_desktop_or_some() {
+        let config = Config::parse("dnd = [\"gnome\", \"macos\"]\n").unwrap();
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().dnd, [Desktop::Gnome, Desktop::MacOs]);
+        let config = Config::parse("dnd = true\n").unwrap();
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().dnd, Desktop::ALL);
+        assert_eq!(parse(&["--dnd"]).unwrap().dnd, Desktop::ALL);
+        assert_eq!(parse(&["permissions"]).unwrap().permissions, Some(PermissionsCommand::List));
+        assert_eq!(parse(&["permissions", "revoke", "dnd"]).unwrap().permissions, Some(PermissionsCommand::Revoke(Permission::Dnd)));
+        assert!(parse(&["permissions", "grant"]).is_err());
+        assert!(Options::parse(std::iter::empty(), &Config::parse("dnd = [\"windows\"]\n").unwrap()).is_err());
+    }
+
+    #[test]
+    fn dry_run_for_every_integration_or_some() {
+        let config = Config::parse("dry_run = [\"webhook\", \"hooks\"]\n").unwrap();
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().dry_run, [Integration::Webhook, Integration::Hooks]);
+        assert_eq!(parse(&["--dry-run"]).unwrap().dry_run, Integration::ALL);
+        assert!(Options::parse(std::iter::empty(), &Config::parse("dry_run = [\"slack\"]\n").unwrap()).is_err());
+    }
+
+    #[test]
+    fn notify_picks_the_backends() {
+        assert_eq!(parse(&[]).unwrap().notify, Backend::DEFAULT);
+        assert_eq!(parse(&["--notify", "desktop,sound"]).unwrap().notify, [Backend::Desktop, Backend::Sound]);
+        let config = Config::parse("notify = [\"bell\", \"desktop\"]\n").unwrap();
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().notify, [Backend::Bell, Backend::Desktop]);
+        assert!(parse(&["--notify", "pushover"]).is_err());
+    }
+
+    #[test]
+    fn idle_pause_in_minutes() {
+        let config = Config::parse("idle_pause = 5\n").unwrap();
+        assert_eq!(Options::parse(std::iter::empty(), &config).unwrap().idle_pause, Some(300));
+        assert_eq!(parse(&["--idle-pause", "2"]).unwrap().idle_pause, Some(120));
+        assert_eq!(parse(&[]).unwrap().idle_pause, None);
+        assert!(parse(&["--idle-pause", "0"]).is_err());
+
+        let config = Config::parse("nudge = 55\nnudge_idle = 10\n").unwrap();
+        let options = Options::parse(std::iter::empty(), &config).unwrap();
+        assert_eq!((options.nudge, options.nudge_idle), (Some(3300), Some(600)));
+        assert_eq!(parse(&["--nudge", "30"]).unwrap().nudge, Some(1800));
+        assert_eq!(parse(&["--remind", "30"]).unwrap().remind, Some(1800));
+        assert_eq!(Options::parse(std::iter::empty(), &Config::parse("remind = 45\n").unwrap()).unwrap().remind, Some(2700));
+    }
+
+    #[test]
+    fn workspace_names_are_safe_paths() {
+        assert_eq!(parse(&["--workspace", "client-a"]).unwrap().workspace.as_deref(), Some("client-a"));
+        assert_eq!(parse(&["stats", "--profile", "deepwork"]).unwrap().profile.as_deref(), Some("deepwork"));
+        assert_eq!(profile(["stats", "--profile", "admin"].map(String::from).into_iter()), Ok(Some("admin".to_string())));
+        assert_eq!(profile(["seed", "--profile", "steady"].map(String::from).into_iter()), Ok(None));
+        assert!(parse(&["--profile"]).is_err());
+        for bad in ["", "..", "../x", "a/b", ".hidden"] {
+            assert!(parse(&["--workspace", bad]).is_err(), "accepted {:?}", bad);
+        }
+    }
+
+    #[test]
+    fn rejects_nonsensical_durations() {
+        for bad in [&["--work", "0"][..], &["--break", "-5"], &["--work", "ten"], &["--work", "1440"], &["--break"]] {
+            assert!(parse(bad).is_err(), "accepted {:?}", bad);
+        }
+    }
+}
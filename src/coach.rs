@@ -0,0 +1,125 @@
+//! Gentle suggestions for `--coach`, shown above the next prompt.
+//!
+//! The rules only look at today's sessions, including what was already in
+//! the history when the timer started, and each tip is given once per
+//! pattern rather than at every prompt:
+//!
+//! - three work sessions in a row were skipped or reset
+//! - every break today was skipped or declined, at least three of them
+//! - pauses kept a session going at least half as long again as planned
+
+use crate::history::{Outcome, SessionRecord};
+use crate::scheduler::Phase;
+
+pub const ABANDONED: &str = "Three sessions in a row were cut short. A shorter work session might be easier to finish.";
+pub const NO_BREAKS: &str = "You haven't taken a break today. A few minutes away from the screen helps the next session.";
+pub const OVERRUN: &str = "Pauses made that session run well over. It can help to stop and start a fresh one instead.";
+
+#[derive(Debug, Default)]
+pub struct Coach {
+    // Work sessions skipped or reset since the last one that completed
+    abandoned: u32,
+    breaks_skipped: u32,
+    breaks_taken: u32,
+    told_no_breaks: bool,
+    tip: Option<&'static str>,
+}
+
+impl Coach {
+    // `today` is the earlier history from the current day, oldest first
+    pub fn new(today: &[SessionRecord]) -> Coach {
+        let mut coach = Coach::default();
+        for record in today {
+            let phase = if record.session == "work" { Phase::Work } else { Phase::Break };
+            coach.session_ended(phase, record.outcome, record.planned.unwrap_or(record.duration), record.paused);
+        }
+        // Old patterns aren't worth bringing up at the first prompt
+        coach.tip = None;
+        coach
+    }
+
+    pub fn session_ended(&mut self, phase: Phase, outcome: Outcome, planned: u64, paused: u64) {
+        match (phase, outcome) {
+            (_, Outcome::Quit) => {}
+            (Phase::Work, Outcome::Completed) => self.abandoned = 0,
+            (Phase::Work, _) => {
+                self.abandoned += 1;
+                if self.abandoned == 3 {
+                    self.abandoned = 0;
+                    self.tip = Some(ABANDONED);
+                }
+            }
+            (_, Outcome::Completed) => self.breaks_taken += 1,
+            _ => self.break_skipped(),
+        }
+        if outcome == Outcome::Completed && paused * 2 >= planned && planned > 0 {
+            self.tip = Some(OVERRUN);
+        }
+    }
+
+    // A break that was turned down at the prompt counts as skipped
+    pub fn break_skipped(&mut self) {
+        self.breaks_skipped += 1;
+        if self.breaks_skipped >= 3 && self.breaks_taken == 0 && !self.told_no_breaks {
+            self.told_no_breaks = true;
+            self.tip = Some(NO_BREAKS);
+        }
+    }
+
+    pub fn tip(&mut self) -> Option<&'static str> {
+        self.tip.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn three_abandoned_sessions_in_a_row() {
+        let mut coach = Coach::default();
+        coach.session_ended(Phase::Work, Outcome::Skipped, 1500, 0);
+        coach.session_ended(Phase::Work, Outcome::Completed, 1500, 0);
+        coach.session_ended(Phase::Work, Outcome::Skipped, 1500, 0);
+        coach.session_ended(Phase::Work, Outcome::Reset, 1500, 0);
+        assert_eq!(coach.tip(), None);
+        coach.session_ended(Phase::Work, Outcome::Skipped, 1500, 0);
+        assert_eq!(coach.tip(), Some(ABANDONED));
+        assert_eq!(coach.tip(), None);
+    }
+
+    #[test]
+    fn skipped_breaks_once_a_day() {
+        let mut coach = Coach::default();
+        coach.session_ended(Phase::Break, Outcome::Skipped, 300, 0);
+        coach.break_skipped();
+        assert_eq!(coach.tip(), None);
+        coach.break_skipped();
+        assert_eq!(coach.tip(), Some(NO_BREAKS));
+        coach.break_skipped();
+        assert_eq!(coach.tip(), None);
+    }
+
+    #[test]
+    fn long_pauses_and_earlier_history() {
+        let mut coach = Coach::default();
+        coach.session_ended(Phase::Work, Outcome::Completed, 1500, 600);
+        assert_eq!(coach.tip(), None);
+        coach.session_ended(Phase::Work, Outcome::Completed, 1500, 750);
+        assert_eq!(coach.tip(), Some(OVERRUN));
+
+        let skipped = SessionRecord {
+            session: "work".to_string(),
+            duration: 60,
+            outcome: Outcome::Skipped,
+            planned: Some(1500),
+            ..Default::default()
+        };
+        let mut coach = Coach::new(&[skipped.clone(), skipped.clone(), skipped.clone()]);
+        assert_eq!(coach.tip(), None);
+        coach.session_ended(Phase::Work, Outcome::Skipped, 1500, 0);
+        coach.session_ended(Phase::Work, Outcome::Skipped, 1500, 0);
+        coach.session_ended(Phase::Work, Outcome::Skipped, 1500, 0);
+        assert_eq!(coach.tip(), Some(ABANDONED));
+    }
+}
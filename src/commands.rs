@@ -0,0 +1,80 @@
+use crossterm::event::KeyCode;
+
+use crate::scheduler::Event;
+use crate::timers;
+
+// Actions that can be applied to a running session, whichever input they
+// arrive from
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Start,
+    Decline,
+    Quit,
+    Reset,
+    Pause,
+    Resume,
+    TogglePause,
+    Skip,
+    AddTime,
+    SubtractTime,
+    // Only means something to the stopwatch
+    Lap,
+    // A countdown of its own alongside the session, with its name and
+    // seconds, see `timers`
+    AddTimer(String, u64),
+    // Sent on a shutdown signal rather than by anyone
+    Shutdown,
+}
+
+impl Command {
+    pub fn parse(text: &str) -> Option<Command> {
+        // The name keeps its case
+        if let Some(timer) = text.trim().strip_prefix("add-timer ") {
+            return timers::parse(timer).ok().map(|(name, seconds)| Command::AddTimer(name, seconds));
+        }
+        match text.trim().to_lowercase().as_str() {
+            "start" => Some(Command::Start),
+            "decline" => Some(Command::Decline),
+            "quit" | "q" => Some(Command::Quit),
+            "reset" | "r" => Some(Command::Reset),
+            "pause" => Some(Command::Pause),
+            "resume" => Some(Command::Resume),
+            "toggle-pause" | "p" => Some(Command::TogglePause),
+            "skip" | "s" => Some(Command::Skip),
+            "add-time" | "+" => Some(Command::AddTime),
+            "subtract-time" | "-" => Some(Command::SubtractTime),
+            "lap" | "l" => Some(Command::Lap),
+            _ => None,
+        }
+    }
+
+    pub fn from_key(code: KeyCode) -> Option<Command> {
+        match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => Some(Command::Quit),
+            KeyCode::Char('r') | KeyCode::Char('R') => Some(Command::Reset),
+            KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Char(' ') => Some(Command::TogglePause),
+            KeyCode::Char('s') | KeyCode::Char('S') => Some(Command::Skip),
+            KeyCode::Char('+') | KeyCode::Char('=') => Some(Command::AddTime),
+            KeyCode::Char('-') => Some(Command::SubtractTime),
+            KeyCode::Char('l') | KeyCode::Char('L') => Some(Command::Lap),
+            _ => None,
+        }
+    }
+
+    // The scheduler event this command means for a running session, if any
+    pub fn session_event(self, paused: bool) -> Option<Event> {
+        match self {
+            Command::Quit => Some(Event::Quit),
+            Command::Shutdown => Some(Event::Interrupt),
+            Command::Reset => Some(Event::Reset),
+            Command::Pause => Some(Event::Pause),
+            Command::Resume => Some(Event::Resume),
+            Command::TogglePause if paused => Some(Event::Resume),
+            Command::TogglePause => Some(Event::Pause),
+            Command::Skip => Some(Event::Skip),
+            Command::AddTime => Some(Event::Extend),
+            Command::SubtractTime => Some(Event::Shorten),
+            Command::Start | Command::Decline | Command::Lap | Command::AddTimer(..) => None,
+        }
+    }
+}
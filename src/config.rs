@@ -0,0 +1,394 @@
+//! Settings file at `~/.config/timeadair/config.toml`.
+//!
+//! Every key is optional and mirrors a command line flag, which wins over
+//! the file when both are given:
+//!
+//! ```toml
+//! work = 50              # minutes
+//! break = 10
+//! long_break = 30
+//! long_break_every = 4
+//! plan = [["work", 50], ["break", 10], ["work", 25], ["long_break", 30]]
+//! adjust_step = 2        # minutes
+//! snooze = 10            # minutes 'z' adds before a break
+//! auto = true
+//! auto_advance = 10      # seconds
+//! ui = "plain"
+//! display = "big"        # the time left in block numerals
+//! working_hours = "09:00-17:30"
+//! calendar = "https://cloud.example/dav/calendars/me/work?export"   # warns of meetings
+//! strict_hours = true
+//! false_start = 10       # seconds
+//! idle_pause = 5         # minutes
+//! on_shutdown = "resume" # on SIGTERM, keep the session for next time
+//! nudge = 55             # minutes at the keyboard with no session, in daemon mode
+//! nudge_idle = 5         # minutes away that count as a break from it
+//! remind = 30            # minutes in working_hours with no session, in daemon mode
+//! board = true           # pick tasks from a todo/doing/done board
+//! checklist = ["Phone on silent?", "Slack snoozed?", "Water?"]
+//! break_suggestions = ["Refill the kettle", "Feed the cat"]   # [] for none
+//! break_activities = [["Walk", 3], ["Stretch", 2], ["Water", 1]]   # one picked per break
+//! spin = true            # spin through them first
+//! rate = true
+//! notes = true           # a line on what each work session got done
+//! diary = true           # and a diary of them, in the data directory
+//! coach = true
+//! strict = true          # typed phrase to give up on work, no skipping breaks
+//! daily_goal = 8         # pomodoros, shown as 3/8 today
+//! mute = true
+//! visual_bell = true
+//! final_countdown = "both"   # or "tick", "flash"; the last ten seconds
+//! smooth_bar = false     # move the bar once a second, for slow terminals
+//! tenths = true          # tenths of a second in the final minute
+//! work_art = "/home/me/art/tomato.txt"   # in the header, in place of the title
+//! break_art = "/home/me/art/cup.txt"
+//! theme = "light"        # or "default", "high-contrast"
+//! locale = "gd"          # or "en"; LANG otherwise
+//! no_color = true
+//! accessible = true      # for screen readers, with announce_every = 5 minutes
+//! title = true           # time left in the terminal title
+//! tmux = true            # and in #{@timeadair} for the tmux status line
+//! assistive_command = "/usr/local/bin/brltty-notify"
+//! notify = ["bell", "desktop"]   # on completion; bell, sound and command unless set
+//! webhook = "https://home.example/hooks/focus"
+//! mqtt = "mqtt://broker.local/office/focus"
+//! metrics_port = 9898    # Prometheus text at http://127.0.0.1:9898/metrics
+//! dry_run = ["webhook"]  # or true; logged to dry-run.log, not sent
+//! dnd = true             # or only on some desktops, e.g. ["gnome", "macos"]
+//! macos_focus_shortcuts = ["Work focus on", "Work focus off"]
+//! on_work_start = "makoctl mode -a do-not-disturb"
+//! on_work_end = "makoctl mode -r do-not-disturb"
+//! on_nudge = 'notify-send Tìmeadair "$TIMEADAIR_MESSAGE"'
+//! sound_pack = "rain"    # a directory in ~/.config/timeadair/sounds
+//! volume = 60            # percent
+//! audio_device = "alsa_output.usb-headset"
+//! work_end_sound = "/home/me/sounds/gong.ogg"
+//! break_end_sound = "/home/me/sounds/bell.wav"
+//! vacation = ["2024-12-24", "2024-12-27..2025-01-02"]   # days off for streaks
+//! vacation_calendar = "https://example.com/holidays.ics"
+//!
+//! [profiles.deepwork]
+//! plan = [["work", 90], ["break", 20]]
+//! work_end_sound = "/home/me/sounds/gong.ogg"
+//! on_work_start = "makoctl mode -a do-not-disturb"
+//!
+//! [profiles.admin]
+//! work = 15
+//! break = 3
+//!
+//! [schedule]             # which profile each day, and its goal
+//! mon-thu = { profile = "deepwork", daily_goal = 10 }
+//! fri = { profile = "admin", daily_goal = 6 }
+//!
+//! [leaderboard]          # histories for `timeadair leaderboard`
+//! alice = "~/Sync/team/alice"
+//! bob = "~/Sync/team/bob"
+//!
+//! [media]                # played or paused as sessions start, see `media`
+//! player = "spotify"
+//! work = "spotify:playlist:37i9dQZF1DX8NTLI2TtZa6"
+//! break = "pause"
+//! ```
+//!
+//! With `--workspace <name>`, `workspaces/<name>.toml` next to it is read as
+//! well and its keys win over the main file. With `--profile <name>`, the
+//! keys in that `[profiles.<name>]` table win over both; any key can go in
+//! one, bar `profiles` itself.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::paths;
+
+// `dnd = true` for whichever desktop this is, or a list of desktops
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Desktops {
+    All(bool),
+    Only(Vec<String>),
+}
+
+// `dry_run = true` for every integration, or a list of them
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Integrations {
+    All(bool),
+    Only(Vec<String>),
+}
+
+// `[media]`: what each kind of session does to the music
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MediaConfig {
+    pub player: Option<String>,
+    pub work: Option<String>,
+    #[serde(rename = "break")]
+    pub rest: Option<String>,
+    pub long_break: Option<String>,
+}
+
+// One entry in `[schedule]`
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledDay {
+    pub profile: Option<String>,
+    pub daily_goal: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub work: Option<u64>,
+    #[serde(rename = "break")]
+    pub break_time: Option<u64>,
+    pub long_break: Option<u64>,
+    pub long_break_every: Option<u32>,
+    // (session, minutes) pairs, run in order and then from the top again
+    pub plan: Option<Vec<(String, u64)>>,
+    pub adjust_step: Option<u64>,
+    pub snooze: Option<u64>,
+    pub auto: Option<bool>,
+    pub auto_advance: Option<u64>,
+    pub ui: Option<String>,
+    pub display: Option<String>,
+    pub working_hours: Option<String>,
+    pub strict_hours: Option<bool>,
+    // An ICS file or URL; only settable here
+    pub calendar: Option<String>,
+    pub false_start: Option<u64>,
+    pub idle_pause: Option<u64>,
+    // "record" or "resume"; only settable here
+    pub on_shutdown: Option<String>,
+    pub nudge: Option<u64>,
+    // Only settable here
+    pub nudge_idle: Option<u64>,
+    pub remind: Option<u64>,
+    pub board: Option<bool>,
+    // Asked before every work session; only settable here
+    pub checklist: Option<Vec<String>>,
+    // Only settable here
+    pub break_suggestions: Option<Vec<String>>,
+    // Only settable here
+    pub break_activities: Option<Vec<(String, u32)>>,
+    // Only settable here
+    pub spin: Option<bool>,
+    pub rate: Option<bool>,
+    pub notes: Option<bool>,
+    pub diary: Option<bool>,
+    pub coach: Option<bool>,
+    pub strict: Option<bool>,
+    pub daily_goal: Option<u32>,
+    pub mute: Option<bool>,
+    pub visual_bell: Option<bool>,
+    pub final_countdown: Option<String>,
+    // Only settable here
+    pub smooth_bar: Option<bool>,
+    // Only settable here
+    pub tenths: Option<bool>,
+    // Only settable here
+    pub work_art: Option<PathBuf>,
+    // Only settable here
+    pub break_art: Option<PathBuf>,
+    pub theme: Option<String>,
+    pub locale: Option<String>,
+    pub no_color: Option<bool>,
+    pub accessible: Option<bool>,
+    pub announce_every: Option<u64>,
+    pub title: Option<bool>,
+    pub tmux: Option<bool>,
+    pub assistive_command: Option<PathBuf>,
+    pub notify: Option<Vec<String>>,
+    pub webhook: Option<String>,
+    pub mqtt: Option<String>,
+    pub metrics_port: Option<u16>,
+    pub dry_run: Option<Integrations>,
+    // Shell commands run as sessions start and end; only settable here
+    pub dnd: Option<Desktops>,
+    pub macos_focus_shortcuts: Option<(String, String)>,
+    pub on_work_start: Option<String>,
+    pub on_work_end: Option<String>,
+    pub on_break_start: Option<String>,
+    pub on_break_end: Option<String>,
+    pub on_nudge: Option<String>,
+    pub sound_pack: Option<String>,
+    pub volume: Option<u8>,
+    pub audio_device: Option<String>,
+    // Played when a session of each kind runs to completion; only settable here
+    pub work_end_sound: Option<PathBuf>,
+    pub break_end_sound: Option<PathBuf>,
+    // Only settable here
+    pub media: Option<MediaConfig>,
+    // Named sets of the keys above, picked with --profile
+    pub profiles: Option<BTreeMap<String, Config>>,
+    // Keyed by days such as "mon-thu"; only settable here
+    pub schedule: Option<BTreeMap<String, ScheduledDay>>,
+    // Each participant's data directory or history file; only settable here
+    pub leaderboard: Option<BTreeMap<String, String>>,
+    pub vacation: Option<Vec<String>>,
+    // An ICS file or URL
+    pub vacation_calendar: Option<String>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("config.toml"))
+}
+
+pub fn workspace_path(workspace: &str) -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("workspaces").join(format!("{}.toml", workspace)))
+}
+
+impl Config {
+    // A missing file is the same as an empty one
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text).map_err(|e| format!("Invalid config file {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Unable to read config file {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    // Keys set here win, anything else comes from `base`
+    pub fn or(self, base: Config) -> Config {
+        Config {
+            work: self.work.or(base.work),
+            break_time: self.break_time.or(base.break_time),
+            long_break: self.long_break.or(base.long_break),
+            long_break_every: self.long_break_every.or(base.long_break_every),
+            plan: self.plan.or(base.plan),
+            adjust_step: self.adjust_step.or(base.adjust_step),
+            snooze: self.snooze.or(base.snooze),
+            auto: self.auto.or(base.auto),
+            auto_advance: self.auto_advance.or(base.auto_advance),
+            ui: self.ui.or(base.ui),
+            display: self.display.or(base.display),
+            working_hours: self.working_hours.or(base.working_hours),
+            strict_hours: self.strict_hours.or(base.strict_hours),
+            calendar: self.calendar.or(base.calendar),
+            false_start: self.false_start.or(base.false_start),
+            idle_pause: self.idle_pause.or(base.idle_pause),
+            on_shutdown: self.on_shutdown.or(base.on_shutdown),
+            nudge: self.nudge.or(base.nudge),
+            nudge_idle: self.nudge_idle.or(base.nudge_idle),
+            remind: self.remind.or(base.remind),
+            board: self.board.or(base.board),
+            checklist: self.checklist.or(base.checklist),
+            break_suggestions: self.break_suggestions.or(base.break_suggestions),
+            break_activities: self.break_activities.or(base.break_activities),
+            spin: self.spin.or(base.spin),
+            rate: self.rate.or(base.rate),
+            notes: self.notes.or(base.notes),
+            diary: self.diary.or(base.diary),
+            coach: self.coach.or(base.coach),
+            strict: self.strict.or(base.strict),
+            daily_goal: self.daily_goal.or(base.daily_goal),
+            mute: self.mute.or(base.mute),
+            visual_bell: self.visual_bell.or(base.visual_bell),
+            final_countdown: self.final_countdown.or(base.final_countdown),
+            smooth_bar: self.smooth_bar.or(base.smooth_bar),
+            tenths: self.tenths.or(base.tenths),
+            work_art: self.work_art.or(base.work_art),
+            break_art: self.break_art.or(base.break_art),
+            theme: self.theme.or(base.theme),
+            locale: self.locale.or(base.locale),
+            no_color: self.no_color.or(base.no_color),
+            accessible: self.accessible.or(base.accessible),
+            announce_every: self.announce_every.or(base.announce_every),
+            title: self.title.or(base.title),
+            tmux: self.tmux.or(base.tmux),
+            assistive_command: self.assistive_command.or(base.assistive_command),
+            notify: self.notify.or(base.notify),
+            webhook: self.webhook.or(base.webhook),
+            mqtt: self.mqtt.or(base.mqtt),
+            metrics_port: self.metrics_port.or(base.metrics_port),
+            dry_run: self.dry_run.or(base.dry_run),
+            dnd: self.dnd.or(base.dnd),
+            macos_focus_shortcuts: self.macos_focus_shortcuts.or(base.macos_focus_shortcuts),
+            on_work_start: self.on_work_start.or(base.on_work_start),
+            on_work_end: self.on_work_end.or(base.on_work_end),
+            on_break_start: self.on_break_start.or(base.on_break_start),
+            on_break_end: self.on_break_end.or(base.on_break_end),
+            on_nudge: self.on_nudge.or(base.on_nudge),
+            sound_pack: self.sound_pack.or(base.sound_pack),
+            volume: self.volume.or(base.volume),
+            audio_device: self.audio_device.or(base.audio_device),
+            work_end_sound: self.work_end_sound.or(base.work_end_sound),
+            break_end_sound: self.break_end_sound.or(base.break_end_sound),
+            media: self.media.or(base.media),
+            profiles: self.profiles.or(base.profiles),
+            schedule: self.schedule.or(base.schedule),
+            leaderboard: self.leaderboard.or(base.leaderboard),
+            vacation: self.vacation.or(base.vacation),
+            vacation_calendar: self.vacation_calendar.or(base.vacation_calendar),
+        }
+    }
+
+    // The named profile's keys over the rest
+    pub fn with_profile(mut self, name: &str) -> Result<Config, String> {
+        let mut profiles = self.profiles.take().unwrap_or_default();
+        let names: Vec<_> = profiles.keys().cloned().collect();
+        let profile = profiles.remove(name).ok_or_else(|| {
+            if names.is_empty() {
+                format!("no profile named '{}', add a [profiles.{}] table to the config file", name, name)
+            } else {
+                format!("no profile named '{}', the config file has {}", name, names.join(", "))
+            }
+        })?;
+        if profile.profiles.is_some() {
+            return Err(format!("profile '{}' can't have profiles of its own", name));
+        }
+        Ok(profile.or(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_every_key() {
+        let config = Config::parse("work = 50\nbreak = 10\nlong_break_every = 0\nui = \"plain\"\n").unwrap();
+        assert_eq!(config.work, Some(50));
+        assert_eq!(config.break_time, Some(10));
+        assert_eq!(config.long_break_every, Some(0));
+        assert_eq!(config.ui.as_deref(), Some("plain"));
+        assert_eq!(config.long_break, None);
+        assert_eq!(config.checklist, None);
+    }
+
+    #[test]
+    fn reads_the_checklist() {
+        let config = Config::parse("checklist = [\"Water?\", \"Phone on silent?\"]\n").unwrap();
+        assert_eq!(config.checklist.unwrap(), ["Water?", "Phone on silent?"]);
+    }
+
+    #[test]
+    fn workspace_keys_win() {
+        let base = Config::parse("work = 50\nbreak = 10\n").unwrap();
+        let config = Config::parse("work = 30\n").unwrap().or(base);
+        assert_eq!((config.work, config.break_time), (Some(30), Some(10)));
+    }
+
+    #[test]
+    fn profile_keys_win() {
+        let config = Config::parse("work = 50\nbreak = 10\n[profiles.admin]\nwork = 15\non_work_end = \"true\"\n").unwrap();
+        let admin = config.with_profile("admin").unwrap();
+        assert_eq!((admin.work, admin.break_time, admin.on_work_end.as_deref()), (Some(15), Some(10), Some("true")));
+        assert_eq!(admin.profiles, None);
+        let config = Config::parse("[profiles.admin]\nwork = 15\n").unwrap();
+        assert_eq!(config.with_profile("deepwork").unwrap_err(), "no profile named 'deepwork', the config file has admin");
+        assert!(Config::parse("[profiles.admin]\nwrok = 15\n").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Config::parse("wrok = 50\n").is_err());
+    }
+}
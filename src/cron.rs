@@ -0,0 +1,168 @@
+//! Cron expressions, for tasks that repeat on a schedule of their own:
+//!
+//! ```text
+//! timeadair task add "Inbox zero" --repeat "0 9 * * 1-5"
+//! ```
+//!
+//! The five fields are the usual minute, hour, day of the month, month and
+//! day of the week (0 or 7 for Sunday). Each is a `*`, a number, a range
+//! like `1-5` or a list like `1,3,5`, optionally with a step: `*/15`. As in
+//! cron, when both day fields are given a day matching either will do.
+
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+// How far back to look for the last time an expression fired; far enough
+// for `0 0 29 2 *`
+const LOOKBACK_DAYS: u32 = 5 * 366;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cron {
+    expression: String,
+    // One bit for each value the field allows
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Cron, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!("'{}' needs five fields: minute, hour, day, month and weekday", expression));
+        };
+        let mut sunday_twice = field(weekdays, 0, 7)?;
+        if sunday_twice & 1 << 7 != 0 {
+            sunday_twice |= 1;
+        }
+        Ok(Cron {
+            expression: fields.join(" "),
+            minutes: field(minutes, 0, 59)?,
+            hours: field(hours, 0, 23)?,
+            days: field(days, 1, 31)?,
+            months: field(months, 1, 12)?,
+            weekdays: sunday_twice,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+
+    // The latest time at or before `now` that the expression fires
+    pub fn last(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut date = now.date();
+        for _ in 0..LOOKBACK_DAYS {
+            if self.fires_on(date) {
+                let (hour, minute) = if date == now.date() { (now.hour(), now.minute()) } else { (23, 59) };
+                if let Some(time) = self.last_time(hour, minute) {
+                    return Some(date.and_time(time));
+                }
+            }
+            date = date.pred_opt()?;
+        }
+        None
+    }
+
+    fn fires_on(&self, date: NaiveDate) -> bool {
+        let day = self.days & 1 << date.day() != 0;
+        let weekday = self.weekdays & 1 << date.weekday().num_days_from_sunday() != 0;
+        let either = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        };
+        either && self.months & 1 << date.month() != 0
+    }
+
+    fn last_time(&self, hour: u32, minute: u32) -> Option<NaiveTime> {
+        for h in (0..=hour).rev().filter(|h| self.hours & 1 << h != 0) {
+            let top = if h == hour { minute } else { 59 };
+            if let Some(m) = (0..=top).rev().find(|m| self.minutes & 1 << m != 0) {
+                return NaiveTime::from_hms_opt(h, m, 0);
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn field(text: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().ok().filter(|step| *step > 0).ok_or_else(|| bad(part))?)),
+            None => (part, None),
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (number(first, part)?, number(last, part)?),
+            // `5/15` runs from 5 to the end
+            None if step.is_some() => (number(range, part)?, max),
+            None => (number(range, part)?, number(range, part)?),
+        };
+        if first < min || last > max || first > last {
+            return Err(format!("'{}' is outside {}-{}", part, min, max));
+        }
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn number(text: &str, part: &str) -> Result<u32, String> {
+    text.parse().map_err(|_| bad(part))
+}
+
+fn bad(part: &str) -> String {
+    format!("'{}' isn't a cron field", part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn finds_the_last_firing() {
+        // Thursday 2024-05-02
+        let weekdays = Cron::parse("0 9 * * 1-5").unwrap();
+        assert_eq!(weekdays.last(at("2024-05-02 12:00")), Some(at("2024-05-02 09:00")));
+        assert_eq!(weekdays.last(at("2024-05-02 08:59")), Some(at("2024-05-01 09:00")));
+        // Monday morning goes back to Friday
+        assert_eq!(weekdays.last(at("2024-05-06 08:00")), Some(at("2024-05-03 09:00")));
+
+        let quarter_hours = Cron::parse("*/15 9-17 * * *").unwrap();
+        assert_eq!(quarter_hours.last(at("2024-05-02 10:44")), Some(at("2024-05-02 10:30")));
+        assert_eq!(quarter_hours.last(at("2024-05-02 08:00")), Some(at("2024-05-01 17:45")));
+
+        // Either day field will do: the 1st, or any Sunday
+        let either = Cron::parse("30 8 1 * 0").unwrap();
+        assert_eq!(either.last(at("2024-05-02 12:00")), Some(at("2024-05-01 08:30")));
+        assert_eq!(either.last(at("2024-05-06 12:00")), Some(at("2024-05-05 08:30")));
+        assert_eq!(Cron::parse("0 0 * * 7").unwrap().last(at("2024-05-06 12:00")), Some(at("2024-05-05 00:00")));
+        assert_eq!(Cron::parse("0 0 29 2 *").unwrap().last(at("2024-05-02 12:00")), Some(at("2024-02-29 00:00")));
+        assert_eq!(Cron::parse("0 0 31 2 *").unwrap().last(at("2024-05-02 12:00")), None);
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        for bad in ["0 9 * *", "60 9 * * *", "0 9 * * 8", "*/0 * * * *", "0 9-5 * * *", "a * * * *"] {
+            assert!(Cron::parse(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(Cron::parse(" 0  9 * *  1-5 ").unwrap().to_string(), "0 9 * * 1-5");
+    }
+}
@@ -0,0 +1,217 @@
+//! `timeadair daemon` and `timeadair ctl`.
+//!
+//! The daemon runs sessions without a display and takes one command per
+//! connection on `control.sock` in the runtime directory. It accepts every
+//! command the FIFO does, plus `status`, which replies with the same JSON
+//! line as `--status-format json`; anything else replies `ok` or
+//! `error: <reason>`. `timeadair ctl <command>` sends one and prints the
+//! reply, e.g. from a window manager keybinding. `timeadair start` (or
+//! `ctl start`) starts a detached daemon first if none is running, passing
+//! on any options given before it. Only available on Unix.
+//!
+//! Each connection starts with both sides sending `timeadair <version>`, so
+//! a daemon left running across an upgrade is refused with a clear error
+//! rather than misreading commands:
+//!
+//! ```text
+//! > timeadair 1
+//! > pause
+//! < timeadair 1
+//! < ok
+//! ```
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::Command;
+use crate::paths;
+
+// Bumped whenever a command or reply changes meaning
+pub const PROTOCOL: u32 = 1;
+
+// So a client that connects and says nothing can't hold up the others
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// What came back from the daemon, if it speaks the same protocol
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    Answer(String),
+    // The daemon's version, 0 for one from before the handshake
+    Mismatch(u32),
+}
+
+pub fn socket_path() -> Option<PathBuf> {
+    paths::runtime_dir().map(|dir| dir.join("control.sock"))
+}
+
+// For exit paths that skip destructors, like the Ctrl-C handler
+pub fn remove_socket() {
+    if let Some(path) = socket_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+// `status` is answered from the latest line the session loop left here
+pub fn listen(path: &Path, commands: Sender<Command>, status: Arc<Mutex<String>>) -> io::Result<()> {
+    paths::ensure_writable()?;
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, "another daemon is already running"));
+    }
+    // Nobody answered, so whatever is there was left behind by a crash
+    let _ = fs::remove_file(path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    // The runtime directory may be the data directory, which others can read
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let _ = serve(stream, &commands, &status);
+        }
+    });
+    Ok(())
+}
+
+fn serve(stream: UnixStream, commands: &Sender<Command>, status: &Mutex<String>) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut hello = String::new();
+    reader.read_line(&mut hello)?;
+    writeln!(&stream, "timeadair {}", PROTOCOL)?;
+    let client = version(hello.trim());
+    if client != Some(PROTOCOL) {
+        let age = if client.unwrap_or_default() < PROTOCOL { "old" } else { "new" };
+        return writeln!(&stream, "error: client too {}, this daemon speaks protocol {}", age, PROTOCOL);
+    }
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    writeln!(&stream, "{}", reply(request.trim(), commands, status))
+}
+
+fn version(hello: &str) -> Option<u32> {
+    hello.strip_prefix("timeadair ")?.parse().ok()
+}
+
+fn reply(request: &str, commands: &Sender<Command>, status: &Mutex<String>) -> String {
+    if request == "status" {
+        return status.lock().map(|status| status.clone()).unwrap_or_default();
+    }
+    match Command::parse(request).map(|command| commands.send(command)) {
+        Some(Ok(())) => "ok".to_string(),
+        Some(Err(_)) => "error: the daemon is shutting down".to_string(),
+        None => format!("error: unknown command '{}'", request),
+    }
+}
+
+pub fn ctl(path: &Path, request: &str) -> io::Result<Reply> {
+    let mut stream = UnixStream::connect(path)?;
+    // In one write, so a daemon that hangs up after the first line can't
+    // break the pipe before its reply is read
+    stream.write_all(format!("timeadair {}\n{}\n", PROTOCOL, request).as_bytes())?;
+    let mut reader = BufReader::new(stream);
+    let mut hello = String::new();
+    reader.read_line(&mut hello)?;
+    // A daemon from before the handshake took the hello for a command
+    match version(hello.trim()).unwrap_or(0) {
+        PROTOCOL => {}
+        daemon => return Ok(Reply::Mismatch(daemon)),
+    }
+    let mut reply = String::new();
+    reader.read_line(&mut reply)?;
+    Ok(Reply::Answer(reply.trim_end().to_string()))
+}
+
+// Flags that only change what the client prints; the daemon has no
+// subcommand output for them and would refuse to start
+const CLIENT_ONLY: [&str; 1] = ["--json"];
+
+// The options given before `start`, for a daemon started on demand
+pub fn forwarded_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    args.take_while(|arg| arg != "start" && arg != "ctl").filter(|arg| !CLIENT_ONLY.contains(&arg.as_str())).collect()
+}
+
+// Runs `timeadair <args> daemon` in a session of its own, so it outlives
+// this terminal, and waits for it to start answering
+pub fn spawn(path: &Path, args: &[String]) -> io::Result<()> {
+    let mut command = process::Command::new(env::current_exe()?);
+    command.args(args).arg("daemon").stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    // Safety: setsid is async-signal-safe, so fine between fork and exec
+    unsafe {
+        command.pre_exec(|| match libc::setsid() {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        });
+    }
+    let mut child = command.spawn()?;
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while Instant::now() < deadline {
+        if UnixStream::connect(path).is_ok() {
+            return Ok(());
+        }
+        if let Some(status) = child.try_wait()? {
+            return Err(io::Error::other(format!("it exited straight away ({})", status)));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    Err(io::Error::new(io::ErrorKind::TimedOut, "it didn't start listening"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn commands_and_status_over_the_socket() {
+        let path = env::temp_dir().join(format!("timeadair-test-{}.sock", std::process::id()));
+        let (sender, commands) = mpsc::channel();
+        let status = Arc::new(Mutex::new(r#"{"text":"25:00"}"#.to_string()));
+        listen(&path, sender.clone(), status).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let answer = |text: &str| Reply::Answer(text.to_string());
+        assert_eq!(ctl(&path, "pause").unwrap(), answer("ok"));
+        assert_eq!(commands.recv().unwrap(), Command::Pause);
+        assert_eq!(ctl(&path, "status").unwrap(), answer(r#"{"text":"25:00"}"#));
+        assert_eq!(ctl(&path, "dance").unwrap(), answer("error: unknown command 'dance'"));
+        assert_eq!(listen(&path, sender, Arc::default()).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn client_flags_stay_with_the_client() {
+        let args = ["--json", "--work", "50", "start", "--mute"].map(String::from);
+        assert_eq!(forwarded_args(args.into_iter()), ["--work", "50"]);
+    }
+
+    #[test]
+    fn mismatched_clients_are_refused() {
+        let path = env::temp_dir().join(format!("timeadair-test-{}-versions.sock", std::process::id()));
+        let (sender, commands) = mpsc::channel();
+        listen(&path, sender, Arc::default()).unwrap();
+
+        let exchange = |lines: &str| {
+            let mut stream = UnixStream::connect(&path).unwrap();
+            stream.write_all(lines.as_bytes()).unwrap();
+            let replies: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+            replies
+        };
+        assert_eq!(exchange("pause\n"), ["timeadair 1", "error: client too old, this daemon speaks protocol 1"]);
+        assert_eq!(exchange("timeadair 2\npause\n")[1], "error: client too new, this daemon speaks protocol 1");
+        assert!(commands.try_recv().is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+//! Scripted demo run, used for asciinema recordings and as a golden-file
+//! test of the renderer.
+//!
+//! The script answers prompts and presses keys at fixed points, and a fake
+//! clock advances one minute of session time per frame. Nothing depends on
+//! wall time or real input, so every run produces byte-identical frames.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    execute,
+    terminal::{Clear, ClearType},
+};
+
+use crate::commands::Command;
+use crate::locale;
+use crate::scheduler::{self, Durations, Event, Phase, State};
+use crate::ui::{self, Renderer, Screen};
+
+const TICKS_PER_FRAME: u32 = 60;
+
+enum Action {
+    // Typed at the session prompt, followed by Enter
+    Answer(&'static str),
+    // Frames of a running session; stops early if the session ends
+    Frames(u32),
+    Key(char),
+}
+
+const SCRIPT: &[Action] = &[
+    Action::Answer("y"),
+    Action::Frames(25),
+    Action::Answer(""),
+    Action::Frames(5),
+    Action::Answer("y"),
+    Action::Frames(3),
+    Action::Key('p'),
+    Action::Frames(2),
+    Action::Key('p'),
+    Action::Frames(1),
+    Action::Key('r'),
+    Action::Answer("n"),
+];
+
+pub enum Pause {
+    Frame,
+    Prompt,
+}
+
+// The demo always draws at this size, whatever the terminal is
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 24;
+
+pub fn run() -> io::Result<()> {
+    execute!(io::stdout(), Clear(ClearType::All))?;
+    let mut screen = Screen::fixed(io::stdout(), WIDTH, HEIGHT)?;
+    play(&mut screen, |pause| {
+        thread::sleep(match pause {
+            Pause::Frame => Duration::from_millis(120),
+            Pause::Prompt => Duration::from_millis(900),
+        })
+    })?;
+    screen.show_cursor(true)?;
+    execute!(io::stdout(), cursor::MoveTo(0, HEIGHT))
+}
+
+pub fn play<W: Write, F: FnMut(Pause)>(screen: &mut Screen<W>, mut pause: F) -> io::Result<()> {
+    let mut state = State::new(Durations::default());
+    for action in SCRIPT {
+        screen.set_cycle(state.cycle_position());
+        match action {
+            Action::Answer(input) => {
+                let session_type = state.session_name().to_lowercase();
+                screen.prompt(&session_type, None)?;
+                pause(Pause::Prompt);
+                screen.answer(input)?;
+                let accepted = ui::prompt_accepted(input);
+                if !accepted && state.phase == Phase::AwaitingWork {
+                    screen.message(locale::ENGLISH.farewell)?;
+                }
+                state = scheduler::step(state, if accepted { Event::Start } else { Event::Decline });
+            }
+            Action::Frames(frames) => {
+                let phase = state.phase;
+                let message = format!("Current session: {}", state.session_name());
+                screen.progress(&state.timer, &message, state.paused)?;
+                'frames: for _ in 0..*frames {
+                    pause(Pause::Frame);
+                    for _ in 0..TICKS_PER_FRAME {
+                        state = scheduler::step(state, Event::Tick);
+                        if state.phase != phase {
+                            screen.session_end(&scheduler::session_result(Event::Tick))?;
+                            break 'frames;
+                        }
+                    }
+                    screen.progress(&state.timer, &message, state.paused)?;
+                }
+            }
+            Action::Key(key) => {
+                let command = Command::from_key(KeyCode::Char(*key));
+                let Some(event) = command.and_then(|command| command.session_event(state.paused)) else {
+                    continue;
+                };
+                let phase = state.phase;
+                state = scheduler::step(state, event);
+                if state.phase == phase {
+                    let message = format!("Current session: {}", state.session_name());
+                    screen.progress(&state.timer, &message, state.paused)?;
+                    pause(Pause::Frame);
+                } else {
+                    screen.session_end(&scheduler::session_result(event))?;
+                    pause(Pause::Prompt);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/demo.ansi");
+
+    fn frames() -> Vec<u8> {
+        let mut out = Vec::new();
+        play(&mut Screen::fixed(&mut out, WIDTH, HEIGHT).unwrap(), |_| {}).unwrap();
+        out
+    }
+
+    #[test]
+    fn demo_frames_match_golden_file() {
+        let frames = frames();
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(GOLDEN, &frames).unwrap();
+        }
+        let expected = fs::read(GOLDEN).expect("missing golden file, run with UPDATE_GOLDEN=1");
+        assert!(
+            frames == expected,
+            "demo output differs from tests/golden/demo.ansi; rerun with UPDATE_GOLDEN=1 if the change is intended"
+        );
+    }
+
+    #[test]
+    fn demo_ends_on_farewell_screen() {
+        let mut out = Vec::new();
+        let mut screen = Screen::fixed(&mut out, WIDTH, HEIGHT).unwrap();
+        play(&mut screen, |_| {}).unwrap();
+        assert_eq!(screen.view(), &ui::View::Message(locale::ENGLISH.farewell.to_string()));
+        drop(screen);
+        // The bell belongs to the notifiers, which the demo goes without
+        assert!(!out.contains(&0x07));
+    }
+}
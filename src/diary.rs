@@ -0,0 +1,127 @@
+//! A diary of the day's work with `--diary` (`diary = true` in the config
+//! file): as each work session is recorded, what it was for, how it ended,
+//! its note and the git branch it was on are added to `diary/<date>.md` in
+//! the data directory, along with the latest commit's subject if one was
+//! made during the session:
+//!
+//! ```markdown
+//! ## 09:00-09:25 Write report
+//!
+//! - Completed
+//! - Note: drafted the introduction
+//! - Branch: report-intro
+//! - Committed: Draft the introduction
+//! ```
+//!
+//! The branch is that of the directory the timer was started in, so
+//! nothing is added for it outside a repository.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Local};
+
+use crate::history::SessionRecord;
+use crate::paths;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Git {
+    branch: String,
+    head: String,
+}
+
+impl Git {
+    fn read(dir: &Path) -> Option<Git> {
+        Some(Git { branch: git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])?, head: git(dir, &["rev-parse", "HEAD"])? })
+    }
+}
+
+pub struct Diary {
+    dir: PathBuf,
+    // Where the timer was started, for git
+    repo: PathBuf,
+    // How the repository stood when the running work session started
+    before: Option<Git>,
+}
+
+impl Diary {
+    pub fn new() -> Option<Diary> {
+        Some(Diary { dir: paths::data_dir()?.join("diary"), repo: env::current_dir().ok()?, before: None })
+    }
+
+    pub fn work_started(&mut self) {
+        self.before = Git::read(&self.repo);
+    }
+
+    pub fn write(&mut self, record: &SessionRecord) -> io::Result<()> {
+        paths::ensure_writable()?;
+        let after = Git::read(&self.repo);
+        let committed = match (self.before.take(), &after) {
+            (Some(before), Some(after)) if before.head != after.head => git(&self.repo, &["log", "-1", "--format=%s"]),
+            _ => None,
+        };
+        let started = DateTime::from_timestamp(record.started, 0).unwrap_or_default().with_timezone(&Local);
+        let path = self.dir.join(format!("{}.md", started.format("%Y-%m-%d")));
+        fs::create_dir_all(&self.dir)?;
+        let heading = if path.exists() { String::new() } else { format!("# {}\n\n", started.format("%A %-d %B %Y")) };
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let branch = after.map(|git| git.branch);
+        write!(file, "{}{}", heading, entry(record, started, branch.as_deref(), committed.as_deref()))
+    }
+}
+
+fn entry(record: &SessionRecord, started: DateTime<Local>, branch: Option<&str>, committed: Option<&str>) -> String {
+    let ended = started + chrono::Duration::seconds((record.duration + record.paused) as i64);
+    let mut entry = format!("## {}-{}", started.format("%H:%M"), ended.format("%H:%M"));
+    if let Some(task) = &record.task {
+        entry += &format!(" {}", task);
+    }
+    let outcome = record.outcome.name();
+    entry += &format!("\n\n- {}{}\n", outcome[..1].to_uppercase(), &outcome[1..]);
+    let details = [("Note", record.note.as_deref()), ("Branch", branch), ("Committed", committed)];
+    for (label, detail) in details {
+        if let Some(detail) = detail {
+            entry += &format!("- {}: {}\n", label, detail);
+        }
+    }
+    entry + "\n"
+}
+
+// Trimmed standard output, or None outside a repository or without git
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Outcome;
+    use chrono::TimeZone;
+
+    #[test]
+    fn entry_for_a_work_session() {
+        let record = SessionRecord {
+            session: "work".to_string(),
+            duration: 1500,
+            paused: 60,
+            outcome: Outcome::Completed,
+            task: Some("Write report".to_string()),
+            planned: Some(1500),
+            note: Some("drafted the introduction".to_string()),
+            interruptions: Default::default(),
+            ..Default::default()
+        };
+        let started = Local.with_ymd_and_hms(2024, 5, 2, 9, 0, 0).unwrap();
+        assert_eq!(
+            entry(&record, started, Some("report-intro"), None),
+            "## 09:00-09:26 Write report\n\n- Completed\n- Note: drafted the introduction\n- Branch: report-intro\n\n"
+        );
+        let record = SessionRecord { task: None, note: None, outcome: Outcome::Quit, ..record };
+        assert_eq!(entry(&record, started, None, Some("Draft it")), "## 09:00-09:26\n\n- Quit\n- Committed: Draft it\n\n");
+    }
+}
@@ -0,0 +1,75 @@
+//! The time left in block numerals, for a timer read from across the room:
+//! `--display big` (`display = "big"` in the config file), or `d` while a
+//! session runs. Each numeral is five rows of three blocks, and a block is
+//! drawn two cells wide so the digits don't come out tall and thin. They
+//! grow with the terminal, and a terminal too small for even the smallest
+//! gets the usual lines instead.
+
+const ROWS: usize = 5;
+
+const FONT: [(char, [&str; ROWS]); 12] = [
+    ('0', ["###", "# #", "# #", "# #", "###"]),
+    ('1', ["  #", "  #", "  #", "  #", "  #"]),
+    ('2', ["###", "  #", "###", "#  ", "###"]),
+    ('3', ["###", "  #", "###", "  #", "###"]),
+    ('4', ["# #", "# #", "###", "  #", "  #"]),
+    ('5', ["###", "#  ", "###", "  #", "###"]),
+    ('6', ["###", "#  ", "###", "# #", "###"]),
+    ('7', ["###", "  #", "  #", "  #", "  #"]),
+    ('8', ["###", "# #", "###", "# #", "###"]),
+    ('9', ["###", "# #", "###", "  #", "###"]),
+    (':', [" ", "#", " ", "#", " "]),
+    ('.', [" ", " ", " ", " ", "#"]),
+];
+
+fn glyph(c: char) -> [&'static str; ROWS] {
+    FONT.iter().find(|(key, _)| *key == c).map_or([" "; ROWS], |(_, rows)| *rows)
+}
+
+// Blocks across, with one between each pair of characters
+fn blocks(time: &str) -> usize {
+    let glyphs: usize = time.chars().map(|c| glyph(c)[0].len()).sum();
+    glyphs + time.chars().count().saturating_sub(1)
+}
+
+// The largest scale that fits, if any does
+pub fn scale(time: &str, width: u16, height: u16) -> Option<usize> {
+    let scale = (usize::from(width) / (blocks(time) * 2)).min(usize::from(height) / ROWS);
+    (scale > 0).then_some(scale)
+}
+
+// `ROWS * scale` lines, each block drawn with `on`
+pub fn lines(time: &str, scale: usize, on: char) -> Vec<String> {
+    let mut lines = Vec::with_capacity(ROWS * scale);
+    for row in 0..ROWS {
+        let mut line = String::new();
+        for (i, c) in time.chars().enumerate() {
+            if i > 0 {
+                line.extend(std::iter::repeat_n(' ', 2 * scale));
+            }
+            for block in glyph(c)[row].chars() {
+                line.extend(std::iter::repeat_n(if block == '#' { on } else { ' ' }, 2 * scale));
+            }
+        }
+        lines.extend(std::iter::repeat_n(line, scale));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numerals_scale_to_fit() {
+        assert_eq!(blocks("12:30"), 17);
+        assert_eq!(scale("12:30", 60, 10), Some(1));
+        assert_eq!(scale("12:30", 140, 12), Some(2));
+        assert_eq!(scale("12:30", 30, 10), None);
+        let rows = lines("1:0", 1, '#');
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0], "    ##      ######");
+        assert_eq!(rows[1], "    ##  ##  ##  ##");
+        assert_eq!(lines("1", 2, '#').len(), 10);
+    }
+}
@@ -0,0 +1,179 @@
+//! Do Not Disturb for the length of each work session (`--dnd`, or `dnd`
+//! in the config file), put back the way it was when the session ends,
+//! however it ends. This is built in rather than left to the hooks so the
+//! previous state can be read first and restored:
+//!
+//! - GNOME: notification banners are turned off with `gsettings`
+//! - KDE Plasma: Do Not Disturb is set until the session is due to end in
+//!   `plasmanotifyrc`, so it lapses by itself if the timer dies
+//! - macOS: Focus can only be switched through Shortcuts, so two shortcuts
+//!   named `timeadair focus on` and `timeadair focus off` are run, made with
+//!   the "Set Focus" action; `macos_focus_shortcuts` names others
+//!
+//! `dnd = ["gnome", "macos"]` limits it to those desktops. Windows has no
+//! public way to switch Focus Assist, so it's left alone there. Nothing's
+//! changed until the user has allowed it once, see `timeadair permissions`.
+
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use chrono::{Duration, Local};
+
+const SHORTCUTS: (&str, &str) = ("timeadair focus on", "timeadair focus off");
+const BANNERS: [&str; 2] = ["org.gnome.desktop.notifications", "show-banners"];
+const PLASMA: [&str; 6] = ["--file", "plasmanotifyrc", "--group", "DoNotDisturb", "--key", "Until"];
+
+// What to put back once the session is over, kept where the Ctrl-C
+// handler can get at it
+static SAVED: Mutex<Option<Saved>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Desktop {
+    Gnome,
+    Kde,
+    MacOs,
+}
+
+impl Desktop {
+    pub const ALL: [Desktop; 3] = [Desktop::Gnome, Desktop::Kde, Desktop::MacOs];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Desktop::Gnome => "gnome",
+            Desktop::Kde => "kde",
+            Desktop::MacOs => "macos",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Desktop, String> {
+        Desktop::ALL.into_iter().find(|desktop| desktop.name() == name.trim().to_lowercase()).ok_or_else(|| {
+            let names: Vec<_> = Desktop::ALL.iter().map(|desktop| desktop.name()).collect();
+            format!("dnd expects desktops from {}, not '{}'", names.join(", "), name)
+        })
+    }
+
+    // The one this is running on, if it's one Do Not Disturb can be set on
+    pub fn current() -> Option<Desktop> {
+        if cfg!(target_os = "macos") {
+            return Some(Desktop::MacOs);
+        }
+        from_xdg(&env::var("XDG_CURRENT_DESKTOP").ok()?)
+    }
+}
+
+// `XDG_CURRENT_DESKTOP` is a colon separated list, e.g. `ubuntu:GNOME`
+fn from_xdg(desktops: &str) -> Option<Desktop> {
+    desktops.split(':').find_map(|desktop| match desktop.to_uppercase().as_str() {
+        "GNOME" | "UNITY" => Some(Desktop::Gnome),
+        "KDE" => Some(Desktop::Kde),
+        _ => None,
+    })
+}
+
+enum Saved {
+    Banners(String),
+    // The earlier "until", if there was one
+    Plasma(Option<String>),
+    Shortcut(String),
+}
+
+pub struct Dnd {
+    desktop: Desktop,
+    shortcuts: (String, String),
+}
+
+impl Dnd {
+    // None when the desktop this is running on wasn't asked for
+    pub fn new(desktops: &[Desktop], shortcuts: Option<(String, String)>) -> Option<Dnd> {
+        let desktop = Desktop::current().filter(|desktop| desktops.contains(desktop))?;
+        let shortcuts = shortcuts.unwrap_or_else(|| (SHORTCUTS.0.to_string(), SHORTCUTS.1.to_string()));
+        Some(Dnd { desktop, shortcuts })
+    }
+
+    pub fn start(&self, seconds: u64) -> io::Result<()> {
+        // Still on from a session that didn't get to end, e.g. after --auto
+        if SAVED.lock().unwrap().is_some() {
+            return Ok(());
+        }
+        let saved = match self.desktop {
+            Desktop::Gnome => {
+                let banners = run("gsettings", &["get", BANNERS[0], BANNERS[1]])?;
+                // Already quiet, so there's nothing to undo
+                if banners.trim() == "false" {
+                    return Ok(());
+                }
+                run("gsettings", &["set", BANNERS[0], BANNERS[1], "false"])?;
+                Saved::Banners(banners.trim().to_string())
+            }
+            Desktop::Kde => {
+                let until = run(kde_tool("kreadconfig"), &PLASMA).ok().map(|until| until.trim().to_string()).filter(|until| !until.is_empty());
+                let end = Local::now() + Duration::seconds(seconds as i64);
+                run(kde_tool("kwriteconfig"), &[&PLASMA[..], &[plasma_time(end).as_str()]].concat())?;
+                Saved::Plasma(until)
+            }
+            Desktop::MacOs => {
+                run("shortcuts", &["run", &self.shortcuts.0])?;
+                Saved::Shortcut(self.shortcuts.1.clone())
+            }
+        };
+        *SAVED.lock().unwrap() = Some(saved);
+        Ok(())
+    }
+
+    pub fn end(&self) -> io::Result<()> {
+        restore()
+    }
+}
+
+// Puts Do Not Disturb back how it was, if a session turned it on
+pub fn restore() -> io::Result<()> {
+    let Some(saved) = SAVED.lock().unwrap().take() else { return Ok(()) };
+    match saved {
+        Saved::Banners(banners) => run("gsettings", &["set", BANNERS[0], BANNERS[1], &banners]).map(drop),
+        Saved::Plasma(Some(until)) => run(kde_tool("kwriteconfig"), &[&PLASMA[..], &[until.as_str()]].concat()).map(drop),
+        Saved::Plasma(None) => run(kde_tool("kwriteconfig"), &[&PLASMA[..], &["--delete"]].concat()).map(drop),
+        Saved::Shortcut(shortcut) => run("shortcuts", &["run", &shortcut]).map(drop),
+    }
+}
+
+// Plasma 6 has `kwriteconfig6`, Plasma 5 `kwriteconfig5`
+fn kde_tool(tool: &str) -> &'static str {
+    let plasma6 = env::var("KDE_SESSION_VERSION").is_ok_and(|version| version == "6");
+    match (tool, plasma6) {
+        ("kreadconfig", true) => "kreadconfig6",
+        ("kreadconfig", false) => "kreadconfig5",
+        (_, true) => "kwriteconfig6",
+        (_, false) => "kwriteconfig5",
+    }
+}
+
+// How KConfig writes a date and time: `2024,5,2,12,25,0`
+fn plasma_time(time: chrono::DateTime<Local>) -> String {
+    time.format("%Y,%-m,%-d,%-H,%-M,%-S").to_string()
+}
+
+fn run(program: &str, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(program).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("{} {} failed", program, args.first().copied().unwrap_or_default())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn desktops_by_name_and_environment() {
+        assert_eq!(Desktop::parse("GNOME"), Ok(Desktop::Gnome));
+        assert!(Desktop::parse("windows").is_err());
+        assert_eq!(from_xdg("ubuntu:GNOME"), Some(Desktop::Gnome));
+        assert_eq!(from_xdg("KDE"), Some(Desktop::Kde));
+        assert_eq!(from_xdg("XFCE"), None);
+        assert_eq!(plasma_time(Local.with_ymd_and_hms(2024, 5, 2, 9, 5, 0).unwrap()), "2024,5,2,9,5,0");
+    }
+}
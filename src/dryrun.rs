@@ -0,0 +1,99 @@
+//! `--dry-run` (`dry_run = true` in the config file, or a list such as
+//! `dry_run = ["webhook", "hooks"]` for only some of them): the webhook,
+//! MQTT topic and shell hooks write what they would have sent to
+//! `dry-run.log` in the data directory instead of sending it, e.g.
+//!
+//! ```text
+//! 2024-05-02 09:00:00 webhook: POST https://home.example/hooks/focus {"event":"session_started",...}
+//! 2024-05-02 09:00:00 hooks: on_work_start: makoctl mode -a do-not-disturb (TIMEADAIR_SESSION=work ...)
+//! ```
+//!
+//! so payloads and commands can be checked before anything is pointed at
+//! the real thing.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+
+use crate::paths;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Integration {
+    Webhook,
+    Mqtt,
+    Hooks,
+}
+
+impl Integration {
+    pub const ALL: [Integration; 3] = [Integration::Webhook, Integration::Mqtt, Integration::Hooks];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integration::Webhook => "webhook",
+            Integration::Mqtt => "mqtt",
+            Integration::Hooks => "hooks",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Integration, String> {
+        Integration::ALL.into_iter().find(|integration| integration.name() == name.trim().to_lowercase()).ok_or_else(|| {
+            let names: Vec<_> = Integration::ALL.iter().map(|integration| integration.name()).collect();
+            format!("dry_run expects integrations from {}, not '{}'", names.join(", "), name)
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DryRun {
+    path: PathBuf,
+    only: Vec<Integration>,
+}
+
+impl DryRun {
+    // None when every integration sends for real
+    pub fn new(only: &[Integration]) -> Option<DryRun> {
+        if only.is_empty() {
+            return None;
+        }
+        Some(DryRun { path: paths::data_dir()?.join("dry-run.log"), only: only.to_vec() })
+    }
+
+    // A log of its own, for tests that shouldn't touch the data directory
+    #[cfg(test)]
+    pub fn at(path: PathBuf, only: &[Integration]) -> DryRun {
+        DryRun { path, only: only.to_vec() }
+    }
+
+    pub fn covers(&self, integration: Integration) -> bool {
+        self.only.contains(&integration)
+    }
+
+    pub fn log(&self, integration: Integration, what: &str) -> io::Result<()> {
+        paths::ensure_writable()?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line(Local::now(), integration, what).as_bytes())
+    }
+}
+
+fn line(now: DateTime<Local>, integration: Integration, what: &str) -> String {
+    format!("{} {}: {}\n", now.format("%Y-%m-%d %H:%M:%S"), integration.name(), what)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn integrations_and_lines() {
+        assert_eq!(Integration::parse(" Webhook").unwrap(), Integration::Webhook);
+        assert!(Integration::parse("slack").is_err());
+        let now = Local.with_ymd_and_hms(2024, 5, 2, 9, 0, 0).unwrap();
+        assert_eq!(line(now, Integration::Mqtt, "office/focus {}"), "2024-05-02 09:00:00 mqtt: office/focus {}\n");
+    }
+}
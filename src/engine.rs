@@ -0,0 +1,216 @@
+//! A method-per-action wrapper around the scheduler.
+
+use crate::scheduler::{self, Durations, Event, Phase, State};
+use crate::timer::{Countdown, Timer, TimerResult};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionKind {
+    Work,
+    Break,
+    LongBreak,
+}
+
+// A session that is running (or paused) right now
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Session {
+    pub kind: SessionKind,
+    pub timer: Timer,
+    pub paused: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PomodoroEngine {
+    state: State,
+}
+
+impl PomodoroEngine {
+    pub fn new(durations: Durations) -> PomodoroEngine {
+        PomodoroEngine {
+            state: State::new(durations),
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn completed_work(&self) -> u32 {
+        self.state.completed_work
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.phase == Phase::Finished
+    }
+
+    pub fn session(&self) -> Option<Session> {
+        matches!(self.state.phase, Phase::Work | Phase::Break).then(|| Session {
+            kind: self.kind(),
+            timer: self.state.timer,
+            paused: self.state.paused,
+        })
+    }
+
+    // The running session's last `last` seconds, `fraction` of a second
+    // after the latest tick; None while paused
+    pub fn countdown(&self, fraction: f32, last: u64) -> Option<Countdown> {
+        self.session().filter(|session| !session.paused)?.timer.countdown(fraction, last)
+    }
+
+    // The kind of session `start` would begin, if none is running
+    pub fn next_kind(&self) -> Option<SessionKind> {
+        matches!(self.state.phase, Phase::AwaitingWork | Phase::AwaitingBreak).then(|| self.kind())
+    }
+
+    // Returns whether a session was started
+    pub fn start(&mut self) -> bool {
+        let waiting = self.next_kind().is_some();
+        self.apply(Event::Start);
+        waiting
+    }
+
+    // Turns down the offered session: a declined break goes back to
+    // offering work, a declined work session finishes the engine
+    pub fn decline(&mut self) {
+        self.apply(Event::Decline);
+    }
+
+    // One second of wall time; returns the result if the session ended
+    pub fn tick(&mut self) -> Option<TimerResult> {
+        self.apply(Event::Tick)
+    }
+
+    pub fn pause(&mut self) {
+        self.apply(Event::Pause);
+    }
+
+    pub fn resume(&mut self) {
+        self.apply(Event::Resume);
+    }
+
+    // Add or take away `Durations::adjust_step` from the running session;
+    // shortening never ends it before the next tick
+    pub fn extend(&mut self) {
+        self.apply(Event::Extend);
+    }
+
+    pub fn shorten(&mut self) {
+        self.apply(Event::Shorten);
+    }
+
+    pub fn skip(&mut self) -> Option<TimerResult> {
+        self.apply(Event::Skip)
+    }
+
+    pub fn reset(&mut self) -> Option<TimerResult> {
+        self.apply(Event::Reset)
+    }
+
+    pub fn quit(&mut self) -> Option<TimerResult> {
+        self.apply(Event::Quit)
+    }
+
+    fn kind(&self) -> SessionKind {
+        match self.state.phase {
+            Phase::AwaitingBreak | Phase::Break if self.state.long_break_due() => SessionKind::LongBreak,
+            Phase::AwaitingBreak | Phase::Break => SessionKind::Break,
+            _ => SessionKind::Work,
+        }
+    }
+
+    fn apply(&mut self, event: Event) -> Option<TimerResult> {
+        let running = self.session().is_some();
+        let phase = self.state.phase;
+        self.state = scheduler::step(self.state, event);
+        (running && self.state.phase != phase).then(|| scheduler::session_result(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> PomodoroEngine {
+        PomodoroEngine::new(Durations {
+            work_time: 3,
+            break_time: 1,
+            long_break_time: 2,
+            long_break_every: 2,
+            adjust_step: 1,
+        })
+    }
+
+    #[test]
+    fn ticks_through_a_work_session() {
+        let mut engine = engine();
+        assert_eq!(engine.next_kind(), Some(SessionKind::Work));
+        assert!(engine.start());
+        assert_eq!(engine.tick(), None);
+        assert_eq!(engine.session().unwrap().timer.remaining(), 2);
+        assert_eq!(engine.tick(), None);
+        assert_eq!(engine.tick(), Some(TimerResult::Completed));
+        assert_eq!(engine.session(), None);
+        assert_eq!(engine.next_kind(), Some(SessionKind::Break));
+        assert_eq!(engine.completed_work(), 1);
+    }
+
+    #[test]
+    fn paused_sessions_hold_their_time() {
+        let mut engine = engine();
+        engine.start();
+        engine.tick();
+        engine.pause();
+        for _ in 0..10 {
+            assert_eq!(engine.tick(), None);
+        }
+        assert!(engine.session().unwrap().paused);
+        assert_eq!(engine.session().unwrap().timer.elapsed, 1);
+        engine.resume();
+        engine.tick();
+        assert_eq!(engine.session().unwrap().timer.elapsed, 2);
+        assert_eq!(engine.state().paused_time, 10);
+    }
+
+    #[test]
+    fn skipping_moves_on_to_the_next_session() {
+        let mut engine = engine();
+        engine.start();
+        assert_eq!(engine.skip(), Some(TimerResult::Skipped));
+        engine.start();
+        assert_eq!(engine.session().unwrap().kind, SessionKind::Break);
+        assert_eq!(engine.skip(), Some(TimerResult::Skipped));
+        engine.start();
+        engine.skip();
+        assert_eq!(engine.next_kind(), Some(SessionKind::LongBreak));
+        // Nothing to skip between sessions
+        assert_eq!(engine.skip(), None);
+    }
+
+    #[test]
+    fn counts_down_the_last_seconds() {
+        let mut engine = engine();
+        engine.start();
+        assert_eq!(engine.countdown(0.0, 2), None);
+        engine.tick();
+        assert_eq!(engine.countdown(0.2, 2), Some(Countdown { seconds: 2, lit: true }));
+        assert_eq!(engine.countdown(0.7, 2), Some(Countdown { seconds: 2, lit: false }));
+        engine.pause();
+        assert_eq!(engine.countdown(0.2, 2), None);
+        engine.resume();
+        engine.tick();
+        engine.tick();
+        assert_eq!(engine.countdown(0.2, 2), None);
+    }
+
+    #[test]
+    fn reset_and_quit() {
+        let mut engine = engine();
+        engine.start();
+        assert_eq!(engine.reset(), Some(TimerResult::Reset));
+        assert_eq!(engine.next_kind(), Some(SessionKind::Work));
+        assert_eq!(engine.completed_work(), 0);
+        engine.start();
+        assert_eq!(engine.quit(), Some(TimerResult::Quit));
+        assert!(engine.is_finished());
+        assert!(!engine.start());
+    }
+}
@@ -0,0 +1,76 @@
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+// Everything that can stop timeadair, each with a way out for the user
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Usage(String),
+    #[error("{0}")]
+    Config(String),
+    #[error("unable to open serial device {}: {source}", path.display())]
+    Serial { path: PathBuf, source: io::Error },
+    #[error("unable to create command FIFO {}: {source}", path.display())]
+    Fifo { path: PathBuf, source: io::Error },
+    #[error("{0}")]
+    Sound(io::Error),
+    #[error("unable to listen on {}: {source}", path.display())]
+    Socket { path: PathBuf, source: io::Error },
+    #[error("no daemon is listening on {}: {source}", path.display())]
+    NotRunning { path: PathBuf, source: io::Error },
+    #[error("unable to start the daemon: {0}")]
+    Spawn(io::Error),
+    #[error("{0}")]
+    Daemon(String),
+    #[error("the running daemon speaks protocol {daemon}, but this timeadair speaks {client}")]
+    Protocol { daemon: u32, client: u32 },
+    #[error("unable to read {name}'s history at {}: {source}", path.display())]
+    Leaderboard { name: String, path: PathBuf, source: io::Error },
+    #[error("no session is running")]
+    NothingRunning,
+    #[error("unable to find a {0} directory")]
+    NoDirectory(&'static str),
+    #[cfg(not(unix))]
+    #[error("{0} is only supported on Unix")]
+    Unsupported(&'static str),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+impl Error {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Error::Usage(_) => None,
+            Error::Config(_) => Some("Fix the config file, or move it aside to start from the defaults."),
+            Error::Serial { .. } => Some("Check the device is plugged in and writable, or run without --serial."),
+            Error::Fifo { .. } => Some("Check the data directory is writable, or run without --command-fifo."),
+            Error::Sound(_) => Some("Run 'timeadair sounds list' to see the installed packs, and check an audio player is installed."),
+            Error::Socket { .. } => Some("Another daemon may be running; stop it with 'timeadair ctl quit'."),
+            Error::NotRunning { .. } => Some("Start one with 'timeadair daemon', or just run 'timeadair start'."),
+            Error::Spawn(_) => Some("Run 'timeadair daemon' yourself to see what goes wrong."),
+            Error::Daemon(_) => None,
+            Error::Protocol { daemon, client } if daemon < client => {
+                Some("The daemon is older than this timeadair; stop that process and run 'timeadair daemon' again.")
+            }
+            Error::Protocol { .. } => Some("The daemon is newer than this timeadair; run the matching version of 'timeadair ctl'."),
+            Error::Leaderboard { .. } => Some("Check the path given for them, and that the folder has finished syncing."),
+            Error::NothingRunning => Some("Start one first, e.g. with 'timeadair start'."),
+            Error::NoDirectory(_) => Some("Set HOME, or the matching XDG_*_HOME variable, and try again."),
+            #[cfg(not(unix))]
+            Error::Unsupported(_) => None,
+            Error::Io(_) => Some("The terminal may have gone away. Run timeadair again, or try --ui plain."),
+        }
+    }
+
+    // 2 for anything wrong with how timeadair was asked to run, 3 when
+    // `wait` has nothing to wait for, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) | Error::Config(_) => 2,
+            Error::NothingRunning => 3,
+            _ => 1,
+        }
+    }
+}
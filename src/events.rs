@@ -0,0 +1,191 @@
+//! JSON Lines event log.
+//!
+//! Every session event is written as one JSON object per line to a file or
+//! FIFO as it happens, e.g.
+//! `{"timestamp":1714636800,"event":"session_started","session":"work","duration":1500}`.
+//! Writing happens on a background thread so that opening a FIFO with no
+//! reader attached never blocks the timer.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::outputs::Delivery;
+use crate::scheduler::State;
+use crate::timer::TimerResult;
+
+#[derive(Serialize)]
+#[serde(tag = "event")]
+pub enum SessionEvent<'a> {
+    #[serde(rename = "session_started")]
+    Started {
+        session: &'a str,
+        duration: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task: Option<&'a str>,
+    },
+    // `paused` is the time spent paused on top of `duration`
+    #[serde(rename = "session_completed")]
+    Completed { session: &'a str, duration: u64, paused: u64 },
+    #[serde(rename = "session_skipped")]
+    Skipped { session: &'a str, elapsed: u64 },
+    #[serde(rename = "session_quit")]
+    Quit { session: &'a str, elapsed: u64 },
+    #[serde(rename = "session_reset")]
+    Reset { session: &'a str, elapsed: u64 },
+    // Ended by a shutdown signal
+    #[serde(rename = "session_interrupted")]
+    Interrupted { session: &'a str, elapsed: u64 },
+    #[serde(rename = "session_paused")]
+    Paused { session: &'a str, elapsed: u64 },
+    #[serde(rename = "session_resumed")]
+    Resumed { session: &'a str, paused: u64 },
+    // Logged from the keyboard without stopping the timer
+    #[serde(rename = "interruption")]
+    Interruption { session: &'a str, kind: &'a str, elapsed: u64 },
+    #[serde(rename = "tick")]
+    Tick { session: &'a str, remaining: u64, progress: u32, paused: bool },
+    #[serde(rename = "awaiting_start")]
+    AwaitingStart { session: &'a str },
+    #[serde(rename = "outside_working_hours")]
+    OutsideWorkingHours { hours: &'a str, strict: bool },
+    // `active` is seconds at the keyboard with no session running
+    #[serde(rename = "nudge")]
+    Nudge { active: u64, message: &'a str },
+    // `waited` is seconds in working hours with no session
+    #[serde(rename = "reminder")]
+    Reminder { waited: u64, message: &'a str },
+    #[serde(rename = "daily_goal_reached")]
+    GoalReached { pomodoros: u32, goal: u32 },
+    // Logged on the way out, `focus` in seconds
+    #[serde(rename = "run_summary")]
+    RunSummary { pomodoros: u32, focus: u64, breaks: u32, interruptions: u32 },
+    #[serde(rename = "checklist_done")]
+    ChecklistDone { ticked: usize, skipped: Vec<&'a str> },
+    // An optional output such as the serial display stopped working
+    #[serde(rename = "warning")]
+    Warning { subsystem: &'a str, message: &'a str },
+    #[serde(rename = "invalid_command")]
+    InvalidCommand { message: &'a str },
+    #[serde(rename = "exited")]
+    Exited,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    event: &'a SessionEvent<'a>,
+}
+
+pub struct EventLog {
+    sender: Option<Sender<String>>,
+    done: Receiver<()>,
+    // Shared with the writer thread, for the integrations panel
+    delivery: Arc<Mutex<Delivery>>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl EventLog {
+    pub fn open(path: &Path) -> EventLog {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let (done_sender, done) = mpsc::channel();
+        let path = path.to_path_buf();
+        let delivery = Arc::new(Mutex::new(Delivery::default()));
+        let queued = Arc::new(AtomicUsize::new(0));
+        let (thread_delivery, thread_queued) = (delivery.clone(), queued.clone());
+        thread::spawn(move || {
+            let mut file: Option<File> = None;
+            for line in receiver {
+                let result = match file.as_mut() {
+                    Some(f) => Ok(f),
+                    None => open_log(&path).map(|f| file.insert(f)),
+                }
+                .and_then(|f| writeln!(f, "{}", line).and_then(|_| f.flush()));
+                // Drop the handle on failure (e.g. a FIFO reader went away)
+                // and reopen it for the next event
+                if result.is_err() {
+                    file = None;
+                }
+                thread_queued.fetch_sub(1, Ordering::Relaxed);
+                if let Ok(mut delivery) = thread_delivery.lock() {
+                    delivery.record(&result);
+                }
+            }
+            let _ = done_sender.send(());
+        });
+        EventLog {
+            sender: Some(sender),
+            done,
+            delivery,
+            queued,
+        }
+    }
+
+    pub fn emit(&self, event: &SessionEvent) {
+        if let (Some(line), Some(sender)) = (to_json_line(event), &self.sender) {
+            if sender.send(line).is_ok() {
+                self.queued.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // How writing has gone so far, and how many events are still waiting
+    pub fn delivery(&self) -> (Delivery, usize) {
+        let delivery = self.delivery.lock().map(|delivery| delivery.clone()).unwrap_or_default();
+        (delivery, self.queued.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        // Give queued events a moment to drain, but don't hang on exit if a
+        // FIFO never gets a reader
+        self.sender.take();
+        let _ = self.done.recv_timeout(Duration::from_millis(500));
+    }
+}
+
+// The event for a session that has just ended, however it ended
+pub fn end_event<'a>(session: &'a str, state: &State, result: &TimerResult) -> SessionEvent<'a> {
+    let timer = state.timer;
+    match result {
+        TimerResult::Completed => SessionEvent::Completed {
+            session,
+            duration: timer.duration,
+            paused: state.paused_time,
+        },
+        TimerResult::Skipped => SessionEvent::Skipped { session, elapsed: timer.elapsed },
+        TimerResult::Quit => SessionEvent::Quit { session, elapsed: timer.elapsed },
+        TimerResult::Reset => SessionEvent::Reset { session, elapsed: timer.elapsed },
+        TimerResult::Interrupted => SessionEvent::Interrupted { session, elapsed: timer.elapsed },
+    }
+}
+
+// The event for a session that has just been paused or resumed
+pub fn pause_event<'a>(session: &'a str, state: &State) -> SessionEvent<'a> {
+    if state.paused {
+        SessionEvent::Paused { session, elapsed: state.timer.elapsed }
+    } else {
+        SessionEvent::Resumed { session, paused: state.paused_time }
+    }
+}
+
+pub fn to_json_line(event: &SessionEvent) -> Option<String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    serde_json::to_string(&Record { timestamp, event }).ok()
+}
+
+fn open_log(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
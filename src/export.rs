@@ -0,0 +1,193 @@
+//! `timeadair export`, the session history as CSV or JSON for spreadsheets
+//! and other tracking tools.
+//!
+//! Each session has its type, task, start and end time (RFC 3339 in local
+//! time), planned and actual duration in seconds, outcome, energy rating and note. The end is
+//! the start plus the time the session ran and was paused for. Sessions
+//! recorded before planned durations were kept leave that field empty.
+//!
+//! `--format ics` is a calendar of the completed work sessions instead,
+//! each an event named for its task, to lay over the real calendar and
+//! see where the focus went.
+
+use std::fmt::{Display, Write};
+
+use chrono::{NaiveDate, TimeZone};
+use serde::Serialize;
+
+use crate::history::{Outcome, SessionRecord};
+use crate::ics::{self, Event, When};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Ics,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Export {
+    pub format: ExportFormat,
+    // Only sessions started on or after this day
+    pub since: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct Row<'a> {
+    session: &'a str,
+    task: Option<&'a str>,
+    start: String,
+    end: String,
+    planned_duration: Option<u64>,
+    actual_duration: u64,
+    outcome: Outcome,
+    rating: Option<u8>,
+    note: Option<&'a str>,
+}
+
+pub fn export<Tz: TimeZone>(records: &[SessionRecord], export: &Export, tz: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    let rows = rows(records, export, tz);
+    match export.format {
+        ExportFormat::Json => serde_json::to_string_pretty(&rows).unwrap_or_default() + "\n",
+        ExportFormat::Csv => csv(&rows),
+        ExportFormat::Ics => ics::calendar(&focus_blocks(records, export, tz)),
+    }
+}
+
+// Completed work sessions within the export, as events in UTC
+fn focus_blocks<Tz: TimeZone>(records: &[SessionRecord], export: &Export, tz: &Tz) -> Vec<Event> {
+    records
+        .iter()
+        .filter(|record| record.session == "work" && record.outcome == Outcome::Completed)
+        .filter_map(|record| {
+            let start = tz.timestamp_opt(record.started, 0).single()?;
+            let end = start.clone() + chrono::Duration::seconds((record.duration + record.paused) as i64);
+            export.since.is_none_or(|since| start.date_naive() >= since).then(|| Event {
+                summary: record.task.clone().unwrap_or_else(|| "Work".to_string()),
+                start: Some(When::Utc(start.naive_utc())),
+                end: Some(When::Utc(end.naive_utc())),
+                rule: None,
+            })
+        })
+        .collect()
+}
+
+pub fn rows<'a, Tz: TimeZone>(records: &'a [SessionRecord], export: &Export, tz: &Tz) -> Vec<Row<'a>>
+where
+    Tz::Offset: Display,
+{
+    records
+        .iter()
+        .filter_map(|record| {
+            let start = tz.timestamp_opt(record.started, 0).single()?;
+            let end = tz.timestamp_opt(record.started + (record.duration + record.paused) as i64, 0).single()?;
+            Some((record, start, end))
+        })
+        .filter(|(_, start, _)| export.since.is_none_or(|since| start.date_naive() >= since))
+        .map(|(record, start, end)| Row {
+            session: &record.session,
+            task: record.task.as_deref(),
+            start: start.to_rfc3339(),
+            end: end.to_rfc3339(),
+            planned_duration: record.planned,
+            actual_duration: record.duration,
+            outcome: record.outcome,
+            rating: record.rating,
+            note: record.note.as_deref(),
+        })
+        .collect()
+}
+
+fn csv(rows: &[Row]) -> String {
+    let mut out = String::from("session,task,start,end,planned_duration,actual_duration,outcome,rating,note\n");
+    for row in rows {
+        let planned = row.planned_duration.map(|seconds| seconds.to_string()).unwrap_or_default();
+        let rating = row.rating.map(|rating| rating.to_string()).unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            field(row.session),
+            field(row.task.unwrap_or_default()),
+            row.start,
+            row.end,
+            planned,
+            row.actual_duration,
+            row.outcome.name(),
+            rating,
+            field(row.note.unwrap_or_default())
+        );
+    }
+    out
+}
+
+// Quoted only when it has to be, with any quotes inside doubled
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn record(started: i64, task: Option<&str>, outcome: Outcome) -> SessionRecord {
+        SessionRecord {
+            session: "work".to_string(),
+            started,
+            duration: 1500,
+            paused: 60,
+            outcome,
+            task: task.map(str::to_string),
+            planned: Some(1500),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn csv_since_a_day() {
+        // 2024-05-01 and 2024-05-02, both at noon
+        let records = [
+            record(1714564800, None, Outcome::Completed),
+            SessionRecord { note: Some("Sent it".to_string()), ..record(1714651200, Some("Report, \"final\""), Outcome::Skipped) },
+        ];
+        let since = NaiveDate::from_ymd_opt(2024, 5, 2);
+        let text = export(&records, &Export { format: ExportFormat::Csv, since }, &Utc);
+        assert_eq!(
+            text,
+            "session,task,start,end,planned_duration,actual_duration,outcome,rating,note\n\
+             work,\"Report, \"\"final\"\"\",2024-05-02T12:00:00+00:00,2024-05-02T12:26:00+00:00,1500,1500,skipped,,Sent it\n"
+        );
+    }
+
+    #[test]
+    fn ics_of_completed_work() {
+        let records = [
+            record(1714564800, Some("Report"), Outcome::Completed),
+            record(1714566600, None, Outcome::Quit),
+            SessionRecord { session: "break".to_string(), ..record(1714568400, None, Outcome::Completed) },
+            record(1714570200, None, Outcome::Completed),
+        ];
+        let text = export(&records, &Export { format: ExportFormat::Ics, since: None }, &Utc);
+        let events = ics::events(&text);
+        assert_eq!(events.iter().map(|event| event.summary.as_str()).collect::<Vec<_>>(), ["Report", "Work"]);
+        assert!(text.contains("DTSTART:20240501T120000Z\r\nDTEND:20240501T122600Z\r\n"));
+    }
+
+    #[test]
+    fn json_rows() {
+        let records = [SessionRecord { planned: None, ..record(1714564800, None, Outcome::Completed) }];
+        let text = export(&records, &Export { format: ExportFormat::Json, since: None }, &Utc);
+        let rows: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(rows[0]["start"], "2024-05-01T12:00:00+00:00");
+        assert_eq!(rows[0]["outcome"], "completed");
+        assert!(rows[0]["planned_duration"].is_null());
+    }
+}
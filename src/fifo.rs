@@ -0,0 +1,59 @@
+//! Command input over a named pipe.
+//!
+//! Writing a command name to the FIFO applies it to the running session,
+//! e.g. `echo reset > ~/.local/share/timeadair/cmd`. Unknown lines are
+//! ignored. Only available on Unix.
+
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use crate::commands::Command;
+use crate::paths;
+
+pub fn listen(path: &Path, commands: Sender<Command>) -> io::Result<()> {
+    paths::ensure_writable()?;
+    create_fifo(path)?;
+    let path: PathBuf = path.to_path_buf();
+    thread::spawn(move || loop {
+        // Opening blocks until a writer shows up, and each writer's EOF
+        // sends us back round to wait for the next one
+        let Ok(fifo) = File::open(&path) else { return };
+        for line in BufReader::new(fifo).lines() {
+            let Ok(line) = line else { break };
+            if let Some(command) = Command::parse(&line) {
+                if commands.send(command).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn create_fifo(path: &Path) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(meta) if meta.file_type().is_fifo() => return Ok(()),
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a FIFO", path.display()),
+            ))
+        }
+        Err(_) => {}
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
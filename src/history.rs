@@ -0,0 +1,314 @@
+//! Session history, one JSON object per finished session in
+//! `history.jsonl` in the data directory, e.g.
+//! `{"session":"work","started":1714636800,"duration":1500,"paused":0,"outcome":"completed"}`,
+//! with a `task` field for work sessions that were given one and `planned`
+//! for the length the session was set to. With `--rate`, completed work
+//! sessions also have the 1-5 energy `rating` given afterwards, and work
+//! sessions that were interrupted have `interruptions`, e.g.
+//! `{"internal":2,"external":1}`.
+//!
+//! `duration` is the time the session actually ran, so a session that was
+//! skipped, quit or reset early records how far it got.
+//!
+//! Every append also adds the session to the daily tallies in
+//! `rollups.json` next to it (see `archive`).
+//!
+//! Writers take an exclusive lock on `history.lock` first, so the timer,
+//! the daemon and `history archive` running at once can't interleave their
+//! lines or lose one another's sessions. Each line goes out in a single
+//! write, after a newline if the last one was cut short.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive::Rollups;
+use crate::paths;
+use crate::timer::TimerResult;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    #[default]
+    Completed,
+    Skipped,
+    Quit,
+    Reset,
+    // The session was running when the system shut down
+    #[serde(rename = "system-interrupted")]
+    Interrupted,
+    // Given up on mid-work under --strict, after typing the phrase
+    Abandoned,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Completed => "completed",
+            Outcome::Skipped => "skipped",
+            Outcome::Quit => "quit",
+            Outcome::Reset => "reset",
+            Outcome::Interrupted => "system-interrupted",
+            Outcome::Abandoned => "abandoned",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Outcome> {
+        [Outcome::Completed, Outcome::Skipped, Outcome::Quit, Outcome::Reset, Outcome::Interrupted, Outcome::Abandoned]
+            .into_iter().find(|outcome| outcome.name() == name)
+    }
+}
+
+impl From<&TimerResult> for Outcome {
+    fn from(result: &TimerResult) -> Outcome {
+        match result {
+            TimerResult::Completed => Outcome::Completed,
+            TimerResult::Skipped => Outcome::Skipped,
+            TimerResult::Quit => Outcome::Quit,
+            TimerResult::Reset => Outcome::Reset,
+            TimerResult::Interrupted => Outcome::Interrupted,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interruption {
+    // The user's own train of thought, e.g. remembering an errand
+    Internal,
+    // Someone or something else, e.g. a colleague or a phone call
+    External,
+}
+
+impl Interruption {
+    pub fn name(self) -> &'static str {
+        match self {
+            Interruption::Internal => "internal",
+            Interruption::External => "external",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Interruptions {
+    pub internal: u32,
+    pub external: u32,
+}
+
+impl Interruptions {
+    pub fn add(&mut self, interruption: Interruption) {
+        match interruption {
+            Interruption::Internal => self.internal += 1,
+            Interruption::External => self.external += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.internal + self.external
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub session: String,
+    // Unix timestamp of when the session was started
+    pub started: i64,
+    pub duration: u64,
+    pub paused: u64,
+    pub outcome: Outcome,
+    // What a work session was for, if the user said
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    // How long the session was meant to run, missing from older records
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planned: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    // What the user said they got done, asked for after a work session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // What the roulette picked for a break
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity: Option<String>,
+    #[serde(default, skip_serializing_if = "Interruptions::is_empty")]
+    pub interruptions: Interruptions,
+    // The --profile the session was run with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    // Seconds of `duration` snoozed on past the end of a completed work
+    // session
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub overtime: u64,
+}
+
+fn is_zero(seconds: &u64) -> bool {
+    *seconds == 0
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("history.jsonl"))
+}
+
+pub struct History {
+    path: PathBuf,
+}
+
+pub struct HistoryLock {
+    _file: File,
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> History {
+        History { path }
+    }
+
+    pub fn append(&self, record: &SessionRecord) -> io::Result<()> {
+        let _lock = self.lock()?;
+        let mut line = serde_json::to_string(record)? + "\n";
+        let before = self.size()?;
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&self.path)?;
+        if before > 0 {
+            let mut last = [0];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                line.insert(0, '\n');
+            }
+        }
+        file.write_all(line.as_bytes())?;
+        // The session is recorded either way; tallies that miss it are
+        // rebuilt the next time they're read
+        let _ = Rollups::recorded(&self.rollups_path(), record, before, before + line.len() as u64);
+        Ok(())
+    }
+
+    // Held by whoever is writing the history or its rollups, and let go
+    // when dropped
+    pub fn lock(&self) -> io::Result<HistoryLock> {
+        paths::ensure_writable()?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(self.path.with_file_name("history.lock"))?;
+        file.lock()?;
+        Ok(HistoryLock { _file: file })
+    }
+
+    pub fn rollups_path(&self) -> PathBuf {
+        self.path.with_file_name("rollups.json")
+    }
+
+    // In bytes, for telling whether the rollups have kept up
+    pub fn size(&self) -> io::Result<u64> {
+        match fs::metadata(&self.path) {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Rewrites the whole history, e.g. once old sessions are archived; the
+    // lock is taken by the caller, so nothing can be appended between
+    // reading the history and writing it back
+    pub fn replace(&self, _lock: &HistoryLock, records: &[SessionRecord]) -> io::Result<()> {
+        let mut text = String::new();
+        for record in records {
+            text.push_str(&serde_json::to_string(record)?);
+            text.push('\n');
+        }
+        let staging = self.path.with_extension("tmp");
+        fs::write(&staging, text)?;
+        fs::rename(&staging, &self.path)
+    }
+
+    // Swaps the last record for `record` if it's still `was`, as when
+    // overtime is added to the session that was just recorded. It goes
+    // through `replace`, so any lines that don't parse are dropped for good
+    pub fn amend_last(&self, was: &SessionRecord, record: &SessionRecord) -> io::Result<()> {
+        let lock = self.lock()?;
+        let mut records = self.load()?;
+        match records.last_mut() {
+            Some(last) if last == was => *last = record.clone(),
+            _ => return Ok(()),
+        }
+        self.replace(&lock, &records)
+    }
+
+    // Lines that don't parse (e.g. one cut short by a crash) are skipped
+    pub fn load(&self) -> io::Result<Vec<SessionRecord>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn concurrent_appends_keep_every_line() {
+        let dir = std::env::temp_dir().join(format!("timeadair-history-{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        fs::create_dir_all(&dir).unwrap();
+        // Left without its newline by a crash
+        fs::write(&path, "{\"session\":\"work\",\"sta").unwrap();
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let history = History::new(path.clone());
+                thread::spawn(move || {
+                    for i in 0..20 {
+                        let record = SessionRecord {
+                            session: "work".to_string(),
+                            started: 1714651200 + writer * 100 + i,
+                            duration: 1500,
+                            outcome: Outcome::Completed,
+                            task: Some("x".repeat(5000)),
+                            ..Default::default()
+                        };
+                        history.append(&record).unwrap();
+                    }
+                })
+            })
+            .collect();
+        writers.into_iter().for_each(|writer| writer.join().unwrap());
+        let history = History::new(path);
+        assert_eq!(history.load().unwrap().len(), 160);
+        let days = crate::archive::daily(&history).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(days.values().map(|day| day.pomodoros).sum::<u32>(), 160);
+    }
+
+    #[test]
+    fn shutdowns_are_recorded_as_system_interrupted() {
+        let outcome = Outcome::from(&TimerResult::Interrupted);
+        assert_eq!(serde_json::to_string(&outcome).unwrap(), "\"system-interrupted\"");
+        assert_eq!(Outcome::parse(outcome.name()), Some(outcome));
+    }
+
+    #[test]
+    fn overtime_amends_the_last_record() {
+        let dir = std::env::temp_dir().join(format!("timeadair-overtime-{}", std::process::id()));
+        let history = History::new(dir.join("history.jsonl"));
+        let line = "{\"session\":\"work\",\"started\":1714651200,\"duration\":1500,\"paused\":0,\"outcome\":\"completed\"}";
+        let was: SessionRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(serde_json::to_string(&was).unwrap(), line);
+        history.append(&was).unwrap();
+        let snoozed = SessionRecord { duration: 1800, overtime: 300, ..was.clone() };
+        history.amend_last(&was, &snoozed).unwrap();
+        // Already amended, so it's no longer the one to change
+        history.amend_last(&was, &SessionRecord { duration: 9, ..was.clone() }).unwrap();
+        let records = history.load().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(records, [snoozed]);
+    }
+}
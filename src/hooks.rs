@@ -0,0 +1,134 @@
+//! Shell commands run as sessions start and end, set in the config file:
+//!
+//! ```toml
+//! on_work_start = "makoctl mode -a do-not-disturb"
+//! on_work_end = "makoctl mode -r do-not-disturb"
+//! on_break_start = "playerctl pause"
+//! ```
+//!
+//! Each runs through `sh -c` (`cmd /C` on Windows) without being waited for,
+//! with `TIMEADAIR_SESSION`, `TIMEADAIR_DURATION` (planned, in seconds) and
+//! `TIMEADAIR_TASK` set, plus `TIMEADAIR_ELAPSED` and `TIMEADAIR_OUTCOME`
+//! (`completed`, `skipped`, `quit` or `reset`) for the end hooks.
+//! `on_nudge` runs instead when the daemon nudges, see `nudge`. Under
+//! `--dry-run` each is logged with its environment rather than run.
+
+use std::io;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::dryrun::{DryRun, Integration};
+use crate::history::Outcome;
+use crate::scheduler::Phase;
+use crate::timer::TimerResult;
+
+#[derive(Clone, Debug, Default)]
+pub struct Hooks {
+    pub on_work_start: Option<String>,
+    pub on_work_end: Option<String>,
+    pub on_break_start: Option<String>,
+    pub on_break_end: Option<String>,
+    pub on_nudge: Option<String>,
+    pub dry_run: Option<DryRun>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        [&self.on_work_start, &self.on_work_end, &self.on_break_start, &self.on_break_end, &self.on_nudge].iter().all(|hook| hook.is_none())
+    }
+
+    pub fn session_started(&self, phase: Phase, duration: u64, task: Option<&str>) -> io::Result<()> {
+        let (name, hook) = if phase == Phase::Work { ("on_work_start", &self.on_work_start) } else { ("on_break_start", &self.on_break_start) };
+        match hook {
+            Some(hook) => self.run(name, hook, &environment(phase, duration, task, None)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn session_ended(&self, phase: Phase, duration: u64, task: Option<&str>, elapsed: u64, result: &TimerResult) -> io::Result<()> {
+        let (name, hook) = if phase == Phase::Work { ("on_work_end", &self.on_work_end) } else { ("on_break_end", &self.on_break_end) };
+        match hook {
+            Some(hook) => self.run(name, hook, &environment(phase, duration, task, Some((elapsed, result)))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn nudged(&self, active: u64, message: &str) -> io::Result<()> {
+        match &self.on_nudge {
+            Some(hook) => self.run("on_nudge", hook, &[("TIMEADAIR_ACTIVE", active.to_string()), ("TIMEADAIR_MESSAGE", message.to_string())]),
+            None => Ok(()),
+        }
+    }
+
+    fn run(&self, name: &str, hook: &str, vars: &[(&'static str, String)]) -> io::Result<()> {
+        match self.dry_run.as_ref().filter(|dry_run| dry_run.covers(Integration::Hooks)) {
+            Some(dry_run) => dry_run.log(Integration::Hooks, &dry_run_line(name, hook, vars)),
+            None => run(hook, vars),
+        }
+    }
+}
+
+// `on_work_start: makoctl mode -a do-not-disturb (TIMEADAIR_SESSION=work ...)`
+fn dry_run_line(name: &str, hook: &str, vars: &[(&'static str, String)]) -> String {
+    let vars: Vec<_> = vars.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+    format!("{}: {} ({})", name, hook, vars.join(" "))
+}
+
+fn environment(phase: Phase, duration: u64, task: Option<&str>, ended: Option<(u64, &TimerResult)>) -> Vec<(&'static str, String)> {
+    let mut vars = vec![
+        ("TIMEADAIR_SESSION", phase.name().to_lowercase()),
+        ("TIMEADAIR_DURATION", duration.to_string()),
+        ("TIMEADAIR_TASK", task.unwrap_or_default().to_string()),
+    ];
+    if let Some((elapsed, result)) = ended {
+        vars.push(("TIMEADAIR_ELAPSED", elapsed.to_string()));
+        vars.push(("TIMEADAIR_OUTCOME", Outcome::from(result).name().to_string()));
+    }
+    vars
+}
+
+fn run(hook: &str, vars: &[(&'static str, String)]) -> io::Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", hook]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", hook]);
+        command
+    };
+    // The terminal belongs to the timer
+    let mut child = command
+        .envs(vars.iter().map(|(name, value)| (name, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_for_start_and_end() {
+        let started = environment(Phase::Work, 1500, Some("Write report"), None);
+        assert_eq!(
+            started,
+            [
+                ("TIMEADAIR_SESSION", "work".to_string()),
+                ("TIMEADAIR_DURATION", "1500".to_string()),
+                ("TIMEADAIR_TASK", "Write report".to_string()),
+            ]
+        );
+        let ended = environment(Phase::Break, 300, None, Some((120, &TimerResult::Skipped)));
+        assert_eq!(ended[2], ("TIMEADAIR_TASK", String::new()));
+        assert_eq!(ended[3..], [("TIMEADAIR_ELAPSED", "120".to_string()), ("TIMEADAIR_OUTCOME", "skipped".to_string())]);
+        assert_eq!(
+            dry_run_line("on_work_start", "make lint", &started),
+            "on_work_start: make lint (TIMEADAIR_SESSION=work TIMEADAIR_DURATION=1500 TIMEADAIR_TASK=Write report)"
+        );
+    }
+}
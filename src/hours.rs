@@ -0,0 +1,42 @@
+use std::fmt;
+
+use chrono::{Local, NaiveTime};
+
+#[derive(Clone, Copy)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub strict: bool,
+}
+
+impl WorkingHours {
+    // Parses a range such as "09:00-17:30"; ranges may wrap past midnight
+    pub fn parse(text: &str) -> Result<WorkingHours, String> {
+        let invalid = || format!("invalid working hours '{}', expected HH:MM-HH:MM", text);
+        let (start, end) = text.split_once('-').ok_or_else(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+        if start == end {
+            return Err(format!("working hours '{}' are empty", text));
+        }
+        Ok(WorkingHours { start, end, strict: false })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    pub fn contains_now(&self) -> bool {
+        self.contains(Local::now().time())
+    }
+}
+
+impl fmt::Display for WorkingHours {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
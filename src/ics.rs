@@ -0,0 +1,196 @@
+//! Just enough iCalendar to tell when events are, for the vacation days
+//! that streaks skip and the meetings a work session shouldn't run into.
+//! Times with a TZID are taken to be in the local time zone, since there's
+//! no time zone database to look them up in. It's written too, for
+//! `export --format ics`, with every time in UTC.
+
+use std::fs;
+use std::io;
+use std::process::{Command, Stdio};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum When {
+    Date(NaiveDate),
+    Local(NaiveDateTime),
+    Utc(NaiveDateTime),
+}
+
+impl When {
+    // `20241227`, `20241230T090000` or `20241230T090000Z`
+    pub fn parse(value: &str) -> Option<When> {
+        let value = value.trim();
+        if !value.contains('T') {
+            return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(When::Date);
+        }
+        match value.strip_suffix('Z') {
+            Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok().map(When::Utc),
+            None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok().map(When::Local),
+        }
+    }
+
+    pub fn date(self) -> NaiveDate {
+        match self {
+            When::Date(day) => day,
+            When::Local(time) | When::Utc(time) => time.date(),
+        }
+    }
+
+    // Midnight, as an end, stops short of the day it's on
+    pub fn is_midnight(self) -> bool {
+        match self {
+            When::Date(_) => true,
+            When::Local(time) | When::Utc(time) => time.time() == NaiveTime::MIN,
+        }
+    }
+
+    // In local time; None for a whole day, which has no time of its own
+    pub fn local(self) -> Option<NaiveDateTime> {
+        match self {
+            When::Date(_) => None,
+            When::Local(time) => Some(time),
+            When::Utc(time) => Some(time.and_utc().with_timezone(&Local).naive_local()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub start: Option<When>,
+    pub end: Option<When>,
+    // The RRULE as written, e.g. `FREQ=WEEKLY;BYDAY=MO,WE`
+    pub rule: Option<String>,
+}
+
+// A calendar at a path or an http(s) URL, such as a CalDAV calendar's
+// export link
+pub fn fetch(source: &str) -> io::Result<String> {
+    if !(source.starts_with("http://") || source.starts_with("https://")) {
+        return fs::read_to_string(source);
+    }
+    let output = Command::new("curl").args(["-fsSL", "-m", "10", source]).stdin(Stdio::null()).stderr(Stdio::null()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("unable to fetch {}", source)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn events(ics: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut event: Option<Event> = None;
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        // Parameters such as `;VALUE=DATE` or `;TZID=...` come after the name
+        let property = name.split(';').next().unwrap_or_default().to_uppercase();
+        match (property.as_str(), &mut event) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => event = Some(Event::default()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => events.extend(event.take()),
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("DTSTART", Some(event)) => event.start = When::parse(value),
+            ("DTEND", Some(event)) => event.end = When::parse(value),
+            ("RRULE", Some(event)) => event.rule = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    events
+}
+
+// A whole calendar of `events`, each with a UID made from its start so
+// that importing it again updates them rather than adding more
+pub fn calendar(events: &[Event]) -> String {
+    let mut lines = vec!["BEGIN:VCALENDAR".to_string(), "VERSION:2.0".to_string(), "PRODID:-//timeadair//EN".to_string()];
+    for event in events {
+        let (Some(start), Some(end)) = (event.start, event.end) else { continue };
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@timeadair", value(start)));
+        // When it was recorded, which was when it ended
+        lines.push(format!("DTSTAMP:{}", value(end)));
+        lines.push(format!("DTSTART:{}", value(start)));
+        lines.push(format!("DTEND:{}", value(end)));
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+fn value(when: When) -> String {
+    match when {
+        When::Date(day) => day.format("%Y%m%d").to_string(),
+        When::Local(time) => time.format("%Y%m%dT%H%M%S").to_string(),
+        When::Utc(time) => time.format("%Y%m%dT%H%M%SZ").to_string(),
+    }
+}
+
+// Lines end in CRLF and are broken before 75 bytes, never inside a
+// character
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded + "\r\n"
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+// Long lines carry on in the next one, after a space or tab
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    text.trim().replace("\\n", " ").replace("\\N", " ").replace("\\,", ",").replace("\\;", ";").replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_events() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Stand\r\n up\\, daily\r\nDTSTART;TZID=Europe/London:20241230T090000\r\n\
+                   DTEND;TZID=Europe/London:20241230T091500\r\nRRULE:FREQ=DAILY;COUNT=5\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let day = NaiveDate::from_ymd_opt(2024, 12, 30).unwrap();
+        let events = events(ics);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Standup, daily");
+        assert_eq!(events[0].start, Some(When::Local(day.and_hms_opt(9, 0, 0).unwrap())));
+        assert_eq!(events[0].rule.as_deref(), Some("FREQ=DAILY;COUNT=5"));
+        assert_eq!(When::parse("20241230"), Some(When::Date(day)));
+        assert!(When::parse("20241231T000000Z").unwrap().is_midnight());
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        let start = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        let event = Event {
+            summary: format!("Report, part 1; {}", "long ".repeat(16)).trim_end().to_string(),
+            start: Some(When::Utc(start)),
+            end: Some(When::Utc(start + chrono::Duration::minutes(25))),
+            rule: None,
+        };
+        let ics = calendar(std::slice::from_ref(&event));
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.contains("\r\nUID:20240502T080000Z@timeadair\r\nDTSTAMP:20240502T082500Z\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 76));
+        assert_eq!(events(&ics), [event]);
+    }
+}
@@ -0,0 +1,205 @@
+//! How long since the keyboard or mouse was last touched, for `idle_pause`:
+//! a work session pauses itself once nobody has been there for that many
+//! minutes, and on return asks whether the time away was work.
+//!
+//! The system is asked every few seconds, in whichever way works here:
+//! `xprintidle` on X11, GNOME's idle monitor over D-Bus on Wayland,
+//! `ioreg` on macOS and `GetLastInputInfo` on Windows.
+
+#[cfg(not(windows))]
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::scheduler::Event;
+use crate::stopwatch::format_elapsed;
+
+const CHECK_EVERY: Duration = Duration::from_secs(5);
+
+// A program, its arguments and how to read seconds from what it prints
+#[cfg(not(windows))]
+type Probe = (&'static str, &'static [&'static str], fn(&str) -> Option<u64>);
+
+// Each reports milliseconds, apart from `ioreg`'s nanoseconds
+#[cfg(not(windows))]
+const PROBES: [Probe; 3] = [
+    ("xprintidle", &[], parse_milliseconds),
+    (
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ],
+        parse_gdbus,
+    ),
+    ("ioreg", &["-c", "IOHIDSystem", "-d", "4"], parse_ioreg),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Poll {
+    // Not due for another look yet
+    NotYet,
+    Idle(u64),
+    // Told once, after which the monitor stays quiet
+    Unavailable,
+}
+
+pub struct IdleMonitor {
+    // Seconds
+    pub threshold: u64,
+    // The probe that answered last time, once one has
+    probe: Option<usize>,
+    given_up: bool,
+    next_check: Instant,
+}
+
+impl IdleMonitor {
+    pub fn new(threshold: u64) -> IdleMonitor {
+        IdleMonitor { threshold, probe: None, given_up: false, next_check: Instant::now() }
+    }
+
+    pub fn poll(&mut self) -> Poll {
+        if self.given_up || Instant::now() < self.next_check {
+            return Poll::NotYet;
+        }
+        self.next_check = Instant::now() + CHECK_EVERY;
+        match self.idle_seconds() {
+            Some(seconds) => Poll::Idle(seconds),
+            // Something that worked before can fail for a moment
+            None if self.probe.is_some() => Poll::NotYet,
+            None => {
+                self.given_up = true;
+                Poll::Unavailable
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn idle_seconds(&mut self) -> Option<u64> {
+        let order = self.probe.into_iter().chain(0..PROBES.len());
+        for index in order {
+            let (program, args, parse) = PROBES[index];
+            let Ok(output) = Command::new(program).args(args).output() else { continue };
+            let Some(seconds) = output.status.success().then(|| parse(&String::from_utf8_lossy(&output.stdout))).flatten() else {
+                continue;
+            };
+            self.probe = Some(index);
+            return Some(seconds);
+        }
+        None
+    }
+
+    #[cfg(windows)]
+    fn idle_seconds(&mut self) -> Option<u64> {
+        let seconds = windows::idle_seconds()?;
+        self.probe = Some(0);
+        Some(seconds)
+    }
+}
+
+// A work session paused for want of anyone at the keyboard
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Away {
+    // Idle seconds that ran on the clock before the pause
+    pub counted: u64,
+    // The session's paused time when it paused
+    pub paused_at: u64,
+}
+
+impl Away {
+    pub fn seconds(&self, paused_time: u64) -> u64 {
+        self.counted + paused_time.saturating_sub(self.paused_at)
+    }
+
+    // What to say while the session waits to hear about the time away
+    pub fn message(&self, paused_time: u64) -> String {
+        let away = format_elapsed(self.seconds(paused_time));
+        format!("Paused after {} away: 'd' discards that time, 'k' counts it as work", away)
+    }
+
+    // How the answer changes the session, before it resumes
+    pub fn settle(&self, keep: bool, paused_time: u64) -> Event {
+        if keep {
+            Event::Credit(paused_time.saturating_sub(self.paused_at))
+        } else {
+            Event::Discard(self.counted)
+        }
+    }
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+fn parse_milliseconds(output: &str) -> Option<u64> {
+    output.trim().parse::<u64>().ok().map(|ms| ms / 1000)
+}
+
+// `(uint64 12345,)`
+#[cfg_attr(windows, allow(dead_code))]
+fn parse_gdbus(output: &str) -> Option<u64> {
+    parse_milliseconds(output.trim().strip_prefix("(uint64 ")?.strip_suffix(",)")?)
+}
+
+// `    |   "HIDIdleTime" = 1234567890`
+#[cfg_attr(windows, allow(dead_code))]
+fn parse_ioreg(output: &str) -> Option<u64> {
+    let line = output.lines().find(|line| line.contains("\"HIDIdleTime\""))?;
+    let nanoseconds: u64 = line.split('=').nth(1)?.trim().parse().ok()?;
+    Some(nanoseconds / 1_000_000_000)
+}
+
+#[cfg(windows)]
+mod windows {
+    #[repr(C)]
+    struct LastInputInfo {
+        size: u32,
+        time: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(info: *mut LastInputInfo) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    pub fn idle_seconds() -> Option<u64> {
+        let mut info = LastInputInfo { size: std::mem::size_of::<LastInputInfo>() as u32, time: 0 };
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        // Both wrap after 49 days, together
+        let ticks = unsafe { GetTickCount() };
+        Some(u64::from(ticks.wrapping_sub(info.time)) / 1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_each_probe() {
+        assert_eq!(parse_milliseconds("4521\n"), Some(4));
+        assert_eq!(parse_gdbus("(uint64 125000,)\n"), Some(125));
+        let ioreg = "+-o IOHIDSystem\n    |   \"HIDIdleTime\" = 61000000000\n    |   \"HIDKind\" = 1\n";
+        assert_eq!(parse_ioreg(ioreg), Some(61));
+        assert_eq!(parse_gdbus("Error: GDBus.Error"), None);
+    }
+
+    #[test]
+    fn settles_the_time_away() {
+        // Five idle minutes ran before the pause, then seven and a half paused
+        let away = Away { counted: 300, paused_at: 20 };
+        assert_eq!(away.seconds(470), 750);
+        assert_eq!(away.message(470), "Paused after 12:30 away: 'd' discards that time, 'k' counts it as work");
+        assert_eq!(away.settle(false, 470), Event::Discard(300));
+        assert_eq!(away.settle(true, 470), Event::Credit(450));
+    }
+}
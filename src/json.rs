@@ -0,0 +1,45 @@
+//! `--json` output for the subcommands, so that scripts can drive the whole
+//! tool and not just `ctl status`.
+//!
+//! Every command prints a single JSON document on stdout, whether it worked:
+//!
+//! ```text
+//! {"command":"stats","data":{...},"ok":true}
+//! ```
+//!
+//! or not, in which case the exit code is the same as without `--json`:
+//!
+//! ```text
+//! {"command":"ctl","error":{"exit_code":1,"hint":"...","message":"..."},"ok":false}
+//! ```
+//!
+//! `command` is null when the arguments couldn't be made sense of.
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::Error;
+
+pub fn success(command: &str, data: impl Serialize) -> String {
+    json!({ "ok": true, "command": command, "data": data }).to_string()
+}
+
+pub fn failure(command: Option<&str>, error: &Error) -> String {
+    let error = json!({ "message": error.to_string(), "hint": error.hint(), "exit_code": error.exit_code() });
+    json!({ "ok": false, "command": command, "error": error }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelopes() {
+        assert_eq!(success("sounds", ["default"]), r#"{"command":"sounds","data":["default"],"ok":true}"#);
+        let error = Error::Usage("ctl requires a command".to_string());
+        assert_eq!(
+            failure(Some("ctl"), &error),
+            r#"{"command":"ctl","error":{"exit_code":2,"hint":null,"message":"ctl requires a command"},"ok":false}"#
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! `timeadair leaderboard`: pomodoros this week for everyone in a team, as
+//! a Markdown table to paste into a team channel. Each participant's
+//! history is read from wherever the team keeps them, such as a synced
+//! folder each member points `--data-dir` at, named on the command line or
+//! once in the config file:
+//!
+//! ```toml
+//! [leaderboard]
+//! alice = "~/Sync/team/alice"       # a data directory, or its history.jsonl
+//! bob = "~/Sync/team/bob/history.jsonl"
+//! ```
+//!
+//! `timeadair leaderboard alice=<path> bob=<path>` does the same for one
+//! run. Nothing is read unless asked for, and `--anonymous` puts
+//! "Participant A", "B" and so on in place of the names.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeZone};
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::history::{History, Outcome, SessionRecord};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Leaderboard {
+    pub participants: Vec<(String, PathBuf)>,
+    pub anonymous: bool,
+}
+
+impl Leaderboard {
+    // `name=path` arguments, or the config file's table without any
+    pub fn parse(args: &[String], table: Option<&BTreeMap<String, String>>, anonymous: bool) -> Result<Leaderboard, String> {
+        let mut participants = Vec::new();
+        for arg in args {
+            let (name, path) = arg
+                .split_once('=')
+                .filter(|(name, path)| !name.trim().is_empty() && !path.is_empty())
+                .ok_or_else(|| format!("leaderboard expects participants like alice=~/Sync/alice, not '{}'", arg))?;
+            participants.push((name.trim().to_string(), expand(path)));
+        }
+        if participants.is_empty() {
+            participants = table.into_iter().flatten().map(|(name, path)| (name.clone(), expand(path))).collect();
+        }
+        if participants.is_empty() {
+            return Err("leaderboard needs participants, e.g. 'timeadair leaderboard alice=~/Sync/alice bob=~/Sync/bob'".to_string());
+        }
+        Ok(Leaderboard { participants, anonymous })
+    }
+
+    // Every participant's sessions; a history that isn't there is an
+    // error, since it's most likely a wrong path
+    pub fn load(&self) -> Result<Vec<(String, Vec<SessionRecord>)>, Error> {
+        self.participants
+            .iter()
+            .map(|(name, path)| {
+                let file = if path.is_dir() { path.join("history.jsonl") } else { path.clone() };
+                // History::load takes a missing file for an empty history
+                let records = fs::metadata(&file).and_then(|_| History::new(file.clone()).load());
+                let records = records.map_err(|source| Error::Leaderboard { name: name.clone(), path: file, source })?;
+                Ok((name.clone(), records))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Standing {
+    // Shared by everyone on the same count
+    pub rank: usize,
+    pub name: String,
+    pub pomodoros: u32,
+    pub focus: u64,
+}
+
+// Completed work sessions since Monday, most first
+pub fn standings<Tz: TimeZone>(participants: &[(String, Vec<SessionRecord>)], anonymous: bool, now: DateTime<Tz>) -> Vec<Standing> {
+    let monday = week_of(now.date_naive());
+    let mut standings: Vec<_> = participants
+        .iter()
+        .map(|(name, records)| {
+            let this_week = records.iter().filter(|record| {
+                record.session == "work"
+                    && now.timezone().timestamp_opt(record.started, 0).single().is_some_and(|started| started.date_naive() >= monday)
+            });
+            let (pomodoros, focus) = this_week.fold((0, 0), |(pomodoros, focus), record| {
+                (pomodoros + (record.outcome == Outcome::Completed) as u32, focus + record.duration)
+            });
+            Standing { rank: 0, name: name.clone(), pomodoros, focus }
+        })
+        .collect();
+    standings.sort_by(|a, b| b.pomodoros.cmp(&a.pomodoros).then(b.focus.cmp(&a.focus)).then(a.name.cmp(&b.name)));
+    for i in 0..standings.len() {
+        standings[i].rank = match i {
+            0 => 1,
+            _ if standings[i].pomodoros == standings[i - 1].pomodoros => standings[i - 1].rank,
+            _ => i + 1,
+        };
+        if anonymous {
+            standings[i].name = format!("Participant {}", letters(i));
+        }
+    }
+    standings
+}
+
+pub fn markdown(standings: &[Standing], today: NaiveDate) -> String {
+    let mut out = format!("### Pomodoros, week of {}\n\n", week_of(today).format("%-d %B %Y"));
+    out.push_str("| # | Name | Pomodoros | Focus |\n|--:|:-----|----------:|------:|\n");
+    for standing in standings {
+        let focus = format!("{}h {:02}m", standing.focus / 3600, standing.focus % 3600 / 60);
+        let _ = writeln!(out, "| {} | {} | {} | {} |", standing.rank, escape(&standing.name), standing.pomodoros, focus);
+    }
+    out
+}
+
+// The same for `timeadair leaderboard --json`
+pub fn data(standings: &[Standing], today: NaiveDate) -> Value {
+    json!({
+        "week_of": week_of(today).to_string(),
+        "standings": standings
+            .iter()
+            .map(|standing| json!({
+                "rank": standing.rank,
+                "name": standing.name,
+                "pomodoros": standing.pomodoros,
+                "focus": standing.focus,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn week_of(day: NaiveDate) -> NaiveDate {
+    day - Days::new(day.weekday().num_days_from_monday().into())
+}
+
+// A to Z, then AA, AB and so on
+fn letters(index: usize) -> String {
+    let letter = char::from(b'A' + (index % 26) as u8);
+    match index / 26 {
+        0 => letter.to_string(),
+        more => format!("{}{}", letters(more - 1), letter),
+    }
+}
+
+// A `|` in a name would start a new column
+fn escape(name: &str) -> String {
+    name.replace('|', "\\|")
+}
+
+fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn ranks_the_week() {
+        let session = |started, outcome| SessionRecord {
+            session: "work".to_string(),
+            started,
+            duration: 1500,
+            outcome,
+            ..Default::default()
+        };
+        // Thursday 2024-05-02 noon, the Monday before, and the Sunday before that
+        let (thursday, monday, sunday) = (1714651200, 1714392000, 1714305600);
+        let participants = vec![
+            ("carol".to_string(), vec![session(sunday, Outcome::Completed), session(monday, Outcome::Completed)]),
+            ("alice".to_string(), vec![session(monday, Outcome::Completed), session(thursday, Outcome::Completed)]),
+            ("bob|ops".to_string(), vec![session(thursday, Outcome::Completed), session(thursday, Outcome::Skipped)]),
+        ];
+        let now = Utc.timestamp_opt(thursday, 0).unwrap();
+        let standings = standings(&participants, false, now);
+        let ranks: Vec<_> = standings.iter().map(|standing| (standing.rank, standing.name.as_str(), standing.pomodoros)).collect();
+        assert_eq!(ranks, [(1, "alice", 2), (2, "bob|ops", 1), (2, "carol", 1)]);
+        assert_eq!(
+            markdown(&standings, now.date_naive()),
+            "### Pomodoros, week of 29 April 2024\n\n| # | Name | Pomodoros | Focus |\n|--:|:-----|----------:|------:|\n\
+             | 1 | alice | 2 | 0h 50m |\n| 2 | bob\\|ops | 1 | 0h 50m |\n| 2 | carol | 1 | 0h 25m |\n"
+        );
+        let anonymous: Vec<_> = super::standings(&participants, true, now).into_iter().map(|standing| standing.name).collect();
+        assert_eq!(anonymous, ["Participant A", "Participant B", "Participant C"]);
+        assert_eq!(letters(27), "AB");
+
+        assert!(Leaderboard::parse(&["alice".to_string()], None, false).is_err());
+        let table = BTreeMap::from([("dan".to_string(), "/srv/dan".to_string())]);
+        let board = Leaderboard::parse(&[], Some(&table), true).unwrap();
+        assert_eq!(board.participants, [("dan".to_string(), PathBuf::from("/srv/dan"))]);
+    }
+}
@@ -0,0 +1,15 @@
+//! The pomodoro engine behind timeadair, with no terminal code, for
+//! embedding the timer in other frontends such as status-bar widgets.
+//!
+//! `PomodoroEngine` is the easiest way in: call `tick` once a second and
+//! `start`, `pause`, `skip` and friends as the user asks. The `scheduler`
+//! module underneath is the same pure state machine the timeadair binary
+//! drives, for frontends that want to hold the state themselves.
+
+pub mod engine;
+pub mod scheduler;
+pub mod timer;
+
+pub use engine::{PomodoroEngine, Session, SessionKind};
+pub use scheduler::Durations;
+pub use timer::{Countdown, Timer, TimerResult};
@@ -0,0 +1,399 @@
+//! What the timer says, in English or Gàidhlig. The language comes from
+//! `--locale` or `locale` in the config file, and otherwise from the usual
+//! `LC_ALL`, `LC_MESSAGES` and `LANG`, so `LANG=gd_GB.UTF-8` is enough.
+//! Anything without a translation falls back to English.
+//!
+//! Every string the prompts, header, controls lines and stats report use is
+//! a field of `Text`; the ones with `{}` in them are filled in order by
+//! `fill`. Session names stay English everywhere else, in history and in
+//! the JSON output, and are only translated for display.
+
+use std::fmt::{Display, Write};
+
+pub const LOCALES: [&str; 2] = ["en", "gd"];
+
+// The English names sessions go by, in the order of `Text::sessions`
+const SESSIONS: [&str; 3] = ["work", "break", "long break"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Locale {
+    #[default]
+    English,
+    Gaelic,
+}
+
+impl Locale {
+    // `gd`, or a full locale name like `gd_GB.UTF-8`
+    pub fn parse(name: &str) -> Result<Locale, String> {
+        let language = name.split(['_', '-', '.', '@']).next().unwrap_or_default();
+        match language {
+            "en" | "C" | "POSIX" => Ok(Locale::English),
+            "gd" => Ok(Locale::Gaelic),
+            _ => Err(format!("--locale must be one of {}", LOCALES.join(", "))),
+        }
+    }
+
+    // The first of these that's set wins, as it does for everything else
+    pub fn from_env() -> Locale {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Locale::parse(&value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn text(self) -> &'static Text {
+        match self {
+            Locale::English => &ENGLISH,
+            Locale::Gaelic => &GAELIC,
+        }
+    }
+}
+
+// A noun's forms after a number
+pub struct Noun {
+    pub one: &'static str,
+    pub many: &'static str,
+}
+
+pub struct Text {
+    pub header: &'static str,
+    pub farewell: &'static str,
+    pub timer_reset: &'static str,
+    pub session_complete: &'static str,
+    pub session_skipped: &'static str,
+    pub exit: &'static str,
+    pub controls: &'static str,
+    pub stopwatch_controls: &'static str,
+    pub checklist_controls: &'static str,
+    pub board_controls: &'static str,
+    pub board_search: &'static str,
+    pub integrations_controls: &'static str,
+    pub dashboard_controls: &'static str,
+    pub dashboard_empty: &'static str,
+    pub task_prompt: &'static str,
+    pub task_picker_prompt: &'static str,
+    pub resume_prompt: &'static str,
+    pub rating_prompt: &'static str,
+    pub note_prompt: &'static str,
+    pub timer_prompt: &'static str,
+    // Timer name
+    pub timer_done: &'static str,
+    // The break activity going round, then the one it landed on
+    pub roulette_spin: &'static str,
+    pub roulette_landed: &'static str,
+    // Session type
+    pub start_prompt: &'static str,
+    // Session name, its length, seconds
+    pub countdown: &'static str,
+    // Minutes 'z' adds, at the break prompt
+    pub snooze_notice: &'static str,
+    pub overtime: &'static str,
+    // Task, pomodoros
+    pub done_prompt: &'static str,
+    // Meeting and the time it starts, then minutes and the meeting
+    pub meeting_notice: &'static str,
+    pub shorten_prompt: &'static str,
+    // Typed back to give up on a work session under --strict
+    pub abandon_phrase: &'static str,
+    pub abandon_prompt: &'static str,
+    // Session name, then the task if there is one
+    pub current_session: &'static str,
+    pub current_task: &'static str,
+    pub up_next: &'static str,
+    // A break suggestion, as its own line in plain output
+    pub suggestion: &'static str,
+    // Pomodoros done today, then the daily goal
+    pub goal_today: &'static str,
+    pub goal_reached: &'static str,
+    // Minutes at the keyboard with no session running
+    pub nudge: &'static str,
+    pub reminder: &'static str,
+    pub remaining: &'static str,
+    // Message, time left and percentage, for the plain display
+    pub remaining_plain: &'static str,
+    // Message and the time left in words, for --accessible
+    pub remaining_spoken: &'static str,
+    pub minutes: &'static str,
+    pub one_minute: &'static str,
+    pub seconds: &'static str,
+    pub one_second: &'static str,
+    pub interruptions: &'static str,
+    pub paused: &'static str,
+    pub paused_label: &'static str,
+    pub lap: &'static str,
+    pub progress_title: &'static str,
+    pub session_title: &'static str,
+    pub stopwatch_title: &'static str,
+    pub laps_title: &'static str,
+    pub checklist_title: &'static str,
+    pub checklist_line: &'static str,
+    pub board_columns: [&'static str; 3],
+    // Work, break and long break: as a name, and as in "Start work session?"
+    pub sessions: [(&'static str, &'static str); 3],
+    pub today: &'static str,
+    pub this_week: &'static str,
+    pub all_time: &'static str,
+    pub streak: &'static str,
+    pub longest: &'static str,
+    pub last_days: &'static str,
+    pub last_weeks: &'static str,
+    pub last_year: &'static str,
+    // Before the summary left on exit
+    pub this_run: &'static str,
+    pub week_of: &'static str,
+    pub by_task: &'static str,
+    // Activity, then breaks taken to the end and times picked
+    pub by_activity: &'static str,
+    pub activity_taken: &'static str,
+    pub energy_by_time: &'static str,
+    pub energy_by_length: &'static str,
+    pub focus: &'static str,
+    pub times_of_day: [&'static str; 3],
+    pub lengths: [&'static str; 3],
+    pub day: Noun,
+    pub pomodoro: Noun,
+    pub break_taken: Noun,
+    pub interruption: Noun,
+    pub rating: Noun,
+    // Whether a count takes a noun's `many` form
+    pub many: fn(u32) -> bool,
+}
+
+impl Text {
+    // `3 days`, `3 làithean`
+    pub fn count(&self, count: u32, noun: &Noun) -> String {
+        let form = if (self.many)(count) { noun.many } else { noun.one };
+        format!("{} {}", count, form)
+    }
+
+    // `Work` or `Obair`, for one of the scheduler's session names
+    pub fn session_name<'a>(&self, name: &'a str) -> &'a str {
+        self.session(name).map_or(name, |(name, _)| name)
+    }
+
+    // `work` or `obrach`, as it reads in the middle of a prompt
+    pub fn session_type<'a>(&self, name: &'a str) -> &'a str {
+        self.session(name).map_or(name, |(_, session_type)| session_type)
+    }
+
+    fn session(&self, name: &str) -> Option<(&'static str, &'static str)> {
+        let index = SESSIONS.iter().position(|english| english.eq_ignore_ascii_case(name))?;
+        Some(self.sessions[index])
+    }
+}
+
+// Replaces each `{}` in turn
+pub fn fill(template: &str, values: &[&dyn Display]) -> String {
+    let mut filled = String::new();
+    fill_into(&mut filled, template, values);
+    filled
+}
+
+// `fill`, appending to a string that's reused rather than making a new one
+pub fn fill_into(out: &mut String, template: &str, values: &[&dyn Display]) {
+    let mut parts = template.split("{}");
+    out.push_str(parts.next().unwrap_or_default());
+    for (i, part) in parts.enumerate() {
+        if let Some(value) = values.get(i) {
+            let _ = write!(out, "{}", value);
+        }
+        out.push_str(part);
+    }
+}
+
+pub static ENGLISH: Text = Text {
+    header: "Tìmeadair - Pomodoro Timer",
+    farewell: "Pomodoro session ended. See you next time!",
+    timer_reset: "Timer reset.",
+    session_complete: "Session complete.",
+    session_skipped: "Session skipped.",
+    exit: "Press any key to exit",
+    controls: "Keys: 'q' quit, 'r' reset, 'p' pause, 's' skip, '+/-' time",
+    stopwatch_controls: "Keys: 'q' stop, 'r' reset, 'p' pause, 'l' lap",
+    checklist_controls: "Enter to tick it off, 's' to skip, 'q' to quit",
+    board_controls: "Keys: arrows move, '<'/'>' move the card, Enter starts on it, '/' search, Esc no task",
+    board_search: "Search: {}",
+    integrations_controls: "Press 'o' to go back to the timer",
+    dashboard_controls: "Press 'v' to go back to the timer",
+    dashboard_empty: "No focus time yet today",
+    task_prompt: "What are you working on? (Enter to skip): ",
+    task_picker_prompt: "Pick a task by number, or say what you're working on (Enter to skip): ",
+    resume_prompt: "Carry on where it left off? [Y/n]: ",
+    rating_prompt: "Energy level? 1 (drained) to 5 (sharp), any other key skips: ",
+    note_prompt: "What did you get done? (Enter skips): ",
+    timer_prompt: "New timer, e.g. 'tea 4' for four minutes (Enter cancels): ",
+    timer_done: "Timer done: {}",
+    roulette_spin: "Picking a break... {}",
+    roulette_landed: "This break: {}",
+    start_prompt: "Start {} session? [Y/n]: ",
+    countdown: "Next: {} ({}) - starting in {}s, press any key to start now or 'q' to quit",
+    snooze_notice: "Not done yet? Press z for {} more minutes before the break.",
+    overtime: "Overtime on the last session, s to stop",
+    done_prompt: "'{}' has had all {} of its pomodoros. Move it to done? [Y/n]: ",
+    meeting_notice: "Heads up: '{}' starts at {}, before this session would end.",
+    shorten_prompt: "Shorten this session to {} minutes, to finish before '{}'? [Y/n]: ",
+    abandon_phrase: "I give up",
+    abandon_prompt: "Type '{}' to give up on this session, or press Enter to keep going: ",
+    current_session: "Current session: {}",
+    current_task: "Current session: {} - {}",
+    up_next: "Up next: {}",
+    suggestion: "Break idea: {}",
+    goal_today: "{}/{} today",
+    goal_reached: "Daily goal reached: {}/{} today. Well done!",
+    nudge: "You've been at the keyboard for {} minutes without a pomodoro. Start one, or take a break?",
+    reminder: "No pomodoro for {} minutes. Time to start one?",
+    remaining: "Remaining: {}",
+    remaining_plain: "{} - {} remaining ({}%)",
+    remaining_spoken: "{} - {} remaining",
+    minutes: "{} minutes",
+    one_minute: "1 minute",
+    seconds: "{} seconds",
+    one_second: "1 second",
+    interruptions: "Interruptions: {} internal, {} external",
+    paused: "(paused)",
+    paused_label: "PAUSED",
+    lap: "Lap {}  {}",
+    progress_title: "Progress",
+    session_title: "Session",
+    stopwatch_title: "Stopwatch",
+    laps_title: "Laps",
+    checklist_title: "Before you start ({}/{})",
+    checklist_line: "Checklist {}/{}: {} ({})",
+    board_columns: ["Todo", "Doing", "Done"],
+    sessions: [("Work", "work"), ("Break", "break"), ("Long break", "long break")],
+    today: "Today:",
+    this_week: "This week:",
+    all_time: "All time:",
+    streak: "Streak:",
+    longest: "longest {}",
+    last_days: "Last 7 days",
+    last_weeks: "Last 4 weeks",
+    last_year: "Last 52 weeks",
+    this_run: "This run:",
+    week_of: "w/c {}",
+    by_task: "By task",
+    by_activity: "Break activities",
+    activity_taken: "taken {} of {} times",
+    energy_by_time: "Energy by time of day",
+    energy_by_length: "Energy by session length",
+    focus: "{} focus",
+    times_of_day: ["Morning", "Afternoon", "Evening"],
+    lengths: ["Under 20m", "20-40m", "Over 40m"],
+    day: Noun { one: "day", many: "days" },
+    pomodoro: Noun { one: "pomodoro", many: "pomodoros" },
+    break_taken: Noun { one: "break", many: "breaks" },
+    interruption: Noun { one: "interruption", many: "interruptions" },
+    rating: Noun { one: "rating", many: "ratings" },
+    many: |count| count != 1,
+};
+
+pub static GAELIC: Text = Text {
+    header: "Tìmeadair Pomodoro",
+    farewell: "Tha an seisean pomodoro seachad. Chì sinn a-rithist thu!",
+    timer_reset: "Chaidh an tìmeadair ath-shuidheachadh.",
+    session_complete: "Tha an seisean deiseil.",
+    session_skipped: "Chaidh leum thairis air an t-seisean.",
+    exit: "Brùth iuchair sam bith gus fàgail",
+    controls: "Iuchraichean: 'q' fàg, 'r' ath-shuidhich, 'p' cuir na stad, 's' leum, '+/-' ùine",
+    stopwatch_controls: "Iuchraichean: 'q' crìochnaich, 'r' ath-shuidhich, 'p' cuir na stad, 'l' cuairt",
+    checklist_controls: "Enter gus a chomharrachadh, 's' gus leum thairis air, 'q' gus fàgail",
+    board_controls: "Iuchraichean: saighdean gus gluasad, '<'/'>' gluais a' chairt, Enter tòisich oirre, '/' lorg, Esc gun obair",
+    board_search: "Lorg: {}",
+    integrations_controls: "Brùth 'o' gus tilleadh dhan tìmeadair",
+    dashboard_controls: "Brùth 'v' gus tilleadh dhan tìmeadair",
+    dashboard_empty: "Cha deach ùine fòcais a chlàradh fhathast an-diugh",
+    task_prompt: "Dè air a bheil thu ag obair? (Enter gus leum): ",
+    task_picker_prompt: "Tagh obair le àireamh, no innis dè air a bheil thu ag obair (Enter gus leum): ",
+    resume_prompt: "Lean air adhart far an do stad e? [Y/n]: ",
+    rating_prompt: "Ìre lùiths? 1 (claoidhte) gu 5 (geur), iuchair sam bith eile gus leum: ",
+    note_prompt: "Dè a fhuair thu dèanta? (Enter gus leum): ",
+    timer_prompt: "Tìmear ùr, m.e. 'tì 4' airson ceithir mionaidean (Enter gus sgur dheth): ",
+    timer_done: "Tha an tìmear deiseil: {}",
+    roulette_spin: "A' taghadh fois... {}",
+    roulette_landed: "An fhois seo: {}",
+    start_prompt: "Tòisich seisean {}? [Y/n]: ",
+    countdown: "An ath fhear: {} ({}) - a' tòiseachadh an ceann {}d, brùth iuchair sam bith gus tòiseachadh an-dràsta no 'q' gus sgur",
+    snooze_notice: "Gun a bhith deiseil fhathast? Brùth z airson {} mionaidean eile ron fhois.",
+    overtime: "Ùine a bharrachd air an t-seisean mu dheireadh, s gus stad",
+    done_prompt: "Fhuair '{}' a h-uile pomodoro aice ({}). Cuir gu deiseil? [Y/n]: ",
+    meeting_notice: "An aire: tòisichidh '{}' aig {}, mus crìochnaich an seisean seo.",
+    shorten_prompt: "Giorraich an seisean seo gu {} mionaidean, gus crìochnachadh ro '{}'? [Y/n]: ",
+    abandon_phrase: "tha mi a' gèilleadh",
+    abandon_prompt: "Sgrìobh '{}' gus an seisean seo a leigeil seachad, no brùth Enter airson cumail a' dol: ",
+    current_session: "An seisean seo: {}",
+    current_task: "An seisean seo: {} - {}",
+    up_next: "Ri thighinn: {}",
+    suggestion: "Beachd don fhois: {}",
+    goal_today: "{}/{} an-diugh",
+    goal_reached: "Amas an latha air a choileanadh: {}/{} an-diugh. Math fhèin!",
+    nudge: "Tha thu air a bhith aig a' mheur-chlàr fad {} mionaidean gun phomodoro. Tòisich fear, no gabh fois?",
+    reminder: "Cha robh pomodoro ann fad {} mionaidean. An tòisich thu fear?",
+    remaining: "Air fhàgail: {}",
+    remaining_plain: "{} - {} air fhàgail ({}%)",
+    remaining_spoken: "{} - {} air fhàgail",
+    minutes: "{} mionaidean",
+    one_minute: "1 mhionaid",
+    seconds: "{} diogan",
+    one_second: "1 diog",
+    interruptions: "Brisidhean: {} a-staigh, {} a-muigh",
+    paused: "(na stad)",
+    paused_label: "NA STAD",
+    lap: "Cuairt {}  {}",
+    progress_title: "Adhartas",
+    session_title: "Seisean",
+    stopwatch_title: "Stad-uaireadair",
+    laps_title: "Cuairtean",
+    checklist_title: "Mus tòisich thu ({}/{})",
+    checklist_line: "Liosta {}/{}: {} ({})",
+    board_columns: ["Ri dhèanamh", "Ga dhèanamh", "Deiseil"],
+    sessions: [("Obair", "obrach"), ("Fois", "fois"), ("Fois fhada", "fois fhada")],
+    today: "An-diugh:",
+    this_week: "An t-seachdain seo:",
+    all_time: "Gu h-iomlan:",
+    streak: "Sreath:",
+    longest: "as fhaide {}",
+    last_days: "Na 7 làithean mu dheireadh",
+    last_weeks: "Na 4 seachdainean mu dheireadh",
+    last_year: "Na 52 seachdainean mu dheireadh",
+    this_run: "An turas seo:",
+    week_of: "s/t {}",
+    by_task: "A rèir obrach",
+    by_activity: "Gnìomhan fois",
+    activity_taken: "air a ghabhail {} à {} tursan",
+    energy_by_time: "Lùths a rèir àm an latha",
+    energy_by_length: "Lùths a rèir fad an t-seisein",
+    focus: "{} de dh'fhòcas",
+    times_of_day: ["Madainn", "Feasgar", "Oidhche"],
+    lengths: ["Fo 20m", "20-40m", "Thar 40m"],
+    day: Noun { one: "latha", many: "làithean" },
+    pomodoro: Noun { one: "pomodoro", many: "pomodoro" },
+    break_taken: Noun { one: "fois", many: "foisean" },
+    interruption: Noun { one: "briseadh", many: "brisidhean" },
+    rating: Noun { one: "rangachadh", many: "rangachaidhean" },
+    // 3 to 10 and 13 to 19 take the plural; 1, 2, 11, 12 and 20 up keep
+    // the singular
+    many: |count| matches!(count % 100, 3..=10 | 13..=19),
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_and_fills_a_locale() {
+        assert_eq!(Locale::parse("gd_GB.UTF-8"), Ok(Locale::Gaelic));
+        assert_eq!(Locale::parse("en"), Ok(Locale::English));
+        assert!(Locale::parse("fr_FR").is_err());
+
+        let text = Locale::Gaelic.text();
+        assert_eq!(fill(text.start_prompt, &[&text.session_type("long break")]), "Tòisich seisean fois fhada? [Y/n]: ");
+        assert_eq!(text.session_name("Work"), "Obair");
+        assert_eq!(text.session_name("Standup"), "Standup");
+        let days: Vec<_> = [1, 2, 3, 11, 14, 20].iter().map(|count| text.count(*count, &text.day)).collect();
+        assert_eq!(days, ["1 latha", "2 latha", "3 làithean", "11 latha", "14 làithean", "20 latha"]);
+        assert_eq!(ENGLISH.count(2, &ENGLISH.day), "2 days");
+        assert_eq!(fill("{} - {}", &[&"a"]), "a - ");
+    }
+}
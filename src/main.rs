@@ -1,182 +1,1564 @@
-use std::io::{self, Write};
-use std::time::Duration;
-use crossterm::{
-    cursor, execute, event::{self, Event, KeyCode, KeyEvent},
-    style::{self, Color, Stylize},
-    terminal::{Clear, ClearType, enable_raw_mode, disable_raw_mode},
-};
-use ctrlc;
+use std::cell::RefCell;
+use std::io::{self, IsTerminal};
+use std::panic;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::slice;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::{Local, NaiveTime};
+use serde_json::json;
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
 
-const WORK_TIME: u64 = 25 * 60; // 25 minutes
-const BREAK_TIME: u64 = 5 * 60; // 5 minutes
+mod app;
+mod archive;
+mod art;
+mod assistive;
+mod bench;
+mod board;
+mod bundle;
+mod cli;
+mod coach;
+mod commands;
+mod checkpoint;
+mod config;
+mod cron;
+#[cfg(unix)]
+mod daemon;
+mod demo;
+mod diary;
+mod digits;
+mod dnd;
+mod dryrun;
+mod error;
+mod events;
+mod export;
+#[cfg(unix)]
+mod fifo;
+mod history;
+mod hooks;
+mod hours;
+mod ics;
+mod idle;
+mod json;
+mod leaderboard;
+mod locale;
+mod media;
+mod meetings;
+mod metrics;
+mod notify;
+mod nudge;
+mod outputs;
+mod paths;
+mod permissions;
+mod plain;
+mod publish;
+mod random;
+mod reminder;
+mod roulette;
+mod schedule;
+mod search;
+mod seed;
+mod serial;
+mod shell;
+#[cfg(unix)]
+mod shutdown;
+mod sound;
+mod stats;
+mod statusbar;
+mod status;
+mod stdio;
+mod stopwatch;
+mod suggestions;
+mod taskbar;
+mod tasks;
+mod theme;
+mod timers;
+mod title;
+mod tutorial;
+mod ui;
+mod vacation;
+mod wait;
 
-struct Timer {
-    duration: u64,
-    elapsed: u64,
-}
+// The engine lives in the library crate; importing its modules here keeps
+// `crate::scheduler` and `crate::timer` paths working for the frontend
+use timeadair::{scheduler, timer};
+
+use app::{AppEvent, Events};
+use archive::Rollups;
+use art::Art;
+use assistive::AssistiveCommand;
+use notify::{Backend, Bell, DesktopNotifier, Notifier, SoundNotifier};
+use checkpoint::{Checkpoint, CheckpointFile};
+use cli::{Display, OnShutdown, Options, UiMode};
+use coach::Coach;
+use commands::Command;
+use config::Config;
+use diary::Diary;
+use dnd::Dnd;
+use dryrun::DryRun;
+use error::Error;
+use events::EventLog;
+use history::{History, Interruption, Outcome, SessionRecord};
+use hooks::Hooks;
+use idle::{Away, IdleMonitor, Poll};
+use meetings::{Calendar, Meeting};
+use metrics::Metrics;
+use outputs::Outputs;
+use permissions::{Consent, Permission};
+use plain::PlainRenderer;
+use publish::{Publisher, Targets};
+use random::Rng;
+use schedule::Schedule;
+use scheduler::{Durations, Phase, Planned, State};
+use serial::SerialOutput;
+use sound::{SoundEvent, Sounds, SoundsCommand};
+use stats::Tally;
+use status::StatusFile;
+use statusbar::StatusFormat;
+use taskbar::ProgressState;
+use tasks::{Task, TaskCommand, TaskStore};
+use timer::{Timer, TimerResult};
+use title::TitleOutput;
+use ui::{disable_raw_mode, Pane, RawMode, Renderer, Screen};
+
+// How long a session told to end on SIGTERM gets to record itself and
+// drain its outputs before the handler gives up on it and exits anyway
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+// How long the roulette's pick stays up after a spin, before the break
+const ROULETTE_LANDED: Duration = Duration::from_millis(1200);
+
+fn run_timer(
+    mut state: State,
+    task: Option<&str>,
+    message: &str,
+    screen: &mut dyn Renderer,
+    outputs: &mut Outputs,
+    commands: &Receiver<Command>,
+    mut idle: Option<&mut IdleMonitor>,
+) -> io::Result<(State, TimerResult)> {
+    let phase = state.phase;
+    let raw = RawMode::enable()?;
+    screen.show_cursor(false)?;  // Hide cursor at the start
+
+    outputs.session_started(&state, task)?;
+    screen.set_interruptions(outputs.interruptions());
+    screen.set_timers(&outputs.timers.shown());
+    screen.progress(&state.timer, message, false)?;
+    let mut panel = None;
+    // Set while a work session is paused because nobody was there
+    let mut away: Option<Away> = None;
+    let text = |state: &State, away: Option<Away>| away.map_or_else(|| message.to_string(), |away| away.message(state.paused_time));
 
-impl Timer {
-    fn new(duration: u64) -> Timer {
-        Timer {
-            duration,
-            elapsed: 0,
+    let mut events = Events::start();
+    // For telling when the loop has fallen behind the clock
+    let mut next_tick = Instant::now() + Duration::from_secs(1);
+    let result = 'session: loop {
+        let mut command = None;
+        let mut tick = false;
+        match events.next(commands) {
+            AppEvent::Tick => tick = true,
+            AppEvent::Frame => {
+                if panel.is_none() && !state.paused {
+                    let left = next_tick.saturating_duration_since(Instant::now()).as_secs_f32();
+                    screen.between_ticks(1.0 - left)?;
+                }
+                continue;
+            }
+            AppEvent::Command(Command::AddTimer(name, seconds)) => {
+                outputs.timers.add(name, seconds);
+                screen.set_timers(&outputs.timers.shown());
+                if panel.is_none() {
+                    screen.progress(&state.timer, &text(&state, away), state.paused)?;
+                }
+                continue;
+            }
+            AppEvent::Command(received) => command = Some(received),
+            AppEvent::Resize => {
+                screen.redraw()?;
+                continue;
+            }
+            // Noted without stopping the clock: 'i' for the user's own
+            // distractions, 'I' for everyone else's
+            AppEvent::Key(KeyEvent { code: KeyCode::Char(key @ ('i' | 'I')), .. }) if phase == Phase::Work => {
+                let kind = if key == 'i' { Interruption::Internal } else { Interruption::External };
+                screen.set_interruptions(outputs.interrupted(&state, kind));
+                if panel.is_none() {
+                    screen.progress(&state.timer, &text(&state, away), state.paused)?;
+                }
+                continue;
+            }
+            // Back at the keyboard: 'k' keeps the time away as work
+            AppEvent::Key(KeyEvent { code: KeyCode::Char(key @ ('d' | 'k')), .. }) if away.is_some() => {
+                if let Some(away) = away.take() {
+                    state = scheduler::step(state, away.settle(key == 'k', state.paused_time));
+                }
+                command = Some(Command::Resume);
+            }
+            // The integrations panel and today's stats belong to this
+            // screen, not to the session
+            AppEvent::Key(KeyEvent { code: KeyCode::Char(key @ ('O' | 'o' | 'V' | 'v')), .. }) if outputs.history.is_some() || key.eq_ignore_ascii_case(&'o') => {
+                let pane = if key.eq_ignore_ascii_case(&'o') { Pane::Integrations } else { Pane::Dashboard };
+                panel = (panel != Some(pane)).then_some(pane);
+                match panel {
+                    Some(Pane::Integrations) => screen.integrations(&outputs.integrations())?,
+                    Some(Pane::Dashboard) => screen.dashboard(outputs.today())?,
+                    None => screen.progress(&state.timer, &text(&state, away), state.paused)?,
+                }
+                continue;
+            }
+            AppEvent::Key(KeyEvent { code: KeyCode::Char('D' | 'd'), .. }) if panel.is_none() => {
+                screen.toggle_big_digits()?;
+                continue;
+            }
+            // The keys and the clock stop while it's typed in, as they do
+            // for the phrase under --strict
+            AppEvent::Key(KeyEvent { code: KeyCode::Char('t' | 'T'), .. }) => {
+                drop(events);
+                if let Some((name, seconds)) = prompt_timer(screen, &raw)? {
+                    outputs.timers.add(name, seconds);
+                }
+                events = Events::start();
+                next_tick = Instant::now() + Duration::from_secs(1);
+                panel = None;
+                screen.show_cursor(false)?;
+                screen.set_timers(&outputs.timers.shown());
+                screen.progress(&state.timer, &text(&state, away), state.paused)?;
+                continue;
+            }
+            AppEvent::Key(KeyEvent { code: KeyCode::Char(key @ ('[' | ']')), .. }) => {
+                outputs.change_volume(if key == '[' { -10 } else { 10 });
+                continue;
+            }
+            AppEvent::Key(KeyEvent { code, .. }) => command = Command::from_key(code),
         }
-    }
+        if tick {
+            next_tick += Duration::from_secs(1);
+            if let Some(monitor) = idle.as_deref_mut().filter(|_| phase == Phase::Work && !state.paused) {
+                match monitor.poll() {
+                    Poll::Idle(seconds) if seconds >= monitor.threshold => {
+                        away = Some(Away { counted: seconds.min(state.timer.elapsed), paused_at: state.paused_time });
+                        command = Some(Command::Pause);
+                    }
+                    Poll::Unavailable => outputs.warn("idle detection", "can't tell how long the computer has been idle; on X11, install xprintidle"),
+                    _ => {}
+                }
+            }
+        }
+        let mut command = command.and_then(|command| command.session_event(state.paused));
+        // Under --strict a break runs its full length, and work only ends
+        // early once the phrase has been typed
+        if let Some(phrase) = outputs.strict {
+            match command {
+                Some(scheduler::Event::Skip | scheduler::Event::Shorten | scheduler::Event::Reset) if phase == Phase::Break => {
+                    command = None;
+                }
+                Some(scheduler::Event::Quit | scheduler::Event::Reset) if phase == Phase::Work => {
+                    // The keys and the clock stop while the phrase is asked for
+                    drop(events);
+                    if !confirm_abandon(screen, &raw, phrase)? {
+                        command = None;
+                        panel = None;
+                        screen.progress(&state.timer, &text(&state, away), state.paused)?;
+                    }
+                    events = Events::start();
+                    next_tick = Instant::now() + Duration::from_secs(1);
+                }
+                _ => {}
+            }
+        }
+        if command.is_none() && !tick {
+            continue;
+        }
+        // A tick that comes with a command still counts, after it
+        for event in [command, tick.then_some(scheduler::Event::Tick)].into_iter().flatten() {
+            let was_paused = state.paused;
+            state = scheduler::step(state, event);
+            if state.phase != phase {
+                break 'session scheduler::session_result(event);
+            }
+            if state.paused != was_paused {
+                outputs.pause_changed(&state);
+            }
+        }
+        // However the session got going again
+        if !state.paused {
+            away = None;
+        }
+        // Only draw the latest second when several are due at once
+        if Instant::now() >= next_tick {
+            continue;
+        }
+        outputs.tick(&state)?;
+        for name in outputs.timers_finished() {
+            screen.timer_done(&name)?;
+        }
+        screen.set_timers(&outputs.timers.shown());
+        match panel {
+            Some(Pane::Integrations) => screen.integrations(&outputs.integrations())?,
+            Some(Pane::Dashboard) => screen.dashboard(outputs.today())?,
+            None => screen.progress(&state.timer, &text(&state, away), state.paused)?,
+        }
+        report_warnings(screen, outputs)?;
+    };
+    drop(events);
 
-    fn get_progress(&self) -> f32 {
-        self.elapsed as f32 / self.duration as f32 * 100.0
+    outputs.session_ended(phase, &state, &result)?;
+    report_warnings(screen, outputs)?;
+    drop(raw);
+
+    screen.session_end(&result)?;
+    if outputs.awaiting_details() {
+        // Handed over even if a prompt fails, so the diary still gets its
+        // entry
+        let rating = if outputs.rate { prompt_rating(screen) } else { Ok(None) };
+        let note = if outputs.notes && rating.is_ok() { prompt_note(screen) } else { Ok(None) };
+        outputs.detailed(rating.as_ref().ok().copied().flatten(), note.as_ref().ok().cloned().flatten());
+        rating?;
+        note?;
+        report_warnings(screen, outputs)?;
     }
+    Ok((state, result))
+}
 
-    fn format_time(&self) -> String {
-        let remaining = self.duration - self.elapsed;
-        let minutes = remaining / 60;
-        let seconds = remaining % 60;
-        format!("{:02}:{:02}", minutes, seconds)
+// Snoozed work after a session: up to `seconds` more of it, until s or q
+// stop it sooner, and how much there was
+fn run_overtime(screen: &mut dyn Renderer, outputs: &mut Outputs, commands: &Receiver<Command>, seconds: u64, message: &str) -> io::Result<u64> {
+    let _raw = RawMode::enable()?;
+    screen.show_cursor(false)?;
+    screen.set_phase(Phase::Work);
+    let mut timer = Timer::new(seconds);
+    let mut paused = false;
+    screen.progress(&timer, message, paused)?;
+    let events = Events::start();
+    while timer.remaining() > 0 {
+        let command = match events.next(commands) {
+            AppEvent::Tick if !paused => {
+                timer.elapsed += 1;
+                for name in outputs.timers_finished() {
+                    screen.timer_done(&name)?;
+                }
+                screen.set_timers(&outputs.timers.shown());
+                screen.progress(&timer, message, paused)?;
+                continue;
+            }
+            AppEvent::Tick | AppEvent::Frame => continue,
+            AppEvent::Resize => {
+                screen.redraw()?;
+                continue;
+            }
+            AppEvent::Key(KeyEvent { code: KeyCode::Esc, .. }) => Some(Command::Quit),
+            AppEvent::Key(KeyEvent { code, .. }) => Command::from_key(code),
+            AppEvent::Command(command) => Some(command),
+        };
+        match command {
+            Some(Command::Skip | Command::Quit | Command::Reset | Command::Shutdown) => break,
+            Some(Command::TogglePause) => paused = !paused,
+            Some(Command::Pause) => paused = true,
+            Some(Command::Resume) => paused = false,
+            _ => continue,
+        }
+        screen.progress(&timer, message, paused)?;
     }
+    drop(events);
+    screen.session_end(if timer.remaining() == 0 { &TimerResult::Completed } else { &TimerResult::Skipped })?;
+    Ok(timer.elapsed)
 }
 
-fn clear_screen() -> io::Result<()> {
-    execute!(io::stdout(), Clear(ClearType::All), cursor::MoveTo(0, 0))
+// A side timer's name and length, asked again until it reads as one;
+// Enter on its own adds none
+fn prompt_timer(screen: &mut dyn Renderer, raw: &RawMode) -> io::Result<Option<(String, u64)>> {
+    let mut error = None;
+    raw.cooked(|| loop {
+        screen.timer_prompt(error.as_deref())?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if input.trim().is_empty() {
+            return Ok(None);
+        }
+        match timers::parse(&input) {
+            Ok(timer) => return Ok(Some(timer)),
+            Err(message) => error = Some(message),
+        }
+    })
 }
 
-fn draw_progress_bar(progress: f32, time: &str, message: &str, first_draw: bool) -> io::Result<()> {
-    let width = 50;
-    let filled = (progress * width as f32 / 100.0) as usize;
-    let empty = width - filled;
+fn confirm_abandon(screen: &mut dyn Renderer, raw: &RawMode, phrase: &str) -> io::Result<bool> {
+    let input = raw.cooked(|| {
+        screen.abandon_prompt()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input)
+    })?;
+    screen.show_cursor(false)?;
+    Ok(ui::phrase_typed(phrase, &input))
+}
 
-    if first_draw {
-        display_header()?;
-    }
-    
-    // Move cursor to specific positions for each line
-    execute!(io::stdout(), cursor::MoveTo(0, 3))?;
-    execute!(io::stdout(), Clear(ClearType::FromCursorDown))?;
+// A single work session of any length on its own, outside the cycle and
+// the history, so only the sounds and taskbar progress are wanted
+fn countdown(screen: &mut dyn Renderer, options: &Options, seconds: u64) -> io::Result<()> {
+    let durations = Durations { work_time: seconds, ..options.durations };
+    let state = scheduler::step(State::new(durations), scheduler::Event::Start);
+    let mut outputs = Outputs::default();
+    outputs.taskbar = true;
+    set_up_sounds(&mut outputs, options);
+    set_up_notifiers(&mut outputs, options, true);
+    let (_commands, no_commands) = mpsc::channel();
+    let message = format!("Countdown from {}", state.timer.format_time());
+    let (_, result) = run_timer(state, None, &message, screen, &mut outputs, &no_commands, None)?;
+    screen.message(match result {
+        TimerResult::Completed => "Countdown finished.",
+        TimerResult::Skipped => "Countdown ended early.",
+        TimerResult::Quit | TimerResult::Reset | TimerResult::Interrupted => "Countdown stopped.",
+    })
+}
 
-    // Progress bar line
-    print!("[");
-    execute!(io::stdout(), 
-        style::PrintStyledContent("=".repeat(filled).with(Color::Green)))?;
-    execute!(io::stdout(), 
-        style::PrintStyledContent("-".repeat(empty).with(Color::DarkGrey)))?;
-    print!("] {}% {}", progress as u32, time);
+// A single key press; anything but 1-5 leaves the session unrated
+fn prompt_rating(screen: &mut dyn Renderer) -> io::Result<Option<u8>> {
+    let raw = RawMode::enable()?;
+    screen.rating_prompt()?;
+    let rating = loop {
+        match event::read()? {
+            Event::Key(KeyEvent { code: KeyCode::Char(key @ '1'..='5'), .. }) => break Some(key as u8 - b'0'),
+            Event::Key(_) => break None,
+            Event::Resize(..) => screen.redraw()?,
+            _ => {}
+        }
+    };
+    drop(raw);
+    // The plain frontend left the prompt without a line end
+    screen.message(&match rating {
+        Some(rating) => format!("Energy rated {}/5.", rating),
+        None => "Not rated.".to_string(),
+    })?;
+    Ok(rating)
+}
 
-    // Message line
-    execute!(io::stdout(), cursor::MoveTo(0, 5))?;
-    print!("{}", message);
+// What the roulette picks for the coming break, if there's a list to pick
+// from, going round the list first under `spin`
+fn spin_roulette(screen: &mut dyn Renderer, options: &Options, rng: &mut Rng) -> io::Result<Option<String>> {
+    let activities = &options.break_activities;
+    let Some(landing) = roulette::pick(activities, rng) else { return Ok(None) };
+    if options.spin {
+        let shown = roulette::spin(activities.len(), landing);
+        for (step, &i) in shown.iter().enumerate() {
+            screen.roulette(&activities[i].0, false)?;
+            thread::sleep(Duration::from_millis(roulette::pause(step, shown.len())));
+        }
+    }
+    screen.roulette(&activities[landing].0, true)?;
+    if options.spin {
+        thread::sleep(ROULETTE_LANDED);
+    }
+    Ok(Some(activities[landing].0.clone()))
+}
 
-    // Controls line
-    execute!(io::stdout(), cursor::MoveTo(0, 7))?;
-    print!("Controls: 'q' to quit, 'r' to reset timer");
-    
-    io::stdout().flush()
+// A line of what got done; Enter on its own skips it
+fn prompt_note(screen: &mut dyn Renderer) -> io::Result<Option<String>> {
+    screen.note_prompt()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let note = input.trim();
+    Ok((!note.is_empty()).then(|| note.to_string()))
 }
 
-enum TimerResult {
-    Completed,
+enum Answer {
+    Start,
+    Decline,
     Quit,
-    Reset,
+    // More of the work session that just ended, before its break
+    Snooze,
 }
 
-fn run_timer(duration: u64, type_name: &str) -> io::Result<TimerResult> {
-    let mut timer = Timer::new(duration);
-    enable_raw_mode()?;
-    execute!(io::stdout(), cursor::Hide)?;  // Hide cursor at the start
-
-    let message = format!("Current session: {}", type_name);
-    draw_progress_bar(timer.get_progress(), &timer.format_time(), &message, true)?;
+// Whether to start the next session, `length` seconds of it. With
+// `grace`, it starts by itself once that many seconds are up unless 'q'
+// is pressed, and any other key starts it straight away. Keys are read as
+// they're pressed, so there's no Enter to wait on, while piped input is
+// read a line at a time as before. `z` snoozes, when `snooze` allows it
+fn ask_to_start(
+    screen: &mut dyn Renderer,
+    session_type: &str,
+    length: u64,
+    notice: Option<&str>,
+    grace: Option<u64>,
+    snooze: bool,
+) -> io::Result<Answer> {
+    if !ui::reads_keys() {
+        if let Some(grace) = grace {
+            for remaining in (1..=grace).rev() {
+                screen.countdown(session_type, length, remaining)?;
+                thread::sleep(Duration::from_secs(1));
+            }
+            return Ok(Answer::Start);
+        }
+        screen.prompt(session_type, notice)?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if snooze && input.trim().eq_ignore_ascii_case("z") {
+            return Ok(Answer::Snooze);
+        }
+        return Ok(if ui::prompt_accepted(&input) { Answer::Start } else { Answer::Decline });
+    }
+    let _raw = RawMode::enable()?;
+    if let Some(grace) = grace {
+        screen.show_cursor(false)?;
+        for remaining in (1..=grace).rev() {
+            screen.countdown(session_type, length, remaining)?;
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+                if event::poll(wait)? {
+                    match event::read()? {
+                        Event::Key(KeyEvent { code: KeyCode::Char('q' | 'Q') | KeyCode::Esc, .. }) => return Ok(Answer::Quit),
+                        Event::Key(KeyEvent { code: KeyCode::Char('z' | 'Z'), .. }) if snooze => return Ok(Answer::Snooze),
+                        Event::Key(_) => return Ok(Answer::Start),
+                        Event::Resize(..) => screen.redraw()?,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        return Ok(Answer::Start);
+    }
+    screen.prompt(session_type, notice)?;
+    let answer = loop {
+        match event::read()? {
+            Event::Key(KeyEvent { code: KeyCode::Enter | KeyCode::Char('y' | 'Y' | ' '), .. }) => break Answer::Start,
+            Event::Key(KeyEvent { code: KeyCode::Char('n' | 'N'), .. }) => break Answer::Decline,
+            Event::Key(KeyEvent { code: KeyCode::Char('q' | 'Q') | KeyCode::Esc, .. }) => break Answer::Quit,
+            Event::Key(KeyEvent { code: KeyCode::Char('z' | 'Z'), .. }) if snooze => break Answer::Snooze,
+            Event::Resize(..) => screen.redraw()?,
+            _ => {}
+        }
+    };
+    screen.answer(match answer {
+        Answer::Start => "y",
+        Answer::Decline => "n",
+        Answer::Quit => "q",
+        Answer::Snooze => "z",
+    })?;
+    Ok(answer)
+}
 
-    let result = loop {
-        if event::poll(Duration::from_secs(1))? {
-            if let Event::Key(KeyEvent { code, .. }) = event::read()? {
-                match code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        break TimerResult::Quit;
+// Walks through the pre-session checklist, returning the skipped items,
+// or None if the user quit instead
+fn run_checklist(screen: &mut dyn Renderer, items: &[String]) -> io::Result<Option<Vec<usize>>> {
+    if items.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+    let _raw = RawMode::enable()?;
+    screen.show_cursor(false)?;
+    let mut skipped = Vec::new();
+    let mut quit = false;
+    'items: for (i, item) in items.iter().enumerate() {
+        screen.checklist_item(item, i + 1, items.len())?;
+        loop {
+            match event::read()? {
+                Event::Key(KeyEvent { code, .. }) => match code {
+                    KeyCode::Enter | KeyCode::Char('y' | 'Y' | ' ') => break,
+                    KeyCode::Char('s' | 'S') => {
+                        skipped.push(i);
+                        break;
                     }
-                    KeyCode::Char('r') | KeyCode::Char('R') => {
-                        break TimerResult::Reset;
+                    KeyCode::Char('q' | 'Q') | KeyCode::Esc => {
+                        quit = true;
+                        break 'items;
                     }
                     _ => {}
-                }
+                },
+                Event::Resize(..) => screen.redraw()?,
+                _ => {}
+            }
+        }
+    }
+    Ok((!quit).then_some(skipped))
+}
+
+// An empty answer leaves the session without a task; a number picks one
+// from the task list, as does the board when there is one
+fn prompt_task(screen: &mut dyn Renderer, board: bool) -> io::Result<Option<String>> {
+    let (store, mut list, records) = task_list();
+    let now = Local::now();
+    let picked = if board && !list.is_empty() {
+        match board::run(screen, &mut list, &records, &now)? {
+            Some(picked) => picked,
+            None => {
+                // Cards moved around without picking one stay moved
+                save_tasks(screen, store.as_ref(), &list)?;
+                return Ok(None);
             }
         }
-        timer.elapsed += 1;
-        if timer.elapsed >= timer.duration {
-            break TimerResult::Completed;
+    } else {
+        let open = tasks::open(&list, &records, &now);
+        let choices: Vec<_> = open.iter().map(|(_, line)| line.clone()).collect();
+        screen.task_prompt(&choices)?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let task = input.trim();
+        match open.iter().find(|(number, _)| task.parse() == Ok(*number)) {
+            Some((number, _)) => number - 1,
+            None => return Ok((!task.is_empty()).then(|| task.to_string())),
+        }
+    };
+    list[picked].doing = true;
+    save_tasks(screen, store.as_ref(), &list)?;
+    Ok(Some(list[picked].title.clone()))
+}
+
+// Once a session on a task reaches its estimate, asks whether it's done
+fn offer_done(screen: &mut dyn Renderer, title: &str) -> io::Result<()> {
+    let (store, mut list, records) = task_list();
+    let now = Local::now();
+    let Some(task) = list.iter_mut().find(|task| task.title == title && !task.is_done(&now)) else { return Ok(()) };
+    let pomodoros = tasks::pomodoros(task, &records, &now);
+    if task.estimate.is_none_or(|estimate| pomodoros < estimate) {
+        return Ok(());
+    }
+    screen.done_prompt(title, pomodoros)?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if ui::prompt_accepted(&input) {
+        task.mark_done(now.timestamp());
+        save_tasks(screen, store.as_ref(), &list)?;
+    }
+    Ok(())
+}
+
+// The meeting a work session of `seconds` starting now would run into
+fn upcoming_meeting(calendar: Option<&mut Calendar>, outputs: &mut Outputs, seconds: u64) -> Option<Meeting> {
+    match calendar?.next(Local::now().naive_local(), seconds) {
+        Ok(meeting) => meeting,
+        Err(error) => {
+            outputs.warn("calendar", format!("unable to read the calendar: {}", error));
+            None
+        }
+    }
+}
+
+fn offer_shorter(screen: &mut dyn Renderer, minutes: u64, meeting: &str) -> io::Result<bool> {
+    screen.shorten_prompt(minutes, meeting)?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(ui::prompt_accepted(&input))
+}
+
+// The task list, where it's kept, and the history that counts its pomodoros
+fn task_list() -> (Option<TaskStore>, Vec<Task>, Vec<SessionRecord>) {
+    let store = tasks::default_path().map(TaskStore::new);
+    let list = store.as_ref().and_then(|store| store.load().ok()).unwrap_or_default();
+    if list.is_empty() {
+        return (store, list, Vec::new());
+    }
+    let records = history::default_path().and_then(|path| History::new(path).load().ok()).unwrap_or_default();
+    (store, list, records)
+}
+
+fn save_tasks(screen: &mut dyn Renderer, store: Option<&TaskStore>, list: &[Task]) -> io::Result<()> {
+    match store.map(|store| store.save(list)) {
+        Some(Err(e)) => screen.warning("tasks", &e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+// Sessions recorded since midnight, for rules that look at the whole day
+fn todays_history() -> Vec<SessionRecord> {
+    let Some(path) = history::default_path() else { return Vec::new() };
+    let midnight = Local::now().date_naive().and_time(NaiveTime::MIN);
+    let since = midnight.and_local_timezone(Local).earliest().map_or(0, |time| time.timestamp());
+    let records = History::new(path).load().unwrap_or_default();
+    records.into_iter().filter(|record| record.started >= since).collect()
+}
+
+// Work sessions completed since midnight, for the daily goal
+fn pomodoros_today() -> u32 {
+    todays_history().iter().filter(|record| record.session == "work" && record.outcome == Outcome::Completed).count() as u32
+}
+
+fn report_warnings(screen: &mut dyn Renderer, outputs: &mut Outputs) -> io::Result<()> {
+    for warning in outputs.take_warnings() {
+        screen.warning(warning.subsystem, &warning.message)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn start_command_fifo(commands: mpsc::Sender<Command>) -> Result<(), Error> {
+    let path = paths::data_dir().ok_or(Error::NoDirectory("data"))?.join("cmd");
+    fifo::listen(&path, commands).map_err(|source| Error::Fifo { path, source })
+}
+
+#[cfg(not(unix))]
+fn start_command_fifo(_commands: mpsc::Sender<Command>) -> Result<(), Error> {
+    Err(Error::Unsupported("--command-fifo"))
+}
+
+#[cfg(unix)]
+fn start_daemon(commands: Sender<Command>, status: Arc<Mutex<String>>) -> Result<(), Error> {
+    let path = daemon::socket_path().ok_or(Error::NoDirectory("runtime"))?;
+    daemon::listen(&path, commands, status).map_err(|source| Error::Socket { path, source })
+}
+
+#[cfg(not(unix))]
+fn start_daemon(_commands: Sender<Command>, _status: Arc<Mutex<String>>) -> Result<(), Error> {
+    Err(Error::Unsupported("timeadair daemon"))
+}
+
+#[cfg(unix)]
+fn on_terminate(handler: impl FnMut() + Send + 'static) -> io::Result<()> {
+    shutdown::on_terminate(handler)
+}
+
+// Windows has no SIGTERM to speak of
+#[cfg(not(unix))]
+fn on_terminate(_handler: impl FnMut() + Send + 'static) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn ctl(request: &str) -> Result<String, Error> {
+    let path = daemon::socket_path().ok_or(Error::NoDirectory("runtime"))?;
+    // Starting a session is worth starting the daemon for
+    let reply = match daemon::ctl(&path, request) {
+        Err(error) if request == "start" && matches!(error.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused) => {
+            daemon::spawn(&path, &daemon::forwarded_args(std::env::args().skip(1))).map_err(Error::Spawn)?;
+            daemon::ctl(&path, request)
+        }
+        reply => reply,
+    };
+    let reply = match reply.map_err(|source| Error::NotRunning { path, source })? {
+        daemon::Reply::Answer(reply) => reply,
+        daemon::Reply::Mismatch(daemon) => return Err(Error::Protocol { daemon, client: daemon::PROTOCOL }),
+    };
+    match reply.strip_prefix("error: ") {
+        Some(message) => Err(Error::Daemon(message.to_string())),
+        None => Ok(reply),
+    }
+}
+
+#[cfg(not(unix))]
+fn ctl(_request: &str) -> Result<String, Error> {
+    Err(Error::Unsupported("timeadair ctl"))
+}
+
+// Picks up the workspace first, since it decides which files are read,
+// then lays the profile, or today's from the schedule, over whatever they say
+fn load_config() -> Result<Config, String> {
+    let workspace = cli::workspace(std::env::args().skip(1))?;
+    let load = |path: Option<PathBuf>| path.map(|path| Config::load(&path)).transpose().map(Option::unwrap_or_default);
+    let mut config = load(config::default_path())?;
+    if let Some(name) = workspace {
+        paths::set_workspace(&name);
+        config = load(config::workspace_path(&name))?.or(config);
+    }
+    let scheduled = || -> Result<Option<String>, String> {
+        let schedule = config.schedule.as_ref().map(Schedule::parse).transpose()?;
+        Ok(schedule.and_then(|schedule| schedule.today()?.profile.clone()))
+    };
+    match cli::profile(std::env::args().skip(1))?.map_or_else(scheduled, |name| Ok(Some(name)))? {
+        Some(name) => config.with_profile(&name),
+        None => Ok(config),
+    }
+}
+
+fn renderer(options: &Options) -> io::Result<Box<dyn Renderer>> {
+    Ok(match options.ui {
+        // A full screen needs a terminal to draw on, so output going to a
+        // file or pipe gets plain lines whatever was asked for
+        UiMode::Screen if io::stdout().is_terminal() => {
+            let theme = if options.no_color || theme::no_color_requested() { options.theme.without_color() } else { options.theme };
+            Box::new(Screen::stdout()?.with_visual_bell(options.visual_bell).with_smooth_bar(!options.low_refresh)
+                    .with_big_digits(matches!(options.display, Display::Big))
+                    .with_flashing_countdown(options.final_countdown.flashes()).with_tenths(options.tenths)
+                    .with_art(options.work_art.as_deref().map(Art::load).transpose()?, options.break_art.as_deref().map(Art::load).transpose()?)
+                    .with_theme(theme).with_locale(options.locale))
+        }
+        UiMode::Screen | UiMode::Plain => Box::new(
+            PlainRenderer::stdout()
+                .with_visual_bell(options.visual_bell)
+                .with_locale(options.locale)
+                .with_announcements(options.accessible.then_some(options.announce_every)),
+        ),
+    })
+}
+
+fn set_up_sounds(outputs: &mut Outputs, options: &Options) {
+    if options.mute {
+        return;
+    }
+    let pack = options.sound_pack.as_deref();
+    match Sounds::new(pack, options.work_end_sound.clone(), options.break_end_sound.clone()) {
+        Ok(mut sounds) => {
+            sounds.set_output(options.volume, options.audio_device.clone());
+            outputs.sounds = Some(Rc::new(RefCell::new(sounds)));
+        }
+        Err(error) => outputs.warn("sound", error),
+    }
+}
+
+// Whatever `notify` says to tell of a completed session, sounds included
+// once they're set up; the bell only rings for a `terminal` showing the
+// timer
+fn set_up_notifiers(outputs: &mut Outputs, options: &Options, terminal: bool) {
+    let notifier = |backend: &Backend| -> Option<Box<dyn Notifier>> {
+        match backend {
+            Backend::Bell => (terminal && !options.visual_bell).then(|| Box::new(Bell) as Box<dyn Notifier>),
+            Backend::Desktop => Some(Box::new(DesktopNotifier::new(options.locale.text()))),
+            Backend::Sound => outputs.sounds.clone().map(|sounds| Box::new(SoundNotifier(sounds)) as Box<dyn Notifier>),
+            Backend::Command => options.assistive_command.clone().map(|program| Box::new(AssistiveCommand::new(program)) as Box<dyn Notifier>),
         }
-        draw_progress_bar(timer.get_progress(), &timer.format_time(), &message, false)?;
     };
+    outputs.notifiers = options.notify.iter().filter_map(notifier).collect();
+}
+
+fn set_up_metrics(outputs: &mut Outputs, options: &Options) {
+    let Some(port) = options.metrics_port else { return };
+    // Counters that went back to 0 would look like a reset to Prometheus,
+    // so without the history there's nothing to serve
+    let before = match all_time() {
+        Ok(before) => before,
+        Err(error) => return outputs.warn("metrics", format!("unable to read the history for the metrics: {}", error)),
+    };
+    match Metrics::serve(port, before) {
+        Ok(metrics) => outputs.metrics = Some(metrics),
+        Err(error) => outputs.warn("metrics", format!("unable to serve metrics on port {}: {}", port, error)),
+    }
+}
 
-    execute!(io::stdout(), cursor::Show)?;
-    disable_raw_mode()?;
+// Every work session recorded, archived months included
+fn all_time() -> io::Result<Tally> {
+    let mut total = Rollups::load_default()?.total();
+    if let Some(path) = history::default_path() {
+        archive::daily(&History::new(path))?.values().for_each(|day| total.add(*day));
+    }
+    Ok(total)
+}
 
-    match result {
-        TimerResult::Quit => {
-            display_header()?;
-            println!("Pomodoro session ended. See you next time!");
+fn task_command(command: &TaskCommand, options: &Options) -> Result<(), Error> {
+    let store = TaskStore::new(tasks::default_path().ok_or(Error::NoDirectory("data"))?);
+    let mut list = store.load()?;
+    match command {
+        TaskCommand::Add(task, after) => {
+            let mut task = task.clone();
+            for number in after {
+                let first = list.get(number - 1).ok_or_else(|| Error::Usage(format!("there is no task {}, see 'timeadair task list'", number)))?;
+                task.after.push(first.title.clone());
+            }
+            list.push(task.clone());
+            store.save(&list)?;
+            if options.json {
+                println!("{}", json::success("task", json!({ "number": list.len(), "title": task.title })));
+            } else {
+                println!("Added task {}: {}", list.len(), task.title);
+            }
         }
-        TimerResult::Reset => {
-            display_header()?;
-            println!("Timer reset.");
+        TaskCommand::List => {
+            let records = History::new(history::default_path().ok_or(Error::NoDirectory("data"))?).load()?;
+            let now = Local::now();
+            if options.json {
+                let data: Vec<_> = list
+                    .iter()
+                    .enumerate()
+                    .map(|(i, task)| {
+                        let pomodoros = tasks::pomodoros(task, &records, &now);
+                        json!({ "done": task.is_done(&now), "number": i + 1, "pomodoros": pomodoros, "task": task })
+                    })
+                    .collect();
+                println!("{}", json::success("task", data));
+            } else {
+                print!("{}", tasks::list(&list, &records, &now));
+            }
         }
-        TimerResult::Completed => {
-            print!("\x07");
-            io::stdout().flush()?;
+        TaskCommand::Done(number) => {
+            let task = list.get_mut(number - 1).ok_or_else(|| Error::Usage(format!("there is no task {}, see 'timeadair task list'", number)))?;
+            task.mark_done(Local::now().timestamp());
+            let title = task.title.clone();
+            store.save(&list)?;
+            if options.json {
+                println!("{}", json::success("task", json!({ "number": number, "title": title })));
+            } else {
+                println!("Marked task {} done: {}", number, title);
+            }
         }
     }
-    Ok(result)
+    Ok(())
 }
 
-fn display_header() -> io::Result<()> {
-    clear_screen()?;
-    println!("\n🍅 Tìmeadair - Pomodoro Timer\n");
+// Tasks the history says won't be finished in time, for the work prompt
+fn deadline_warning() -> Option<String> {
+    let tasks = TaskStore::new(tasks::default_path()?).load().ok()?;
+    if tasks.is_empty() {
+        return None;
+    }
+    let records = History::new(history::default_path()?).load().ok()?;
+    let now = Local::now();
+    tasks::warning(&tasks::at_risk(&tasks, &records, now), now.date_naive())
+}
+
+fn sounds_command(command: &SoundsCommand, options: &Options) -> Result<(), Error> {
+    match command {
+        SoundsCommand::List => {
+            let packs = sound::list_packs()?;
+            if options.json {
+                let packs: Vec<_> = packs
+                    .iter()
+                    .map(|(pack, events)| json!({ "pack": pack, "sounds": events.iter().map(|event| event.name()).collect::<Vec<_>>() }))
+                    .collect();
+                println!("{}", json::success("sounds", packs));
+                return Ok(());
+            }
+            if packs.is_empty() {
+                let dir = sound::packs_dir().ok_or(Error::NoDirectory("config"))?;
+                println!("No sound packs yet; add a directory of sounds to {}", dir.display());
+            }
+            for (pack, events) in packs {
+                let names: Vec<_> = events.iter().map(|event| event.name()).collect();
+                println!("{}: {}", pack, if names.is_empty() { "no sounds".to_string() } else { names.join(", ") });
+            }
+        }
+        SoundsCommand::Devices => {
+            let devices = sound::list_devices().map_err(Error::Sound)?;
+            if options.json {
+                println!("{}", json::success("sounds", devices));
+                return Ok(());
+            }
+            for device in devices {
+                println!("{}", device);
+            }
+        }
+        SoundsCommand::Preview { pack, event } => {
+            let mut sounds = Sounds::new(Some(pack), None, None).map_err(Error::Sound)?;
+            sounds.set_output(options.volume, options.audio_device.clone());
+            let events = match event {
+                Some(event) => vec![*event],
+                None => SoundEvent::ALL.into_iter().filter(|event| sounds.has(*event)).collect(),
+            };
+            for event in &events {
+                if !options.json {
+                    println!("Playing {}", event.name());
+                }
+                sounds.play_and_wait(*event).map_err(Error::Sound)?;
+            }
+            if options.json {
+                let played: Vec<_> = events.iter().map(|event| event.name()).collect();
+                println!("{}", json::success("sounds", json!({ "played": played })));
+            }
+        }
+    }
     Ok(())
 }
 
-fn prompt_session(session_type: &str) -> io::Result<bool> {
-    display_header()?;
-    execute!(io::stdout(), cursor::Show)?;
-    print!("Start {} session? [Y/n]: ", session_type);
-    io::stdout().flush()?;
+// Shows what went wrong on the screen that was up when it happened, and
+// waits for a key so it can be read before the screen goes away
+fn show_error(screen: &mut dyn Renderer, error: &Error) -> io::Result<()> {
+    screen.error(&error.to_string(), error.hint())?;
+    if !ui::reads_keys() {
+        return Ok(());
+    }
+    let _raw = RawMode::enable()?;
+    while !matches!(event::read()?, Event::Key(_)) {}
+    Ok(())
+}
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    Ok(input.trim().is_empty() || input.trim().to_lowercase().starts_with('y'))
+fn main() {
+    // The message would be lost on the alternate screen, and the shell left
+    // in raw mode, so the terminal is put back before it's printed. Other
+    // threads don't own the terminal, and their panics leave it alone
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() == Some("main") && io::stdout().is_terminal() {
+            ui::restore_terminal();
+        }
+        report(info);
+    }));
+    let (json, result) = match options() {
+        Ok(options) => (options.json.then(|| options.command()), run(options)),
+        // A script asking for JSON still wants it when the arguments are wrong
+        Err(error) => (std::env::args().any(|arg| arg == "--json").then_some(None), Err(error)),
+    };
+    if let Err(error) = result {
+        if let Some(command) = json {
+            println!("{}", json::failure(command, &error));
+            std::process::exit(error.exit_code());
+        }
+        if io::stdout().is_terminal() {
+            ui::restore_terminal();
+        }
+        eprintln!("timeadair: {}", error);
+        if let Some(hint) = error.hint() {
+            eprintln!("{}", hint);
+        }
+        std::process::exit(error.exit_code());
+    }
+}
+
+fn options() -> Result<Options, Error> {
+    let config = load_config().map_err(Error::Config)?;
+    Options::parse(std::env::args().skip(1), &config).map_err(Error::Usage)
+}
+
+// Asked on the terminal the first time, before the timer takes it over;
+// the frontends without one leave it off until it's granted
+fn dnd_allowed(outputs: &mut Outputs, options: &Options) -> bool {
+    let interactive = ui::reads_keys() && !(options.stdio || options.daemon || options.status_format.is_some());
+    match permissions::consent(Permission::Dnd, interactive) {
+        Ok(Consent::Allowed) => true,
+        Ok(Consent::Denied) => false,
+        Ok(Consent::Undecided) => {
+            outputs.warn("dnd", "Do Not Disturb hasn't been allowed yet, run 'timeadair permissions grant dnd' to allow it");
+            false
+        }
+        Err(error) => {
+            outputs.warn("dnd", format!("unable to read permissions.toml: {}", error));
+            false
+        }
+    }
 }
 
-fn main() -> io::Result<()> {
+fn run(options: Options) -> Result<(), Error> {
+    if options.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if let Some(dir) = &options.data_dir {
+        paths::set_data_dir(dir.clone());
+    }
+    if options.read_only {
+        paths::set_read_only();
+    }
+    if let Some(shell) = options.shell_init {
+        let path = status::default_path().ok_or(Error::NoDirectory("runtime"))?;
+        let script = shell::init_script(shell, &path);
+        if options.json {
+            println!("{}", json::success("shell-init", json!({ "script": script })));
+        } else {
+            print!("{}", script);
+        }
+        return Ok(());
+    }
+    if options.bench {
+        let timings = bench::run()?;
+        if options.json {
+            println!("{}", json::success("bench", bench::data(&timings)));
+        } else {
+            print!("{}", bench::report(&timings));
+        }
+        return Ok(());
+    }
+    if options.stats && options.year {
+        let path = history::default_path().ok_or(Error::NoDirectory("data"))?;
+        let days = match &options.profile {
+            Some(profile) => {
+                let mut rollups = Rollups::default();
+                rollups.rebuild(&stats::in_profile(History::new(path).load()?, profile), 0, &Local);
+                rollups.days
+            }
+            None => archive::daily(&History::new(path))?,
+        };
+        let today = Local::now().date_naive();
+        if options.json {
+            println!("{}", json::success("stats", stats::year_data(&days, today)));
+        } else {
+            let ascii = options.no_color || theme::no_color_requested();
+            print!("{}", stats::year(&days, today, options.locale.text(), ascii));
+        }
+        return Ok(());
+    }
+    if options.stats {
+        let path = history::default_path().ok_or(Error::NoDirectory("data"))?;
+        let records = History::new(path).load()?;
+        let mut schedule = options.schedule.clone();
+        if let Some(source) = &options.vacation_calendar {
+            match vacation::calendar(source) {
+                Ok(days) => schedule.add_vacation(days),
+                Err(error) => eprintln!("Warning: unable to read the vacation calendar {}: {}", source, error),
+            }
+        }
+        // Archived sessions are only kept as totals, which can't be split by profile
+        let summary = match &options.profile {
+            Some(profile) => stats::summarize(&stats::in_profile(records, profile), &Rollups::default(), &schedule, Local::now()),
+            None => stats::summarize(&records, &Rollups::load_default()?, &schedule, Local::now()),
+        };
+        if options.json {
+            println!("{}", json::success("stats", stats::data(&summary)));
+        } else {
+            print!("{}", stats::report(&summary, options.locale.text()));
+        }
+        return Ok(());
+    }
+    if let Some(request) = &options.export {
+        let path = history::default_path().ok_or(Error::NoDirectory("data"))?;
+        let records = History::new(path).load()?;
+        if options.json {
+            println!("{}", json::success("export", export::rows(&records, request, &Local)));
+        } else {
+            print!("{}", export::export(&records, request, &Local));
+        }
+        return Ok(());
+    }
+    if let Some(before) = options.archive {
+        let archived = archive::archive(before)?;
+        let file = archived.file.as_ref().map(|file| file.display().to_string());
+        if options.json {
+            println!("{}", json::success("history", json!({ "archived": archived.sessions, "file": file })));
+        } else if let Some(file) = file {
+            let noun = if archived.sessions == 1 { "session" } else { "sessions" };
+            println!("Archived {} {} from before {} to {}.", archived.sessions, noun, before, file);
+        } else {
+            println!("No sessions from before {} to archive.", before);
+        }
+        return Ok(());
+    }
+    if let Some(output) = &options.debug_bundle {
+        let file = bundle::write(output.as_deref(), options.workspace.as_deref(), options.event_log.as_deref())?;
+        if options.json {
+            println!("{}", json::success("debug-bundle", json!({ "file": file })));
+        } else {
+            println!("Wrote {}, have a look through it before attaching it to a bug report.", file.display());
+        }
+        return Ok(());
+    }
+    if let Some(request) = &options.seed {
+        let sessions = seed::seed(request)?;
+        if options.json {
+            println!("{}", json::success("seed", json!({ "sessions": sessions, "days": request.days })));
+        } else {
+            println!("Added {} made-up sessions over the last {} days, see 'timeadair stats'.", sessions, request.days);
+        }
+        return Ok(());
+    }
+    if let Some(board) = &options.leaderboard {
+        let now = Local::now();
+        let standings = leaderboard::standings(&board.load()?, board.anonymous, now);
+        if options.json {
+            println!("{}", json::success("leaderboard", leaderboard::data(&standings, now.date_naive())));
+        } else {
+            print!("{}", leaderboard::markdown(&standings, now.date_naive()));
+        }
+        return Ok(());
+    }
+    if let Some(request) = &options.search {
+        let path = history::default_path().ok_or(Error::NoDirectory("data"))?;
+        let records = History::new(path).load()?;
+        let list = tasks::default_path().map(|path| TaskStore::new(path).load()).transpose()?.unwrap_or_default();
+        let found = search::search(request, &list, &records, &Local);
+        if options.json {
+            println!("{}", json::success("search", search::data(&found, &Local)));
+        } else {
+            print!("{}", search::report(&found, &Local));
+        }
+        return Ok(());
+    }
+    if let Some(command) = &options.tasks {
+        return task_command(command, &options);
+    }
+    if let Some(request) = &options.ctl {
+        let reply = ctl(request)?;
+        if options.json {
+            // The status is JSON already
+            let data = serde_json::from_str(&reply).ok().filter(|_| request == "status");
+            println!("{}", json::success(options.command().unwrap_or("ctl"), data.unwrap_or_else(|| json!({ "reply": reply }))));
+        } else {
+            println!("{}", reply);
+        }
+        return Ok(());
+    }
+    if options.wait {
+        let path = status::default_path().ok_or(Error::NoDirectory("runtime"))?;
+        let ended = wait::wait(&path)?.ok_or(Error::NothingRunning)?;
+        let outcome = ended.outcome.map(|outcome| outcome.name());
+        if options.json {
+            println!("{}", json::success("wait", json!({ "session": ended.session, "outcome": outcome })));
+        } else {
+            println!("{} {}", ended.session, outcome.unwrap_or("ended"));
+        }
+        std::process::exit(ended.exit_code());
+    }
+    if let Some(command) = &options.sounds {
+        return sounds_command(command, &options);
+    }
+    if let Some(command) = &options.permissions {
+        let grants = permissions::run(command)?;
+        if options.json {
+            println!("{}", json::success("permissions", permissions::data(&grants)));
+        } else {
+            print!("{}", permissions::text(&grants));
+        }
+        return Ok(());
+    }
+    if options.json {
+        return Err(Error::Usage("--json goes with a subcommand, e.g. 'timeadair stats --json'; for the timer itself see --status-format json".to_string()));
+    }
+    if options.demo {
+        return Ok(demo::run()?);
+    }
+    if options.tutorial {
+        return Ok(tutorial::run()?);
+    }
+    if let Some(seconds) = options.countdown {
+        let mut screen = renderer(&options)?;
+        return Ok(countdown(screen.as_mut(), &options, seconds)?);
+    }
+    if options.stopwatch {
+        let mut screen = renderer(&options)?;
+        let stopwatch = stopwatch::run(screen.as_mut())?;
+        return Ok(screen.message(&stopwatch.summary())?);
+    }
+    // None of the outputs are needed to keep time, so one that can't be set
+    // up is reported as a warning and the timer runs without it
+    let mut outputs = Outputs::default();
+    outputs.events = options.event_log.as_deref().map(EventLog::open);
+    let dry_run = DryRun::new(&options.dry_run);
+    outputs.publisher = Publisher::start(Targets { dry_run: dry_run.clone(), ..options.publish.clone() });
+    if let Some(path) = &options.serial {
+        match SerialOutput::open(path) {
+            Ok(output) => outputs.serial = Some(output),
+            Err(source) => outputs.warn("serial", Error::Serial { path: path.clone(), source }),
+        }
+    }
+    // Neither file could be written anyway
+    if !options.read_only {
+        outputs.status = status::default_path().map(StatusFile::new);
+        outputs.history = history::default_path().map(History::new);
+    }
+    outputs.schedule = options.schedule.clone();
+    outputs.false_start = options.false_start;
+    outputs.diary = (options.diary && !options.read_only).then(Diary::new).flatten();
+    outputs.strict = options.strict.then_some(options.locale.text().abandon_phrase);
+    outputs.countdown_pips = options.final_countdown.ticks();
+    outputs.profile = options.profile.clone();
+    outputs.dnd = Dnd::new(&options.dnd, options.focus_shortcuts.clone()).filter(|_| dnd_allowed(&mut outputs, &options));
+    outputs.hooks = Hooks { dry_run, ..options.hooks.clone() };
+    outputs.media = options.media.clone();
+    set_up_metrics(&mut outputs, &options);
+
+    let (command_sender, commands) = mpsc::channel();
+    // A status bar has no other way to be told to start a session
+    if options.command_fifo || options.status_format.is_some() {
+        if let Err(error) = start_command_fifo(command_sender.clone()) {
+            outputs.warn("command FIFO", error);
+        }
+    }
+
+    if options.stdio || options.status_format.is_some() || options.daemon {
+        let clean_up = || {
+            let _ = dnd::restore();
+            status::remove_default();
+            #[cfg(unix)]
+            daemon::remove_socket();
+        };
+        ctrlc::set_handler(move || {
+            clean_up();
+            std::process::exit(0);
+        }).expect("Error setting Ctrl-C handler");
+        // Always listening for commands, so the session ends and is recorded
+        // the usual way; there's no checkpoint here to resume from
+        let shutdown = command_sender.clone();
+        let handled = on_terminate(move || {
+            if shutdown.send(Command::Shutdown).is_ok() {
+                thread::sleep(SHUTDOWN_GRACE);
+            }
+            clean_up();
+            std::process::exit(0);
+        });
+        if let Err(error) = handled {
+            outputs.warn("shutdown", format!("unable to handle SIGTERM: {}", error));
+        }
+    }
+    if options.daemon {
+        set_up_sounds(&mut outputs, &options);
+        set_up_notifiers(&mut outputs, &options, false);
+        let status = Arc::new(Mutex::new(String::new()));
+        start_daemon(command_sender, status.clone())?;
+        let result = statusbar::drive(&mut outputs, &commands, &options, &mut |state| {
+            *status.lock().unwrap() = statusbar::line(state, StatusFormat::Json);
+            Ok(())
+        });
+        #[cfg(unix)]
+        daemon::remove_socket();
+        return Ok(result?);
+    }
+    if options.stdio || options.status_format.is_some() {
+        set_up_notifiers(&mut outputs, &options, false);
+    }
+    if options.stdio {
+        stdio::read_commands(command_sender);
+        return Ok(stdio::run(&mut outputs, &commands, &options)?);
+    }
+    if let Some(format) = options.status_format {
+        return Ok(statusbar::run(&mut outputs, &commands, &options, format)?);
+    }
+    outputs.taskbar = true;
+    // Only asked for where there's someone at the keyboard to answer
+    outputs.rate = options.rate;
+    outputs.notes = options.notes;
+    if options.title {
+        match TitleOutput::new(options.tmux) {
+            Ok(title) => outputs.title = Some(title),
+            Err(error) => outputs.warn("title", format!("unable to set the terminal title: {}", error)),
+        }
+    }
+    set_up_sounds(&mut outputs, &options);
+    set_up_notifiers(&mut outputs, &options, true);
+    if !options.read_only {
+        outputs.checkpoint = checkpoint::default_path().map(CheckpointFile::new);
+    }
+
+    let (ui_mode, farewell, title, tmux) = (options.ui, options.locale.text().farewell, options.title, options.tmux);
+    // Everything a session would have put back on its way out, keeping the
+    // checkpoint when the session is to be picked up again
+    let clean_up = move |keep_checkpoint: bool| {
+        let _ = taskbar::set_progress(ProgressState::Clear, 0);
+        let _ = dnd::restore();
+        if title {
+            title::restore(tmux);
+        }
+        status::remove_default();
+        if !keep_checkpoint {
+            checkpoint::remove_default();
+        }
+        match ui_mode {
+            UiMode::Screen => {
+                ui::restore_terminal();
+                println!("{}", farewell);
+            }
+            UiMode::Plain => {
+                let _ = disable_raw_mode();
+                let _ = PlainRenderer::stdout().message(farewell);
+            }
+        }
+    };
     ctrlc::set_handler(move || {
-        let _ = execute!(io::stdout(), cursor::Show);
-        let _ = disable_raw_mode();
-        let _ = display_header();
-        println!("Pomodoro session ended. See you next time!");
+        clean_up(false);
         std::process::exit(0);
     }).expect("Error setting Ctrl-C handler");
+    // A running session takes the command like any other and ends itself,
+    // recorded as interrupted; at a prompt there's nothing to record
+    let (shutdown, on_shutdown) = (command_sender.clone(), options.on_shutdown);
+    let handled = on_terminate(move || {
+        let record = on_shutdown == OnShutdown::Record;
+        if record && app::session_running() && shutdown.send(Command::Shutdown).is_ok() {
+            thread::sleep(SHUTDOWN_GRACE);
+        }
+        clean_up(!record);
+        std::process::exit(0);
+    });
+    if let Err(error) = handled {
+        outputs.warn("shutdown", format!("unable to handle SIGTERM: {}", error));
+    }
 
-    loop {
-        if !prompt_session("work")? {
-            display_header()?;
-            println!("Pomodoro session ended. See you next time!");
-            break;
+    let mut screen = renderer(&options)?;
+    if let Err(error) = interactive(screen.as_mut(), &mut outputs, &commands, &options) {
+        let error = Error::from(error);
+        let _ = show_error(screen.as_mut(), &error);
+        return Err(error);
+    }
+    screen.show_cursor(true)?;
+    // Off the alternate screen first, so the summary stays after the farewell
+    drop(screen);
+    let run = outputs.run_ended();
+    if !run.is_empty() {
+        println!("{}", run.line(options.locale.text()));
+    }
+    Ok(())
+}
+
+// A session the last timer to run never finished; one that can't be read
+// is left where it is and reported
+fn interrupted_session(outputs: &mut Outputs) -> Option<Checkpoint> {
+    match outputs.checkpoint.as_ref()?.interrupted() {
+        Ok(checkpoint) => checkpoint,
+        Err(error) => {
+            outputs.warn("checkpoint", format!("unable to read the interrupted session: {}", error));
+            None
         }
-        match run_timer(WORK_TIME, "Work")? {
-            TimerResult::Completed => {
-                // Continue to break prompt
+    }
+}
+
+// The next few sessions after the current one, round to the top of the plan
+fn upcoming(plan: &[Planned], current: usize) -> Vec<Planned> {
+    (1..plan.len().min(4)).map(|ahead| plan[(current + ahead) % plan.len()]).collect()
+}
+
+fn interactive(screen: &mut dyn Renderer, outputs: &mut Outputs, commands: &Receiver<Command>, options: &Options) -> io::Result<()> {
+    let mut state = State::new(options.durations);
+    // Under --auto the first session counts down like all the others
+    let mut hand_over = options.auto;
+    let mut task = options.task.clone();
+    let mut coach = options.coach.then(|| Coach::new(&todays_history()));
+    let mut idle = options.idle_pause.map(IdleMonitor::new);
+    let mut goal = options.daily_goal.map(|goal| (pomodoros_today(), goal));
+    let mut calendar = options.calendar.clone().map(Calendar::new);
+    let break_suggestions = options.break_suggestions.clone().unwrap_or_else(|| suggestions::defaults(options.locale));
+    let mut rng = Rng::from_clock();
+    // Shown at the next prompt once the goal is reached
+    let mut celebration = None;
+    // The plan's current entry, moved on whenever a session hands over
+    let mut planned = 0;
+    if let Some(checkpoint) = interrupted_session(outputs) {
+        screen.resume_prompt(&checkpoint.summary())?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        match checkpoint.restore(state).filter(|_| ui::prompt_accepted(&input)) {
+            Some(resumed) => {
+                state = resumed;
+                task = checkpoint.task.clone();
+                outputs.resume(checkpoint);
             }
-            TimerResult::Quit => break,
-            TimerResult::Reset => continue, // Go back to work session prompt
-        }
-        if prompt_session("break")? {
-            // Break session
-            match run_timer(BREAK_TIME, "Break")? {
-                TimerResult::Completed => {
-                    // Continue to next work session
+            None => {
+                if let Some(file) = &outputs.checkpoint {
+                    file.clear()?;
                 }
-                TimerResult::Quit => break,
-                TimerResult::Reset => continue, // Go back to work session prompt
             }
         }
     }
-
-    execute!(io::stdout(), cursor::Show)?;
+    loop {
+        // Only a session that ran to completion, or was cut short on
+        // purpose, hands over automatically
+        let auto_start = hand_over && options.auto_advance.is_some();
+        hand_over = false;
+        let grace = options.auto_advance.unwrap_or_default();
+        screen.set_phase(state.phase);
+        if options.plan.is_empty() {
+            screen.set_cycle(state.cycle_position());
+        } else {
+            state = scheduler::plan(state, options.plan[planned]);
+            screen.set_cycle(Some((planned as u32 + 1, options.plan.len() as u32)));
+            screen.set_upcoming(&upcoming(&options.plan, planned));
+        }
+        screen.set_goal(goal);
+        report_warnings(screen, outputs)?;
+        state = match state.phase {
+            Phase::AwaitingWork => {
+                outputs.waiting(&state);
+                let mut notice = None;
+                if let Some(hours) = options.working_hours.filter(|hours| !hours.contains_now()) {
+                    if hours.strict {
+                        screen.message(&format!("It's outside your working hours ({}), so no work sessions right now.", hours))?;
+                        break;
+                    }
+                    notice = Some(format!("Heads up: it's outside your working hours ({}).", hours));
+                }
+                let work = scheduler::step(state, scheduler::Event::Start).timer.duration;
+                let meeting = upcoming_meeting(calendar.as_mut(), outputs, work);
+                notice = notice.or_else(|| {
+                    let meeting = meeting.as_ref()?;
+                    Some(locale::fill(options.locale.text().meeting_notice, &[&meeting.summary, &meeting.start.format("%H:%M")]))
+                });
+                notice = notice.or_else(|| celebration.take());
+                notice = notice.or_else(deadline_warning);
+                notice = notice.or_else(|| coach.as_mut().and_then(Coach::tip).map(str::to_string));
+                let answer = ask_to_start(screen, "work", work, notice.as_deref(), auto_start.then_some(grace), false)?;
+                let auto_started = auto_start && matches!(answer, Answer::Start);
+                let accepted = matches!(answer, Answer::Start);
+                // A session that starts itself carries on with the last task
+                if accepted && !auto_started && options.task.is_none() {
+                    task = prompt_task(screen, options.board && matches!(options.ui, UiMode::Screen))?;
+                }
+                let checked = accepted
+                    && match run_checklist(screen, &options.checklist)? {
+                        Some(skipped) => {
+                            outputs.checklist_done(&options.checklist, &skipped);
+                            true
+                        }
+                        None => false,
+                    };
+                if checked {
+                    let mut next = scheduler::step(state, scheduler::Event::Start);
+                    if let Some(meeting) = meeting.filter(|_| !auto_started) {
+                        let room = meeting.room(Local::now().naive_local());
+                        if (60..next.timer.duration).contains(&room) && offer_shorter(screen, room / 60, &meeting.summary)? {
+                            next.timer = Timer::new(room);
+                        }
+                    }
+                    next
+                } else {
+                    screen.message(options.locale.text().farewell)?;
+                    scheduler::step(state, scheduler::Event::Decline)
+                }
+            }
+            Phase::AwaitingBreak => {
+                outputs.waiting(&state);
+                let session_type = state.session_name().to_lowercase();
+                let celebrated = celebration.take();
+                let notice = celebrated.as_deref().or_else(|| coach.as_mut().and_then(Coach::tip));
+                // Only a work session that ran its course can be snoozed on
+                let snooze = outputs.can_snooze();
+                let offer = snooze.then(|| locale::fill(options.locale.text().snooze_notice, &[&(options.snooze / 60)]));
+                let notice = match (notice, offer) {
+                    (Some(notice), Some(offer)) => Some(format!("{} {}", notice, offer)),
+                    (notice, offer) => offer.or_else(|| notice.map(str::to_string)),
+                };
+                let length = scheduler::step(state, scheduler::Event::Start).timer.duration;
+                // Under --strict there's no turning a break down
+                let answer = if options.strict {
+                    Answer::Start
+                } else {
+                    ask_to_start(screen, &session_type, length, notice.as_deref(), auto_start.then_some(grace), snooze)?
+                };
+                let event = match answer {
+                    Answer::Start => scheduler::Event::Start,
+                    Answer::Decline => {
+                        if let Some(coach) = &mut coach {
+                            coach.break_skipped();
+                        }
+                        planned = (planned + 1) % options.plan.len().max(1);
+                        scheduler::Event::Decline // Straight back to the work prompt
+                    }
+                    Answer::Quit => {
+                        screen.message(options.locale.text().farewell)?;
+                        scheduler::Event::Quit
+                    }
+                    // Back to this prompt afterwards, counting down again
+                    // if it was
+                    Answer::Snooze => {
+                        let worked = run_overtime(screen, outputs, commands, options.snooze, options.locale.text().overtime)?;
+                        outputs.overtime(worked);
+                        hand_over = auto_start;
+                        continue;
+                    }
+                };
+                scheduler::step(state, event)
+            }
+            Phase::Work | Phase::Break => {
+                let task = if state.phase == Phase::Work { task.as_deref() } else { None };
+                let phase = state.phase;
+                let text = options.locale.text();
+                let name = text.session_name(state.session_name());
+                let message = match task {
+                    Some(task) => locale::fill(text.current_task, &[&name, &task]),
+                    None => locale::fill(text.current_session, &[&name]),
+                };
+                let activity = if phase == Phase::Break { spin_roulette(screen, options, &mut rng)? } else { None };
+                match &activity {
+                    Some(activity) => screen.set_suggestions(slice::from_ref(activity)),
+                    None => screen.set_suggestions(if phase == Phase::Break { &break_suggestions } else { &[] }),
+                }
+                outputs.activity = activity;
+                let (next, result) = run_timer(state, task, &message, screen, outputs, commands, idle.as_mut())?;
+                if let Some(coach) = &mut coach {
+                    coach.session_ended(phase, Outcome::from(&result), next.timer.duration, next.paused_time);
+                }
+                if let (Phase::Work, TimerResult::Completed, Some((done, target))) = (phase, &result, &mut goal) {
+                    let before = *done;
+                    *done = pomodoros_today();
+                    if before < *target && *done >= *target {
+                        outputs.goal_reached(*done, *target);
+                        celebration = Some(locale::fill(text.goal_reached, &[&*done, &*target]));
+                    }
+                }
+                if let (Phase::Work, TimerResult::Completed, Some(task)) = (phase, &result, task) {
+                    offer_done(screen, task)?;
+                }
+                hand_over = matches!(result, TimerResult::Completed | TimerResult::Skipped);
+                if hand_over {
+                    planned = (planned + 1) % options.plan.len().max(1);
+                }
+                next
+            }
+            Phase::Finished => break,
+        };
+    }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+//! Music for each kind of session, set in the config file's `[media]`
+//! table:
+//!
+//! ```toml
+//! [media]
+//! player = "spotify"
+//! work = "spotify:playlist:37i9dQZF1DX8NTLI2TtZa6"
+//! break = "pause"
+//! long_break = "spotify:playlist:37i9dQZF1DWZqd5JICZI0u"
+//! ```
+//!
+//! Each is `play` to carry on with whatever was playing, `pause`, or a URI
+//! for the player to open and start. A long break does what `break` does
+//! unless it's given its own, and a kind left out leaves the music alone.
+//!
+//! On Linux this goes through MPRIS with `playerctl`, to `player` as
+//! playerctl names it or otherwise whichever player it picks. On macOS
+//! Spotify is told through AppleScript and `player` is ignored.
+
+use std::io;
+use std::process::{Command, Stdio};
+
+use crate::config::MediaConfig;
+use crate::scheduler::{Phase, State};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Play,
+    Pause,
+    Open(String),
+}
+
+impl Action {
+    fn parse(key: &str, text: &str) -> Result<Action, String> {
+        match text.trim() {
+            "play" => Ok(Action::Play),
+            "pause" => Ok(Action::Pause),
+            // Every URI has a scheme, which also catches typos like "pasue"
+            uri if uri.contains(':') => Ok(Action::Open(uri.to_string())),
+            _ => Err(format!("media {} expects \"play\", \"pause\" or a URI such as spotify:playlist:<id>, not '{}'", key, text)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Media {
+    player: Option<String>,
+    work: Option<Action>,
+    rest: Option<Action>,
+    long_break: Option<Action>,
+}
+
+impl Media {
+    pub fn parse(config: &MediaConfig) -> Result<Media, String> {
+        let action = |key, text: &Option<String>| text.as_deref().map(|text| Action::parse(key, text)).transpose();
+        Ok(Media {
+            player: config.player.clone(),
+            work: action("work", &config.work)?,
+            rest: action("break", &config.rest)?,
+            long_break: action("long_break", &config.long_break)?,
+        })
+    }
+
+    fn action(&self, state: &State) -> Option<&Action> {
+        match state.phase {
+            Phase::Work => self.work.as_ref(),
+            Phase::Break if state.session_name() == "Long break" => self.long_break.as_ref().or(self.rest.as_ref()),
+            Phase::Break => self.rest.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn session_started(&self, state: &State) -> io::Result<()> {
+        let Some(action) = self.action(state) else { return Ok(()) };
+        let (program, args) = command(action, self.player.as_deref(), cfg!(target_os = "macos"));
+        let output = Command::new(program).args(&args).stdin(Stdio::null()).stdout(Stdio::null()).output()?;
+        if !output.status.success() {
+            let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(io::Error::other(if reason.is_empty() { format!("{} failed", program) } else { reason }));
+        }
+        Ok(())
+    }
+}
+
+fn command(action: &Action, player: Option<&str>, macos: bool) -> (&'static str, Vec<String>) {
+    if macos {
+        let verb = match action {
+            Action::Play => "play".to_string(),
+            Action::Pause => "pause".to_string(),
+            Action::Open(uri) => format!("play track \"{}\"", uri.replace('\\', "\\\\").replace('"', "\\\"")),
+        };
+        return ("osascript", vec!["-e".to_string(), format!("tell application \"Spotify\" to {}", verb)]);
+    }
+    let mut args = Vec::new();
+    if let Some(player) = player {
+        args.extend(["--player".to_string(), player.to_string()]);
+    }
+    match action {
+        Action::Play => args.push("play".to_string()),
+        Action::Pause => args.push("pause".to_string()),
+        Action::Open(uri) => args.extend(["open".to_string(), uri.clone()]),
+    }
+    ("playerctl", args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{self, Durations, Event};
+
+    #[test]
+    fn each_kind_of_session_gets_its_own() {
+        let config = MediaConfig {
+            player: Some("spotify".to_string()),
+            work: Some("spotify:playlist:focus".to_string()),
+            rest: Some("pause".to_string()),
+            long_break: None,
+        };
+        let media = Media::parse(&config).unwrap();
+        let durations = Durations { long_break_every: 1, ..Durations::default() };
+        let work = scheduler::step(State::new(durations), Event::Start);
+        assert_eq!(media.action(&work), Some(&Action::Open("spotify:playlist:focus".to_string())));
+        // Every break is a long one here, and falls back to `break`
+        let rest = scheduler::step(scheduler::step(work, Event::Skip), Event::Start);
+        assert_eq!(media.action(&rest), Some(&Action::Pause));
+        assert!(Media::parse(&MediaConfig { work: Some("pasue".to_string()), ..config }).is_err());
+
+        let open = Action::Open("spotify:playlist:focus".to_string());
+        assert_eq!(command(&open, Some("spotify"), false), ("playerctl", vec!["--player".into(), "spotify".into(), "open".into(), "spotify:playlist:focus".into()]));
+        assert_eq!(command(&Action::Pause, None, true).1[1], "tell application \"Spotify\" to pause");
+        assert_eq!(command(&open, None, true).1[1], "tell application \"Spotify\" to play track \"spotify:playlist:focus\"");
+    }
+}
@@ -0,0 +1,190 @@
+//! Meetings from a calendar, so a work session doesn't run into one:
+//!
+//! ```toml
+//! calendar = "https://cloud.example/remote.php/dav/calendars/me/work?export"   # or an .ics file
+//! ```
+//!
+//! The work prompt warns about a meeting that starts before the session
+//! would end, and once the session is accepted offers to shorten it to end
+//! on the last whole minute before. One that starts itself under
+//! `--auto-advance` only gets the warning, since nobody may be there to
+//! answer. All-day events aren't meetings. Repeating events are followed
+//! for daily and weekly rules with INTERVAL, COUNT, UNTIL and BYDAY; any
+//! other rule only counts the first time. A CalDAV calendar is read
+//! through its export link, which most servers have, and a URL is fetched
+//! with `curl` at most every ten minutes.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Days, NaiveDateTime, TimeDelta, Weekday};
+
+use crate::ics::{self, Event, When};
+
+const REFRESH: Duration = Duration::from_secs(600);
+// Keeps a daily meeting from years back from taking long to catch up on
+const MOST_OCCURRENCES: usize = 20_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Meeting {
+    pub summary: String,
+    pub start: NaiveDateTime,
+}
+
+impl Meeting {
+    // Seconds of a session starting `now` that fit before it, in whole minutes
+    pub fn room(&self, now: NaiveDateTime) -> u64 {
+        let seconds = (self.start - now).num_seconds().max(0) as u64;
+        seconds - seconds % 60
+    }
+}
+
+pub struct Calendar {
+    source: String,
+    events: Vec<Event>,
+    loaded: Option<Instant>,
+}
+
+impl Calendar {
+    pub fn new(source: String) -> Calendar {
+        Calendar { source, events: Vec::new(), loaded: None }
+    }
+
+    // The first meeting to start in the `seconds` after `now`, reading the
+    // calendar again when it's due; the last good read is kept on an error
+    pub fn next(&mut self, now: NaiveDateTime, seconds: u64) -> io::Result<Option<Meeting>> {
+        if self.loaded.is_none_or(|loaded| loaded.elapsed() >= REFRESH) {
+            self.loaded = Some(Instant::now());
+            self.events = ics::events(&ics::fetch(&self.source)?);
+        }
+        Ok(next_meeting(&self.events, now, now + TimeDelta::seconds(seconds as i64)))
+    }
+}
+
+fn next_meeting(events: &[Event], from: NaiveDateTime, to: NaiveDateTime) -> Option<Meeting> {
+    events
+        .iter()
+        .filter_map(|event| {
+            let start = occurrences(event).take(MOST_OCCURRENCES).take_while(|start| *start < to).find(|start| *start > from)?;
+            Some(Meeting { summary: event.summary.clone(), start })
+        })
+        .min_by_key(|meeting| meeting.start)
+}
+
+// Every start of a timed event, in order
+fn occurrences(event: &Event) -> Box<dyn Iterator<Item = NaiveDateTime>> {
+    let Some(first) = event.start.and_then(When::local) else { return Box::new(std::iter::empty()) };
+    let Some(rule) = event.rule.as_deref().and_then(Rule::parse) else { return Box::new(std::iter::once(first)) };
+    let starts: Box<dyn Iterator<Item = NaiveDateTime>> = match rule.weekly {
+        None => Box::new((0..).map_while(move |n| first.checked_add_days(Days::new(n * rule.interval)))),
+        Some(days) => {
+            // Weeks run from Monday, as they do unless WKST says otherwise
+            let monday = first - Days::new(first.weekday().num_days_from_monday().into());
+            let days = if days.is_empty() { vec![first.weekday()] } else { days };
+            Box::new((0..).flat_map(move |week| {
+                let days = days.clone();
+                days.into_iter().filter_map(move |day| {
+                    monday.checked_add_days(Days::new(week * 7 * rule.interval + u64::from(day.num_days_from_monday())))
+                })
+            }))
+        }
+    };
+    let starts = starts.filter(move |start| *start >= first).take_while(move |start| rule.until.is_none_or(|until| *start <= until));
+    match rule.count {
+        Some(count) => Box::new(starts.take(count)),
+        None => Box::new(starts),
+    }
+}
+
+struct Rule {
+    interval: u64,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    // None for daily, otherwise the days of the week it's on
+    weekly: Option<Vec<Weekday>>,
+}
+
+impl Rule {
+    // None for anything but FREQ=DAILY and FREQ=WEEKLY
+    fn parse(rule: &str) -> Option<Rule> {
+        let mut parsed = Rule { interval: 1, count: None, until: None, weekly: None };
+        let mut daily = false;
+        for part in rule.split(';') {
+            let (name, value) = part.split_once('=')?;
+            match name.to_uppercase().as_str() {
+                "FREQ" if value.eq_ignore_ascii_case("DAILY") => daily = true,
+                "FREQ" if value.eq_ignore_ascii_case("WEEKLY") => parsed.weekly = Some(Vec::new()),
+                "FREQ" => return None,
+                "INTERVAL" => parsed.interval = value.parse().ok().filter(|interval| *interval > 0)?,
+                "COUNT" => parsed.count = Some(value.parse().ok()?),
+                // A date on its own takes in the whole of that day
+                "UNTIL" => parsed.until = Some(match When::parse(value)? {
+                    When::Date(day) => day.and_hms_opt(23, 59, 59)?,
+                    when => when.local()?,
+                }),
+                "BYDAY" => parsed.weekly.get_or_insert_with(Vec::new).extend(value.split(',').map(weekday).collect::<Option<Vec<_>>>()?),
+                "WKST" => {}
+                _ => return None,
+            }
+        }
+        if let Some(days) = &mut parsed.weekly {
+            days.sort_by_key(|day| day.num_days_from_monday());
+            days.dedup();
+        }
+        match (daily, parsed.weekly.is_some()) {
+            (true, false) | (false, true) => Some(parsed),
+            // Daily on some days of the week is weekly on those days
+            (true, true) if parsed.interval == 1 => Some(parsed),
+            _ => None,
+        }
+    }
+}
+
+fn weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn finds_the_next_meeting() {
+        let at = |day, hour, minute| NaiveDate::from_ymd_opt(2024, 12, day).unwrap().and_hms_opt(hour, minute, 0).unwrap();
+        let event = |summary: &str, start, rule: Option<&str>| Event {
+            summary: summary.to_string(),
+            start: Some(When::Local(start)),
+            end: None,
+            rule: rule.map(str::to_string),
+        };
+        // Monday 2 December, the first of a weekly standup on Mondays and Thursdays
+        let events = vec![
+            event("Standup", at(2, 9, 30), Some("FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20241219")),
+            event("Review", at(12, 14, 0), None),
+            event("Retro", at(5, 16, 0), Some("FREQ=MONTHLY;BYDAY=1TH")),
+        ];
+        let next = |from| next_meeting(&events, from, from + TimeDelta::minutes(25));
+        assert_eq!(next(at(12, 9, 10)).map(|meeting| meeting.start), Some(at(12, 9, 30)));
+        assert_eq!(next(at(12, 13, 50)).map(|meeting| meeting.summary), Some("Review".to_string()));
+        assert_eq!(next(at(13, 9, 10)), None);
+        assert_eq!(next(at(19, 9, 10)).map(|meeting| meeting.start), Some(at(19, 9, 30)));
+        assert_eq!(next(at(23, 9, 10)), None);
+        assert_eq!(next(at(5, 15, 50)).map(|meeting| meeting.summary), Some("Retro".to_string()));
+        assert_eq!(next(at(2, 9, 30)), None);
+
+        let daily = event("Sync", at(2, 8, 0), Some("FREQ=DAILY;INTERVAL=2;COUNT=3"));
+        assert_eq!(occurrences(&daily).collect::<Vec<_>>(), [at(2, 8, 0), at(4, 8, 0), at(6, 8, 0)]);
+        let meeting = Meeting { summary: "Review".to_string(), start: at(12, 14, 0) };
+        assert_eq!(meeting.room(at(12, 13, 47) + TimeDelta::seconds(30)), 720);
+    }
+}
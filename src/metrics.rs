@@ -0,0 +1,166 @@
+//! `--metrics-port <port>` (`metrics_port` in the config file): the timer's
+//! state and running totals in the Prometheus text format, at
+//! `http://127.0.0.1:<port>/metrics`, for graphing focus habits next to
+//! other personal metrics. Only the loopback interface is listened on.
+//!
+//! The counters start from everything in the history, archived months
+//! included, so they carry on across restarts. A work session's focus is
+//! counted once it's recorded, so the counter never goes down when one is
+//! stopped short; until then its seconds are in
+//! `timeadair_focus_in_progress_seconds`.
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::scheduler::{Phase, State};
+use crate::stats::{RunSummary, Tally};
+
+// Long enough for a scraper on the same machine, short enough that one
+// that stalls doesn't hold up the next
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Snapshot {
+    // None between sessions
+    phase: Option<Phase>,
+    remaining: u64,
+    length: u64,
+    paused: bool,
+    // Of the running work session, not yet in the totals
+    in_progress: u64,
+    totals: Tally,
+}
+
+pub struct Metrics {
+    snapshot: Arc<Mutex<Snapshot>>,
+    // From the history, before this run
+    before: Tally,
+}
+
+impl Metrics {
+    pub fn serve(port: u16, before: Tally) -> io::Result<Metrics> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let snapshot = Arc::new(Mutex::new(Snapshot { totals: before, ..Snapshot::default() }));
+        let shared = snapshot.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let snapshot = *shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                // A scraper that goes away half way is its own problem
+                let _ = respond(stream, &snapshot);
+            }
+        });
+        Ok(Metrics { snapshot, before })
+    }
+
+    // `state` is None once a session has ended, until the next one starts
+    pub fn update(&self, state: Option<&State>, run: &RunSummary) {
+        let running = state.filter(|state| matches!(state.phase, Phase::Work | Phase::Break));
+        let mut totals = self.before;
+        totals.add(run.work);
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *snapshot = Snapshot {
+            phase: running.map(|state| state.phase),
+            remaining: running.map_or(0, |state| state.timer.remaining()),
+            length: running.map_or(0, |state| state.timer.duration),
+            paused: running.is_some_and(|state| state.paused),
+            in_progress: running.filter(|state| state.phase == Phase::Work).map_or(0, |state| state.timer.elapsed),
+            totals,
+        };
+    }
+}
+
+fn respond(stream: TcpStream, snapshot: &Snapshot) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers don't change the answer, but are read so the client
+    // isn't cut off mid-request
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = match path {
+        "/metrics" | "/" => ("200 OK", render(snapshot)),
+        _ => ("404 Not Found", "Try /metrics\n".to_string()),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn render(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, values: &[(&str, u64)]| {
+        let _ = writeln!(out, "# HELP timeadair_{} {}\n# TYPE timeadair_{} {}", name, help, name, kind);
+        for (labels, value) in values {
+            let _ = writeln!(out, "timeadair_{}{} {}", name, labels, value);
+        }
+    };
+    let totals = snapshot.totals;
+    metric("pomodoros_total", "counter", "Work sessions run to completion.", &[("", totals.pomodoros.into())]);
+    metric("focus_seconds_total", "counter", "Seconds spent in recorded work sessions.", &[("", totals.focus)]);
+    metric("focus_in_progress_seconds", "gauge", "Seconds so far in the running work session.", &[("", snapshot.in_progress)]);
+    metric("interruptions_total", "counter", "Interruptions logged during work sessions.", &[("", totals.interruptions.into())]);
+    let phase = |phase| u64::from(snapshot.phase == phase);
+    metric(
+        "session",
+        "gauge",
+        "1 for the kind of session running, 0 otherwise.",
+        &[("{phase=\"work\"}", phase(Some(Phase::Work))), ("{phase=\"break\"}", phase(Some(Phase::Break))), ("{phase=\"none\"}", phase(None))],
+    );
+    metric("session_paused", "gauge", "1 while the running session is paused.", &[("", snapshot.paused.into())]);
+    metric("session_remaining_seconds", "gauge", "Seconds left in the running session.", &[("", snapshot.remaining)]);
+    metric("session_length_seconds", "gauge", "How long the running session is set to run.", &[("", snapshot.length)]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn serves_prometheus_text() {
+        let snapshot = Snapshot {
+            phase: Some(Phase::Work),
+            remaining: 900,
+            length: 1500,
+            paused: false,
+            in_progress: 600,
+            totals: Tally { pomodoros: 40, focus: 60_600, interruptions: 3 },
+        };
+        let text = render(&snapshot);
+        assert!(text.contains("# TYPE timeadair_pomodoros_total counter\ntimeadair_pomodoros_total 40\n"));
+        assert!(text.contains("timeadair_focus_seconds_total 60600\n"));
+        assert!(text.contains("# TYPE timeadair_focus_in_progress_seconds gauge\ntimeadair_focus_in_progress_seconds 600\n"));
+        assert!(text.contains("timeadair_session{phase=\"work\"} 1\ntimeadair_session{phase=\"break\"} 0\n"));
+        assert!(text.contains("timeadair_session_remaining_seconds 900\n"));
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+            stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        respond(listener.accept().unwrap().0, &snapshot).unwrap();
+        let response = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&text));
+    }
+}
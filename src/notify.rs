@@ -0,0 +1,191 @@
+//! What's told that a session ran to completion, and how. Each way of
+//! telling is a `Notifier`, and `notify` in the config file (or `--notify
+//! bell,desktop`) picks any number of them:
+//!
+//! - `bell`: the terminal bell, unless `--visual-bell` flashes the screen
+//!   instead; only for the interactive timer
+//! - `desktop`: a desktop notification, through `notify-send` or, on
+//!   macOS, `osascript`
+//! - `sound`: the chime, or the sound pack's `work_end` and `break_end`
+//! - `command`: the `--assistive-command` program, see `assistive`
+//!
+//! Without `notify` it's the bell, the sound and the command, whichever of
+//! them are set up. The same backends give the daemon's reminders to start
+//! a session, see `reminder`. A new way in, e.g. for Pushover or ntfy.sh,
+//! is a type implementing `Notifier`, a `Backend` to name it by and a line
+//! in `set_up_notifiers` in main.rs.
+//!
+//! One that fails is reported and dropped for the rest of the run, like
+//! the other outputs.
+
+use std::cell::RefCell;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::thread;
+
+use crate::locale::Text;
+use crate::scheduler::Phase;
+use crate::sound::{SoundEvent, Sounds};
+
+pub struct Completion<'a> {
+    pub phase: Phase,
+    // Seconds
+    pub elapsed: u64,
+    pub task: Option<&'a str>,
+}
+
+pub trait Notifier {
+    // For the integrations panel and warnings
+    fn name(&self) -> &'static str;
+    fn completed(&mut self, completion: &Completion) -> io::Result<()>;
+    // No session for `waited` seconds, see `reminder`
+    fn remind(&mut self, waited: u64, message: &str) -> io::Result<()>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    Bell,
+    Desktop,
+    Sound,
+    Command,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 4] = [Backend::Bell, Backend::Desktop, Backend::Sound, Backend::Command];
+    pub const DEFAULT: [Backend; 3] = [Backend::Bell, Backend::Sound, Backend::Command];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Bell => "bell",
+            Backend::Desktop => "desktop",
+            Backend::Sound => "sound",
+            Backend::Command => "command",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Backend, String> {
+        Backend::ALL.into_iter().find(|backend| backend.name() == name.trim().to_lowercase()).ok_or_else(|| {
+            let names: Vec<_> = Backend::ALL.iter().map(|backend| backend.name()).collect();
+            format!("notify expects some of {}, not '{}'", names.join(", "), name)
+        })
+    }
+}
+
+pub struct Bell;
+
+impl Notifier for Bell {
+    fn name(&self) -> &'static str {
+        "bell"
+    }
+
+    fn completed(&mut self, _completion: &Completion) -> io::Result<()> {
+        ring()
+    }
+
+    fn remind(&mut self, _waited: u64, _message: &str) -> io::Result<()> {
+        ring()
+    }
+}
+
+// Nothing to ring when the output is going to a file
+fn ring() -> io::Result<()> {
+    let mut out = io::stdout();
+    if !out.is_terminal() {
+        return Ok(());
+    }
+    out.write_all(b"\x07")?;
+    out.flush()
+}
+
+pub struct DesktopNotifier {
+    text: &'static Text,
+}
+
+impl DesktopNotifier {
+    pub fn new(text: &'static Text) -> DesktopNotifier {
+        DesktopNotifier { text }
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn completed(&mut self, completion: &Completion) -> io::Result<()> {
+        let (title, body) = notification(completion, self.text);
+        show(&title, &body)
+    }
+
+    fn remind(&mut self, _waited: u64, message: &str) -> io::Result<()> {
+        show("Tìmeadair", message)
+    }
+}
+
+// Not waited for, like the assistive command
+fn show(title: &str, body: &str) -> io::Result<()> {
+    let mut child = desktop_command(title, body).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+// Shared with the sounds played at other moments, so the volume keys
+// change this one too
+pub struct SoundNotifier(pub Rc<RefCell<Sounds>>);
+
+impl Notifier for SoundNotifier {
+    fn name(&self) -> &'static str {
+        "sound"
+    }
+
+    fn completed(&mut self, completion: &Completion) -> io::Result<()> {
+        self.0.borrow().play(if completion.phase == Phase::Work { SoundEvent::WorkEnd } else { SoundEvent::BreakEnd })
+    }
+
+    // The sound for getting back to work
+    fn remind(&mut self, _waited: u64, _message: &str) -> io::Result<()> {
+        self.0.borrow().play(SoundEvent::BreakEnd)
+    }
+}
+
+// `Tìmeadair: Work` over `Session complete. Write report`
+fn notification(completion: &Completion, text: &Text) -> (String, String) {
+    let title = format!("Tìmeadair: {}", text.session_name(completion.phase.name()));
+    let body = match completion.task {
+        Some(task) => format!("{} {}", text.session_complete, task),
+        None => text.session_complete.to_string(),
+    };
+    (title, body)
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let quoted = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(format!("display notification {} with title {}", quoted(body), quoted(title)));
+    command
+}
+
+#[cfg(not(target_os = "macos"))]
+fn desktop_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=timeadair", title, body]);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::ENGLISH;
+
+    #[test]
+    fn backends_and_notifications() {
+        assert_eq!(Backend::parse(" Desktop").unwrap(), Backend::Desktop);
+        assert_eq!(Backend::parse("pushover").unwrap_err(), "notify expects some of bell, desktop, sound, command, not 'pushover'");
+        let completion = Completion { phase: Phase::Work, elapsed: 1500, task: Some("Write report") };
+        assert_eq!(notification(&completion, &ENGLISH), ("Tìmeadair: Work".to_string(), "Session complete. Write report".to_string()));
+        let completion = Completion { phase: Phase::Break, elapsed: 300, task: None };
+        assert_eq!(notification(&completion, &ENGLISH).1, "Session complete.");
+    }
+}
@@ -0,0 +1,97 @@
+//! Nudges for working without a pomodoro (`--nudge <minutes>`, or `nudge`
+//! in the config file), for the daemon and status bar modes that sit in the
+//! background between sessions. The keyboard and mouse are watched with
+//! the same probes as `idle_pause`, and once they've been in use that long
+//! with no session running and no break of `nudge_idle` minutes (5 unless
+//! set) in between, the `on_nudge` hook runs and a `nudge` event is logged.
+//! It comes round again after each further stretch of the same length.
+//!
+//! ```toml
+//! nudge = 55
+//! on_nudge = 'notify-send Tìmeadair "$TIMEADAIR_MESSAGE"'
+//! ```
+//!
+//! The hook gets `TIMEADAIR_ACTIVE`, the seconds at the keyboard so far,
+//! and `TIMEADAIR_MESSAGE`, saying as much.
+
+use std::time::Instant;
+
+use crate::idle::{IdleMonitor, Poll};
+
+// Seconds away from the keyboard that count as a break
+pub const BREAK: u64 = 5 * 60;
+
+#[derive(Debug, PartialEq)]
+pub enum Nudge {
+    // Seconds at the keyboard
+    Due(u64),
+    // Told once, like the idle monitor
+    Unavailable,
+}
+
+pub struct Nudger {
+    monitor: IdleMonitor,
+    // Seconds
+    after: u64,
+    // When the keyboard was first seen in use since the last break
+    active_since: Option<Instant>,
+    nudges: u64,
+}
+
+impl Nudger {
+    pub fn new(after: u64, idle: u64) -> Nudger {
+        Nudger { monitor: IdleMonitor::new(idle), after, active_since: None, nudges: 0 }
+    }
+
+    // Asks the system every few seconds, like the idle monitor it's built on
+    pub fn poll(&mut self) -> Option<Nudge> {
+        match self.monitor.poll() {
+            Poll::Idle(seconds) => self.observe(seconds, Instant::now()).map(Nudge::Due),
+            Poll::Unavailable => Some(Nudge::Unavailable),
+            Poll::NotYet => None,
+        }
+    }
+
+    fn observe(&mut self, idle: u64, now: Instant) -> Option<u64> {
+        if idle >= self.monitor.threshold {
+            self.reset();
+            return None;
+        }
+        let since = *self.active_since.get_or_insert(now);
+        let active = now.saturating_duration_since(since).as_secs();
+        if active < self.after * (self.nudges + 1) {
+            return None;
+        }
+        self.nudges += 1;
+        Some(active)
+    }
+
+    // A session is structure enough, so the count starts again after one
+    pub fn reset(&mut self) {
+        self.active_since = None;
+        self.nudges = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn nudges_after_each_stretch_until_a_break() {
+        let mut nudger = Nudger::new(55 * 60, BREAK);
+        let start = Instant::now();
+        let at = |minutes: u64| start + Duration::from_secs(minutes * 60);
+        assert_eq!(nudger.observe(2, at(0)), None);
+        assert_eq!(nudger.observe(10, at(54)), None);
+        assert_eq!(nudger.observe(3, at(55)), Some(55 * 60));
+        assert_eq!(nudger.observe(3, at(60)), None);
+        assert_eq!(nudger.observe(3, at(110)), Some(110 * 60));
+        // Five minutes away and it starts over
+        assert_eq!(nudger.observe(BREAK, at(115)), None);
+        assert_eq!(nudger.observe(1, at(120)), None);
+        assert_eq!(nudger.observe(1, at(174)), None);
+        assert_eq!(nudger.observe(1, at(175)), Some(55 * 60));
+    }
+}
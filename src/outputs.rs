@@ -0,0 +1,624 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::io;
+use std::mem;
+use std::rc::Rc;
+use std::time::Instant;
+
+use chrono::{Local, Utc};
+
+use crate::archive::Rollups;
+use crate::checkpoint::{Checkpoint, CheckpointFile};
+use crate::diary::Diary;
+use crate::dnd::Dnd;
+use crate::events::{self, EventLog, SessionEvent};
+use crate::history::{History, Interruption, Interruptions, Outcome, SessionRecord};
+use crate::hooks::Hooks;
+use crate::media::Media;
+use crate::metrics::Metrics;
+use crate::notify::{Completion, Notifier};
+use crate::publish::Publisher;
+use crate::schedule::Schedule;
+use crate::scheduler::{Phase, State};
+use crate::serial::SerialOutput;
+use crate::sound::{SoundEvent, Sounds};
+use crate::stats::{self, RunSummary, Today};
+use crate::status::StatusFile;
+use crate::taskbar::{self, ProgressState};
+use crate::title::TitleOutput;
+use crate::timer::{self, TimerResult};
+use crate::timers::Timers;
+
+// An optional output that failed, reported to the user instead of
+// stopping the timer
+pub struct Warning {
+    pub subsystem: &'static str,
+    pub message: String,
+}
+
+// How an integration has been getting on, for the status panel
+#[derive(Clone, Debug, Default)]
+pub struct Delivery {
+    pub last: Option<Instant>,
+    pub error: Option<String>,
+}
+
+impl Delivery {
+    pub fn record<E: Display>(&mut self, result: &Result<(), E>) {
+        match result {
+            Ok(()) => self.last = Some(Instant::now()),
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+}
+
+pub struct Integration {
+    pub name: &'static str,
+    pub delivery: Delivery,
+    // Events waiting to be written, for outputs written in the background
+    pub queued: Option<usize>,
+}
+
+// Everything that mirrors the timer outside of the main terminal view
+#[derive(Default)]
+pub struct Outputs {
+    pub serial: Option<SerialOutput>,
+    pub events: Option<EventLog>,
+    pub publisher: Option<Publisher>,
+    pub status: Option<StatusFile>,
+    pub history: Option<History>,
+    pub checkpoint: Option<CheckpointFile>,
+    // Shared with the sound notifier, if there is one
+    pub sounds: Option<Rc<RefCell<Sounds>>>,
+    // A pip for each of the last seconds, in place of the pack's tick
+    pub countdown_pips: bool,
+    // Told when a session runs to completion, see `notify`
+    pub notifiers: Vec<Box<dyn Notifier>>,
+    pub dnd: Option<Dnd>,
+    pub hooks: Hooks,
+    pub media: Option<Media>,
+    pub taskbar: bool,
+    pub title: Option<TitleOutput>,
+    pub metrics: Option<Metrics>,
+    // Sessions abandoned within this many seconds were started by mistake
+    pub false_start: u64,
+    // Completed work sessions are asked for an energy rating, a note or
+    // both, which are added to their record once given
+    pub rate: bool,
+    pub notes: bool,
+    pub diary: Option<Diary>,
+    // Under --strict, the phrase that has to be typed to quit or reset a work
+    // session; those sessions are recorded as abandoned
+    pub strict: Option<&'static str>,
+    // The --profile this run is using, recorded with each session
+    pub profile: Option<String>,
+    // What the roulette picked for the coming break, recorded with it
+    pub activity: Option<String>,
+    // Countdowns of their own, kept from one session to the next
+    pub timers: Timers,
+    // Which days count towards the streak in the 'v' view
+    pub schedule: Schedule,
+    // When the running session started, for its history record
+    started: Option<i64>,
+    task: Option<String>,
+    interruptions: Interruptions,
+    // The last work session is recorded, but its rating and note, and so
+    // its diary entry, are still to come
+    details_due: bool,
+    // The work session last recorded, as it was written, for overtime;
+    // only one that ran to completion can be snoozed on past its end
+    last_work: Option<SessionRecord>,
+    // Today from the history, for the 'v' view, read when it's first
+    // wanted and again once another session has been recorded
+    today: Option<Today>,
+    // An interrupted session about to be picked up again
+    resuming: Option<Checkpoint>,
+    // Every session so far, for the summary on exit
+    run: RunSummary,
+    // Failures not yet handed to the frontend
+    warnings: Vec<Warning>,
+    // Kept after an output has been dropped, so the panel can say why
+    deliveries: Vec<(&'static str, Delivery)>,
+}
+
+impl Outputs {
+    pub fn session_started(&mut self, state: &State, task: Option<&str>) -> io::Result<()> {
+        let resumed = self.resuming.take();
+        self.last_work = None;
+        self.started = Some(resumed.as_ref().map_or_else(|| Utc::now().timestamp(), |checkpoint| checkpoint.started));
+        self.task = task.map(str::to_string);
+        self.interruptions = resumed.map(|checkpoint| checkpoint.interruptions).unwrap_or_default();
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::Started {
+                session: &state.phase.name().to_lowercase(),
+                duration: state.timer.duration,
+                task,
+            });
+        }
+        if let (Some(publisher), Some(started)) = (&self.publisher, self.started) {
+            publisher.started(state.phase, task, started, state.timer.duration);
+        }
+        if let Some(dnd) = self.dnd.as_ref().filter(|_| state.phase == Phase::Work) {
+            let result = dnd.start(state.timer.remaining());
+            self.delivery("dnd").record(&result);
+            if let Err(error) = result {
+                self.dnd = None;
+                self.warn("dnd", format!("unable to turn on Do Not Disturb: {}", error));
+            }
+        }
+        if let Some(diary) = self.diary.as_mut().filter(|_| state.phase == Phase::Work) {
+            diary.work_started();
+        }
+        let result = self.hooks.session_started(state.phase, state.timer.duration, task);
+        self.record_hook(result);
+        // Kept on after a failure, since that's often only the player not
+        // being open yet
+        if let Some(media) = &self.media {
+            let result = media.session_started(state);
+            self.delivery("media").record(&result);
+            if let Err(error) = result {
+                self.warn("media", format!("unable to control the music player: {}", error));
+            }
+        }
+        self.tick(state)
+    }
+
+    pub fn tick(&mut self, state: &State) -> io::Result<()> {
+        let timer = &state.timer;
+        let type_name = state.phase.name();
+        // Turn the taskbar button red for the final minute
+        let progress = if state.paused {
+            ProgressState::Paused
+        } else if timer.remaining() <= 60 {
+            ProgressState::Error
+        } else {
+            ProgressState::Normal
+        };
+        // Nothing to announce at the very start, or while paused
+        if timer.elapsed > 0 && !state.paused {
+            if self.countdown_pips && timer.countdown(0.0, timer::FINAL_SECONDS).is_some() {
+                self.play(SoundEvent::Countdown);
+            } else if timer.remaining() == 60 {
+                self.play(SoundEvent::LastMinute);
+            } else {
+                self.play(SoundEvent::Tick);
+            }
+        }
+        if self.taskbar && taskbar::supported() {
+            let result = taskbar::set_progress(progress, timer.get_progress() as u32);
+            self.delivery("taskbar").record(&result);
+            if let Err(error) = result {
+                self.taskbar = false;
+                self.warn("taskbar", format!("unable to show progress on the taskbar: {}", error));
+            }
+        }
+        if let Some(title) = &mut self.title {
+            let result = title.show(state.session_name(), timer.remaining(), state.paused);
+            self.delivery("title").record(&result);
+            if let Err(error) = result {
+                self.title = None;
+                self.warn("title", format!("unable to set the terminal title: {}", error));
+            }
+        }
+
+        // A display that has been unplugged shouldn't take the timer down with it
+        if let Some(output) = &mut self.serial {
+            let result = output.send(&type_name.to_uppercase(), timer.remaining());
+            self.delivery("serial").record(&result);
+            if let Err(error) = result {
+                self.serial = None;
+                self.warn("serial", format!("serial display stopped responding: {}", error));
+            }
+        }
+        self.write_status(state);
+        self.update_metrics(Some(state));
+        self.save_checkpoint(state, false);
+        Ok(())
+    }
+
+    // The next session to start carries on from `checkpoint`: its history
+    // record keeps the original start time and interruptions
+    pub fn resume(&mut self, checkpoint: Checkpoint) {
+        self.resuming = Some(checkpoint);
+    }
+
+    // An unreadable history shows as a day with nothing in it yet
+    pub fn today(&mut self) -> &Today {
+        let history = self.history.as_ref();
+        let schedule = &self.schedule;
+        self.today.get_or_insert_with(|| {
+            let Some(history) = history else { return Today::default() };
+            let records = history.load().unwrap_or_default();
+            let rollups = Rollups::load(&history.rollups_path()).unwrap_or_default();
+            stats::today(&records, &rollups, schedule, Local::now())
+        })
+    }
+
+    pub fn interruptions(&self) -> Interruptions {
+        self.interruptions
+    }
+
+    // Counted towards the running session's history record
+    pub fn interrupted(&mut self, state: &State, interruption: Interruption) -> Interruptions {
+        self.interruptions.add(interruption);
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::Interruption {
+                session: &state.phase.name().to_lowercase(),
+                kind: interruption.name(),
+                elapsed: state.timer.elapsed,
+            });
+        }
+        self.interruptions
+    }
+
+    pub fn pause_changed(&mut self, state: &State) {
+        if let Some(events) = &self.events {
+            events.emit(&events::pause_event(&state.phase.name().to_lowercase(), state));
+        }
+        self.save_checkpoint(state, true);
+        self.update_metrics(Some(state));
+    }
+
+    // Which pre-session checklist items were skipped rather than ticked off
+    pub fn checklist_done(&mut self, items: &[String], skipped: &[usize]) {
+        if items.is_empty() {
+            return;
+        }
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::ChecklistDone {
+                ticked: items.len() - skipped.len(),
+                skipped: skipped.iter().map(|&i| items[i].as_str()).collect(),
+            });
+        }
+    }
+
+    // Logs what the run got through as the timer exits, and hands it back
+    // to be printed
+    pub fn run_ended(&self) -> RunSummary {
+        let run = self.run;
+        if let Some(events) = self.events.as_ref().filter(|_| !run.is_empty()) {
+            events.emit(&SessionEvent::RunSummary {
+                pomodoros: run.work.pomodoros,
+                focus: run.work.focus,
+                breaks: run.breaks,
+                interruptions: run.work.interruptions,
+            });
+        }
+        run
+    }
+
+    pub fn nudge(&mut self, active: u64, message: &str) {
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::Nudge { active, message });
+        }
+        let hook = self.hooks.nudged(active, message);
+        self.record_hook(hook);
+    }
+
+    // No session for `waited` seconds, see `reminder`
+    pub fn remind(&mut self, waited: u64, message: &str) {
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::Reminder { waited, message });
+        }
+        self.notify(|notifier| notifier.remind(waited, message));
+    }
+
+    // The first time today's completed work sessions reach the daily goal
+    pub fn goal_reached(&mut self, pomodoros: u32, goal: u32) {
+        self.play(SoundEvent::Goal);
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::GoalReached { pomodoros, goal });
+        }
+    }
+
+    // Called while a prompt is waiting for the next session to be started
+    pub fn waiting(&mut self, state: &State) {
+        self.write_status(state);
+        self.update_metrics(Some(state));
+    }
+
+    pub fn session_ended(&mut self, phase: Phase, state: &State, result: &TimerResult) -> io::Result<()> {
+        let timer = &state.timer;
+        let type_name = phase.name();
+        if self.taskbar && taskbar::supported() {
+            let result = taskbar::set_progress(ProgressState::Clear, 0);
+            self.delivery("taskbar").record(&result);
+            if let Err(error) = result {
+                self.taskbar = false;
+                self.warn("taskbar", format!("unable to show progress on the taskbar: {}", error));
+            }
+        }
+        if let Some(title) = &mut self.title {
+            let result = title.idle();
+            self.delivery("title").record(&result);
+            if let Err(error) = result {
+                self.title = None;
+                self.warn("title", format!("unable to set the terminal title: {}", error));
+            }
+        }
+        if let Some(output) = &mut self.serial {
+            let result = output.send_idle();
+            self.delivery("serial").record(&result);
+            if let Err(error) = result {
+                self.serial = None;
+                self.warn("serial", format!("serial display stopped responding: {}", error));
+            }
+        }
+        if *result == TimerResult::Completed {
+            let task = self.task.clone();
+            let completion = Completion { phase, elapsed: timer.elapsed, task: task.as_deref() };
+            self.notify(|notifier| notifier.completed(&completion));
+        }
+        if let Some(dnd) = self.dnd.as_ref().filter(|_| phase == Phase::Work) {
+            let result = dnd.end();
+            self.delivery("dnd").record(&result);
+            if let Err(error) = result {
+                self.dnd = None;
+                self.warn("dnd", format!("unable to turn Do Not Disturb back off: {}", error));
+            }
+        }
+        let hook = self.hooks.session_ended(phase, timer.duration, self.task.as_deref(), timer.elapsed, result);
+        self.record_hook(hook);
+        if let Some(events) = &self.events {
+            events.emit(&events::end_event(&type_name.to_lowercase(), state, result));
+        }
+        let abandoned = self.strict.is_some() && phase == Phase::Work && matches!(result, TimerResult::Quit | TimerResult::Reset);
+        let outcome = if abandoned { Outcome::Abandoned } else { result.into() };
+        if let Some(publisher) = &self.publisher {
+            publisher.ended(phase, self.task.as_deref(), self.started, timer.elapsed, outcome);
+        }
+        // Left out of history altogether, so stats aren't skewed by a start
+        // that was undone straight away
+        let false_start = *result != TimerResult::Completed && timer.elapsed < self.false_start;
+        if !false_start {
+            self.run.add(phase, timer.elapsed, result, self.interruptions);
+        }
+        if let Some(started) = self.started.take().filter(|_| self.history.is_some() && !false_start) {
+            let record = SessionRecord {
+                session: type_name.to_lowercase(),
+                started,
+                duration: timer.elapsed,
+                paused: state.paused_time,
+                outcome,
+                task: self.task.take(),
+                planned: Some(timer.duration),
+                activity: self.activity.take(),
+                interruptions: mem::take(&mut self.interruptions),
+                profile: self.profile.clone(),
+                ..Default::default()
+            };
+            self.details_due = (self.rate || self.notes) && phase == Phase::Work && *result == TimerResult::Completed;
+            self.record(&record);
+        }
+        if let Some(status) = &mut self.status {
+            status.session_ended(outcome);
+        }
+        self.write_status(state);
+        self.update_metrics(None);
+        if let Some(checkpoint) = &self.checkpoint {
+            let result = checkpoint.clear();
+            self.delivery("checkpoint").record(&result);
+        }
+        Ok(())
+    }
+
+    pub fn awaiting_details(&self) -> bool {
+        self.details_due
+    }
+
+    // Adds the rating and note to the session that just ended, if they
+    // were given, and writes its diary entry
+    pub fn detailed(&mut self, rating: Option<u8>, note: Option<String>) {
+        if !mem::take(&mut self.details_due) {
+            return;
+        }
+        let Some(was) = self.last_work.clone() else { return };
+        let record = SessionRecord { rating, note, ..was.clone() };
+        self.write_diary(&record);
+        if record != was {
+            self.amend(&was, record);
+        }
+    }
+
+    // Whether the break prompt can offer more of the last work session
+    pub fn can_snooze(&self) -> bool {
+        self.last_work.is_some()
+    }
+
+    // Work snoozed on past the end of the last work session, added to its
+    // record rather than making one of its own
+    pub fn overtime(&mut self, seconds: u64) {
+        let Some(was) = self.last_work.take() else { return };
+        self.play(SoundEvent::WorkEnd);
+        let record = SessionRecord { duration: was.duration + seconds, overtime: was.overtime + seconds, ..was.clone() };
+        self.amend(&was, record);
+    }
+
+    fn record(&mut self, record: &SessionRecord) {
+        self.today = None;
+        self.last_work = (record.session == "work" && record.outcome == Outcome::Completed).then(|| record.clone());
+        if record.session == "work" && !self.details_due {
+            self.write_diary(record);
+        }
+        let Some(history) = &self.history else { return };
+        // Losing the history shouldn't interrupt the timer itself
+        let result = history.append(record);
+        self.delivery("history").record(&result);
+        if let Err(error) = result {
+            self.history = None;
+            self.warn("history", format!("unable to record session history: {}", error));
+        }
+    }
+
+    // Swaps the last work session's record for `record`, as written
+    fn amend(&mut self, was: &SessionRecord, record: SessionRecord) {
+        self.today = None;
+        if let Some(history) = &self.history {
+            let result = history.amend_last(was, &record);
+            self.delivery("history").record(&result);
+            if let Err(error) = result {
+                self.history = None;
+                self.warn("history", format!("unable to record session history: {}", error));
+            }
+        }
+        self.last_work = Some(record);
+    }
+
+    fn write_diary(&mut self, record: &SessionRecord) {
+        let Some(diary) = &mut self.diary else { return };
+        let result = diary.write(record);
+        self.delivery("diary").record(&result);
+        if let Err(error) = result {
+            self.diary = None;
+            self.warn("diary", format!("unable to write to the diary: {}", error));
+        }
+    }
+
+    // The side timers that have just run out, with a sound for them
+    pub fn timers_finished(&mut self) -> Vec<String> {
+        let finished = self.timers.finished();
+        if !finished.is_empty() {
+            self.play(SoundEvent::WorkEnd);
+        }
+        finished
+    }
+
+    // Plays the end-of-work sound at the new volume so it can be judged
+    pub fn change_volume(&mut self, step: i8) {
+        if let Some(sounds) = &self.sounds {
+            sounds.borrow_mut().change_volume(step);
+            self.play(SoundEvent::WorkEnd);
+        }
+    }
+
+    // A notifier that fails is dropped, and the rest carry on
+    fn notify(&mut self, tell: impl Fn(&mut dyn Notifier) -> io::Result<()>) {
+        let mut notifiers = mem::take(&mut self.notifiers);
+        notifiers.retain_mut(|notifier| {
+            let result = tell(notifier.as_mut());
+            self.delivery(notifier.name()).record(&result);
+            let Err(error) = result else { return true };
+            self.warn(notifier.name(), format!("unable to send a notification: {}", error));
+            false
+        });
+        self.notifiers = notifiers;
+    }
+
+    fn play(&mut self, event: SoundEvent) {
+        let Some(sounds) = self.sounds.as_ref().filter(|sounds| sounds.borrow().has(event)) else { return };
+        let result = sounds.borrow().play(event);
+        self.delivery("sound").record(&result);
+        if let Err(error) = result {
+            self.sounds = None;
+            self.warn("sound", format!("unable to play a sound: {}", error));
+        }
+    }
+
+    // Unlike the other outputs a broken hook stays configured, since the
+    // next one to run may be a different command
+    fn record_hook(&mut self, result: io::Result<()>) {
+        self.delivery("hooks").record(&result);
+        if let Err(error) = result {
+            self.warn("hooks", format!("unable to run a hook: {}", error));
+        }
+    }
+
+    fn write_status(&mut self, state: &State) {
+        // Prompt hooks treat a missing file as "not running", so stop
+        // writing rather than failing if the file can't be updated
+        if let Some(status) = &self.status {
+            let result = status.write(state);
+            self.delivery("status").record(&result);
+            if let Err(error) = result {
+                self.status = None;
+                self.warn("status", format!("unable to update the status file: {}", error));
+            }
+        }
+    }
+
+    fn update_metrics(&self, state: Option<&State>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.update(state, &self.run);
+        }
+    }
+
+    // Losing the checkpoint only matters if the timer dies, so carry on
+    // without it
+    fn save_checkpoint(&mut self, state: &State, now: bool) {
+        let (Some(file), Some(started)) = (&self.checkpoint, self.started) else { return };
+        let Some(checkpoint) = Checkpoint::of(state, started, self.task.as_deref(), self.interruptions) else { return };
+        let result = file.save(&checkpoint, now);
+        self.delivery("checkpoint").record(&result);
+        if let Err(error) = result {
+            self.checkpoint = None;
+            self.warn("checkpoint", format!("unable to save the running session: {}", error));
+        }
+    }
+
+    pub fn warn(&mut self, subsystem: &'static str, message: impl Display) {
+        let message = message.to_string();
+        self.delivery(subsystem).error = Some(message.clone());
+        if let Some(events) = &self.events {
+            events.emit(&SessionEvent::Warning { subsystem, message: &message });
+        }
+        self.warnings.push(Warning { subsystem, message });
+    }
+
+    // Every output that has been set up or has failed, in that order
+    pub fn integrations(&self) -> Vec<Integration> {
+        let configured = [
+            ("serial", self.serial.is_some()),
+            ("status", self.status.is_some()),
+            ("history", self.history.is_some()),
+            ("checkpoint", self.checkpoint.is_some()),
+            ("taskbar", self.taskbar && taskbar::supported()),
+            ("title", self.title.is_some()),
+            ("sound", self.sounds.is_some()),
+            ("dnd", self.dnd.is_some()),
+            ("media", self.media.is_some()),
+            ("diary", self.diary.is_some()),
+            ("hooks", !self.hooks.is_empty()),
+        ];
+        // The sound notifier goes by the same name as the sounds
+        let notifiers = self.notifiers.iter().map(|notifier| (notifier.name(), true));
+        let mut untried = Vec::new();
+        for (name, configured) in configured.into_iter().chain(notifiers) {
+            let known = |known: &(&str, Delivery)| known.0 == name;
+            if configured && !self.deliveries.iter().any(known) && !untried.iter().any(known) {
+                untried.push((name, Delivery::default()));
+            }
+        }
+        let mut integrations: Vec<Integration> = self
+            .deliveries
+            .iter()
+            .cloned()
+            .chain(untried)
+            .map(|(name, delivery)| Integration { name, delivery, queued: None })
+            .collect();
+        if let Some(events) = &self.events {
+            let (delivery, queued) = events.delivery();
+            integrations.push(Integration { name: "event log", delivery, queued: Some(queued) });
+        }
+        if let Some(publisher) = &self.publisher {
+            let (delivery, queued) = publisher.delivery();
+            integrations.push(Integration { name: "publish", delivery, queued: Some(queued) });
+        }
+        integrations
+    }
+
+    fn delivery(&mut self, name: &'static str) -> &mut Delivery {
+        let index = match self.deliveries.iter().position(|(known, _)| *known == name) {
+            Some(index) => index,
+            None => {
+                self.deliveries.push((name, Delivery::default()));
+                self.deliveries.len() - 1
+            }
+        };
+        &mut self.deliveries[index].1
+    }
+
+    // Warnings raised since the last call, for the frontend to show
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        mem::take(&mut self.warnings)
+    }
+}
@@ -0,0 +1,90 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static WORKSPACE: OnceLock<String> = OnceLock::new();
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// Keeps all data and runtime files for this run under the named workspace;
+// only the first call has any effect
+pub fn set_workspace(name: &str) {
+    let _ = WORKSPACE.set(name.to_string());
+}
+
+pub fn workspace() -> Option<&'static str> {
+    WORKSPACE.get().map(String::as_str)
+}
+
+// Uses `dir` in place of the usual data directory, e.g. to look at a backup
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR.set(dir);
+}
+
+pub fn set_read_only() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
+// Every write to the data and runtime directories checks this first, so
+// --read-only can't be bypassed by a code path that forgot about it
+pub fn ensure_writable() -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "running with --read-only"));
+    }
+    Ok(())
+}
+
+fn scoped(dir: PathBuf) -> PathBuf {
+    match workspace() {
+        Some(name) => dir.join("workspaces").join(name),
+        None => dir,
+    }
+}
+
+// Per-user data directory, following the XDG base directory layout with
+// sensible fallbacks where the variables aren't set
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR.get() {
+        return Some(scoped(dir.clone()));
+    }
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        return Some(scoped(PathBuf::from(dir).join("timeadair")));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = env::var_os("APPDATA") {
+            return Some(scoped(PathBuf::from(dir).join("timeadair")));
+        }
+    }
+    home_dir().map(|home| scoped(home.join(".local").join("share").join("timeadair")))
+}
+
+// Per-user config directory, XDG_CONFIG_HOME or ~/.config (APPDATA on
+// Windows); shared by all workspaces, which keep their overrides inside it
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir).join("timeadair"));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = env::var_os("APPDATA") {
+            return Some(PathBuf::from(dir).join("timeadair"));
+        }
+    }
+    home_dir().map(|home| home.join(".config").join("timeadair"))
+}
+
+// Short-lived runtime files go in XDG_RUNTIME_DIR when there is one
+pub fn runtime_dir() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|d| !d.is_empty())
+        .map(|dir| scoped(PathBuf::from(dir).join("timeadair")))
+        .or_else(data_dir)
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,224 @@
+//! Consent for the features that change things outside timeadair. Before
+//! one is first used, the terminal says what it will do and asks; the
+//! answer is kept in `permissions.toml` next to `config.toml`, e.g.
+//!
+//! ```toml
+//! [dnd]
+//! allowed = true
+//! decided = "2024-05-02"
+//! ```
+//!
+//! and `timeadair permissions` lists them, `permissions grant <name>` and
+//! `permissions revoke <name>` change them. It's a file of its own so the
+//! config file, comments and all, is never rewritten.
+//!
+//! Without a terminal to ask on, e.g. for the daemon, a feature that
+//! hasn't been decided on stays off and a warning says how to allow it.
+//! Do Not Disturb is the only such feature so far.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::paths;
+
+const HEADER: &str = "# Written by timeadair; see 'timeadair permissions' to change these\n\n";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    Dnd,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 1] = [Permission::Dnd];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Permission::Dnd => "dnd",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Permission, String> {
+        Permission::ALL.into_iter().find(|permission| permission.name() == name.trim().to_lowercase()).ok_or_else(|| {
+            let names: Vec<_> = Permission::ALL.iter().map(|permission| permission.name()).collect();
+            format!("permissions expects one of {}, not '{}'", names.join(", "), name)
+        })
+    }
+
+    // What it will do, to finish "timeadair would like to ..."
+    fn what(self) -> &'static str {
+        match self {
+            Permission::Dnd => {
+                "turn on Do Not Disturb during work sessions, by changing the desktop's notification settings and putting them back after"
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PermissionsCommand {
+    List,
+    Grant(Permission),
+    Revoke(Permission),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Grant {
+    pub allowed: bool,
+    pub decided: NaiveDate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Consent {
+    Allowed,
+    Denied,
+    // Not asked yet, and there was no terminal to ask on
+    Undecided,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Grants(BTreeMap<String, Grant>);
+
+impl Grants {
+    // A missing file is the same as an empty one
+    pub fn load(path: &Path) -> io::Result<Grants> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.message().to_string())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Grants::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        paths::ensure_writable()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, HEADER.to_string() + &toml::to_string(self).map_err(io::Error::other)?)
+    }
+
+    pub fn get(&self, permission: Permission) -> Option<&Grant> {
+        self.0.get(permission.name())
+    }
+
+    pub fn set(&mut self, permission: Permission, allowed: bool, today: NaiveDate) {
+        self.0.insert(permission.name().to_string(), Grant { allowed, decided: today });
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("permissions.toml"))
+}
+
+// Whether `permission` may be used, asking on the terminal first if it's
+// never been decided and `interactive`
+pub fn consent(permission: Permission, interactive: bool) -> io::Result<Consent> {
+    let path = default_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let mut grants = Grants::load(&path)?;
+    if let Some(grant) = grants.get(permission) {
+        return Ok(if grant.allowed { Consent::Allowed } else { Consent::Denied });
+    }
+    if !interactive {
+        return Ok(Consent::Undecided);
+    }
+    let allowed = ask(permission, &mut io::stdin().lock(), &mut io::stdout())?;
+    grants.set(permission, allowed, Local::now().date_naive());
+    // Under --read-only the answer holds for this run only
+    if paths::ensure_writable().is_ok() {
+        grants.save(&path)?;
+    }
+    Ok(if allowed { Consent::Allowed } else { Consent::Denied })
+}
+
+fn ask(permission: Permission, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<bool> {
+    writeln!(output, "timeadair would like to {}.", permission.what())?;
+    loop {
+        write!(output, "Allow it? You can change this later with 'timeadair permissions'. [y/n] ")?;
+        output.flush()?;
+        let mut answer = String::new();
+        // Closed input is as good as a no
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(output)?;
+            return Ok(false);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+pub fn run(command: &PermissionsCommand) -> io::Result<Grants> {
+    let path = default_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let mut grants = Grants::load(&path)?;
+    let changed = match command {
+        PermissionsCommand::List => None,
+        PermissionsCommand::Grant(permission) => Some((*permission, true)),
+        PermissionsCommand::Revoke(permission) => Some((*permission, false)),
+    };
+    if let Some((permission, allowed)) = changed {
+        grants.set(permission, allowed, Local::now().date_naive());
+        grants.save(&path)?;
+    }
+    Ok(grants)
+}
+
+// Every permission, decided or not
+pub fn text(grants: &Grants) -> String {
+    let mut text = String::new();
+    for permission in Permission::ALL {
+        let state = match grants.get(permission) {
+            Some(grant) if grant.allowed => format!("allowed on {}", grant.decided),
+            Some(grant) => format!("refused on {}", grant.decided),
+            None => "not asked yet".to_string(),
+        };
+        text += &format!("{:<8}{}\n        would {}\n", permission.name(), state, permission.what());
+    }
+    text
+}
+
+pub fn data(grants: &Grants) -> Value {
+    let permissions: Vec<_> = Permission::ALL
+        .iter()
+        .map(|permission| {
+            let grant = grants.get(*permission);
+            json!({
+                "name": permission.name(),
+                "allowed": grant.map(|grant| grant.allowed),
+                "decided": grant.map(|grant| grant.decided.to_string()),
+            })
+        })
+        .collect();
+    json!({ "permissions": permissions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asks_until_answered_and_keeps_the_answer() {
+        let mut output = Vec::new();
+        assert!(ask(Permission::Dnd, &mut "maybe\nY\n".as_bytes(), &mut output).unwrap());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("timeadair would like to turn on Do Not Disturb"));
+        assert_eq!(output.matches("[y/n]").count(), 2);
+        assert!(!ask(Permission::Dnd, &mut "".as_bytes(), &mut Vec::new()).unwrap());
+
+        let mut grants = Grants::default();
+        assert!(text(&grants).starts_with("dnd     not asked yet\n"));
+        grants.set(Permission::Dnd, false, NaiveDate::from_ymd_opt(2024, 5, 2).unwrap());
+        let saved = toml::to_string(&grants).unwrap();
+        assert_eq!(saved, "[dnd]\nallowed = false\ndecided = \"2024-05-02\"\n");
+        assert_eq!(toml::from_str::<Grants>(&saved).unwrap(), grants);
+        assert!(text(&grants).starts_with("dnd     refused on 2024-05-02\n"));
+        assert!(Permission::parse("hosts").is_err());
+    }
+}
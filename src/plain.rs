@@ -0,0 +1,417 @@
+//! Plain line-based output for `--ui plain`.
+//!
+//! Every update is printed as a new line with no cursor movement, clearing
+//! or colors, so the output reads sensibly in logs, `watch` and dumb
+//! terminals.
+//!
+//! `--accessible` (`accessible = true` in the config file) is this with
+//! fewer, wordier lines, for screen readers: rather than a line a second,
+//! the time left is said when a session starts, pauses or changes length,
+//! every `--announce-every` minutes (5 unless set) and with a minute to go,
+//! e.g. `Current session: Work - 20 minutes remaining`.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::Instant;
+
+use crate::history::Interruptions;
+use crate::locale::{self, Locale, Text};
+use crate::outputs::Integration;
+use crate::scheduler::{Phase, Planned};
+use crate::stats::{self, Today};
+use crate::stopwatch;
+use crate::suggestions::Suggestions;
+use crate::timer::{Timer, TimerResult};
+use crate::ui::{self, Pane, Renderer};
+
+pub struct PlainRenderer<W: Write> {
+    out: W,
+    // Raw mode turns off newline translation, so a terminal needs an
+    // explicit carriage return while a log file is better off without one
+    line_end: &'static str,
+    // A pane is refreshed every second, but printed once per opening
+    panel_shown: Option<Pane>,
+    // There is nothing to flash in a log, so the bell is just left out
+    visual_bell: bool,
+    // Nor anything to ring, when the output isn't a terminal
+    bell: bool,
+    interruptions: Interruptions,
+    upcoming: Option<String>,
+    // The side timers, with the time each has left
+    timers: Vec<String>,
+    // Each suggestion gets a line of its own when it comes round
+    suggestions: Suggestions,
+    suggested: Option<usize>,
+    // Under --accessible, how many seconds apart the time left is said
+    announce_every: Option<u64>,
+    // What was last said and the second it was said at, so a line isn't
+    // said again until something's changed
+    announced: Option<((String, bool, u64), u64)>,
+    // The seconds the last countdown line was for
+    counted: Option<u64>,
+    text: &'static Text,
+}
+
+impl PlainRenderer<io::Stdout> {
+    pub fn stdout() -> PlainRenderer<io::Stdout> {
+        let terminal = io::stdout().is_terminal();
+        let mut plain = PlainRenderer::new(io::stdout(), if terminal { "\r\n" } else { "\n" });
+        plain.bell = terminal;
+        plain
+    }
+}
+
+impl<W: Write> PlainRenderer<W> {
+    pub fn new(out: W, line_end: &'static str) -> PlainRenderer<W> {
+        PlainRenderer {
+            out,
+            line_end,
+            panel_shown: None,
+            visual_bell: false,
+            bell: true,
+            interruptions: Interruptions::default(),
+            upcoming: None,
+            timers: Vec::new(),
+            suggestions: Suggestions::default(),
+            suggested: None,
+            announce_every: None,
+            announced: None,
+            counted: None,
+            text: Locale::default().text(),
+        }
+    }
+
+    pub fn with_visual_bell(mut self, visual_bell: bool) -> PlainRenderer<W> {
+        self.visual_bell = visual_bell;
+        self
+    }
+
+    pub fn with_announcements(mut self, every: Option<u64>) -> PlainRenderer<W> {
+        self.announce_every = every;
+        self
+    }
+
+    // Whether an announcement is due at second `at`; always for a new
+    // `what`, and otherwise every `announce_every` seconds and with a
+    // minute left
+    fn announcing(&mut self, what: (String, bool, u64), at: u64, from_end: bool) -> bool {
+        let Some(every) = self.announce_every else { return true };
+        let due = match &self.announced {
+            Some((last, said)) if *last == what => *said != at && at > 0 && (at.is_multiple_of(every) || (from_end && at == 60)),
+            _ => true,
+        };
+        if due {
+            self.announced = Some((what, at));
+        }
+        due
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> PlainRenderer<W> {
+        self.text = locale.text();
+        self
+    }
+
+    fn line(&mut self, text: &str) -> io::Result<()> {
+        write!(self.out, "{}{}", text, self.line_end)?;
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Renderer for PlainRenderer<W> {
+    fn prompt(&mut self, session_type: &str, notice: Option<&str>) -> io::Result<()> {
+        if let Some(notice) = notice {
+            self.line(notice)?;
+        }
+        write!(self.out, "{}", ui::prompt_text(self.text, session_type))?;
+        self.out.flush()
+    }
+
+    // Raw mode doesn't echo the key that answered
+    fn answer(&mut self, text: &str) -> io::Result<()> {
+        write!(self.out, "{}{}", text, self.line_end)?;
+        self.out.flush()
+    }
+
+    fn task_prompt(&mut self, choices: &[String]) -> io::Result<()> {
+        if choices.is_empty() {
+            write!(self.out, "{}", self.text.task_prompt)?;
+            return self.out.flush();
+        }
+        for choice in choices {
+            self.line(choice)?;
+        }
+        write!(self.out, "{}", self.text.task_picker_prompt)?;
+        self.out.flush()
+    }
+
+    // The board needs the full screen, so this is only a fallback
+    fn board(&mut self, columns: &[Vec<String>; 3], _selected: (usize, usize), _search: Option<&str>) -> io::Result<()> {
+        let columns: Vec<_> = self.text.board_columns.iter().zip(columns).map(|(name, cards)| format!("{}: {}", name, cards.join(", "))).collect();
+        self.line(&columns.join(" | "))
+    }
+
+    fn done_prompt(&mut self, task: &str, pomodoros: u32) -> io::Result<()> {
+        write!(self.out, "{}", ui::done_prompt_text(self.text, task, pomodoros))?;
+        self.out.flush()
+    }
+
+    fn shorten_prompt(&mut self, minutes: u64, meeting: &str) -> io::Result<()> {
+        write!(self.out, "{}", locale::fill(self.text.shorten_prompt, &[&minutes, &meeting]))?;
+        self.out.flush()
+    }
+
+    fn abandon_prompt(&mut self) -> io::Result<()> {
+        write!(self.out, "{}", locale::fill(self.text.abandon_prompt, &[&self.text.abandon_phrase]))?;
+        self.out.flush()
+    }
+
+    fn resume_prompt(&mut self, summary: &str) -> io::Result<()> {
+        self.line(summary)?;
+        write!(self.out, "{}", self.text.resume_prompt)?;
+        self.out.flush()
+    }
+
+    fn note_prompt(&mut self) -> io::Result<()> {
+        write!(self.out, "{}", self.text.note_prompt)?;
+        self.out.flush()
+    }
+
+    fn timer_prompt(&mut self, error: Option<&str>) -> io::Result<()> {
+        if let Some(error) = error {
+            self.line(error)?;
+        }
+        write!(self.out, "{}", self.text.timer_prompt)?;
+        self.out.flush()
+    }
+
+    fn timer_done(&mut self, name: &str) -> io::Result<()> {
+        let done = locale::fill(self.text.timer_done, &[&name]);
+        if self.visual_bell || !self.bell {
+            self.line(&done)
+        } else {
+            self.line(&format!("{}\x07", done))
+        }
+    }
+
+    fn rating_prompt(&mut self) -> io::Result<()> {
+        write!(self.out, "{}", self.text.rating_prompt)?;
+        self.out.flush()
+    }
+
+    fn progress(&mut self, timer: &Timer, message: &str, paused: bool) -> io::Result<()> {
+        self.panel_shown = None;
+        if let Some((index, suggestion)) = self.suggestions.at(timer.elapsed).filter(|(index, _)| self.suggested != Some(*index)) {
+            let suggestion = locale::fill(self.text.suggestion, &[&suggestion]);
+            self.suggested = Some(index);
+            self.line(&suggestion)?;
+        }
+        let mut line = if self.announce_every.is_some() {
+            if !self.announcing((message.to_string(), paused, timer.duration), timer.remaining(), true) {
+                return Ok(());
+            }
+            locale::fill(self.text.remaining_spoken, &[&message, &spoken(self.text, timer.remaining())])
+        } else {
+            locale::fill(self.text.remaining_plain, &[&message, &timer.format_time(), &(timer.get_progress() as u32)])
+        };
+        if !self.interruptions.is_empty() {
+            line.push_str(&format!(", interrupted {}x", self.interruptions.total()));
+        }
+        if paused {
+            line.push_str(&format!(" [{}]", self.text.paused_label.to_lowercase()));
+        }
+        if !self.timers.is_empty() {
+            line.push_str(&format!("; {}", self.timers.join(", ")));
+        }
+        self.line(&line)
+    }
+
+    fn stopwatch(&mut self, elapsed: u64, laps: &[u64], paused: bool) -> io::Result<()> {
+        if !self.announcing((String::new(), paused, laps.len() as u64), elapsed, false) {
+            return Ok(());
+        }
+        let mut line = format!("Stopwatch {}", stopwatch::format_elapsed(elapsed));
+        if let Some(lap) = laps.last() {
+            line.push_str(&format!(" - lap {} {}", laps.len(), stopwatch::format_elapsed(*lap)));
+        }
+        if paused {
+            line.push_str(" [paused]");
+        }
+        self.line(&line)
+    }
+
+    fn session_end(&mut self, result: &TimerResult) -> io::Result<()> {
+        self.announced = None;
+        match result {
+            TimerResult::Completed => self.line(self.text.session_complete)?,
+            TimerResult::Skipped => self.line(self.text.session_skipped)?,
+            TimerResult::Reset => self.line(self.text.timer_reset)?,
+            TimerResult::Quit | TimerResult::Interrupted => return self.line(self.text.farewell),
+        }
+        // Said once per session rather than on every line
+        match self.upcoming.take() {
+            Some(upcoming) => self.line(&upcoming),
+            None => Ok(()),
+        }
+    }
+
+    // Said once per countdown under --accessible
+    fn countdown(&mut self, session_type: &str, length: u64, seconds: u64) -> io::Result<()> {
+        let repeated = self.announce_every.is_some() && self.counted.is_some_and(|counted| seconds < counted);
+        self.counted = Some(seconds);
+        if repeated {
+            return Ok(());
+        }
+        self.line(&ui::countdown_text(self.text, session_type, length, seconds))
+    }
+
+    fn checklist_item(&mut self, item: &str, number: usize, total: usize) -> io::Result<()> {
+        let text = self.text;
+        self.line(&locale::fill(text.checklist_line, &[&number, &total, &item, &text.checklist_controls]))
+    }
+
+    // A line for each name going past would only scroll, so just the last
+    fn roulette(&mut self, activity: &str, landed: bool) -> io::Result<()> {
+        if !landed {
+            return Ok(());
+        }
+        self.line(&locale::fill(self.text.roulette_landed, &[&activity]))
+    }
+
+    fn message(&mut self, text: &str) -> io::Result<()> {
+        self.line(text)
+    }
+
+    fn integrations(&mut self, integrations: &[Integration]) -> io::Result<()> {
+        if self.panel_shown.replace(Pane::Integrations) == Some(Pane::Integrations) {
+            return Ok(());
+        }
+        if integrations.is_empty() {
+            return self.line("Integrations: none are set up");
+        }
+        for [name, last, queued, error] in ui::integration_rows(integrations, Instant::now()) {
+            self.line(&format!("Integration {}: last delivery {}, queue {}, last error {}", name, last, queued, error))?;
+        }
+        Ok(())
+    }
+
+    fn dashboard(&mut self, today: &Today) -> io::Result<()> {
+        if self.panel_shown.replace(Pane::Dashboard) == Some(Pane::Dashboard) {
+            return Ok(());
+        }
+        for line in stats::dashboard(today, self.text, true).iter().filter(|line| !line.is_empty()) {
+            self.line(line)?;
+        }
+        Ok(())
+    }
+
+    fn error(&mut self, message: &str, hint: Option<&str>) -> io::Result<()> {
+        self.line(&format!("Error: {}", message))?;
+        if let Some(hint) = hint {
+            self.line(hint)?;
+        }
+        Ok(())
+    }
+
+    // There's no header to show the cycle in
+    fn set_cycle(&mut self, _cycle: Option<(u32, u32)>) {}
+
+    // Nor the daily goal; reaching it is still announced at the next prompt
+    fn set_goal(&mut self, _goal: Option<(u32, u32)>) {}
+
+    fn set_interruptions(&mut self, interruptions: Interruptions) {
+        self.interruptions = interruptions;
+    }
+
+    fn set_upcoming(&mut self, upcoming: &[Planned]) {
+        self.upcoming = ui::upcoming_text(self.text, upcoming);
+    }
+
+    fn set_timers(&mut self, timers: &[(String, Timer)]) {
+        self.timers = timers.iter().map(|(name, timer)| format!("{} {}", name, timer.format_time())).collect();
+    }
+
+    fn set_suggestions(&mut self, suggestions: &[String]) {
+        self.suggestions.set(suggestions);
+        self.suggested = None;
+    }
+
+    // There's no header to put art in
+    fn set_phase(&mut self, _phase: Phase) {}
+
+    // The time is already in every line
+    fn toggle_big_digits(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // A line a second is plenty for a log
+    fn between_ticks(&mut self, _fraction: f32) -> io::Result<()> {
+        Ok(())
+    }
+
+    // Every update is a new line, so there's nothing to redraw
+    fn redraw(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn show_cursor(&mut self, _visible: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn warning(&mut self, _subsystem: &'static str, message: &str) -> io::Result<()> {
+        self.line(&format!("Warning: {}", message))
+    }
+}
+
+// `20 minutes`, rounding up, or the seconds in the last minute
+fn spoken(text: &Text, seconds: u64) -> String {
+    match seconds.div_ceil(60) {
+        _ if seconds == 1 => text.one_second.to_string(),
+        _ if seconds < 60 => locale::fill(text.seconds, &[&seconds]),
+        1 => text.one_minute.to_string(),
+        minutes => locale::fill(text.minutes, &[&minutes]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_line_per_update() {
+        let mut output = Vec::new();
+        let mut plain = PlainRenderer::new(&mut output, "\n");
+        let mut timer = Timer::new(300);
+        plain.progress(&timer, "Current session: Break", false).unwrap();
+        timer.elapsed = 150;
+        plain.progress(&timer, "Current session: Break", true).unwrap();
+        plain.session_end(&TimerResult::Reset).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Current session: Break - 05:00 remaining (0%)\n\
+             Current session: Break - 02:30 remaining (50%) [paused]\n\
+             Timer reset.\n"
+        );
+    }
+
+    #[test]
+    fn accessible_lines_only_now_and_then() {
+        let mut output = Vec::new();
+        let mut plain = PlainRenderer::new(&mut output, "\n").with_announcements(Some(300));
+        let mut timer = Timer::new(1500);
+        for elapsed in [0, 1, 2, 299, 300, 300, 301, 1440, 1441] {
+            timer.elapsed = elapsed;
+            plain.progress(&timer, "Current session: Work", false).unwrap();
+        }
+        plain.progress(&timer, "Current session: Work", true).unwrap();
+        for seconds in [5, 4, 3] {
+            plain.countdown("break", 300, seconds).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Current session: Work - 25 minutes remaining\n\
+             Current session: Work - 20 minutes remaining\n\
+             Current session: Work - 1 minute remaining\n\
+             Current session: Work - 59 seconds remaining [paused]\n\
+             Next: Break (05:00) - starting in 5s, press any key to start now or 'q' to quit\n"
+        );
+    }
+}
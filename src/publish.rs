@@ -0,0 +1,233 @@
+//! Session starts and ends sent on to other systems as they happen, e.g. to
+//! turn the office lights red for the length of a work session:
+//!
+//! - `--webhook <url>` (`webhook` in the config file) POSTs each one as JSON,
+//!   with `curl`
+//! - `--mqtt mqtt://<host>[:<port>]/<topic>` (`mqtt`) publishes it to the
+//!   topic, with `mosquitto_pub` from the Mosquitto clients
+//!
+//! Payloads read e.g.
+//! `{"elapsed":1500,"ended":1714638300,"event":"session_ended","label":"Write report","outcome":"completed","session":"work","started":1714636800}`,
+//! with `planned` instead of `ended`, `elapsed` and `outcome` on
+//! `session_started`; `label` is the task, when there is one. Sending
+//! happens on a background thread, in order, so a slow broker or server
+//! never holds up the timer. Under `--dry-run` they're logged instead,
+//! see `dryrun`.
+
+use std::io;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::dryrun::{DryRun, Integration};
+use crate::history::Outcome;
+use crate::outputs::Delivery;
+use crate::scheduler::Phase;
+
+const MQTT_PORT: u16 = 1883;
+// For each request, and for what's still queued on exit
+const TIMEOUT: Duration = Duration::from_secs(10);
+const DRAIN: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mqtt {
+    pub host: String,
+    pub port: u16,
+    pub topic: String,
+}
+
+impl Mqtt {
+    // `mqtt://broker.local:1883/office/focus`
+    pub fn parse(url: &str) -> Result<Mqtt, String> {
+        let error = || format!("mqtt expects a URL like mqtt://broker.local:1883/office/focus, not '{}'", url);
+        let rest = url.trim().strip_prefix("mqtt://").ok_or_else(error)?;
+        let (address, topic) = rest.split_once('/').filter(|(_, topic)| !topic.is_empty()).ok_or_else(error)?;
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| error())?),
+            None => (address, MQTT_PORT),
+        };
+        if host.is_empty() {
+            return Err(error());
+        }
+        Ok(Mqtt { host: host.to_string(), port, topic: topic.to_string() })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Targets {
+    pub webhook: Option<String>,
+    pub mqtt: Option<Mqtt>,
+    pub dry_run: Option<DryRun>,
+}
+
+impl Targets {
+    pub fn is_empty(&self) -> bool {
+        self.webhook.is_none() && self.mqtt.is_none()
+    }
+
+    // Each target on its own, so one that's down doesn't keep the event
+    // from the other
+    fn send(&self, payload: &str) -> io::Result<()> {
+        let results = [self.webhook.as_ref().map(|url| self.post(url, payload)), self.mqtt.as_ref().map(|mqtt| self.publish(mqtt, payload))];
+        let errors: Vec<_> = results.into_iter().flatten().filter_map(Result::err).map(|error| error.to_string()).collect();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(io::Error::other(errors.join("; ")))
+    }
+
+    fn post(&self, url: &str, payload: &str) -> io::Result<()> {
+        if let Some(dry_run) = self.dry_run(Integration::Webhook) {
+            return dry_run.log(Integration::Webhook, &format!("POST {} {}", url, payload));
+        }
+        let timeout = TIMEOUT.as_secs().to_string();
+        run("curl", &["-fsS", "-m", &timeout, "-X", "POST", "-H", "Content-Type: application/json", "--data-binary", payload, url])
+    }
+
+    fn publish(&self, mqtt: &Mqtt, payload: &str) -> io::Result<()> {
+        if let Some(dry_run) = self.dry_run(Integration::Mqtt) {
+            return dry_run.log(Integration::Mqtt, &format!("{}:{} {} {}", mqtt.host, mqtt.port, mqtt.topic, payload));
+        }
+        let port = mqtt.port.to_string();
+        run("mosquitto_pub", &["-h", &mqtt.host, "-p", &port, "-t", &mqtt.topic, "-m", payload])
+    }
+
+    fn dry_run(&self, integration: Integration) -> Option<&DryRun> {
+        self.dry_run.as_ref().filter(|dry_run| dry_run.covers(integration))
+    }
+}
+
+pub struct Publisher {
+    sender: Option<Sender<String>>,
+    done: Receiver<()>,
+    // Shared with the sending thread, for the integrations panel
+    delivery: Arc<Mutex<Delivery>>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl Publisher {
+    // None when there's nowhere to publish to
+    pub fn start(targets: Targets) -> Option<Publisher> {
+        if targets.is_empty() {
+            return None;
+        }
+        let (sender, receiver) = mpsc::channel::<String>();
+        let (done_sender, done) = mpsc::channel();
+        let delivery = Arc::new(Mutex::new(Delivery::default()));
+        let queued = Arc::new(AtomicUsize::new(0));
+        let (thread_delivery, thread_queued) = (delivery.clone(), queued.clone());
+        thread::spawn(move || {
+            for payload in receiver {
+                let result = targets.send(&payload);
+                thread_queued.fetch_sub(1, Ordering::Relaxed);
+                if let Ok(mut delivery) = thread_delivery.lock() {
+                    delivery.record(&result);
+                }
+            }
+            let _ = done_sender.send(());
+        });
+        Some(Publisher { sender: Some(sender), done, delivery, queued })
+    }
+
+    pub fn started(&self, phase: Phase, label: Option<&str>, started: i64, planned: u64) {
+        self.send(started_payload(phase, label, started, planned));
+    }
+
+    pub fn ended(&self, phase: Phase, label: Option<&str>, started: Option<i64>, elapsed: u64, outcome: Outcome) {
+        self.send(ended_payload(phase, label, started, Utc::now().timestamp(), elapsed, outcome));
+    }
+
+    fn send(&self, payload: Value) {
+        if let Some(sender) = &self.sender {
+            if sender.send(payload.to_string()).is_ok() {
+                self.queued.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // How sending has gone so far, and how many events are still waiting
+    pub fn delivery(&self) -> (Delivery, usize) {
+        let delivery = self.delivery.lock().map(|delivery| delivery.clone()).unwrap_or_default();
+        (delivery, self.queued.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        // The session that just ended is worth a short wait, a dead broker isn't
+        self.sender.take();
+        let _ = self.done.recv_timeout(DRAIN);
+    }
+}
+
+fn started_payload(phase: Phase, label: Option<&str>, started: i64, planned: u64) -> Value {
+    json!({
+        "event": "session_started",
+        "session": phase.name().to_lowercase(),
+        "label": label,
+        "started": started,
+        "planned": planned,
+    })
+}
+
+fn ended_payload(phase: Phase, label: Option<&str>, started: Option<i64>, ended: i64, elapsed: u64, outcome: Outcome) -> Value {
+    json!({
+        "event": "session_ended",
+        "session": phase.name().to_lowercase(),
+        "label": label,
+        "started": started,
+        "ended": ended,
+        "elapsed": elapsed,
+        "outcome": outcome.name(),
+    })
+}
+
+fn run(program: &str, args: &[&str]) -> io::Result<()> {
+    let status = Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed", program)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brokers_and_payloads() {
+        let mqtt = Mqtt::parse("mqtt://broker.local/office/focus").unwrap();
+        assert_eq!(mqtt, Mqtt { host: "broker.local".to_string(), port: 1883, topic: "office/focus".to_string() });
+        assert_eq!(Mqtt::parse("mqtt://10.0.0.2:1884/lights").unwrap().port, 1884);
+        assert!(Mqtt::parse("mqtt://broker.local").is_err());
+        assert!(Mqtt::parse("http://broker.local/lights").is_err());
+
+        assert_eq!(
+            ended_payload(Phase::Work, Some("Write report"), Some(1714636800), 1714638300, 1500, Outcome::Completed).to_string(),
+            r#"{"elapsed":1500,"ended":1714638300,"event":"session_ended","label":"Write report","outcome":"completed","session":"work","started":1714636800}"#
+        );
+        assert_eq!(started_payload(Phase::Break, None, 1714638300, 300)["label"], Value::Null);
+    }
+
+    #[test]
+    fn a_failing_webhook_still_publishes_to_mqtt() {
+        let log = std::env::temp_dir().join(format!("timeadair-publish-{}.log", std::process::id()));
+        let targets = Targets {
+            // Nothing listens on port 1, so the POST fails straight away
+            webhook: Some("http://127.0.0.1:1/hook".to_string()),
+            mqtt: Some(Mqtt::parse("mqtt://broker.local/office/focus").unwrap()),
+            dry_run: Some(DryRun::at(log.clone(), &[Integration::Mqtt])),
+        };
+        let error = targets.send("{}").unwrap_err();
+        let logged = std::fs::read_to_string(&log).unwrap();
+        std::fs::remove_file(&log).unwrap();
+        assert!(error.to_string().starts_with("curl"), "{}", error);
+        assert!(logged.ends_with("mqtt: broker.local:1883 office/focus {}\n"), "{}", logged);
+    }
+}
@@ -0,0 +1,86 @@
+//! Reminders to start a pomodoro (`--remind <minutes>`, or `remind` in the
+//! config file), for the daemon and status bar modes. Once that long has
+//! gone by with no session, within `working_hours` if they're set, every
+//! `notify` backend is told, e.g. with a desktop notification, and a
+//! `reminder` event is logged. It comes round again after each further
+//! stretch of the same length.
+//!
+//! ```toml
+//! working_hours = "09:00-17:30"
+//! remind = 30
+//! notify = ["desktop"]
+//! ```
+//!
+//! Unlike `nudge`, it goes by the clock alone, so it reminds whether or
+//! not anyone's at the keyboard.
+
+use std::time::Instant;
+
+use crate::hours::WorkingHours;
+
+pub struct Reminder {
+    // Seconds
+    after: u64,
+    hours: Option<WorkingHours>,
+    // When the wait began: the last session's end, the start of the
+    // working hours or the start of the run
+    waiting_since: Option<Instant>,
+    reminders: u64,
+}
+
+impl Reminder {
+    pub fn new(after: u64, hours: Option<WorkingHours>) -> Reminder {
+        Reminder { after, hours, waiting_since: None, reminders: 0 }
+    }
+
+    // Seconds waited, when a reminder is due
+    pub fn poll(&mut self) -> Option<u64> {
+        let working = self.hours.is_none_or(|hours| hours.contains_now());
+        self.observe(working, Instant::now())
+    }
+
+    fn observe(&mut self, working: bool, now: Instant) -> Option<u64> {
+        if !working {
+            self.reset();
+            return None;
+        }
+        let since = *self.waiting_since.get_or_insert(now);
+        let waited = now.saturating_duration_since(since).as_secs();
+        if waited < self.after * (self.reminders + 1) {
+            return None;
+        }
+        self.reminders += 1;
+        Some(waited)
+    }
+
+    // Each session starts the wait again once it's over
+    pub fn reset(&mut self) {
+        self.waiting_since = None;
+        self.reminders = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn reminds_after_each_stretch_in_working_hours() {
+        let mut reminder = Reminder::new(30 * 60, None);
+        let start = Instant::now();
+        let at = |minutes: u64| start + Duration::from_secs(minutes * 60);
+        assert_eq!(reminder.observe(true, at(0)), None);
+        assert_eq!(reminder.observe(true, at(29)), None);
+        assert_eq!(reminder.observe(true, at(30)), Some(30 * 60));
+        assert_eq!(reminder.observe(true, at(31)), None);
+        assert_eq!(reminder.observe(true, at(60)), Some(60 * 60));
+        // The end of the day starts it over
+        assert_eq!(reminder.observe(false, at(70)), None);
+        assert_eq!(reminder.observe(true, at(80)), None);
+        assert_eq!(reminder.observe(true, at(109)), None);
+        assert_eq!(reminder.observe(true, at(110)), Some(30 * 60));
+        reminder.reset();
+        assert_eq!(reminder.observe(true, at(140)), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Break activity roulette: each break picks one thing to do from a
+//! weighted list, shown under the bar for the whole break in place of the
+//! rotating suggestions and kept in the break's history record:
+//!
+//! ```toml
+//! break_activities = [["Walk", 3], ["Stretch", 2], ["Water", 2], ["Stare out the window", 1]]
+//! spin = true            # spin through them before landing on one
+//! ```
+//!
+//! A weight of 2 comes up twice as often as a weight of 1, and 0 never.
+//! `timeadair stats` shows how often each one was picked and how many of
+//! those breaks were taken to the end.
+
+use crate::random::Rng;
+
+// Names shown before the spin lands, and how long each is shown for,
+// slowing down towards the end
+const SPINS: usize = 14;
+const FIRST_PAUSE_MS: u64 = 50;
+const LAST_PAUSE_MS: u64 = 300;
+
+// None when nothing has any weight
+pub fn pick(activities: &[(String, u32)], rng: &mut Rng) -> Option<usize> {
+    let total: u64 = activities.iter().map(|(_, weight)| u64::from(*weight)).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut ticket = rng.below(total);
+    activities.iter().position(|(_, weight)| {
+        let weight = u64::from(*weight);
+        if ticket < weight {
+            return true;
+        }
+        ticket -= weight;
+        false
+    })
+}
+
+// The activities a spin passes through in order, like a wheel going
+// round, ending on `landing`
+pub fn spin(count: usize, landing: usize) -> Vec<usize> {
+    let steps = if count < 2 { 1 } else { SPINS };
+    (0..steps).rev().map(|back| (landing + count * SPINS - back) % count).collect()
+}
+
+// How long the `step`th of `steps` names stays up
+pub fn pause(step: usize, steps: usize) -> u64 {
+    let steps = steps.max(2) as u64 - 1;
+    FIRST_PAUSE_MS + (LAST_PAUSE_MS - FIRST_PAUSE_MS) * (step as u64).pow(2) / steps.pow(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_by_weight() {
+        let activities = vec![("Walk".to_string(), 3), ("Nap".to_string(), 0), ("Water".to_string(), 1)];
+        let mut rng = Rng::new(7);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[pick(&activities, &mut rng).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((2800..3200).contains(&counts[0]), "{:?}", counts);
+        assert_eq!(pick(&[("Nap".to_string(), 0)], &mut rng), None);
+
+        assert_eq!(spin(3, 2)[SPINS - 4..], [2, 0, 1, 2]);
+        assert_eq!(spin(1, 0), [0]);
+        assert_eq!(pause(0, 10), FIRST_PAUSE_MS);
+        assert_eq!(pause(9, 10), LAST_PAUSE_MS);
+    }
+}
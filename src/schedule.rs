@@ -0,0 +1,151 @@
+//! Defaults that change with the day of the week, from the `[schedule]`
+//! table in the config file:
+//!
+//! ```toml
+//! [schedule]
+//! mon-thu = { profile = "deepwork", daily_goal = 10 }
+//! fri = { profile = "admin", daily_goal = 6 }
+//! weekend = { profile = "light" }
+//! ```
+//!
+//! Keys are days, ranges of them (`fri-mon` wraps round the weekend), lists
+//! such as `"sat,sun"`, or `weekdays` and `weekend` for Monday to Friday
+//! and the other two, and no day can be in two of them. Today's
+//! profile is used unless `--profile` names another, and its goal is the
+//! one shown in the header. Streaks go by each day's goal too: a scheduled
+//! day only keeps one going once its goal is reached, a day with no goal of
+//! its own needs a single pomodoro, and days the schedule leaves out are
+//! days off, which neither add to a streak nor break it. Vacation days
+//! (see `vacation`) are days off as well, whatever the weekday.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+
+use crate::config::ScheduledDay;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule {
+    // Monday first; None for days off
+    days: [Option<ScheduledDay>; 7],
+    vacation: BTreeSet<NaiveDate>,
+}
+
+// Without a schedule every day is a working one
+impl Default for Schedule {
+    fn default() -> Schedule {
+        Schedule { days: std::array::from_fn(|_| Some(ScheduledDay::default())), vacation: BTreeSet::new() }
+    }
+}
+
+impl Schedule {
+    pub fn parse(table: &BTreeMap<String, ScheduledDay>) -> Result<Schedule, String> {
+        if table.is_empty() {
+            return Ok(Schedule::default());
+        }
+        let mut days: [Option<(&str, &ScheduledDay)>; 7] = [None; 7];
+        for (key, day) in table {
+            for weekday in weekdays(key)? {
+                let slot = &mut days[weekday.num_days_from_monday() as usize];
+                if let Some((other, _)) = slot {
+                    return Err(format!("schedule has {} in both '{}' and '{}'", name(weekday), other, key));
+                }
+                *slot = Some((key, day));
+            }
+        }
+        Ok(Schedule { days: days.map(|day| day.map(|(_, day)| day.clone())), vacation: BTreeSet::new() })
+    }
+
+    // None on a day off
+    pub fn day(&self, weekday: Weekday) -> Option<&ScheduledDay> {
+        self.days[weekday.num_days_from_monday() as usize].as_ref()
+    }
+
+    pub fn today(&self) -> Option<&ScheduledDay> {
+        self.day(Local::now().weekday())
+    }
+
+    pub fn add_vacation(&mut self, days: impl IntoIterator<Item = NaiveDate>) {
+        self.vacation.extend(days);
+    }
+
+    // Pomodoros that keep a streak going, or None on a day off
+    pub fn streak_goal(&self, date: NaiveDate) -> Option<u32> {
+        if self.vacation.contains(&date) {
+            return None;
+        }
+        self.day(date.weekday()).map(|day| day.daily_goal.unwrap_or(1))
+    }
+
+    pub fn goals(&self) -> impl Iterator<Item = u32> + '_ {
+        self.days.iter().flatten().filter_map(|day| day.daily_goal)
+    }
+}
+
+// `mon`, `mon-thu`, `sat,sun` or `weekdays`, with full names allowed too
+fn weekdays(key: &str) -> Result<Vec<Weekday>, String> {
+    let day = |text: &str| {
+        text.trim().parse::<Weekday>().map_err(|_| format!("schedule expects days like mon, mon-thu, \"sat,sun\" or weekend, not '{}'", key))
+    };
+    let mut weekdays = Vec::new();
+    for part in key.split(',') {
+        if let Some(days) = group(part) {
+            weekdays.extend_from_slice(days);
+            continue;
+        }
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (mut weekday, to) = (day(from)?, day(to)?);
+                weekdays.push(weekday);
+                while weekday != to {
+                    weekday = weekday.succ();
+                    weekdays.push(weekday);
+                }
+            }
+            None => weekdays.push(day(part)?),
+        }
+    }
+    Ok(weekdays)
+}
+
+fn group(name: &str) -> Option<&'static [Weekday]> {
+    match name.trim().to_lowercase().as_str() {
+        "weekdays" => Some(&[Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]),
+        "weekend" | "weekends" => Some(&[Weekday::Sat, Weekday::Sun]),
+        _ => None,
+    }
+}
+
+fn name(weekday: Weekday) -> String {
+    weekday.to_string().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_ranges_and_days_off() {
+        let day = |profile: &str, daily_goal| ScheduledDay { profile: Some(profile.to_string()), daily_goal };
+        let table = BTreeMap::from([("mon-thu".to_string(), day("deepwork", Some(10))), ("fri".to_string(), day("admin", Some(6)))]);
+        let schedule = Schedule::parse(&table).unwrap();
+        assert_eq!(schedule.day(Weekday::Wed).unwrap().profile.as_deref(), Some("deepwork"));
+        // Thursday 2024-05-02 to Sunday 2024-05-05
+        let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        assert_eq!(schedule.streak_goal(date(3)), Some(6));
+        assert_eq!(schedule.streak_goal(date(5)), None);
+        let mut every_day = Schedule::default();
+        assert_eq!(every_day.streak_goal(date(5)), Some(1));
+        every_day.add_vacation([date(2), date(5)]);
+        assert_eq!(every_day.streak_goal(date(5)), None);
+        assert_eq!(every_day.streak_goal(date(3)), Some(1));
+
+        assert_eq!(weekdays("fri-mon").unwrap(), [Weekday::Fri, Weekday::Sat, Weekday::Sun, Weekday::Mon]);
+        assert_eq!(weekdays("Saturday, sun").unwrap(), [Weekday::Sat, Weekday::Sun]);
+        assert_eq!(weekdays("Weekend").unwrap(), [Weekday::Sat, Weekday::Sun]);
+        assert_eq!(weekdays("weekdays").unwrap().len(), 5);
+        assert!(weekdays("workdays").is_err());
+        let overlapping = BTreeMap::from([("mon-fri".to_string(), day("a", None)), ("fri".to_string(), day("b", None))]);
+        assert_eq!(Schedule::parse(&overlapping).unwrap_err(), "schedule has fri in both 'fri' and 'mon-fri'");
+    }
+}
@@ -0,0 +1,468 @@
+//! The session scheduler as a pure state machine.
+//!
+//! Frontends feed `Event`s into `step` and render whatever `State` comes
+//! back; nothing in here touches the terminal or the clock, so every
+//! transition can be tested directly.
+
+use crate::timer::{Timer, TimerResult};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    AwaitingWork,
+    Work,
+    AwaitingBreak,
+    Break,
+    Finished,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::AwaitingWork | Phase::Work => "Work",
+            Phase::AwaitingBreak | Phase::Break => "Break",
+            Phase::Finished => "Finished",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    // Answers to the prompt shown in the awaiting phases
+    Start,
+    Decline,
+    // One second of a running session
+    Tick,
+    Pause,
+    Resume,
+    // Ends the running session early and moves on as if it had completed
+    Skip,
+    Reset,
+    Quit,
+    // Ends everything like `Quit`, but from outside, as when the system
+    // shuts down
+    Interrupt,
+    // Lengthen or shorten the running session by `Durations::adjust_step`
+    Extend,
+    Shorten,
+    // Time away from the keyboard: `Discard` turns seconds that ran on the
+    // clock into paused time, and `Credit` paused time back into work
+    Discard(u64),
+    Credit(u64),
+}
+
+// Session lengths in seconds, plus how many work sessions make up a cycle
+// that ends in a long break (0 for no long breaks) and how much `Extend`
+// and `Shorten` change a running session by
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Durations {
+    pub work_time: u64,
+    pub break_time: u64,
+    pub long_break_time: u64,
+    pub long_break_every: u32,
+    pub adjust_step: u64,
+}
+
+impl Default for Durations {
+    fn default() -> Durations {
+        Durations {
+            work_time: 25 * 60,
+            break_time: 5 * 60,
+            long_break_time: 15 * 60,
+            long_break_every: 4,
+            adjust_step: 60,
+        }
+    }
+}
+
+// One entry of a session plan, with its length in seconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Planned {
+    Work(u64),
+    Break(u64),
+    LongBreak(u64),
+}
+
+impl Planned {
+    // Named as in the config file
+    pub fn new(name: &str, seconds: u64) -> Option<Planned> {
+        match name {
+            "work" => Some(Planned::Work(seconds)),
+            "break" => Some(Planned::Break(seconds)),
+            "long_break" => Some(Planned::LongBreak(seconds)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Planned::Work(_) => "Work",
+            Planned::Break(_) => "Break",
+            Planned::LongBreak(_) => "Long break",
+        }
+    }
+
+    pub fn seconds(&self) -> u64 {
+        match self {
+            Planned::Work(seconds) | Planned::Break(seconds) | Planned::LongBreak(seconds) => *seconds,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct State {
+    pub phase: Phase,
+    pub timer: Timer,
+    pub paused: bool,
+    // Seconds the current session has spent paused; never part of elapsed
+    pub paused_time: u64,
+    pub completed_work: u32,
+    pub durations: Durations,
+    // What the awaiting phase starts next when a plan says, not the cycle
+    pub planned: Option<Planned>,
+}
+
+impl State {
+    pub fn new(durations: Durations) -> State {
+        State {
+            phase: Phase::AwaitingWork,
+            timer: Timer::new(durations.work_time),
+            paused: false,
+            paused_time: 0,
+            completed_work: 0,
+            durations,
+            planned: None,
+        }
+    }
+
+    // Whether the break after the latest work session is the long one
+    pub fn long_break_due(&self) -> bool {
+        let every = self.durations.long_break_every;
+        every > 0 && self.completed_work > 0 && self.completed_work.is_multiple_of(every)
+    }
+
+    // Like `Phase::name`, but tells a long break apart from a short one
+    pub fn session_name(&self) -> &'static str {
+        match self.phase {
+            Phase::AwaitingBreak | Phase::Break => match self.planned {
+                Some(Planned::LongBreak(_)) => "Long break",
+                None if self.long_break_due() => "Long break",
+                _ => "Break",
+            },
+            phase => phase.name(),
+        }
+    }
+
+    // Which work session of the current cycle this is, counting a break as
+    // part of the session before it; None when long breaks are off
+    pub fn cycle_position(&self) -> Option<(u32, u32)> {
+        let every = self.durations.long_break_every;
+        if every == 0 {
+            return None;
+        }
+        let position = match self.phase {
+            Phase::AwaitingBreak | Phase::Break => (self.completed_work.max(1) - 1) % every + 1,
+            _ => self.completed_work % every + 1,
+        };
+        Some((position, every))
+    }
+}
+
+pub fn step(state: State, event: Event) -> State {
+    let mut next = state;
+    match (state.phase, event) {
+        (Phase::Finished, _) => {}
+        (_, Event::Quit | Event::Interrupt) => next.phase = Phase::Finished,
+        (Phase::AwaitingWork, Event::Start) => {
+            let duration = match state.planned {
+                Some(Planned::Work(seconds)) => seconds,
+                _ => state.durations.work_time,
+            };
+            start(&mut next, Phase::Work, duration)
+        }
+        (Phase::AwaitingWork, Event::Decline) => next.phase = Phase::Finished,
+        (Phase::AwaitingBreak, Event::Start) => {
+            let duration = if let Some(planned @ (Planned::Break(_) | Planned::LongBreak(_))) = state.planned {
+                planned.seconds()
+            } else if state.long_break_due() {
+                state.durations.long_break_time
+            } else {
+                state.durations.break_time
+            };
+            start(&mut next, Phase::Break, duration)
+        }
+        (Phase::AwaitingBreak, Event::Decline) => next.phase = Phase::AwaitingWork,
+        (Phase::Work | Phase::Break, Event::Reset) => {
+            next.phase = Phase::AwaitingWork;
+            next.paused = false;
+        }
+        (Phase::Work, Event::Skip) => {
+            next.completed_work += 1;
+            next.phase = Phase::AwaitingBreak;
+            next.paused = false;
+        }
+        (Phase::Break, Event::Skip) => {
+            next.phase = Phase::AwaitingWork;
+            next.paused = false;
+        }
+        (Phase::Work | Phase::Break, Event::Pause) => next.paused = true,
+        (Phase::Work | Phase::Break, Event::Resume) => next.paused = false,
+        (Phase::Work | Phase::Break, Event::Extend) => next.timer.duration += state.durations.adjust_step,
+        // Never past what has already run, so the session still ends on a tick
+        (Phase::Work | Phase::Break, Event::Shorten) => {
+            let shortest = state.timer.elapsed + 1;
+            next.timer.duration = state.timer.duration.saturating_sub(state.durations.adjust_step).max(shortest);
+        }
+        (Phase::Work | Phase::Break, Event::Discard(seconds)) => {
+            let moved = seconds.min(state.timer.elapsed);
+            next.timer.elapsed -= moved;
+            next.paused_time += moved;
+        }
+        // Up to the whole session, which then ends on the next tick
+        (Phase::Work | Phase::Break, Event::Credit(seconds)) => {
+            let moved = seconds.min(state.paused_time).min(state.timer.remaining());
+            next.timer.elapsed += moved;
+            next.paused_time -= moved;
+        }
+        (Phase::Work | Phase::Break, Event::Tick) if state.paused => next.paused_time += 1,
+        (Phase::Work | Phase::Break, Event::Tick) => {
+            next.timer.elapsed += 1;
+            if next.timer.elapsed >= next.timer.duration {
+                next.timer.elapsed = next.timer.duration;
+                if state.phase == Phase::Work {
+                    next.completed_work += 1;
+                    next.phase = Phase::AwaitingBreak;
+                } else {
+                    next.phase = Phase::AwaitingWork;
+                }
+            }
+        }
+        _ => {}
+    }
+    next
+}
+
+// Points a state waiting for its next session at the one a plan has next,
+// whichever phase the cycle would have gone on to
+pub fn plan(state: State, next: Planned) -> State {
+    let phase = match (state.phase, next) {
+        (Phase::AwaitingWork | Phase::AwaitingBreak, Planned::Work(_)) => Phase::AwaitingWork,
+        (Phase::AwaitingWork | Phase::AwaitingBreak, _) => Phase::AwaitingBreak,
+        _ => return state,
+    };
+    State { phase, planned: Some(next), ..state }
+}
+
+fn start(state: &mut State, phase: Phase, duration: u64) {
+    state.phase = phase;
+    state.timer = Timer::new(duration);
+    state.paused = false;
+    state.paused_time = 0;
+}
+
+// How a running session ended, given the event that moved it on
+pub fn session_result(event: Event) -> TimerResult {
+    match event {
+        Event::Quit => TimerResult::Quit,
+        Event::Interrupt => TimerResult::Interrupted,
+        Event::Reset => TimerResult::Reset,
+        Event::Skip => TimerResult::Skipped,
+        _ => TimerResult::Completed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn durations(work_time: u64, break_time: u64) -> Durations {
+        // Every other break is a long one, so generated runs reach both
+        Durations {
+            work_time,
+            break_time,
+            long_break_time: break_time * 3,
+            long_break_every: 2,
+            adjust_step: 2,
+        }
+    }
+
+    fn event() -> impl Strategy<Value = Event> {
+        // Weight ticks so that sessions regularly run to completion
+        prop_oneof![
+            2 => Just(Event::Start),
+            1 => Just(Event::Decline),
+            8 => Just(Event::Tick),
+            1 => Just(Event::Pause),
+            1 => Just(Event::Resume),
+            1 => Just(Event::Skip),
+            1 => Just(Event::Reset),
+            1 => Just(Event::Quit),
+            1 => Just(Event::Interrupt),
+            1 => Just(Event::Extend),
+            1 => Just(Event::Shorten),
+            1 => (0u64..20).prop_map(Event::Discard),
+            1 => (0u64..20).prop_map(Event::Credit),
+        ]
+    }
+
+    #[test]
+    fn plans_override_the_cycle() {
+        let mut state = plan(State::new(durations(5, 1)), Planned::Break(120));
+        assert_eq!(state.phase, Phase::AwaitingBreak);
+        state = step(state, Event::Start);
+        assert_eq!((state.phase, state.timer.duration, state.session_name()), (Phase::Break, 120, "Break"));
+        // A running session is left alone
+        assert_eq!(plan(state, Planned::Work(60)), state);
+        state = step(state, Event::Skip);
+        state = step(plan(state, Planned::Work(60)), Event::Start);
+        assert_eq!(state.timer.duration, 60);
+        state = step(state, Event::Skip);
+        // Work after work, though the cycle would have had a break
+        state = plan(state, Planned::Work(90));
+        assert_eq!(state.phase, Phase::AwaitingWork);
+        state = step(step(state, Event::Start), Event::Skip);
+        let long = step(plan(state, Planned::LongBreak(600)), Event::Start);
+        assert_eq!((long.timer.duration, long.session_name()), (600, "Long break"));
+    }
+
+    proptest! {
+        #[test]
+        fn elapsed_never_exceeds_duration(
+            work in 1u64..10,
+            rest in 1u64..10,
+            events in proptest::collection::vec(event(), 0..200),
+        ) {
+            let mut state = State::new(durations(work, rest));
+            for event in events {
+                state = step(state, event);
+                prop_assert!(state.timer.elapsed <= state.timer.duration);
+            }
+        }
+
+        #[test]
+        fn cycles_advance_only_when_work_completes(
+            work in 1u64..10,
+            rest in 1u64..10,
+            events in proptest::collection::vec(event(), 0..200),
+        ) {
+            let mut state = State::new(durations(work, rest));
+            for event in events {
+                let next = step(state, event);
+                let completed = state.phase == Phase::Work && next.phase == Phase::AwaitingBreak;
+                prop_assert_eq!(next.completed_work, state.completed_work + completed as u32);
+                if completed && event != Event::Skip {
+                    prop_assert_eq!(event, Event::Tick);
+                    // Which is `work`, give or take any adjustments
+                    prop_assert_eq!(next.timer.elapsed, next.timer.duration);
+                }
+                state = next;
+            }
+        }
+
+        #[test]
+        fn running_sessions_never_lose_ticks(
+            work in 1u64..10,
+            rest in 1u64..10,
+            events in proptest::collection::vec(event(), 0..200),
+        ) {
+            let mut state = State::new(durations(work, rest));
+            for event in events {
+                let next = step(state, event);
+                let running = matches!(state.phase, Phase::Work | Phase::Break);
+                let moved = matches!(event, Event::Discard(_) | Event::Credit(_));
+                if running && moved {
+                    // Time only moves between the clock and the pauses
+                    prop_assert_eq!(next.timer.elapsed + next.paused_time, state.timer.elapsed + state.paused_time);
+                } else if running && next.phase == state.phase {
+                    let ticked = event == Event::Tick;
+                    let expected = state.timer.elapsed + (ticked && !state.paused) as u64;
+                    prop_assert_eq!(next.timer.elapsed, expected);
+                    let expected = state.paused_time + (ticked && state.paused) as u64;
+                    prop_assert_eq!(next.paused_time, expected);
+                }
+                state = next;
+            }
+        }
+
+        #[test]
+        fn pausing_never_loses_time(
+            work in 2u64..10,
+            before in 0u64..10,
+            held in 0u64..10,
+        ) {
+            let mut state = step(State::new(durations(work, 1)), Event::Start);
+            for _ in 0..before.min(work - 1) {
+                state = step(state, Event::Tick);
+            }
+            let elapsed = state.timer.elapsed;
+            state = step(state, Event::Pause);
+            for _ in 0..held {
+                state = step(state, Event::Tick);
+            }
+            state = step(state, Event::Resume);
+            prop_assert_eq!(state.phase, Phase::Work);
+            prop_assert_eq!(state.timer.elapsed, elapsed);
+            prop_assert_eq!(state.paused_time, held);
+        }
+
+        #[test]
+        fn finished_is_terminal(
+            events in proptest::collection::vec(event(), 0..50),
+        ) {
+            let mut state = step(State::new(durations(5, 5)), Event::Quit);
+            for event in events {
+                let next = step(state, event);
+                prop_assert_eq!(next, state);
+                state = next;
+            }
+        }
+    }
+
+    #[test]
+    fn full_cycle() {
+        let mut state = step(State::new(durations(2, 1)), Event::Start);
+        assert_eq!(state.phase, Phase::Work);
+        state = step(step(state, Event::Tick), Event::Tick);
+        assert_eq!(state.phase, Phase::AwaitingBreak);
+        assert_eq!(state.completed_work, 1);
+        state = step(step(state, Event::Start), Event::Tick);
+        assert_eq!(state.phase, Phase::AwaitingWork);
+    }
+
+    #[test]
+    fn long_break_ends_each_cycle() {
+        let mut state = State::new(durations(1, 1));
+        let mut breaks = Vec::new();
+        for _ in 0..4 {
+            state = step(step(state, Event::Start), Event::Tick);
+            state = step(state, Event::Start);
+            breaks.push((state.session_name(), state.timer.duration, state.cycle_position()));
+            while state.phase == Phase::Break {
+                state = step(state, Event::Tick);
+            }
+        }
+        assert_eq!(
+            breaks,
+            [
+                ("Break", 1, Some((1, 2))),
+                ("Long break", 3, Some((2, 2))),
+                ("Break", 1, Some((1, 2))),
+                ("Long break", 3, Some((2, 2))),
+            ]
+        );
+        assert_eq!(state.cycle_position(), Some((1, 2)));
+    }
+
+    #[test]
+    fn adjusting_the_running_session() {
+        let mut state = step(State::new(durations(5, 1)), Event::Start);
+        state = step(step(state, Event::Tick), Event::Extend);
+        assert_eq!(state.timer, Timer { duration: 7, elapsed: 1 });
+        state = step(step(step(state, Event::Shorten), Event::Shorten), Event::Shorten);
+        assert_eq!(state.timer, Timer { duration: 2, elapsed: 1 });
+        assert_eq!(step(state, Event::Tick).phase, Phase::AwaitingBreak);
+        // Nothing to adjust between sessions
+        let waiting = State::new(durations(5, 1));
+        assert_eq!(step(waiting, Event::Extend), waiting);
+    }
+}
@@ -0,0 +1,146 @@
+//! `timeadair search <text>`: finds tasks and recorded sessions by their
+//! titles, with `--since` and `--until` to narrow the sessions to a range
+//! of days. Every word has to appear, in any order and any case, so
+//! `search "acme invoice"` finds "Invoice for ACME".
+//!
+//! The board (`--board`) has the same search behind `/`, over its cards.
+
+use std::fmt::{Display, Write};
+
+use chrono::{DateTime, NaiveDate, TimeZone};
+use serde_json::{json, Value};
+
+use crate::history::SessionRecord;
+use crate::stopwatch::format_elapsed;
+use crate::tasks::Task;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Search {
+    pub query: String,
+    // Sessions started on or after this day, and on or before this one
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Found<'a> {
+    // Task numbers as `task list` shows them
+    pub tasks: Vec<(usize, &'a Task)>,
+    // Newest first
+    pub sessions: Vec<&'a SessionRecord>,
+}
+
+// Whether every word of `query` is somewhere in `text`
+pub fn matches(text: &str, query: &str) -> bool {
+    let text = text.to_lowercase();
+    query.to_lowercase().split_whitespace().all(|word| text.contains(word))
+}
+
+pub fn search<'a, Tz: TimeZone>(search: &Search, tasks: &'a [Task], records: &'a [SessionRecord], tz: &Tz) -> Found<'a> {
+    let tasks = tasks.iter().enumerate().filter(|(_, task)| matches(&task.title, &search.query)).map(|(i, task)| (i + 1, task)).collect();
+    let in_range = |record: &SessionRecord| {
+        let Some(day) = tz.timestamp_opt(record.started, 0).single().map(|start| start.date_naive()) else { return false };
+        search.since.is_none_or(|since| day >= since) && search.until.is_none_or(|until| day <= until)
+    };
+    let mut sessions: Vec<_> = records
+        .iter()
+        .filter(|record| record.task.as_deref().is_some_and(|task| matches(task, &search.query)) && in_range(record))
+        .collect();
+    sessions.reverse();
+    Found { tasks, sessions }
+}
+
+pub fn report<Tz: TimeZone>(found: &Found, tz: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    if found.tasks.is_empty() && found.sessions.is_empty() {
+        return "Nothing found.\n".to_string();
+    }
+    let mut out = String::new();
+    if !found.tasks.is_empty() {
+        let _ = writeln!(out, "Tasks");
+        for (number, task) in &found.tasks {
+            let _ = writeln!(out, "  {}. {}", number, task.title);
+        }
+    }
+    if !found.sessions.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "Sessions");
+        for record in &found.sessions {
+            let _ = writeln!(
+                out,
+                "  {}  {}  {}  {}",
+                started(record, tz).map_or_else(String::new, |start| start.format("%Y-%m-%d %H:%M").to_string()),
+                format_elapsed(record.duration),
+                record.outcome.name(),
+                record.task.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    out
+}
+
+// The same for `timeadair search --json`
+pub fn data<Tz: TimeZone>(found: &Found, tz: &Tz) -> Value
+where
+    Tz::Offset: Display,
+{
+    json!({
+        "tasks": found.tasks.iter().map(|(number, task)| json!({ "number": number, "title": task.title })).collect::<Vec<_>>(),
+        "sessions": found
+            .sessions
+            .iter()
+            .map(|record| json!({
+                "session": record.session,
+                "task": record.task,
+                "start": started(record, tz).map(|start| start.to_rfc3339()),
+                "duration": record.duration,
+                "outcome": record.outcome.name(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn started<Tz: TimeZone>(record: &SessionRecord, tz: &Tz) -> Option<DateTime<Tz>> {
+    tz.timestamp_opt(record.started, 0).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Outcome;
+    use chrono::Utc;
+
+    #[test]
+    fn finds_tasks_and_sessions() {
+        let tasks = vec![Task::new("Invoice for ACME".to_string()), Task::new("Slides".to_string())];
+        let session = |started, task: &str| SessionRecord {
+            session: "work".to_string(),
+            started,
+            duration: 1500,
+            outcome: Outcome::Completed,
+            task: Some(task.to_string()),
+            ..Default::default()
+        };
+        // 2024-05-01 and 2024-05-02, noon
+        let records = vec![session(1714564800, "Invoice for ACME"), session(1714651200, "acme invoice"), session(1714651300, "Slides")];
+        let query = Search { query: "ACME  invoice".to_string(), ..Search::default() };
+        let found = search(&query, &tasks, &records, &Utc);
+        assert_eq!(found.tasks, [(1, &tasks[0])]);
+        assert_eq!(found.sessions, [&records[1], &records[0]]);
+        assert_eq!(
+            report(&found, &Utc),
+            "Tasks\n  1. Invoice for ACME\n\nSessions\n  2024-05-02 12:00  25:00  completed  acme invoice\n  2024-05-01 12:00  25:00  completed  Invoice for ACME\n"
+        );
+
+        let day = NaiveDate::from_ymd_opt(2024, 5, 1);
+        let found = search(&Search { until: day, ..query.clone() }, &tasks, &records, &Utc);
+        assert_eq!(found.sessions, [&records[0]]);
+        let found = search(&Search { query: "budget".to_string(), since: day, until: None }, &tasks, &records, &Utc);
+        assert_eq!(report(&found, &Utc), "Nothing found.\n");
+        assert_eq!(data(&search(&query, &tasks, &records, &Utc), &Utc)["sessions"][0]["start"], "2024-05-02T12:00:00+00:00");
+    }
+}
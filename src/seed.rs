@@ -0,0 +1,188 @@
+//! `timeadair seed [--days <n>] [--profile <realistic|steady>] [--seed <n>]`:
+//! fills an empty history with made-up sessions, so stats, the heatmap and
+//! the report have something to show, e.g. in a workspace of its own with
+//! `timeadair --workspace demo seed --days 90`.
+//!
+//! - `realistic` (the default): busier weekdays than weekends, the odd day
+//!   off, starts between 8 and 10, a lunch break, and now and then a
+//!   session quit, skipped, paused or interrupted, with tasks, ratings and
+//!   notes to match
+//! - `steady`: the same eight completed pomodoros from 9 every weekday,
+//!   for numbers that are easy to check
+//!
+//! The same `--seed` gives the same sessions. A history that already has
+//! sessions is left alone.
+
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone, Weekday};
+
+use crate::archive::Rollups;
+use crate::error::Error;
+use crate::history::{self, History, Interruptions, Outcome, SessionRecord};
+use crate::paths;
+use crate::random::Rng;
+
+pub const DEFAULT_DAYS: u32 = 30;
+
+const TASKS: [&str; 6] = ["Write report", "Email", "Code review", "Slides", "Planning", "Reading"];
+const NOTES: [&str; 4] = ["got further than expected", "kept getting pulled into chat", "finished the first draft", "stuck on a bug"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Pattern {
+    #[default]
+    Realistic,
+    Steady,
+}
+
+impl Pattern {
+    pub fn parse(name: &str) -> Result<Pattern, String> {
+        match name {
+            "realistic" => Ok(Pattern::Realistic),
+            "steady" => Ok(Pattern::Steady),
+            _ => Err(format!("seed --profile expects realistic or steady, not '{}'", name)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Seed {
+    pub days: u32,
+    pub pattern: Pattern,
+    // From the clock when not given
+    pub seed: Option<u64>,
+}
+
+impl Default for Seed {
+    fn default() -> Seed {
+        Seed { days: DEFAULT_DAYS, pattern: Pattern::default(), seed: None }
+    }
+}
+
+// How many sessions were written
+pub fn seed(options: &Seed) -> Result<usize, Error> {
+    paths::ensure_writable()?;
+    let path = history::default_path().ok_or(Error::NoDirectory("data"))?;
+    let history = History::new(path);
+    let lock = history.lock()?;
+    if !history.load()?.is_empty() {
+        let message = "the history already has sessions; seed an empty workspace instead, e.g. 'timeadair --workspace demo seed'";
+        return Err(Error::Usage(message.to_string()));
+    }
+    let mut rng = options.seed.map_or_else(Rng::from_clock, Rng::new);
+    let records = sessions(&mut rng, options.pattern, Local::now().date_naive(), options.days, &Local);
+    history.replace(&lock, &records)?;
+    let mut rollups = Rollups::load(&history.rollups_path())?;
+    rollups.rebuild(&records, history.size()?, &Local);
+    rollups.save(&history.rollups_path())?;
+    Ok(records.len())
+}
+
+// The `days` before `today`, oldest first; today itself is left to be
+// worked
+fn sessions<Tz: TimeZone>(rng: &mut Rng, pattern: Pattern, today: NaiveDate, days: u32, tz: &Tz) -> Vec<SessionRecord> {
+    let mut records = Vec::new();
+    for back in (1..=i64::from(days)).rev() {
+        let day = today - Duration::days(back);
+        let weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+        let (start, count) = match pattern {
+            Pattern::Steady if weekend => continue,
+            Pattern::Steady => (9 * 3600, 8),
+            // One weekday in ten off, and most weekends
+            Pattern::Realistic if rng.below(10) < if weekend { 7 } else { 1 } => continue,
+            Pattern::Realistic if weekend => (10 * 3600 + rng.below(24) * 300, 1 + rng.below(4)),
+            Pattern::Realistic => (8 * 3600 + rng.below(24) * 300, 4 + rng.below(7)),
+        };
+        let Some(midnight) = day.and_hms_opt(0, 0, 0).and_then(|midnight| tz.from_local_datetime(&midnight).earliest()) else {
+            continue;
+        };
+        let mut at = midnight.timestamp() + start as i64;
+        for pomodoro in 1..=count {
+            let work = work(rng, pattern, at);
+            at += (work.duration + work.paused) as i64;
+            records.push(work);
+            if pomodoro == count {
+                break;
+            }
+            let long = pomodoro % 4 == 0;
+            let break_time = if long { 900 } else { 300 };
+            let skipped = pattern == Pattern::Realistic && rng.below(10) == 0;
+            let duration = if skipped { rng.below(break_time / 2) } else { break_time };
+            records.push(SessionRecord {
+                session: "break".to_string(),
+                started: at,
+                duration,
+                outcome: if skipped { Outcome::Skipped } else { Outcome::Completed },
+                planned: Some(break_time),
+                ..Default::default()
+            });
+            at += duration as i64;
+            // Lunch after the third, or a few minutes wandering off otherwise
+            at += match pattern {
+                Pattern::Realistic if pomodoro == 3 => 2400 + rng.below(1800) as i64,
+                Pattern::Realistic => rng.below(180) as i64,
+                Pattern::Steady => 0,
+            };
+        }
+    }
+    records
+}
+
+fn work(rng: &mut Rng, pattern: Pattern, started: i64) -> SessionRecord {
+    let steady = pattern == Pattern::Steady;
+    let planned = 1500;
+    let outcome = match rng.below(20) {
+        _ if steady => Outcome::Completed,
+        0 | 1 => Outcome::Quit,
+        2 => Outcome::Skipped,
+        _ => Outcome::Completed,
+    };
+    let duration = if outcome == Outcome::Completed { planned } else { 60 + rng.below(planned - 120) };
+    let pick = |rng: &mut Rng, chance: u64| !steady && rng.below(100) < chance;
+    let paused = if pick(rng, 15) { 30 + rng.below(270) } else { 0 };
+    let interruptions = Interruptions { internal: pick(rng, 25) as u32 + pick(rng, 5) as u32, external: pick(rng, 15) as u32 };
+    // Mostly middling, with the odd very good or bad one
+    let rating = pick(rng, 60).then(|| [1, 2, 3, 3, 4, 4, 4, 5][rng.below(8) as usize]);
+    let note = pick(rng, 10).then(|| NOTES[rng.below(NOTES.len() as u64) as usize].to_string());
+    SessionRecord {
+        session: "work".to_string(),
+        started,
+        duration,
+        paused,
+        outcome,
+        task: Some(TASKS[if steady { 0 } else { rng.below(TASKS.len() as u64) as usize }].to_string()),
+        planned: Some(planned),
+        rating,
+        note,
+        interruptions,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn seeded_history_looks_like_weeks_of_work() {
+        // A Monday, so 28 days are four whole weeks
+        let today = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let generate = |pattern, seed| sessions(&mut Rng::new(seed), pattern, today, 28, &Utc);
+        let records = generate(Pattern::Realistic, 7);
+        assert_eq!(records, generate(Pattern::Realistic, 7));
+        assert_ne!(records, generate(Pattern::Realistic, 8));
+        let first = Utc.timestamp_opt(records[0].started, 0).unwrap().date_naive();
+        assert!(first >= today - Duration::days(28));
+        assert!(records.iter().all(|record| Utc.timestamp_opt(record.started, 0).unwrap().date_naive() < today));
+        assert!(records.windows(2).all(|pair| pair[0].started + (pair[0].duration + pair[0].paused) as i64 <= pair[1].started));
+
+        let weekend = |record: &SessionRecord| matches!(Utc.timestamp_opt(record.started, 0).unwrap().weekday(), Weekday::Sat | Weekday::Sun);
+        let work: Vec<_> = records.iter().filter(|record| record.session == "work").collect();
+        assert!(work.iter().filter(|record| weekend(record)).count() * 4 < work.len());
+        assert!(work.iter().any(|record| record.outcome != Outcome::Completed));
+
+        let steady = generate(Pattern::Steady, 7);
+        assert_eq!(steady.iter().filter(|record| record.session == "work").count(), 20 * 8);
+        assert!(steady.iter().all(|record| record.outcome == Outcome::Completed && record.paused == 0));
+        assert_eq!(Pattern::parse("lazy").unwrap_err(), "seed --profile expects realistic or steady, not 'lazy'");
+    }
+}
@@ -0,0 +1,32 @@
+//! Output for serial/USB desk displays.
+//!
+//! One ASCII line is written per timer tick in the form `<PHASE> <SECONDS>\n`,
+//! where PHASE is `WORK` or `BREAK` and SECONDS is the whole number of
+//! seconds remaining. `IDLE 0` is written whenever a session stops.
+//!
+//! The port is opened as-is, so set the line speed beforehand if the
+//! device needs one, e.g. `stty -F /dev/ttyUSB0 9600 raw`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+pub struct SerialOutput {
+    port: File,
+}
+
+impl SerialOutput {
+    pub fn open(path: &Path) -> io::Result<SerialOutput> {
+        let port = OpenOptions::new().write(true).open(path)?;
+        Ok(SerialOutput { port })
+    }
+
+    pub fn send(&mut self, phase: &str, remaining: u64) -> io::Result<()> {
+        writeln!(self.port, "{} {}", phase, remaining)?;
+        self.port.flush()
+    }
+
+    pub fn send_idle(&mut self) -> io::Result<()> {
+        self.send("IDLE", 0)
+    }
+}
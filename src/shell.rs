@@ -0,0 +1,103 @@
+//! Prompt hooks for `timeadair shell-init`.
+//!
+//! Each hook reads the status file before the prompt is drawn and exports
+//! `TIMEADAIR_PHASE`, `TIMEADAIR_REMAINING` and `TIMEADAIR_COUNT`, or
+//! unsets them when no timer is running.
+
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn parse(name: &str) -> Option<Shell> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+}
+
+const POSIX_HOOK: &str = r#"__timeadair_hook() {
+    unset TIMEADAIR_PHASE TIMEADAIR_REMAINING TIMEADAIR_COUNT
+    [ -r @FILE@ ] || return 0
+    local key value pid phase remaining count
+    while IFS='=' read -r key value; do
+        case "$key" in
+            pid) pid=$value ;;
+            phase) phase=$value ;;
+            remaining) remaining=$value ;;
+            completed) count=$value ;;
+        esac
+    done < @FILE@
+    # A leftover file from a timer that died is not a running timer
+    [ -n "$pid" ] && kill -0 "$pid" 2>/dev/null || return 0
+    export TIMEADAIR_PHASE="$phase" TIMEADAIR_REMAINING="$remaining" TIMEADAIR_COUNT="$count"
+}
+"#;
+
+const FISH_HOOK: &str = r#"function __timeadair_hook --on-event fish_prompt
+    set -e TIMEADAIR_PHASE TIMEADAIR_REMAINING TIMEADAIR_COUNT
+    test -r @FILE@; or return 0
+    set -l pid
+    set -l phase
+    set -l remaining
+    set -l count
+    while read -l line
+        set -l kv (string split -m 1 = -- $line)
+        switch $kv[1]
+            case pid
+                set pid $kv[2]
+            case phase
+                set phase $kv[2]
+            case remaining
+                set remaining $kv[2]
+            case completed
+                set count $kv[2]
+        end
+    end < @FILE@
+    # A leftover file from a timer that died is not a running timer
+    test -n "$pid"; and kill -0 $pid 2>/dev/null; or return 0
+    set -gx TIMEADAIR_PHASE $phase
+    set -gx TIMEADAIR_REMAINING $remaining
+    set -gx TIMEADAIR_COUNT $count
+end
+"#;
+
+pub fn init_script(shell: Shell, status_file: &Path) -> String {
+    let path = status_file.to_string_lossy();
+    match shell {
+        Shell::Bash => POSIX_HOOK.replace("@FILE@", &posix_quote(&path))
+            + "PROMPT_COMMAND=\"__timeadair_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"\n",
+        Shell::Zsh => POSIX_HOOK.replace("@FILE@", &posix_quote(&path))
+            + "typeset -ag precmd_functions\nprecmd_functions+=(__timeadair_hook)\n",
+        Shell::Fish => FISH_HOOK.replace("@FILE@", &fish_quote(&path)),
+    }
+}
+
+fn posix_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_status_path() {
+        let script = init_script(Shell::Bash, Path::new("/tmp/it's/status"));
+        assert!(script.contains(r"done < '/tmp/it'\''s/status'"));
+        let script = init_script(Shell::Fish, Path::new("/tmp/it's/status"));
+        assert!(script.contains(r"end < '/tmp/it\'s/status'"));
+    }
+}
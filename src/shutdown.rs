@@ -0,0 +1,48 @@
+//! SIGTERM, as sent when the system shuts down or a service manager stops
+//! the timer. The signal handler only writes a byte to a pipe, and a thread
+//! reading the other end does the real work, since almost nothing is safe
+//! to do inside a handler. Only available on Unix.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::FromRawFd;
+use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::thread;
+
+// The pipe's write end, once there's a handler
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn terminated(_: libc::c_int) {
+    let fd = PIPE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        // Safety: write is async-signal-safe and the byte outlives the call
+        unsafe { libc::write(fd, [1u8].as_ptr().cast(), 1) };
+    }
+}
+
+// Runs `handler` on its own thread each time SIGTERM arrives
+pub fn on_terminate(mut handler: impl FnMut() + Send + 'static) -> io::Result<()> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Safety: the read end is ours alone from here on
+    let mut pipe = unsafe { File::from_raw_fd(fds[0]) };
+    PIPE.store(fds[1], Ordering::Relaxed);
+    // Restarting keeps a blocked read of stdin or a socket from failing
+    // with EINTR just because the signal came in
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = terminated as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    action.sa_flags = libc::SA_RESTART;
+    if unsafe { libc::sigaction(libc::SIGTERM, &action, ptr::null_mut()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    thread::spawn(move || {
+        let mut byte = [0];
+        while pipe.read(&mut byte).is_ok_and(|read| read > 0) {
+            handler();
+        }
+    });
+    Ok(())
+}
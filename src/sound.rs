@@ -0,0 +1,408 @@
+//! Completion sounds, for terminals with the bell turned off.
+//!
+//! Sounds are played by handing the file to the first audio player found on
+//! the PATH (`paplay`, `pw-play`, `aplay` or `afplay`), so timeadair doesn't
+//! link against any audio library. Without a configured file, a short chime
+//! bundled with timeadair is written to the temp directory and used instead.
+//! Which formats work depends on the player; all of them play `.wav`, and
+//! `paplay` and `pw-play` play `.ogg` too.
+//!
+//! A sound pack is a directory of files named after the moment they're
+//! played, such as `work_end.ogg` or `tick.wav`, kept in
+//! `~/.config/timeadair/sounds/<pack>/` and picked with `sound_pack` in the
+//! config file. A pack doesn't need every sound; only the end of a session
+//! falls back to the chime, reaching the daily goal (`goal`) to a rising
+//! fanfare, and each of the last ten seconds under `--final-countdown`
+//! (`countdown`) to a short pip.
+//!
+//! Volume and output device are passed on to the player where it supports
+//! them: `paplay` and `pw-play` take both, `aplay` only a device and
+//! `afplay` only a volume.
+
+use std::env;
+use std::f32::consts::TAU;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::paths;
+
+const PLAYERS: [&str; 4] = ["paplay", "pw-play", "aplay", "afplay"];
+const SAMPLE_RATE: u32 = 22050;
+
+// The moments a sound can be played at, named as in a sound pack
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoundEvent {
+    WorkEnd,
+    BreakEnd,
+    LastMinute,
+    Tick,
+    Goal,
+    Countdown,
+}
+
+impl SoundEvent {
+    pub const ALL: [SoundEvent; 6] =
+        [SoundEvent::WorkEnd, SoundEvent::BreakEnd, SoundEvent::LastMinute, SoundEvent::Tick, SoundEvent::Goal, SoundEvent::Countdown];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SoundEvent::WorkEnd => "work_end",
+            SoundEvent::BreakEnd => "break_end",
+            SoundEvent::LastMinute => "last_minute",
+            SoundEvent::Tick => "tick",
+            SoundEvent::Goal => "goal",
+            SoundEvent::Countdown => "countdown",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<SoundEvent> {
+        SoundEvent::ALL.into_iter().find(|event| event.name() == name)
+    }
+}
+
+// `timeadair sounds list|preview`
+#[derive(Clone, Debug, PartialEq)]
+pub enum SoundsCommand {
+    List,
+    Preview { pack: String, event: Option<SoundEvent> },
+    Devices,
+}
+
+pub struct Sounds {
+    player: PathBuf,
+    files: Vec<(SoundEvent, PathBuf)>,
+    // Percent; None leaves it to the player
+    volume: Option<u8>,
+    device: Option<String>,
+}
+
+impl Sounds {
+    // Files given on their own win over the pack's
+    pub fn new(pack: Option<&str>, work_end: Option<PathBuf>, break_end: Option<PathBuf>) -> io::Result<Sounds> {
+        let player = find_player().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no audio player found, install one of {}", PLAYERS.join(", ")),
+            )
+        })?;
+        let mut files = match pack {
+            Some(pack) => pack_files(&pack_dir(pack)?)?,
+            None => Vec::new(),
+        };
+        for (event, path) in [(SoundEvent::WorkEnd, work_end), (SoundEvent::BreakEnd, break_end)] {
+            let Some(path) = path else { continue };
+            if !path.is_file() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("sound file {} not found", path.display()),
+                ));
+            }
+            files.retain(|(known, _)| *known != event);
+            files.push((event, path));
+        }
+        for event in [SoundEvent::WorkEnd, SoundEvent::BreakEnd, SoundEvent::Goal, SoundEvent::Countdown] {
+            if !files.iter().any(|(known, _)| *known == event) {
+                let path = match event {
+                    SoundEvent::Goal => write_sound("timeadair-fanfare.wav", &fanfare())?,
+                    SoundEvent::Countdown => write_sound("timeadair-pip.wav", &pip())?,
+                    _ => write_sound("timeadair-chime.wav", &chime())?,
+                };
+                files.push((event, path));
+            }
+        }
+        Ok(Sounds {
+            player,
+            files,
+            volume: None,
+            device: None,
+        })
+    }
+
+    pub fn set_output(&mut self, volume: Option<u8>, device: Option<String>) {
+        self.volume = volume.map(|volume| volume.min(100));
+        self.device = device;
+    }
+
+    // Steps the volume up or down from where it is, starting from full
+    pub fn change_volume(&mut self, step: i8) {
+        self.volume = Some((self.volume.unwrap_or(100) as i16 + step as i16).clamp(0, 100) as u8);
+    }
+
+    pub fn has(&self, event: SoundEvent) -> bool {
+        self.file(event).is_some()
+    }
+
+    // Plays in the background; the player is left to finish on its own
+    pub fn play(&self, event: SoundEvent) -> io::Result<()> {
+        let Some(path) = self.file(event) else { return Ok(()) };
+        let mut child = self.command(path).spawn()?;
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+
+    // For previews, which play one sound after another
+    pub fn play_and_wait(&self, event: SoundEvent) -> io::Result<()> {
+        let Some(path) = self.file(event) else { return Ok(()) };
+        self.command(path).status()?;
+        Ok(())
+    }
+
+    fn file(&self, event: SoundEvent) -> Option<&Path> {
+        self.files.iter().find(|(known, _)| *known == event).map(|(_, path)| path.as_path())
+    }
+
+    fn command(&self, path: &Path) -> Command {
+        let mut command = Command::new(&self.player);
+        command
+            .args(player_args(player_name(&self.player), self.volume, self.device.as_deref()))
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        command
+    }
+}
+
+fn player_name(player: &Path) -> &str {
+    player.file_name().and_then(|name| name.to_str()).unwrap_or_default()
+}
+
+// Each player spells volume and device differently, and some can't do both
+fn player_args(player: &str, volume: Option<u8>, device: Option<&str>) -> Vec<String> {
+    let mut args = Vec::new();
+    match player {
+        "paplay" => {
+            args.extend(volume.map(|volume| format!("--volume={}", volume as u32 * 65536 / 100)));
+            args.extend(device.map(|device| format!("--device={}", device)));
+        }
+        "pw-play" => {
+            args.extend(volume.map(|volume| format!("--volume={:.2}", volume as f32 / 100.0)));
+            args.extend(device.map(|device| format!("--target={}", device)));
+        }
+        "aplay" => {
+            if let Some(device) = device {
+                args.extend(["-D".to_string(), device.to_string()]);
+            }
+        }
+        "afplay" => {
+            if let Some(volume) = volume {
+                args.extend(["-v".to_string(), format!("{:.2}", volume as f32 / 100.0)]);
+            }
+        }
+        _ => {}
+    }
+    args
+}
+
+// The output devices the player can be pointed at with `audio_device`
+pub fn list_devices() -> io::Result<Vec<String>> {
+    let player = find_player().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no audio player found"))?;
+    match player_name(&player) {
+        "paplay" | "pw-play" => Ok(pactl_sinks(&run("pactl", &["list", "short", "sinks"])?)),
+        "aplay" => Ok(aplay_devices(&run("aplay", &["-L"])?)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "afplay always plays on the default output device",
+        )),
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(program).args(args).stderr(Stdio::null()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("{} failed", program)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// `pactl list short sinks` is tab separated, with the name second
+fn pactl_sinks(text: &str) -> Vec<String> {
+    text.lines().filter_map(|line| line.split('\t').nth(1)).map(str::to_string).collect()
+}
+
+// `aplay -L` puts each device name on its own line, with its description
+// indented below
+fn aplay_devices(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with(char::is_whitespace))
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn packs_dir() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("sounds"))
+}
+
+// A pack is named by its directory under `packs_dir`, or given as a path
+fn pack_dir(pack: &str) -> io::Result<PathBuf> {
+    let dir = if pack.contains(std::path::MAIN_SEPARATOR) || pack.contains('/') {
+        PathBuf::from(pack)
+    } else {
+        let packs = packs_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        packs.join(pack)
+    };
+    if !dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("sound pack {} not found", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+// The file for each event a pack has, whatever its extension
+fn pack_files(dir: &Path) -> io::Result<Vec<(SoundEvent, PathBuf)>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+    paths.sort();
+    Ok(SoundEvent::ALL
+        .into_iter()
+        .filter_map(|event| {
+            let path = paths
+                .iter()
+                .find(|path| path.is_file() && path.file_stem().and_then(|stem| stem.to_str()) == Some(event.name()))?;
+            Some((event, path.clone()))
+        })
+        .collect())
+}
+
+// Every pack under `packs_dir` with the events it has sounds for
+pub fn list_packs() -> io::Result<Vec<(String, Vec<SoundEvent>)>> {
+    let Some(dir) = packs_dir() else { return Ok(Vec::new()) };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut packs = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()).filter(|entry| entry.path().is_dir()) {
+        let events = pack_files(&entry.path())?.into_iter().map(|(event, _)| event).collect();
+        packs.push((entry.file_name().to_string_lossy().into_owned(), events));
+    }
+    packs.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(packs)
+}
+
+fn find_player() -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    let dirs: Vec<PathBuf> = env::split_paths(&path).collect();
+    PLAYERS
+        .iter()
+        .flat_map(|player| dirs.iter().map(move |dir| dir.join(player)))
+        .find(|candidate| candidate.is_file())
+}
+
+fn write_sound(name: &str, sound: &[u8]) -> io::Result<PathBuf> {
+    let path = env::temp_dir().join(name);
+    if fs::read(&path).ok().as_deref() != Some(sound) {
+        write_atomically(&path, sound)?;
+    }
+    Ok(path)
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temp = path.with_extension(format!("wav.{}", std::process::id()));
+    fs::write(&temp, bytes)?;
+    fs::rename(&temp, path)
+}
+
+// Two soft bell tones, a sixth apart, as a 16-bit mono WAV file
+pub fn chime() -> Vec<u8> {
+    bells(&[(1318.5, 0.0), (1046.5, 0.18)], 0.9)
+}
+
+// A rising C major arpeggio, for the daily goal
+pub fn fanfare() -> Vec<u8> {
+    bells(&[(523.3, 0.0), (659.3, 0.12), (784.0, 0.24), (1046.5, 0.36)], 1.3)
+}
+
+// A single short high note, quick enough for one every second
+pub fn pip() -> Vec<u8> {
+    bells(&[(2093.0, 0.0)], 0.08)
+}
+
+// Each note starts at its offset in seconds and dies away on its own
+fn bells(notes: &[(f32, f32)], seconds: f32) -> Vec<u8> {
+    let length = (SAMPLE_RATE as f32 * seconds) as usize;
+    let samples = (0..length).map(|i| {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let value: f32 = notes
+            .iter()
+            .filter(|(_, start)| t >= *start)
+            .map(|(frequency, start)| (TAU * frequency * (t - start)).sin() * (-5.0 * (t - start)).exp())
+            .sum();
+        (value * 0.3 * i16::MAX as f32) as i16
+    });
+    wav(samples.collect::<Vec<_>>().as_slice())
+}
+
+fn wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        out.extend_from_slice(&sample.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_and_device_for_each_player() {
+        assert_eq!(player_args("paplay", Some(50), Some("usb")), ["--volume=32768", "--device=usb"]);
+        assert_eq!(player_args("pw-play", Some(50), None), ["--volume=0.50"]);
+        assert_eq!(player_args("aplay", Some(50), Some("hw:1")), ["-D", "hw:1"]);
+        assert_eq!(player_args("afplay", Some(50), Some("usb")), ["-v", "0.50"]);
+        assert!(player_args("paplay", None, None).is_empty());
+    }
+
+    #[test]
+    fn parses_device_lists() {
+        let sinks = "0\talsa_output.pci.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tSUSPENDED\n\
+                     1\tbluez_output.headset\tPipeWire\ts16le 1ch 16000Hz\tIDLE\n";
+        assert_eq!(pactl_sinks(sinks), ["alsa_output.pci.analog-stereo", "bluez_output.headset"]);
+        let aplay = "default\n    Playback/recording through the PulseAudio sound server\nhw:CARD=PCH,DEV=0\n    HDA Intel PCH\n";
+        assert_eq!(aplay_devices(aplay), ["default", "hw:CARD=PCH,DEV=0"]);
+    }
+
+    #[test]
+    fn pack_files_are_matched_by_name() {
+        let dir = env::temp_dir().join(format!("timeadair-pack-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["work_end.ogg", "tick.wav", "README.txt"] {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        let files = pack_files(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files, [(SoundEvent::WorkEnd, dir.join("work_end.ogg")), (SoundEvent::Tick, dir.join("tick.wav"))]);
+    }
+
+    #[test]
+    fn chime_is_a_wav_file() {
+        for chime in [chime(), fanfare(), pip()] {
+            assert_eq!(&chime[0..4], b"RIFF");
+            assert_eq!(u32::from_le_bytes(chime[4..8].try_into().unwrap()) as usize, chime.len() - 8);
+            assert_eq!(&chime[8..16], b"WAVEfmt ");
+            assert_eq!(u32::from_le_bytes(chime[40..44].try_into().unwrap()) as usize, chime.len() - 44);
+            // Loud enough to hear, without clipping where the notes overlap
+            let peak = chime[44..].chunks(2).map(|s| i16::from_le_bytes([s[0], s[1]]).unsigned_abs()).max().unwrap();
+            assert!(peak > i16::MAX as u16 / 4 && peak < i16::MAX as u16);
+        }
+    }
+}
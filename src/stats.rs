@@ -0,0 +1,682 @@
+//! Summaries of the session history for `timeadair stats`.
+//!
+//! A pomodoro is a work session that ran to completion; focus time counts
+//! every work session, including ones that were skipped, quit or reset part way.
+//! Energy ratings from `--rate` are averaged by time of day and session
+//! length, to show when focus tends to go best, and interruptions logged
+//! during work sessions are counted per day and per task. Breaks the
+//! roulette picked an activity for are counted per activity, along with
+//! how many of them ran to the end.
+//!
+//! Sessions moved out by `timeadair history archive` still count towards
+//! the all-time tally and the longest streak, through their rollups.
+//!
+//! `timeadair stats --year` draws the last 52 weeks as a heatmap, a column
+//! per week and a row per weekday, from the daily rollups alone.
+//!
+//! The timer keeps a `RunSummary` of the sessions it has been through too,
+//! counted the same way, to print when it exits.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::archive::Rollups;
+use crate::history::{Interruptions, Outcome, SessionRecord};
+use crate::locale::{self, Text};
+use crate::schedule::Schedule;
+use crate::scheduler::Phase;
+use crate::timer::TimerResult;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tally {
+    pub pomodoros: u32,
+    pub focus: u64,
+    pub interruptions: u32,
+}
+
+impl Tally {
+    pub fn add(&mut self, other: Tally) {
+        self.pomodoros += other.pomodoros;
+        self.focus += other.focus;
+        self.interruptions += other.interruptions;
+    }
+}
+
+// Everything since the timer was started
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunSummary {
+    pub work: Tally,
+    pub breaks: u32,
+}
+
+impl RunSummary {
+    pub fn add(&mut self, phase: Phase, elapsed: u64, result: &TimerResult, interruptions: Interruptions) {
+        if phase == Phase::Work {
+            self.work.add(Tally {
+                pomodoros: (*result == TimerResult::Completed) as u32,
+                focus: elapsed,
+                interruptions: interruptions.total(),
+            });
+        // A reset break is taken again from the start, and counted then
+        } else if *result != TimerResult::Reset {
+            self.breaks += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == RunSummary::default()
+    }
+
+    // `This run: 3 pomodoros, 1h 15m focus, 2 breaks, 1 interruption`
+    pub fn line(&self, text: &Text) -> String {
+        let mut line = format!("{} {}, {}", text.this_run, text.count(self.work.pomodoros, &text.pomodoro), focus_text(self.work.focus, text));
+        let _ = write!(line, ", {}", text.count(self.breaks, &text.break_taken));
+        if self.work.interruptions > 0 {
+            let _ = write!(line, ", {}", text.count(self.work.interruptions, &text.interruption));
+        }
+        line
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Energy {
+    pub total: u32,
+    pub ratings: u32,
+}
+
+impl Energy {
+    fn add(&mut self, rating: u8) {
+        self.total += u32::from(rating);
+        self.ratings += 1;
+    }
+
+    pub fn average(&self) -> f64 {
+        f64::from(self.total) / f64::from(self.ratings.max(1))
+    }
+}
+
+const TIMES_OF_DAY: [&str; 3] = ["Morning", "Afternoon", "Evening"];
+const LENGTHS: [&str; 3] = ["Under 20m", "20-40m", "Over 40m"];
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    // The last seven days and the last four weeks (keyed by their Monday),
+    // oldest first
+    pub days: Vec<(NaiveDate, Tally)>,
+    pub weeks: Vec<(NaiveDate, Tally)>,
+    pub total: Tally,
+    // Consecutive days with at least one pomodoro, up to today or yesterday
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    // All-time totals for each task, most focus first
+    pub tasks: Vec<(String, Tally)>,
+    // Average ratings, leaving out buckets with none
+    pub energy_by_time: Vec<(&'static str, Energy)>,
+    pub energy_by_length: Vec<(&'static str, Energy)>,
+    // Break activities from the roulette, most often picked first
+    pub activities: Vec<(String, Picked)>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Picked {
+    pub times: u32,
+    // Of those, breaks that ran to completion
+    pub taken: u32,
+}
+
+// Only the sessions run with `--profile <name>`
+pub fn in_profile(records: Vec<SessionRecord>, profile: &str) -> Vec<SessionRecord> {
+    records.into_iter().filter(|record| record.profile.as_deref() == Some(profile)).collect()
+}
+
+pub fn summarize<Tz: TimeZone>(records: &[SessionRecord], rollups: &Rollups, schedule: &Schedule, now: DateTime<Tz>) -> Summary {
+    let mut by_day: BTreeMap<NaiveDate, Tally> = BTreeMap::new();
+    let mut by_task: BTreeMap<&str, Tally> = BTreeMap::new();
+    let mut by_time = [Energy::default(); 3];
+    let mut by_length = [Energy::default(); 3];
+    for record in records.iter().filter(|r| r.session == "work") {
+        let Some(started) = now.timezone().timestamp_opt(record.started, 0).single() else {
+            continue;
+        };
+        let tally = Tally {
+            pomodoros: (record.outcome == Outcome::Completed) as u32,
+            focus: record.duration,
+            interruptions: record.interruptions.total(),
+        };
+        by_day.entry(started.date_naive()).or_default().add(tally);
+        if let Some(task) = &record.task {
+            by_task.entry(task).or_default().add(tally);
+        }
+        if let Some(rating) = record.rating {
+            let time = match started.hour() {
+                0..=11 => 0,
+                12..=16 => 1,
+                _ => 2,
+            };
+            let length = match record.duration / 60 {
+                0..20 => 0,
+                20..=40 => 1,
+                _ => 2,
+            };
+            by_time[time].add(rating);
+            by_length[length].add(rating);
+        }
+    }
+    let mut by_activity: BTreeMap<&str, Picked> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.session != "work") {
+        if let Some(activity) = &record.activity {
+            let picked = by_activity.entry(activity).or_default();
+            picked.times += 1;
+            picked.taken += (record.outcome == Outcome::Completed) as u32;
+        }
+    }
+    let mut activities: Vec<(String, Picked)> = by_activity.into_iter().map(|(name, picked)| (name.to_string(), picked)).collect();
+    activities.sort_by_key(|(_, picked)| Reverse(picked.times));
+    let rated = |names: [&'static str; 3], energy: [Energy; 3]| -> Vec<(&'static str, Energy)> {
+        names.into_iter().zip(energy).filter(|(_, energy)| energy.ratings > 0).collect()
+    };
+    let mut tasks: Vec<(String, Tally)> = by_task.into_iter().map(|(task, tally)| (task.to_string(), tally)).collect();
+    tasks.sort_by_key(|(_, tally)| Reverse(tally.focus));
+
+    let today = now.date_naive();
+    let tally_between = |from: NaiveDate, to: NaiveDate| {
+        let mut tally = Tally::default();
+        for (_, day) in by_day.range(from..=to) {
+            tally.add(*day);
+        }
+        tally
+    };
+    let days = (0..7)
+        .rev()
+        .map(|back| today - Days::new(back))
+        .map(|day| (day, tally_between(day, day)))
+        .collect();
+    let monday = today - Days::new(today.weekday().num_days_from_monday().into());
+    let weeks = (0..4)
+        .rev()
+        .map(|back| monday - Days::new(7 * back))
+        .map(|start| (start, tally_between(start, start + Days::new(6))))
+        .collect();
+    let mut total = rollups.total();
+    by_day.values().for_each(|day| total.add(*day));
+
+    // Whether the day reached its goal, or None for a day off
+    let met = |day: NaiveDate| {
+        let goal = schedule.streak_goal(day)?;
+        Some(by_day.get(&day).is_some_and(|tally| tally.pomodoros >= goal))
+    };
+    let first = by_day.keys().next().copied().unwrap_or(today);
+    let mut longest_streak = rollups.longest_streak;
+    let mut streak = 0;
+    for day in first.iter_days().take_while(|day| *day <= today) {
+        match met(day) {
+            Some(true) => {
+                streak += 1;
+                longest_streak = longest_streak.max(streak);
+            }
+            // Today isn't over, so it can't break the streak yet
+            Some(false) if day != today => streak = 0,
+            _ => {}
+        }
+    }
+    let current_streak = streak;
+
+    Summary {
+        days,
+        weeks,
+        total,
+        current_streak,
+        longest_streak,
+        tasks,
+        energy_by_time: rated(TIMES_OF_DAY, by_time),
+        energy_by_length: rated(LENGTHS, by_length),
+        activities,
+    }
+}
+
+pub fn report(summary: &Summary, text: &Text) -> String {
+    let mut out = String::new();
+    let today = summary.days.last().map(|(_, tally)| *tally).unwrap_or_default();
+    let week = summary.weeks.last().map(|(_, tally)| *tally).unwrap_or_default();
+    // The figures line up after the longest label
+    let width = [text.today, text.this_week, text.all_time, text.streak].iter().map(|label| label.chars().count()).max().unwrap_or(0) + 2;
+    let _ = writeln!(out, "{:<width$}{}", text.today, tally_line(today, text));
+    let _ = writeln!(out, "{:<width$}{}", text.this_week, tally_line(week, text));
+    let _ = writeln!(out, "{:<width$}{}", text.all_time, tally_line(summary.total, text));
+    let longest = locale::fill(text.longest, &[&text.count(summary.longest_streak, &text.day)]);
+    let _ = writeln!(out, "{:<width$}{} ({})", text.streak, text.count(summary.current_streak, &text.day), longest);
+
+    let _ = writeln!(out, "\n{}", text.last_days);
+    for (day, tally) in &summary.days {
+        let bar = "#".repeat(tally.pomodoros as usize);
+        let mut line = format!("  {}  {:>3} {}", day.format("%a %d %b"), tally.pomodoros, bar);
+        if tally.interruptions > 0 {
+            let _ = write!(line, " ({})", text.count(tally.interruptions, &text.interruption));
+        }
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    let _ = writeln!(out, "\n{}", text.last_weeks);
+    for (monday, tally) in &summary.weeks {
+        let week_of = locale::fill(text.week_of, &[&monday.format("%d %b")]);
+        let _ = writeln!(out, "  {}  {}", week_of, tally_line(*tally, text));
+    }
+    if !summary.tasks.is_empty() {
+        let _ = writeln!(out, "\n{}", text.by_task);
+        for (task, tally) in &summary.tasks {
+            let _ = writeln!(out, "  {}  {}", task, tally_line(*tally, text));
+        }
+    }
+    if !summary.activities.is_empty() {
+        let _ = writeln!(out, "\n{}", text.by_activity);
+        for (name, picked) in &summary.activities {
+            let _ = writeln!(out, "  {}  {}", name, locale::fill(text.activity_taken, &[&picked.taken, &picked.times]));
+        }
+    }
+    let sections = [
+        (text.energy_by_time, &summary.energy_by_time, TIMES_OF_DAY, text.times_of_day),
+        (text.energy_by_length, &summary.energy_by_length, LENGTHS, text.lengths),
+    ];
+    for (heading, energy, english, names) in sections {
+        if !energy.is_empty() {
+            let _ = writeln!(out, "\n{}", heading);
+            for (name, energy) in energy {
+                let name = english.iter().position(|english| english == name).map_or(*name, |i| names[i]);
+                let _ = writeln!(out, "  {:<10} {:.1} ({})", name, energy.average(), text.count(energy.ratings, &text.rating));
+            }
+        }
+    }
+    out
+}
+
+// The same summary for `timeadair stats --json`, with focus in seconds
+pub fn data(summary: &Summary) -> Value {
+    let tally = |t: &Tally| json!({ "pomodoros": t.pomodoros, "focus": t.focus, "interruptions": t.interruptions });
+    let dated = |tallies: &[(NaiveDate, Tally)]| -> Vec<Value> {
+        tallies
+            .iter()
+            .map(|(date, t)| json!({ "date": date.to_string(), "pomodoros": t.pomodoros, "focus": t.focus, "interruptions": t.interruptions }))
+            .collect()
+    };
+    let energy = |buckets: &[(&str, Energy)]| -> Vec<Value> {
+        buckets.iter().map(|(name, energy)| json!({ "name": name, "average": energy.average(), "ratings": energy.ratings })).collect()
+    };
+    json!({
+        "today": summary.days.last().map(|(_, t)| tally(t)),
+        "this_week": summary.weeks.last().map(|(_, t)| tally(t)),
+        "total": tally(&summary.total),
+        "current_streak": summary.current_streak,
+        "longest_streak": summary.longest_streak,
+        "days": dated(&summary.days),
+        "weeks": dated(&summary.weeks),
+        "tasks": summary
+            .tasks
+            .iter()
+            .map(|(task, t)| json!({ "task": task, "pomodoros": t.pomodoros, "focus": t.focus, "interruptions": t.interruptions }))
+            .collect::<Vec<_>>(),
+        "energy_by_time": energy(&summary.energy_by_time),
+        "energy_by_length": energy(&summary.energy_by_length),
+        "activities": summary
+            .activities
+            .iter()
+            .map(|(name, picked)| json!({ "activity": name, "picked": picked.times, "taken": picked.taken }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+// Shades for none, 1-2, 3-5, 6-8 and 9 or more pomodoros
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+const ASCII_SHADES: [char; 5] = ['.', ':', '+', '*', '#'];
+const YEAR_WEEKS: u64 = 53;
+
+// The Monday the heatmap starts on, so it ends with the current week
+fn year_start(today: NaiveDate) -> NaiveDate {
+    today - Days::new(today.weekday().num_days_from_monday().into()) - Days::new(7 * (YEAR_WEEKS - 1))
+}
+
+pub fn year(days: &BTreeMap<NaiveDate, Tally>, today: NaiveDate, text: &Text, ascii: bool) -> String {
+    let start = year_start(today);
+    let shades = if ascii { ASCII_SHADES } else { SHADES };
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", text.last_year);
+
+    // Each month is named over the week its first day falls in
+    let mut months = " ".repeat(4);
+    for week in 0..YEAR_WEEKS {
+        let monday = start + Days::new(7 * week);
+        let Some(first) = (0..7).map(|day| monday + Days::new(day)).find(|day| day.day() == 1) else { continue };
+        if months.chars().count() <= 4 + week as usize {
+            months = format!("{:<width$}{}", months, first.format("%b"), width = 4 + week as usize);
+        }
+    }
+    let _ = writeln!(out, "{}", months.trim_end());
+    for weekday in 0..7 {
+        let label = if weekday % 2 == 0 { (start + Days::new(weekday)).format("%a").to_string() } else { String::new() };
+        let mut line = format!("{:<4}", label);
+        for week in 0..YEAR_WEEKS {
+            let day = start + Days::new(7 * week + weekday);
+            if day > today {
+                break;
+            }
+            let pomodoros = days.get(&day).map_or(0, |tally| tally.pomodoros);
+            line.push(shades[match pomodoros {
+                0 => 0,
+                1..=2 => 1,
+                3..=5 => 2,
+                6..=8 => 3,
+                _ => 4,
+            }]);
+        }
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = writeln!(out, "\n{}", tally_line(year_total(days, today), text));
+    out
+}
+
+fn year_total(days: &BTreeMap<NaiveDate, Tally>, today: NaiveDate) -> Tally {
+    let mut total = Tally::default();
+    days.range(year_start(today)..=today).for_each(|(_, day)| total.add(*day));
+    total
+}
+
+// The same for `timeadair stats --year --json`, leaving out days with nothing
+pub fn year_data(days: &BTreeMap<NaiveDate, Tally>, today: NaiveDate) -> Value {
+    let total = year_total(days, today);
+    json!({
+        "since": year_start(today).to_string(),
+        "total": { "pomodoros": total.pomodoros, "focus": total.focus, "interruptions": total.interruptions },
+        "days": days
+            .range(year_start(today)..=today)
+            .map(|(date, t)| json!({ "date": date.to_string(), "pomodoros": t.pomodoros, "focus": t.focus, "interruptions": t.interruptions }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+// The running timer's 'v' view
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Today {
+    pub tally: Tally,
+    pub current_streak: u32,
+    // Seconds of focus in each hour since midnight, a session that runs
+    // over the hour counting towards both
+    pub hours: [u64; 24],
+}
+
+pub fn today<Tz: TimeZone>(records: &[SessionRecord], rollups: &Rollups, schedule: &Schedule, now: DateTime<Tz>) -> Today {
+    let summary = summarize(records, rollups, schedule, now.clone());
+    let mut hours = [0; 24];
+    for record in records.iter().filter(|r| r.session == "work") {
+        let Some(started) = now.timezone().timestamp_opt(record.started, 0).single() else {
+            continue;
+        };
+        if started.date_naive() != now.date_naive() {
+            continue;
+        }
+        let mut at = u64::from(started.num_seconds_from_midnight());
+        let mut left = record.duration;
+        while left > 0 && at < 24 * 3600 {
+            let part = left.min(3600 - at % 3600);
+            hours[(at / 3600) as usize] += part;
+            at += part;
+            left -= part;
+        }
+    }
+    Today { tally: summary.days.last().map(|(_, tally)| *tally).unwrap_or_default(), current_streak: summary.current_streak, hours }
+}
+
+// `09:00  ████████  50m` for each hour with any focus in it, the longest
+// bar `width` wide
+pub fn hour_bars(hours: &[u64; 24], width: usize, ascii: bool) -> Vec<String> {
+    let most = hours.iter().copied().max().unwrap_or_default().max(1);
+    let block = if ascii { "#" } else { "█" };
+    let first = hours.iter().position(|seconds| *seconds > 0);
+    let last = hours.iter().rposition(|seconds| *seconds > 0);
+    let (Some(first), Some(last)) = (first, last) else { return Vec::new() };
+    (first..=last)
+        .map(|hour| {
+            let bar = block.repeat((hours[hour] as usize * width).div_ceil(most as usize));
+            format!("{:02}:00  {:<width$}  {}m", hour, bar, hours[hour] / 60, width = width)
+        })
+        .collect()
+}
+
+// Today's tally and streak, then its hours
+pub fn dashboard(today: &Today, text: &Text, ascii: bool) -> Vec<String> {
+    let width = [text.today, text.streak].iter().map(|label| label.chars().count()).max().unwrap_or(0) + 2;
+    let mut lines = vec![
+        format!("{:<width$}{}", text.today, tally_line(today.tally, text)),
+        format!("{:<width$}{}", text.streak, text.count(today.current_streak, &text.day)),
+        String::new(),
+    ];
+    let bars = hour_bars(&today.hours, 30, ascii);
+    if bars.is_empty() {
+        lines.push(text.dashboard_empty.to_string());
+    }
+    lines.extend(bars);
+    lines
+}
+
+// The longest run of consecutive days in `days`, which are in order
+pub fn longest_streak(days: &[NaiveDate]) -> u32 {
+    let mut longest = 0;
+    let mut run = 0;
+    for (i, day) in days.iter().enumerate() {
+        let follows = i > 0 && days[i - 1].succ_opt() == Some(*day);
+        run = if follows { run + 1 } else { 1 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+fn tally_line(tally: Tally, text: &Text) -> String {
+    let mut line = format!("{}, {}", text.count(tally.pomodoros, &text.pomodoro), focus_text(tally.focus, text));
+    if tally.interruptions > 0 {
+        let _ = write!(line, ", {}", text.count(tally.interruptions, &text.interruption));
+    }
+    line
+}
+
+fn focus_text(seconds: u64, text: &Text) -> String {
+    locale::fill(text.focus, &[&format_focus(seconds)])
+}
+
+fn format_focus(seconds: u64) -> String {
+    format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ScheduledDay;
+    use crate::history::Interruptions;
+    use chrono::Utc;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn work(started: i64, duration: u64, outcome: Outcome) -> SessionRecord {
+        SessionRecord {
+            session: "work".to_string(),
+            started,
+            duration,
+            outcome,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn streaks_follow_the_schedule() {
+        // Thursday 2024-05-02 at noon, with weekends off and two a day needed on Mondays
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let today = 1714651200 - 6 * 60 * 60;
+        let day = |goal| ScheduledDay { profile: None, daily_goal: goal };
+        let table = BTreeMap::from([("mon".to_string(), day(Some(2))), ("tue-fri".to_string(), day(None))]);
+        let schedule = Schedule::parse(&table).unwrap();
+        let pomodoro = |days_ago: i64| work(today - days_ago * DAY, 1500, Outcome::Completed);
+        // Fri, Mon twice, Tue, Wed; the weekend between is passed over
+        let records = [pomodoro(6), pomodoro(3), pomodoro(3), pomodoro(2), pomodoro(1)];
+        let summary = summarize(&records, &Rollups::default(), &schedule, now);
+        assert_eq!((summary.current_streak, summary.longest_streak), (4, 4));
+        // One on Monday isn't enough there
+        let summary = summarize(&records[1..], &Rollups::default(), &schedule, now);
+        assert_eq!((summary.current_streak, summary.longest_streak), (3, 3));
+        let summary = summarize(&records[2..], &Rollups::default(), &schedule, now);
+        assert_eq!((summary.current_streak, summary.longest_streak), (2, 2));
+    }
+
+    #[test]
+    fn filters_by_profile() {
+        let deep = |started| SessionRecord { profile: Some("deepwork".to_string()), ..work(started, 5400, Outcome::Completed) };
+        let records = vec![deep(1714651200), work(1714651200 + 3600, 1500, Outcome::Completed), deep(1714651200 + 7200)];
+        let summary = summarize(&in_profile(records, "deepwork"), &Rollups::default(), &Schedule::default(), Utc.timestamp_opt(1714680000, 0).unwrap());
+        assert_eq!(summary.total, Tally { pomodoros: 2, focus: 10800, interruptions: 0 });
+    }
+
+    #[test]
+    fn year_heatmap_shades_each_day() {
+        // Thursday 2024-05-02
+        let today = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+        let tally = |pomodoros| Tally { pomodoros, focus: 1500 * pomodoros as u64, interruptions: 0 };
+        let days = BTreeMap::from([(today, tally(9)), (today - Days::new(1), tally(4)), (today - Days::new(400), tally(2))]);
+        let heatmap = year(&days, today, &locale::ENGLISH, false);
+        let lines: Vec<_> = heatmap.lines().collect();
+        assert_eq!(lines[0], "Last 52 weeks");
+        assert!(lines[1].starts_with("    May") && lines[1].ends_with("May"));
+        assert_eq!(lines[2].chars().count(), 4 + 53);
+        assert!(lines[2].starts_with("Mon ·"));
+        assert!(lines[4].ends_with("·▒"));
+        assert!(lines[5].ends_with('█'));
+        // The rest of this week hasn't happened yet
+        assert_eq!(lines[6].chars().count(), 4 + 52);
+        assert_eq!(lines[10], "13 pomodoros, 5h 25m focus");
+        assert_eq!(year_data(&days, today)["days"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn counts_pomodoros_focus_and_streaks() {
+        // Thursday 2024-05-02 at noon
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let today = 1714651200 - 6 * 60 * 60;
+        let records = [
+            work(today - 9 * DAY, 1500, Outcome::Completed),
+            work(today - 8 * DAY, 1500, Outcome::Completed),
+            work(today - 7 * DAY, 1500, Outcome::Completed),
+            work(today - DAY, 1500, Outcome::Completed),
+            work(today, 1500, Outcome::Completed),
+            work(today + 3600, 600, Outcome::Reset),
+            SessionRecord {
+                session: "break".to_string(),
+                ..work(today, 300, Outcome::Completed)
+            },
+        ];
+        let summary = summarize(&records, &Rollups::default(), &Schedule::default(), now);
+        assert_eq!(summary.days.last(), Some(&(now.date_naive(), Tally { pomodoros: 1, focus: 2100, interruptions: 0 })));
+        assert_eq!(summary.weeks.last().unwrap().1, Tally { pomodoros: 2, focus: 3600, interruptions: 0 });
+        assert_eq!(summary.total, Tally { pomodoros: 5, focus: 8100, interruptions: 0 });
+        assert_eq!(summary.current_streak, 2);
+        assert_eq!(summary.longest_streak, 3);
+        assert!(report(&summary, &locale::ENGLISH).starts_with("Today:      1 pomodoro, 0h 35m focus\n"));
+        let gaelic = report(&summary, &locale::GAELIC);
+        assert!(gaelic.starts_with("An-diugh:            1 pomodoro, 0h 35m de dh'fhòcas\n"), "{}", gaelic);
+        assert!(gaelic.contains("Sreath:              2 latha (as fhaide 3 làithean)\n"), "{}", gaelic);
+        assert!(summary.tasks.is_empty());
+        let data = data(&summary);
+        assert_eq!(data["today"], json!({ "pomodoros": 1, "focus": 2100, "interruptions": 0 }));
+        assert_eq!(data["days"][6]["date"], "2024-05-02");
+        assert_eq!(data["longest_streak"], 3);
+    }
+
+    #[test]
+    fn groups_focus_by_task() {
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let task = |task: &str, duration, outcome| SessionRecord {
+            task: Some(task.to_string()),
+            ..work(1714651200 - 3600, duration, outcome)
+        };
+        let records = [
+            task("report", 1500, Outcome::Completed),
+            task("email", 1500, Outcome::Completed),
+            SessionRecord {
+                interruptions: Interruptions { internal: 2, external: 1 },
+                ..task("email", 900, Outcome::Quit)
+            },
+            work(1714651200 - 60, 1500, Outcome::Completed),
+        ];
+        let summary = summarize(&records, &Rollups::default(), &Schedule::default(), now);
+        assert_eq!(
+            summary.tasks,
+            [
+                ("email".to_string(), Tally { pomodoros: 1, focus: 2400, interruptions: 3 }),
+                ("report".to_string(), Tally { pomodoros: 1, focus: 1500, interruptions: 0 }),
+            ]
+        );
+        let report = report(&summary, &locale::ENGLISH);
+        assert!(report.contains("  Thu 02 May    3 ### (3 interruptions)\n"));
+        assert!(report.ends_with("By task\n  email  1 pomodoro, 0h 40m focus, 3 interruptions\n  report  1 pomodoro, 0h 25m focus\n"));
+    }
+
+    #[test]
+    fn averages_energy_ratings() {
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let rated = |hour: i64, duration, rating| SessionRecord {
+            rating: Some(rating),
+            ..work(1714651200 + (hour - 12) * 3600, duration, Outcome::Completed)
+        };
+        let records = [rated(9, 1500, 4), rated(10, 3000, 2), rated(14, 1500, 5), work(15 * 3600, 1500, Outcome::Completed)];
+        let summary = summarize(&records, &Rollups::default(), &Schedule::default(), now);
+        assert_eq!(
+            summary.energy_by_time,
+            [("Morning", Energy { total: 6, ratings: 2 }), ("Afternoon", Energy { total: 5, ratings: 1 })]
+        );
+        assert_eq!(summary.energy_by_length.len(), 2);
+        assert!(report(&summary, &locale::ENGLISH).ends_with("Energy by session length\n  20-40m     4.5 (2 ratings)\n  Over 40m   2.0 (1 rating)\n"));
+    }
+
+    #[test]
+    fn counts_the_breaks_each_activity_got() {
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let rest = |activity: &str, outcome| SessionRecord {
+            session: "break".to_string(),
+            activity: Some(activity.to_string()),
+            ..work(1714640000, 300, outcome)
+        };
+        let records = [rest("Walk", Outcome::Completed), rest("Water", Outcome::Completed), rest("Walk", Outcome::Skipped)];
+        let summary = summarize(&records, &Rollups::default(), &Schedule::default(), now);
+        assert_eq!(summary.activities[0], ("Walk".to_string(), Picked { times: 2, taken: 1 }));
+        assert!(report(&summary, &locale::ENGLISH).contains("Break activities\n  Walk  taken 1 of 2 times\n  Water  taken 1 of 1 times\n"));
+    }
+
+    #[test]
+    fn today_splits_focus_by_the_hour() {
+        // Thursday 2024-05-02 at noon
+        let now = Utc.timestamp_opt(1714651200, 0).unwrap();
+        let nine = 1714651200 - 3 * 60 * 60;
+        let records = [
+            work(nine + 50 * 60, 1500, Outcome::Completed),
+            work(nine + 2 * 60 * 60, 600, Outcome::Quit),
+            work(nine - DAY, 1500, Outcome::Completed),
+        ];
+        let today = today(&records, &Rollups::default(), &Schedule::default(), now);
+        assert_eq!(today.tally, Tally { pomodoros: 1, focus: 2100, interruptions: 0 });
+        assert_eq!(today.current_streak, 2);
+        assert_eq!((today.hours[9], today.hours[10], today.hours[11]), (600, 900, 600));
+        let bars = hour_bars(&today.hours, 6, true);
+        assert_eq!(bars, ["09:00  ####    10m", "10:00  ######  15m", "11:00  ####    10m"]);
+        assert!(hour_bars(&[0; 24], 6, true).is_empty());
+    }
+
+    #[test]
+    fn run_summary_adds_up_the_sessions() {
+        let mut run = RunSummary::default();
+        assert!(run.is_empty());
+        run.add(Phase::Work, 1500, &TimerResult::Completed, Interruptions { internal: 1, external: 0 });
+        run.add(Phase::Break, 300, &TimerResult::Completed, Interruptions::default());
+        run.add(Phase::Break, 20, &TimerResult::Reset, Interruptions::default());
+        run.add(Phase::Work, 900, &TimerResult::Quit, Interruptions::default());
+        assert_eq!(run.work, Tally { pomodoros: 1, focus: 2400, interruptions: 1 });
+        assert_eq!(run.line(&locale::ENGLISH), "This run: 1 pomodoro, 0h 40m focus, 1 break, 1 interruption");
+    }
+}
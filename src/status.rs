@@ -0,0 +1,80 @@
+//! A small status file describing the running timer, read by the prompt
+//! hooks from `timeadair shell-init`.
+//!
+//! The file holds one `key=value` per line (`pid`, `phase`, `remaining`,
+//! `completed`, and `ended` and `outcome` for how many sessions have ended
+//! and how the last one did) and is replaced atomically on every update.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+use crate::history::Outcome;
+use crate::paths;
+use crate::scheduler::{Phase, State};
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::runtime_dir().map(|dir| dir.join("status"))
+}
+
+// For exit paths that skip destructors, like the Ctrl-C handler
+pub fn remove_default() {
+    if paths::ensure_writable().is_err() {
+        return;
+    }
+    if let Some(path) = default_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub struct StatusFile {
+    path: PathBuf,
+    ended: u32,
+    outcome: Option<Outcome>,
+}
+
+impl StatusFile {
+    pub fn new(path: PathBuf) -> StatusFile {
+        StatusFile { path, ended: 0, outcome: None }
+    }
+
+    // Shows up in the next write, for `timeadair wait`
+    pub fn session_ended(&mut self, outcome: Outcome) {
+        self.ended += 1;
+        self.outcome = Some(outcome);
+    }
+
+    pub fn write(&self, state: &State) -> io::Result<()> {
+        let (phase, remaining) = match state.phase {
+            Phase::Work => ("work", state.timer.format_time()),
+            Phase::Break => ("break", state.timer.format_time()),
+            _ => ("waiting", String::new()),
+        };
+        let contents = format!(
+            "pid={}\nphase={}\nremaining={}\ncompleted={}\nended={}\noutcome={}\n",
+            process::id(),
+            phase,
+            remaining,
+            state.completed_work,
+            self.ended,
+            self.outcome.map_or("", |outcome| outcome.name())
+        );
+        paths::ensure_writable()?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let staging = self.path.with_extension("tmp");
+        fs::write(&staging, contents)?;
+        fs::rename(&staging, &self.path)
+    }
+}
+
+impl Drop for StatusFile {
+    fn drop(&mut self) {
+        if paths::ensure_writable().is_err() {
+            return;
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
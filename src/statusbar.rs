@@ -0,0 +1,214 @@
+//! Status bar output for `--status-format json|plain`.
+//!
+//! One line is printed on stdout every second instead of drawing the full
+//! screen, for waybar or polybar custom modules and tmux status segments.
+//! `json` lines follow waybar's custom module format, e.g.
+//! `{"text":"12:34","tooltip":"Work, 50% done","class":"work","percentage":50,"session":"work","remaining":754}`,
+//! while `plain` lines read `Work 12:34 50%`.
+//!
+//! There are no prompts: sessions are started with `start` on the command
+//! FIFO, which is always set up in this mode, e.g. from a click handler, or
+//! straight away with `--auto`. With `--nudge`, time at the keyboard between
+//! sessions is watched as well, and with `--remind`, time without one.
+
+use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::cli::Options;
+use crate::commands::Command;
+use crate::locale;
+use crate::nudge::{self, Nudge, Nudger};
+use crate::outputs::Outputs;
+use crate::reminder::Reminder;
+use crate::scheduler::{self, Event, Phase, State};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusFormat {
+    Json,
+    Plain,
+}
+
+impl StatusFormat {
+    pub fn parse(format: &str) -> Result<StatusFormat, String> {
+        match format {
+            "json" => Ok(StatusFormat::Json),
+            "plain" => Ok(StatusFormat::Plain),
+            _ => Err("--status-format must be 'json' or 'plain'".to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Status<'a> {
+    text: String,
+    tooltip: String,
+    class: &'static str,
+    percentage: u32,
+    session: &'a str,
+    remaining: u64,
+}
+
+pub fn run(outputs: &mut Outputs, commands: &Receiver<Command>, options: &Options, format: StatusFormat) -> io::Result<()> {
+    drive(outputs, commands, options, &mut |state| print_line(state, format))
+}
+
+// Runs sessions with no prompts, reporting the state every second; shared
+// with the daemon, which keeps the latest report for `ctl status`
+pub fn drive(outputs: &mut Outputs, commands: &Receiver<Command>, options: &Options, report: &mut dyn FnMut(&State) -> io::Result<()>) -> io::Result<()> {
+    let mut state = State::new(options.durations);
+    let mut next_line = Instant::now();
+    let mut nudger = options.nudge.map(|after| Nudger::new(after, options.nudge_idle.unwrap_or(nudge::BREAK)));
+    let mut reminder = options.remind.map(|after| Reminder::new(after, options.working_hours));
+    loop {
+        if Instant::now() >= next_line {
+            report(&state)?;
+            outputs.timers_finished();
+            next_line += Duration::from_secs(1);
+        }
+        let event = match state.phase {
+            Phase::AwaitingWork | Phase::AwaitingBreak if options.auto => Event::Start,
+            Phase::AwaitingWork | Phase::AwaitingBreak => {
+                outputs.waiting(&state);
+                match nudger.as_mut().and_then(Nudger::poll) {
+                    Some(Nudge::Due(active)) => outputs.nudge(active, &locale::fill(options.locale.text().nudge, &[&(active / 60)])),
+                    Some(Nudge::Unavailable) => outputs.warn("nudge", "can't tell when the keyboard was last used; on X11, install xprintidle"),
+                    None => {}
+                }
+                if let Some(waited) = reminder.as_mut().and_then(Reminder::poll) {
+                    outputs.remind(waited, &locale::fill(options.locale.text().reminder, &[&(waited / 60)]));
+                }
+                match commands.recv_timeout(next_line.saturating_duration_since(Instant::now())) {
+                    Ok(Command::Start) => Event::Start,
+                    Ok(Command::Decline) => Event::Decline,
+                    Ok(Command::Shutdown) => Event::Interrupt,
+                    Ok(Command::Quit) | Err(RecvTimeoutError::Disconnected) => Event::Quit,
+                    Ok(Command::AddTimer(name, seconds)) => {
+                        outputs.timers.add(name, seconds);
+                        continue;
+                    }
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => continue,
+                }
+            }
+            Phase::Work | Phase::Break => {
+                state = run_session(state, outputs, commands, report)?;
+                if let Some(nudger) = &mut nudger {
+                    nudger.reset();
+                }
+                if let Some(reminder) = &mut reminder {
+                    reminder.reset();
+                }
+                next_line = Instant::now();
+                continue;
+            }
+            Phase::Finished => break,
+        };
+        state = scheduler::step(state, event);
+        next_line = Instant::now();
+    }
+    Ok(())
+}
+
+fn run_session(mut state: State, outputs: &mut Outputs, commands: &Receiver<Command>, report: &mut dyn FnMut(&State) -> io::Result<()>) -> io::Result<State> {
+    let phase = state.phase;
+    outputs.session_started(&state, None)?;
+
+    let mut next_tick = Instant::now() + Duration::from_secs(1);
+    let result = loop {
+        let wait = next_tick.saturating_duration_since(Instant::now());
+        let event = match commands.recv_timeout(wait) {
+            Ok(Command::Quit) | Err(RecvTimeoutError::Disconnected) => Event::Quit,
+            Ok(Command::AddTimer(name, seconds)) => {
+                outputs.timers.add(name, seconds);
+                continue;
+            }
+            Ok(command) => match command.session_event(state.paused) {
+                Some(event) => event,
+                None => continue,
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                next_tick += Duration::from_secs(1);
+                Event::Tick
+            }
+        };
+        let was_paused = state.paused;
+        state = scheduler::step(state, event);
+        if state.phase != phase {
+            break scheduler::session_result(event);
+        }
+        if state.paused != was_paused {
+            outputs.pause_changed(&state);
+        }
+        outputs.tick(&state)?;
+        outputs.timers_finished();
+        report(&state)?;
+    };
+
+    outputs.session_ended(phase, &state, &result)?;
+    Ok(state)
+}
+
+fn print_line(state: &State, format: StatusFormat) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", line(state, format))?;
+    stdout.flush()
+}
+
+pub fn line(state: &State, format: StatusFormat) -> String {
+    let timer = &state.timer;
+    let session = state.session_name();
+    let waiting = matches!(state.phase, Phase::AwaitingWork | Phase::AwaitingBreak);
+    let class = match state.phase {
+        Phase::AwaitingWork | Phase::AwaitingBreak | Phase::Finished => "idle",
+        _ if state.paused => "paused",
+        Phase::Work => "work",
+        Phase::Break => "break",
+    };
+    let percentage = timer.get_progress() as u32;
+    match format {
+        StatusFormat::Plain if waiting => format!("{} {} (waiting)", session, timer.format_time()),
+        StatusFormat::Plain if state.paused => format!("{} {} {}% (paused)", session, timer.format_time(), percentage),
+        StatusFormat::Plain => format!("{} {} {}%", session, timer.format_time(), percentage),
+        StatusFormat::Json => {
+            let tooltip = if waiting {
+                format!("{} is up next", session)
+            } else if state.paused {
+                format!("{}, {}% done, paused", session, percentage)
+            } else {
+                format!("{}, {}% done", session, percentage)
+            };
+            let status = Status {
+                text: timer.format_time(),
+                tooltip,
+                class,
+                percentage,
+                session: &session.to_lowercase(),
+                remaining: timer.remaining(),
+            };
+            serde_json::to_string(&status).unwrap_or_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::Durations;
+
+    #[test]
+    fn lines_for_each_state() {
+        let mut state = scheduler::step(State::new(Durations::default()), Event::Start);
+        state.timer.elapsed = 750;
+        assert_eq!(line(&state, StatusFormat::Plain), "Work 12:30 50%");
+        assert_eq!(
+            line(&state, StatusFormat::Json),
+            r#"{"text":"12:30","tooltip":"Work, 50% done","class":"work","percentage":50,"session":"work","remaining":750}"#
+        );
+        state = scheduler::step(state, Event::Pause);
+        assert_eq!(line(&state, StatusFormat::Plain), "Work 12:30 50% (paused)");
+        assert!(line(&state, StatusFormat::Json).contains(r#""class":"paused""#));
+        assert_eq!(line(&State::new(Durations::default()), StatusFormat::Plain), "Work 25:00 (waiting)");
+    }
+}
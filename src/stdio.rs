@@ -0,0 +1,160 @@
+//! Headless mode for embedding timeadair in other programs.
+//!
+//! Commands are read from stdin as newline-delimited JSON objects such as
+//! `{"command":"start"}`; the accepted commands are `start` and `decline`
+//! (answering an `awaiting_start` event), `pause`, `resume`, `skip`,
+//! `add-time`, `subtract-time`, `reset` and `quit`. Events are written to stdout in the same JSON Lines format as
+//! `--event-log`, with an additional `tick` event every second and an `outside_working_hours`
+//! event before a work session offered outside `--working-hours`, and a `warning`
+//! event if an optional output such as `--serial` stops working. Closing
+//! stdin quits.
+
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::cli::Options;
+use crate::commands::Command;
+use crate::events::{self, SessionEvent};
+use crate::outputs::Outputs;
+use crate::scheduler::{self, Event, Phase, State};
+
+#[derive(Deserialize)]
+struct Request {
+    command: String,
+}
+
+pub fn read_commands(commands: Sender<Command>) {
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = serde_json::from_str::<Request>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(|request| {
+                    Command::parse(&request.command)
+                        .ok_or_else(|| format!("unknown command '{}'", request.command))
+                });
+            match command {
+                Ok(command) => {
+                    if commands.send(command).is_err() {
+                        return;
+                    }
+                }
+                Err(message) => {
+                    let _ = write_event(&SessionEvent::InvalidCommand { message: &message });
+                }
+            }
+        }
+        let _ = commands.send(Command::Quit);
+    });
+}
+
+pub fn run(outputs: &mut Outputs, commands: &Receiver<Command>, options: &Options) -> io::Result<()> {
+    let mut state = State::new(options.durations);
+    loop {
+        write_warnings(outputs)?;
+        state = match state.phase {
+            Phase::AwaitingWork => {
+                outputs.waiting(&state);
+                if let Some(hours) = options.working_hours.filter(|hours| !hours.contains_now()) {
+                    write_event(&SessionEvent::OutsideWorkingHours {
+                        hours: &hours.to_string(),
+                        strict: hours.strict,
+                    })?;
+                    if hours.strict {
+                        break;
+                    }
+                }
+                scheduler::step(state, await_start("work", commands)?)
+            }
+            Phase::AwaitingBreak => {
+                outputs.waiting(&state);
+                scheduler::step(state, await_start("break", commands)?)
+            }
+            Phase::Work | Phase::Break => run_session(state, outputs, commands)?,
+            Phase::Finished => break,
+        };
+    }
+    write_event(&SessionEvent::Exited)
+}
+
+fn await_start(session: &str, commands: &Receiver<Command>) -> io::Result<Event> {
+    write_event(&SessionEvent::AwaitingStart { session })?;
+    loop {
+        match commands.recv() {
+            Ok(Command::Start) => return Ok(Event::Start),
+            Ok(Command::Decline) => return Ok(Event::Decline),
+            Ok(Command::Shutdown) => return Ok(Event::Interrupt),
+            Ok(Command::Quit) | Err(_) => return Ok(Event::Quit),
+            Ok(_) => {}
+        }
+    }
+}
+
+fn run_session(mut state: State, outputs: &mut Outputs, commands: &Receiver<Command>) -> io::Result<State> {
+    let phase = state.phase;
+    let type_name = phase.name();
+    let session = type_name.to_lowercase();
+    outputs.session_started(&state, None)?;
+    write_event(&SessionEvent::Started { session: &session, duration: state.timer.duration, task: None })?;
+
+    let mut next_tick = Instant::now() + Duration::from_secs(1);
+    let result = loop {
+        let wait = next_tick.saturating_duration_since(Instant::now());
+        let event = match commands.recv_timeout(wait) {
+            Ok(Command::Quit) | Err(RecvTimeoutError::Disconnected) => Event::Quit,
+            Ok(command) => match command.session_event(state.paused) {
+                Some(event) => event,
+                None => continue,
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                next_tick += Duration::from_secs(1);
+                Event::Tick
+            }
+        };
+        let was_paused = state.paused;
+        state = scheduler::step(state, event);
+        if state.phase != phase {
+            break scheduler::session_result(event);
+        }
+        if state.paused != was_paused {
+            outputs.pause_changed(&state);
+            write_event(&events::pause_event(&session, &state))?;
+            continue;
+        }
+        outputs.tick(&state)?;
+        write_event(&SessionEvent::Tick {
+            session: &session,
+            remaining: state.timer.remaining(),
+            progress: state.timer.get_progress() as u32,
+            paused: state.paused,
+        })?;
+        write_warnings(outputs)?;
+    };
+
+    outputs.session_ended(phase, &state, &result)?;
+    write_event(&events::end_event(&session, &state, &result))?;
+    Ok(state)
+}
+
+fn write_warnings(outputs: &mut Outputs) -> io::Result<()> {
+    for warning in outputs.take_warnings() {
+        write_event(&SessionEvent::Warning { subsystem: warning.subsystem, message: &warning.message })?;
+    }
+    Ok(())
+}
+
+fn write_event(event: &SessionEvent) -> io::Result<()> {
+    if let Some(line) = events::to_json_line(event) {
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", line)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! `timeadair stopwatch`, an open-ended count up on the same screen and
+//! keys as the timer: 'p' pauses, 'r' goes back to zero, 'l' marks a lap
+//! and 'q' stops. Nothing is written to the history.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEvent};
+
+use crate::commands::Command;
+use crate::ui::{RawMode, Renderer};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stopwatch {
+    pub elapsed: u64,
+    // The length of each lap, oldest first
+    pub laps: Vec<u64>,
+    pub paused: bool,
+}
+
+impl Stopwatch {
+    pub fn tick(&mut self) {
+        if !self.paused {
+            self.elapsed += 1;
+        }
+    }
+
+    // Returns false once the stopwatch has been stopped
+    pub fn apply(&mut self, command: Command) -> bool {
+        match command {
+            Command::Quit => return false,
+            Command::Reset => {
+                self.elapsed = 0;
+                self.laps.clear();
+            }
+            Command::Pause => self.paused = true,
+            Command::Resume => self.paused = false,
+            Command::TogglePause => self.paused = !self.paused,
+            // Time since the last lap, or since the start
+            Command::Lap => self.laps.push(self.elapsed - self.laps.iter().sum::<u64>()),
+            _ => {}
+        }
+        true
+    }
+
+    // What's left on the screen afterwards
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Stopwatch stopped at {}.", format_elapsed(self.elapsed));
+        if !self.laps.is_empty() {
+            let laps: Vec<_> = self.laps.iter().map(|lap| format_elapsed(*lap)).collect();
+            summary.push_str(&format!(" Laps: {}.", laps.join(", ")));
+        }
+        summary
+    }
+}
+
+// `MM:SS`, with the hours in front once there are any
+pub fn format_elapsed(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+pub fn run(screen: &mut dyn Renderer) -> io::Result<Stopwatch> {
+    let mut stopwatch = Stopwatch::default();
+    let _raw = RawMode::enable()?;
+    screen.show_cursor(false)?;
+    screen.stopwatch(stopwatch.elapsed, &stopwatch.laps, stopwatch.paused)?;
+
+    // Ticks are due on whole seconds since the start, like the timer's
+    let mut next_tick = Instant::now() + Duration::from_secs(1);
+    loop {
+        if event::poll(next_tick.saturating_duration_since(Instant::now()))? {
+            match event::read()? {
+                Event::Key(KeyEvent { code, .. }) => match Command::from_key(code).map(|command| stopwatch.apply(command)) {
+                    Some(false) => break,
+                    Some(true) => {}
+                    None => continue,
+                },
+                Event::Resize(..) => {
+                    screen.redraw()?;
+                    continue;
+                }
+                _ => continue,
+            }
+        } else {
+            next_tick += Duration::from_secs(1);
+            stopwatch.tick();
+        }
+        screen.stopwatch(stopwatch.elapsed, &stopwatch.laps, stopwatch.paused)?;
+    }
+
+    Ok(stopwatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn laps_pauses_and_resets() {
+        let mut stopwatch = Stopwatch::default();
+        (0..70).for_each(|_| stopwatch.tick());
+        assert!(stopwatch.apply(Command::Lap));
+        stopwatch.apply(Command::TogglePause);
+        stopwatch.tick();
+        stopwatch.apply(Command::TogglePause);
+        (0..30).for_each(|_| stopwatch.tick());
+        stopwatch.apply(Command::Lap);
+        assert_eq!(stopwatch.laps, [70, 30]);
+        assert_eq!(stopwatch.summary(), "Stopwatch stopped at 01:40. Laps: 01:10, 00:30.");
+        assert!(!stopwatch.apply(Command::Quit));
+
+        stopwatch.apply(Command::Reset);
+        assert_eq!(stopwatch, Stopwatch::default());
+        assert_eq!(format_elapsed(3725), "1:02:05");
+    }
+}
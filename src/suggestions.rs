@@ -0,0 +1,92 @@
+//! Something to do with a break, shown under the bar while it runs and
+//! changed every 30 seconds. The built-in ones follow the locale, and
+//! `break_suggestions` in the config file replaces them:
+//!
+//! ```toml
+//! break_suggestions = ["Refill the kettle", "Feed the cat"]
+//! ```
+//!
+//! An empty list turns them off. Each break starts one further along the
+//! list than the last, so short breaks don't all open with the same one.
+
+use crate::locale::Locale;
+
+pub const INTERVAL: u64 = 30;
+
+const ENGLISH: [&str; 8] = [
+    "Stand up and stretch",
+    "Drink some water",
+    "Look at something far away for a while",
+    "Take a short walk",
+    "Roll your shoulders and neck",
+    "Take a few slow, deep breaths",
+    "Open a window for some fresh air",
+    "Rest your eyes",
+];
+
+const GAELIC: [&str; 8] = [
+    "Seas suas agus sìn thu fhèin",
+    "Òl beagan uisge",
+    "Coimhead air rudeigin fad' air falbh airson greis",
+    "Gabh cuairt ghoirid",
+    "Gluais do ghuailnean is d' amhach",
+    "Gabh anail mhall dhomhainn no dhà",
+    "Fosgail uinneag airson àile ùr",
+    "Leig fois le do shùilean",
+];
+
+pub fn defaults(locale: Locale) -> Vec<String> {
+    let list = match locale {
+        Locale::English => ENGLISH,
+        Locale::Gaelic => GAELIC,
+    };
+    list.iter().map(|suggestion| suggestion.to_string()).collect()
+}
+
+// What a renderer keeps from one break to the next
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Suggestions {
+    list: Vec<String>,
+    breaks: usize,
+}
+
+impl Suggestions {
+    // For the coming session; empty for anything but a break
+    pub fn set(&mut self, list: &[String]) {
+        if !list.is_empty() {
+            self.breaks += 1;
+        }
+        self.list = list.to_vec();
+    }
+
+    // The one for this far into the break, and its place in the list
+    pub fn at(&self, elapsed: u64) -> Option<(usize, &str)> {
+        if self.list.is_empty() {
+            return None;
+        }
+        let index = (self.breaks.saturating_sub(1) + (elapsed / INTERVAL) as usize) % self.list.len();
+        Some((index, &self.list[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_through_the_list() {
+        let list = vec!["Stretch".to_string(), "Water".to_string(), "Walk".to_string()];
+        let mut suggestions = Suggestions::default();
+        assert_eq!(suggestions.at(0), None);
+        suggestions.set(&list);
+        assert_eq!(suggestions.at(29), Some((0, "Stretch")));
+        assert_eq!(suggestions.at(30), Some((1, "Water")));
+        assert_eq!(suggestions.at(95), Some((0, "Stretch")));
+        suggestions.set(&[]);
+        assert_eq!(suggestions.at(0), None);
+        // The next break opens with the next one
+        suggestions.set(&list);
+        assert_eq!(suggestions.at(0), Some((1, "Water")));
+        assert_eq!(defaults(Locale::Gaelic).len(), ENGLISH.len());
+    }
+}
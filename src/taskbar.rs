@@ -0,0 +1,31 @@
+use std::env;
+use std::io::{self, Write};
+
+// Taskbar progress is reported with the OSC 9;4 sequence understood by
+// Windows Terminal and ConEmu, which forward it to the Win32 taskbar button.
+#[derive(Clone, Copy)]
+pub enum ProgressState {
+    Clear,
+    Normal,
+    Error,
+    Paused,
+}
+
+pub fn supported() -> bool {
+    env::var_os("WT_SESSION").is_some()
+        || env::var("ConEmuANSI").map(|v| v == "ON").unwrap_or(false)
+}
+
+pub fn set_progress(state: ProgressState, percent: u32) -> io::Result<()> {
+    if !supported() {
+        return Ok(());
+    }
+    let code = match state {
+        ProgressState::Clear => 0,
+        ProgressState::Normal => 1,
+        ProgressState::Error => 2,
+        ProgressState::Paused => 4,
+    };
+    print!("\x1b]9;4;{};{}\x07", code, percent.min(100));
+    io::stdout().flush()
+}
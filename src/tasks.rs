@@ -0,0 +1,443 @@
+//! The task list for `timeadair task`, kept in `tasks.json` in the data
+//! directory:
+//!
+//! ```text
+//! timeadair task add "Quarterly report" --due 2024-05-10 --estimate 8
+//! timeadair task list
+//! timeadair task done 1
+//! timeadair task add "Stand-up notes" --repeat daily
+//! timeadair task add "Send the report" --after 1
+//! ```
+//!
+//! A task's pomodoros are the completed work sessions in the history that
+//! have its title as their task. A task can repeat `daily`, `weekly` (from
+//! Monday) or on a cron expression (see [`crate::cron`]). Each time it comes
+//! round it starts over: it's back in the picker at the task prompt, and only
+//! the pomodoros since then count towards it.
+//!
+//! A task added `--after` others waits on them: the picker offers tasks in
+//! an order that puts what they wait on first, and leaves those still
+//! blocked until last. For tasks with a due date and an estimate,
+//! the work prompt warns when the pomodoros left look like more than there
+//! is time for: the daily pace over the last four weeks of history, times
+//! the days up to and including the due date.
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::cron::Cron;
+use crate::history::{Outcome, SessionRecord};
+use crate::paths;
+
+// Days of history the pace is taken from
+const PACE_DAYS: u64 = 28;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    // In pomodoros
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<u32>,
+    #[serde(default)]
+    pub done: bool,
+    // On the board's doing column
+    #[serde(default)]
+    pub doing: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<Repeat>,
+    // When it was last marked done, which for a repeating task only counts
+    // until it comes round again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub done_at: Option<i64>,
+    // Titles of the tasks that come first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+impl Task {
+    pub fn new(title: String) -> Task {
+        Task { title, due: None, estimate: None, done: false, doing: false, repeat: None, done_at: None, after: Vec::new() }
+    }
+
+    pub fn mark_done(&mut self, at: i64) {
+        self.done = self.repeat.is_none();
+        self.doing = false;
+        self.done_at = Some(at);
+    }
+
+    pub fn is_done<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> bool {
+        match self.round_start(now) {
+            Some(start) => self.done_at.and_then(|at| local(now, at)).is_some_and(|at| at >= start),
+            None => self.done,
+        }
+    }
+
+    // When a repeating task last came round; None for one-off tasks, and for
+    // cron expressions that haven't fired in years
+    fn round_start<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<NaiveDateTime> {
+        let now = now.naive_local();
+        match self.repeat.as_ref()? {
+            Repeat::Daily => Some(now.date().and_time(NaiveTime::MIN)),
+            Repeat::Weekly => Some((now.date() - Days::new(now.weekday().num_days_from_monday().into())).and_time(NaiveTime::MIN)),
+            Repeat::Cron(cron) => cron.last(now),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Repeat {
+    Daily,
+    Weekly,
+    Cron(Cron),
+}
+
+impl Repeat {
+    pub fn parse(text: &str) -> Result<Repeat, String> {
+        match text {
+            "daily" => Ok(Repeat::Daily),
+            "weekly" => Ok(Repeat::Weekly),
+            _ => Cron::parse(text).map(Repeat::Cron),
+        }
+    }
+}
+
+impl fmt::Display for Repeat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Repeat::Daily => f.write_str("daily"),
+            Repeat::Weekly => f.write_str("weekly"),
+            Repeat::Cron(cron) => write!(f, "'{}'", cron),
+        }
+    }
+}
+
+impl TryFrom<String> for Repeat {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Repeat, String> {
+        Repeat::parse(&text)
+    }
+}
+
+impl From<Repeat> for String {
+    fn from(repeat: Repeat) -> String {
+        match repeat {
+            Repeat::Cron(cron) => cron.to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+// Tasks are numbered from 1, in the order they were added
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaskCommand {
+    // With the numbers of the tasks it comes after
+    Add(Task, Vec<usize>),
+    List,
+    Done(usize),
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join("tasks.json"))
+}
+
+pub struct TaskStore {
+    path: PathBuf,
+}
+
+impl TaskStore {
+    pub fn new(path: PathBuf) -> TaskStore {
+        TaskStore { path }
+    }
+
+    pub fn load(&self) -> io::Result<Vec<Task>> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Replaced atomically, so a crash never leaves half a list behind
+    pub fn save(&self, tasks: &[Task]) -> io::Result<()> {
+        paths::ensure_writable()?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let staging = self.path.with_extension("tmp");
+        fs::write(&staging, serde_json::to_string_pretty(tasks)? + "\n")?;
+        fs::rename(&staging, &self.path)
+    }
+}
+
+// Completed pomodoros towards a task, since it last came round if it repeats
+pub fn pomodoros<Tz: TimeZone>(task: &Task, records: &[SessionRecord], now: &DateTime<Tz>) -> u32 {
+    let start = task.round_start(now);
+    records
+        .iter()
+        .filter(|r| r.session == "work" && r.outcome == Outcome::Completed)
+        .filter(|r| r.task.as_deref() == Some(task.title.as_str()))
+        .filter(|r| start.is_none_or(|start| local(now, r.started).is_some_and(|started| started >= start)))
+        .count() as u32
+}
+
+// The task prompt's picker: the tasks still to do, numbered as in the list,
+// with those still waiting on others at the end
+pub fn open<Tz: TimeZone>(tasks: &[Task], records: &[SessionRecord], now: &DateTime<Tz>) -> Vec<(usize, String)> {
+    let (ready, blocked): (Vec<_>, Vec<_>) = ordered(tasks)
+        .into_iter()
+        .filter(|i| !tasks[*i].is_done(now))
+        .partition(|i| blockers(&tasks[*i], tasks, now).is_empty());
+    ready
+        .into_iter()
+        .chain(blocked.iter().copied())
+        .map(|i| {
+            let mut line = format!("{}. {}", i + 1, describe(&tasks[i], pomodoros(&tasks[i], records, now)));
+            if blocked.contains(&i) {
+                line.push_str(" [blocked]");
+            }
+            (i + 1, line)
+        })
+        .collect()
+}
+
+// The tasks this one comes after that aren't done yet
+pub fn blockers<'a, Tz: TimeZone>(task: &'a Task, tasks: &[Task], now: &DateTime<Tz>) -> Vec<&'a str> {
+    task.after
+        .iter()
+        .filter(|title| tasks.iter().any(|other| other.title == **title && !other.is_done(now)))
+        .map(String::as_str)
+        .collect()
+}
+
+// Indices with every task after the ones it waits on, and otherwise in the
+// order they were added; a cycle is broken at its earliest task
+fn ordered(tasks: &[Task]) -> Vec<usize> {
+    let mut placed = vec![false; tasks.len()];
+    let mut order = Vec::with_capacity(tasks.len());
+    while order.len() < tasks.len() {
+        let waits = |i: usize| {
+            tasks[i].after.iter().any(|title| (0..tasks.len()).any(|j| j != i && !placed[j] && tasks[j].title == *title))
+        };
+        let unplaced = || (0..tasks.len()).filter(|i| !placed[*i]);
+        let next = unplaced().find(|i| !waits(*i)).or_else(|| unplaced().next()).unwrap_or_default();
+        placed[next] = true;
+        order.push(next);
+    }
+    order
+}
+
+fn local<Tz: TimeZone>(now: &DateTime<Tz>, timestamp: i64) -> Option<NaiveDateTime> {
+    now.timezone().timestamp_opt(timestamp, 0).single().map(|at| at.naive_local())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtRisk {
+    pub title: String,
+    pub due: NaiveDate,
+    pub left: u32,
+    // Pomodoros there's likely time for before the due date
+    pub available: u32,
+}
+
+pub fn at_risk<Tz: TimeZone>(tasks: &[Task], records: &[SessionRecord], now: DateTime<Tz>) -> Vec<AtRisk> {
+    let today = now.date_naive();
+    let days: Vec<NaiveDate> = records
+        .iter()
+        .filter(|r| r.session == "work" && r.outcome == Outcome::Completed)
+        .filter_map(|r| now.timezone().timestamp_opt(r.started, 0).single())
+        .map(|started| started.date_naive())
+        .collect();
+    let window = today - Days::new(PACE_DAYS - 1);
+    let recent = days.iter().filter(|day| **day >= window).count();
+    // No pace to go on yet
+    let Some(first) = days.iter().min().filter(|_| recent > 0) else { return Vec::new() };
+    let pace = recent as f64 / ((today - *first.max(&window)).num_days() + 1) as f64;
+
+    tasks
+        .iter()
+        .filter(|task| !task.is_done(&now))
+        .filter_map(|task| {
+            let (due, estimate) = (task.due?, task.estimate?);
+            let left = estimate.saturating_sub(pomodoros(task, records, &now));
+            let days_left = ((due - today).num_days() + 1).max(0);
+            let available = (pace * days_left as f64) as u32;
+            (left > available).then(|| AtRisk { title: task.title.clone(), due, left, available })
+        })
+        .collect()
+}
+
+// For the work prompt, about the first task at risk
+pub fn warning(at_risk: &[AtRisk], today: NaiveDate) -> Option<String> {
+    let first = at_risk.first()?;
+    let due = first.due.format("%a %d %b");
+    let mut warning = if first.due < today {
+        format!("Heads up: '{}' was due {}, with {} to go.", first.title, due, pomodoro_count(first.left))
+    } else {
+        format!(
+            "Heads up: '{}' needs {} by {}, and your pace allows about {}.",
+            first.title,
+            pomodoro_count(first.left),
+            due,
+            first.available
+        )
+    };
+    if at_risk.len() > 1 {
+        let _ = write!(warning, " {} more at risk, see 'timeadair task list'.", at_risk.len() - 1);
+    }
+    Some(warning)
+}
+
+// `timeadair task list`, one numbered line per task
+pub fn list<Tz: TimeZone>(tasks: &[Task], records: &[SessionRecord], now: &DateTime<Tz>) -> String {
+    if tasks.is_empty() {
+        return "No tasks yet; add one with 'timeadair task add <title>'.\n".to_string();
+    }
+    let mut out = String::new();
+    for (number, task) in tasks.iter().enumerate() {
+        let mut line = format!("{}. {}", number + 1, describe(task, pomodoros(task, records, now)));
+        if task.is_done(now) {
+            line.push_str(" [done]");
+        }
+        let _ = writeln!(out, "{}", line);
+    }
+    out
+}
+
+fn describe(task: &Task, count: u32) -> String {
+    let mut line = format!("{}  ", task.title);
+    match task.estimate {
+        Some(estimate) => line.push_str(&format!("{}/{} pomodoros", count, estimate)),
+        None => line.push_str(&pomodoro_count(count)),
+    }
+    if let Some(due) = task.due {
+        let _ = write!(line, ", due {}", due.format("%a %d %b"));
+    }
+    if let Some(repeat) = &task.repeat {
+        let _ = write!(line, ", repeats {}", repeat);
+    }
+    if !task.after.is_empty() {
+        let titles: Vec<_> = task.after.iter().map(|title| format!("'{}'", title)).collect();
+        let _ = write!(line, ", after {}", titles.join(" and "));
+    }
+    line
+}
+
+fn pomodoro_count(count: u32) -> String {
+    if count == 1 {
+        "1 pomodoro".to_string()
+    } else {
+        format!("{} pomodoros", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    const DAY: i64 = 24 * 60 * 60;
+    // Thursday 2024-05-02 at noon
+    const NOW: i64 = 1714651200;
+
+    fn pomodoro(started: i64, task: Option<&str>) -> SessionRecord {
+        SessionRecord {
+            session: "work".to_string(),
+            started,
+            duration: 1500,
+            outcome: Outcome::Completed,
+            task: task.map(str::to_string),
+            planned: Some(1500),
+            ..Default::default()
+        }
+    }
+
+    fn task(title: &str, due: &str, estimate: u32) -> Task {
+        Task { due: due.parse().ok(), estimate: Some(estimate), ..Task::new(title.to_string()) }
+    }
+
+    #[test]
+    fn warns_when_the_pace_falls_short() {
+        let now = Utc.timestamp_opt(NOW, 0).unwrap();
+        // Two pomodoros a day for the last four days
+        let records: Vec<_> = (0..8).map(|i| pomodoro(NOW - (i / 2) * DAY, (i == 0).then_some("report"))).collect();
+        let tasks = [task("report", "2024-05-04", 8), task("slides", "2024-05-04", 6), task("email", "2024-04-30", 1)];
+        let risks = at_risk(&tasks, &records, now);
+        assert_eq!(risks[0], AtRisk { title: "report".to_string(), due: tasks[0].due.unwrap(), left: 7, available: 6 });
+        assert_eq!(risks.iter().map(|risk| risk.title.as_str()).collect::<Vec<_>>(), ["report", "email"]);
+        assert_eq!(
+            warning(&risks, now.date_naive()).unwrap(),
+            "Heads up: 'report' needs 7 pomodoros by Sat 04 May, and your pace allows about 6. 1 more at risk, see 'timeadair task list'."
+        );
+        assert_eq!(
+            warning(&risks[1..], now.date_naive()).unwrap(),
+            "Heads up: 'email' was due Tue 30 Apr, with 1 pomodoro to go."
+        );
+        // Nothing to judge a pace by
+        assert!(at_risk(&tasks, &[], now).is_empty());
+    }
+
+    #[test]
+    fn lists_and_stores_tasks() {
+        let records = [pomodoro(NOW, Some("report")), pomodoro(NOW, Some("report"))];
+        let tasks = [task("report", "2024-05-04", 8), Task { done: true, ..Task::new("email".to_string()) }];
+        let now = Utc.timestamp_opt(NOW, 0).unwrap();
+        assert_eq!(list(&tasks, &records, &now), "1. report  2/8 pomodoros, due Sat 04 May\n2. email  0 pomodoros [done]\n");
+
+        let path = std::env::temp_dir().join(format!("timeadair-tasks-{}.json", std::process::id()));
+        let store = TaskStore::new(path.clone());
+        store.save(&tasks).unwrap();
+        assert_eq!(store.load().unwrap(), tasks);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn repeating_tasks_come_round_again() {
+        let now = Utc.timestamp_opt(NOW, 0).unwrap();
+        let mut standup = Task { repeat: Some(Repeat::Daily), estimate: Some(1), ..Task::new("standup".to_string()) };
+        // Done yesterday, so it's due again today with nothing counted yet
+        let records = [pomodoro(NOW - DAY, Some("standup"))];
+        standup.mark_done(NOW - DAY);
+        assert!(!standup.done && !standup.is_done(&now));
+        assert_eq!(pomodoros(&standup, &records, &now), 0);
+        assert_eq!(open(&[standup.clone()], &records, &now), [(1, "1. standup  0/1 pomodoros, repeats daily".to_string())]);
+        standup.mark_done(NOW);
+        assert!(standup.is_done(&now));
+        assert!(open(&[standup.clone()], &records, &now).is_empty());
+
+        // Weekly rounds start on Monday, 2024-04-29
+        let review = Task { repeat: Some(Repeat::Weekly), ..Task::new("review".to_string()) };
+        let records = [pomodoro(NOW - 4 * DAY, Some("review")), pomodoro(NOW - 2 * DAY, Some("review"))];
+        assert_eq!(pomodoros(&review, &records, &now), 1);
+        let inbox = Task { repeat: Some(Repeat::parse("0 9 * * 1-5").unwrap()), ..Task::new("inbox".to_string()) };
+        assert_eq!(list(&[review, inbox.clone()], &[], &now), "1. review  0 pomodoros, repeats weekly\n2. inbox  0 pomodoros, repeats '0 9 * * 1-5'\n");
+        assert_eq!(serde_json::to_string(&inbox.repeat).unwrap(), r#""0 9 * * 1-5""#);
+        assert!(Repeat::parse("hourly").is_err());
+    }
+
+    #[test]
+    fn blocked_tasks_wait_their_turn() {
+        let now = Utc.timestamp_opt(NOW, 0).unwrap();
+        let after = |title: &str, after: &[&str]| Task { after: after.iter().map(|t| t.to_string()).collect(), ..Task::new(title.to_string()) };
+        let mut tasks = vec![after("send", &["review"]), after("review", &["draft"]), after("draft", &[]), after("tidy", &[])];
+        assert_eq!(ordered(&tasks), [2, 1, 0, 3]);
+        let picked: Vec<_> = open(&tasks, &[], &now).into_iter().map(|(number, _)| number).collect();
+        assert_eq!(picked, [3, 4, 2, 1]);
+        assert_eq!(open(&tasks, &[], &now)[2].1, "2. review  0 pomodoros, after 'draft' [blocked]");
+
+        tasks[2].mark_done(NOW);
+        assert_eq!(blockers(&tasks[1], &tasks, &now), Vec::<&str>::new());
+        assert_eq!(blockers(&tasks[0], &tasks, &now), ["review"]);
+        // A cycle still puts every task somewhere
+        tasks[1].after.push("send".to_string());
+        assert_eq!(ordered(&tasks), [2, 3, 0, 1]);
+    }
+}
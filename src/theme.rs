@@ -0,0 +1,65 @@
+//! Colour themes for the full-screen display, chosen with `--theme` or
+//! `theme` in the config file.
+//!
+//! `NO_COLOR` (see <https://no-color.org>) or `--no-color` turn colour off
+//! altogether, and the frame falls back to plain ASCII: `+-|` borders and a
+//! `[####----]` bar.
+
+use ratatui::style::Color;
+
+pub const THEMES: [&str; 3] = ["default", "light", "high-contrast"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub bar_fill: Color,
+    pub bar_empty: Color,
+    // The header title
+    pub accent: Color,
+    pub paused: Color,
+    pub color: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            bar_fill: Color::Green,
+            bar_empty: Color::DarkGray,
+            accent: Color::Reset,
+            paused: Color::Yellow,
+            color: true,
+        }
+    }
+}
+
+impl Theme {
+    pub fn parse(name: &str) -> Result<Theme, String> {
+        match name {
+            "default" => Ok(Theme::default()),
+            // Dark grey and yellow wash out on a white background
+            "light" => Ok(Theme {
+                bar_fill: Color::Blue,
+                bar_empty: Color::Gray,
+                accent: Color::Blue,
+                paused: Color::Magenta,
+                color: true,
+            }),
+            "high-contrast" => Ok(Theme {
+                bar_fill: Color::White,
+                bar_empty: Color::Black,
+                accent: Color::LightYellow,
+                paused: Color::LightYellow,
+                color: true,
+            }),
+            _ => Err(format!("--theme must be one of {}", THEMES.join(", "))),
+        }
+    }
+
+    pub fn without_color(self) -> Theme {
+        Theme { color: false, ..self }
+    }
+}
+
+// NO_COLOR counts when set to anything but an empty string
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+// How many seconds a shot-clock countdown covers, for frontends that don't
+// pick their own
+pub const FINAL_SECONDS: u64 = 10;
+// The final minute, which can be shown to a tenth of a second
+pub const TENTHS_FROM: u64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timer {
+    pub duration: u64,
+    pub elapsed: u64,
+}
+
+impl Timer {
+    pub fn new(duration: u64) -> Timer {
+        Timer {
+            duration,
+            elapsed: 0,
+        }
+    }
+
+    pub fn get_progress(&self) -> f32 {
+        self.elapsed as f32 / self.duration as f32 * 100.0
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    pub fn format_time(&self) -> String {
+        let mut time = String::new();
+        self.write_time(&mut time);
+        time
+    }
+
+    // Where the timer is in its last `last` seconds, `fraction` of the way
+    // through the current one, for frontends that redraw between ticks;
+    // None before then and once the time is up
+    pub fn countdown(&self, fraction: f32, last: u64) -> Option<Countdown> {
+        let seconds = self.remaining();
+        (seconds > 0 && seconds <= last).then_some(Countdown { seconds, lit: fraction < 0.5 })
+    }
+
+    // Appends the time left to a string kept from one tick to the next
+    pub fn write_time(&self, out: &mut String) {
+        let remaining = self.remaining();
+        let _ = write!(out, "{:02}:{:02}", remaining / 60, remaining % 60);
+    }
+
+    // Like `write_time`, `fraction` of the way into the current second and
+    // with tenths after the seconds
+    pub fn write_tenths(&self, fraction: f32, out: &mut String) {
+        let tenths = (self.remaining() * 10).saturating_sub((fraction.clamp(0.0, 1.0) * 10.0).round() as u64);
+        let _ = write!(out, "{:02}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10);
+    }
+}
+
+// One of the last few seconds: the count shown, and whether it's in the
+// first half of the second, for a display that flashes on and off
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Countdown {
+    pub seconds: u64,
+    pub lit: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimerResult {
+    Completed,
+    // Ended early on purpose, moving on to the next session
+    Skipped,
+    Quit,
+    Reset,
+    // Cut short by a shutdown signal
+    Interrupted,
+}
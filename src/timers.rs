@@ -0,0 +1,116 @@
+//! Named countdowns alongside the sessions, e.g. for the tea: press 't'
+//! while a session runs and type `tea 4`, or send `add-timer tea 4` to the
+//! command FIFO, or `timeadair ctl add-timer tea 4` to the daemon. The
+//! length is in minutes unless it ends in `s`, as in `eggs 90s`.
+//!
+//! They go by the clock rather than the session, so they carry on through
+//! pauses, prompts and into the next session. Each is a bar under the
+//! session's while it runs and rings on its own when it's done; one that
+//! runs out during a prompt rings once the next session starts.
+
+use std::time::{Duration, Instant};
+
+use crate::timer::Timer;
+
+// How long a timer that's done stays up, full, after ringing, which says
+// which one it was
+const DONE_SHOWN: Duration = Duration::from_secs(30);
+
+struct SideTimer {
+    name: String,
+    length: u64,
+    started: Instant,
+    rung: Option<Instant>,
+}
+
+impl SideTimer {
+    fn timer(&self, now: Instant) -> Timer {
+        let elapsed = now.saturating_duration_since(self.started).as_secs().min(self.length);
+        Timer { duration: self.length, elapsed }
+    }
+
+    fn ends(&self) -> Instant {
+        self.started + Duration::from_secs(self.length)
+    }
+}
+
+#[derive(Default)]
+pub struct Timers {
+    timers: Vec<SideTimer>,
+}
+
+impl Timers {
+    pub fn add(&mut self, name: String, seconds: u64) {
+        self.timers.push(SideTimer { name, length: seconds, started: Instant::now(), rung: None });
+    }
+
+    // The names of those that have run out since last asked
+    pub fn finished(&mut self) -> Vec<String> {
+        self.finished_at(Instant::now())
+    }
+
+    fn finished_at(&mut self, now: Instant) -> Vec<String> {
+        self.timers.retain(|timer| timer.rung.is_none_or(|rung| now < rung + DONE_SHOWN));
+        let mut names = Vec::new();
+        for timer in self.timers.iter_mut().filter(|timer| timer.rung.is_none() && now >= timer.ends()) {
+            timer.rung = Some(now);
+            names.push(timer.name.clone());
+        }
+        names
+    }
+
+    // Each with how far it's got, in the order they were added
+    pub fn shown(&self) -> Vec<(String, Timer)> {
+        self.shown_at(Instant::now())
+    }
+
+    fn shown_at(&self, now: Instant) -> Vec<(String, Timer)> {
+        self.timers.iter().map(|timer| (timer.name.clone(), timer.timer(now))).collect()
+    }
+}
+
+// `tea 4`, `tea: 4 min` or `eggs 90s`, to a name and seconds
+pub fn parse(text: &str) -> Result<(String, u64), String> {
+    let error = || format!("a timer is a name and minutes, e.g. 'tea 4', not '{}'", text.trim());
+    let text = text.trim();
+    let text = text.strip_suffix("min").or_else(|| text.strip_suffix('m')).unwrap_or(text).trim_end();
+    let (name, length) = text.rsplit_once(char::is_whitespace).ok_or_else(error)?;
+    let name = name.trim().trim_end_matches(':').trim();
+    let seconds = match length.strip_suffix('s') {
+        Some(seconds) => seconds.parse::<u64>().map_err(|_| error())?,
+        None => length.parse::<u64>().map_err(|_| error())? * 60,
+    };
+    if name.is_empty() || seconds == 0 {
+        return Err(error());
+    }
+    Ok((name.to_string(), seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Command;
+
+    #[test]
+    fn timers_ring_once_and_stay_up_a_while() {
+        assert_eq!(parse("tea 4"), Ok(("tea".to_string(), 240)));
+        assert_eq!(parse(" tea: 4 min"), Ok(("tea".to_string(), 240)));
+        assert_eq!(parse("boiled eggs 90s"), Ok(("boiled eggs".to_string(), 90)));
+        assert!(parse("tea").is_err());
+        assert!(parse("tea 0").is_err());
+        assert_eq!(Command::parse("add-timer Tea 4"), Some(Command::AddTimer("Tea".to_string(), 240)));
+
+        let mut timers = Timers::default();
+        timers.add("tea".to_string(), 240);
+        timers.add("eggs".to_string(), 90);
+        let start = timers.timers[0].started;
+        let at = |seconds| start + Duration::from_secs(seconds);
+        assert_eq!(timers.shown_at(at(60))[0].1, Timer { duration: 240, elapsed: 60 });
+        assert!(timers.finished_at(at(60)).is_empty());
+        assert_eq!(timers.finished_at(at(100)), ["eggs"]);
+        assert!(timers.finished_at(at(101)).is_empty());
+        assert_eq!(timers.shown_at(at(101))[1].1.remaining(), 0);
+        assert_eq!(timers.finished_at(at(300)), ["tea"]);
+        assert_eq!(timers.shown_at(at(300)).len(), 1);
+    }
+}
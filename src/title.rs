@@ -0,0 +1,103 @@
+//! The running session in the terminal's title (`--title`), e.g.
+//! `🍅 12:30 Work`, set with the OSC 2 sequence that xterm, iTerm2, kitty,
+//! GNOME Terminal and tmux all understand, so it shows in the tab; the
+//! Windows console's own call is used where it doesn't. The title from
+//! before is saved on the terminal's title stack first and put back when
+//! the timer exits, except on Windows, which has no such stack.
+//!
+//! With `--tmux` the same text also goes into the `@timeadair` option of
+//! the tmux server, to be shown with e.g.
+//! `set -g status-right '#{@timeadair}'` in `.tmux.conf`.
+
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crossterm::{execute, terminal::SetTitle};
+
+use crate::stopwatch::format_elapsed;
+
+const OPTION: &str = "@timeadair";
+const IDLE: &str = "Tìmeadair";
+
+pub struct TitleOutput {
+    tmux: bool,
+    // Left alone when nothing has changed since the last tick
+    last: String,
+}
+
+impl TitleOutput {
+    pub fn new(tmux: bool) -> io::Result<TitleOutput> {
+        if !cfg!(windows) {
+            let mut out = io::stdout();
+            write!(out, "\x1b[22;0t")?;
+            out.flush()?;
+        }
+        Ok(TitleOutput { tmux: tmux && env::var_os("TMUX").is_some(), last: String::new() })
+    }
+
+    pub fn show(&mut self, phase: &str, remaining: u64, paused: bool) -> io::Result<()> {
+        self.set(&title(phase, remaining, paused))
+    }
+
+    // Between sessions
+    pub fn idle(&mut self) -> io::Result<()> {
+        self.set(IDLE)
+    }
+
+    fn set(&mut self, text: &str) -> io::Result<()> {
+        if text == self.last {
+            return Ok(());
+        }
+        self.last = text.to_string();
+        execute!(io::stdout(), SetTitle(text))?;
+        if self.tmux {
+            let value = if text == IDLE { "" } else { text };
+            run_tmux(&["set-option", "-gq", OPTION, value, ";", "refresh-client", "-S"])?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TitleOutput {
+    fn drop(&mut self) {
+        restore(self.tmux);
+    }
+}
+
+// Puts back the title from before, for exit paths that can't drop the
+// output, like the Ctrl-C handler
+pub fn restore(tmux: bool) {
+    if !cfg!(windows) {
+        let mut out = io::stdout();
+        let _ = write!(out, "\x1b[23;0t");
+        let _ = out.flush();
+    }
+    if tmux && env::var_os("TMUX").is_some() {
+        let _ = run_tmux(&["set-option", "-gqu", OPTION, ";", "refresh-client", "-S"]);
+    }
+}
+
+fn run_tmux(args: &[&str]) -> io::Result<()> {
+    let status = Command::new("tmux").args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+    if !status.success() {
+        return Err(io::Error::other("tmux set-option failed"));
+    }
+    Ok(())
+}
+
+// `🍅 12:30 Work`, or `⏸ 12:30 Work` while paused
+pub fn title(phase: &str, remaining: u64, paused: bool) -> String {
+    format!("{} {} {}", if paused { "⏸" } else { "🍅" }, format_elapsed(remaining), phase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_shows_time_left_and_session() {
+        assert_eq!(title("Work", 750, false), "🍅 12:30 Work");
+        assert_eq!(title("Long Break", 60, true), "⏸ 01:00 Long Break");
+    }
+}
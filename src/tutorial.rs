@@ -0,0 +1,301 @@
+//! `timeadair tutorial`: a guided run through one pomodoro and a break, in
+//! about three minutes. The sessions are the usual lengths on a simulated
+//! clock, like the demo's: a second of session time per frame while there's
+//! a key to try, a minute per frame once there isn't. Every step waits for
+//! the user to do what it describes, and `q` leaves at any point.
+//!
+//! Nothing is written to the history or the task list. The sessions are
+//! kept in memory instead, so the last step can show what `timeadair stats`
+//! would make of them.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use chrono::Local;
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+
+use crate::archive::Rollups;
+use crate::commands::Command;
+use crate::history::{Interruption, Interruptions, SessionRecord};
+use crate::locale::ENGLISH;
+use crate::schedule::Schedule;
+use crate::scheduler::{self, Durations, Phase, State};
+use crate::stats;
+use crate::ui::{self, RawMode, Renderer, Screen};
+
+const TICKS_PER_FRAME: u32 = 60;
+// Slower than the timer's own frames, so the first minute can be watched
+const FRAME: Duration = Duration::from_millis(250);
+
+// Made up, so the task prompt has something to pick from
+const TASKS: [&str; 2] = ["Draft the report", "Answer email"];
+
+const WELCOME: &str = "Welcome to Tìmeadair. This tour runs one work session and a break, \
+sped up so it takes about three minutes, and saves nothing.\n\nPress any key to begin, or q to leave at any time.";
+const START: &str = "Every session starts with a question. Type y and press Enter.";
+const START_AGAIN: &str = "The tour needs a session to show you. Type y and press Enter.";
+const SPED_UP: &str = "That's the keys. Watch the rest of it go by, or press s to skip to the end.";
+const TAKE_BREAK: &str = "Work's done, so a break is offered. y takes it, n goes straight back to work.";
+const BREAK: &str = "A break, sped up like the rest. s skips it.";
+const LEFT: &str = "Left the tour early. 'timeadair tutorial' starts it again.";
+const DONE: &str = "That's the tour.";
+const FINISH: &str = "That's the tour. Press any key to finish.";
+
+// What the tour is waiting for
+#[derive(Clone, Copy)]
+pub enum Wait {
+    // Any key, to go on from an explanation
+    Key,
+    // A line typed at a prompt
+    Line,
+    // A key, if one comes before the next frame of a running session
+    Frame,
+}
+
+pub enum Input {
+    Key(KeyCode),
+    Line(String),
+    // The frame went by without a key
+    Idle,
+    Resize,
+}
+
+// The keys taught during the work session, in order
+#[derive(Clone, Copy)]
+enum Lesson {
+    Pause,
+    Resume,
+    Adjust,
+    Interruption,
+}
+
+const LESSONS: [Lesson; 4] = [Lesson::Pause, Lesson::Resume, Lesson::Adjust, Lesson::Interruption];
+
+impl Lesson {
+    fn text(self) -> &'static str {
+        match self {
+            Lesson::Pause => "The clock runs until the session is done. Press p to pause it.",
+            Lesson::Resume => "Paused, and the time is kept. Press p again to carry on.",
+            Lesson::Adjust => "Press + to give the session another minute, or - to take one off.",
+            Lesson::Interruption => "Been pulled away? Press i to note it, or I when it was someone else.",
+        }
+    }
+
+    fn tried(self, key: KeyCode) -> bool {
+        let command = Command::from_key(key);
+        match self {
+            Lesson::Pause | Lesson::Resume => command == Some(Command::TogglePause),
+            Lesson::Adjust => matches!(command, Some(Command::AddTime | Command::SubtractTime)),
+            Lesson::Interruption => matches!(key, KeyCode::Char('i' | 'I')),
+        }
+    }
+}
+
+pub fn run() -> io::Result<()> {
+    let mut screen = Screen::stdout()?;
+    let mut raw = None;
+    let played = play(&mut screen, |wait| {
+        // Prompts are typed with the terminal's own line editing
+        let want_raw = !matches!(wait, Wait::Line);
+        if want_raw != raw.is_some() {
+            raw = want_raw.then(RawMode::enable).transpose()?;
+        }
+        read(wait)
+    });
+    drop(raw);
+    // Left in the scrollback once the screen is gone
+    match played? {
+        Some(records) => screen.message(&finale(DONE, &records)),
+        None => screen.message(LEFT),
+    }
+}
+
+fn read(wait: Wait) -> io::Result<Input> {
+    if let Wait::Line = wait {
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        return Ok(Input::Line(input));
+    }
+    if let Wait::Frame = wait {
+        if !event::poll(FRAME)? {
+            return Ok(Input::Idle);
+        }
+    }
+    loop {
+        match event::read()? {
+            Event::Key(KeyEvent { code, .. }) => return Ok(Input::Key(code)),
+            Event::Resize(..) => return Ok(Input::Resize),
+            _ => {}
+        }
+    }
+}
+
+// The whole tour, with the sessions it ran, or None if the user left
+pub fn play<W: Write, F: FnMut(Wait) -> io::Result<Input>>(screen: &mut Screen<W>, mut input: F) -> io::Result<Option<Vec<SessionRecord>>> {
+    let mut records = Vec::new();
+    let mut state = State::new(Durations::default());
+    screen.message(WELCOME)?;
+    if left(&wait(screen, &mut input, Wait::Key)?) {
+        return Ok(None);
+    }
+
+    let mut notice = START;
+    loop {
+        screen.set_cycle(state.cycle_position());
+        screen.prompt("work", Some(notice))?;
+        let Input::Line(answer) = wait(screen, &mut input, Wait::Line)? else { continue };
+        if ui::prompt_accepted(&answer) {
+            break;
+        }
+        notice = START_AGAIN;
+    }
+    let choices: Vec<_> = TASKS.iter().enumerate().map(|(i, task)| format!("{}. {}", i + 1, task)).collect();
+    screen.task_prompt(&choices)?;
+    let Input::Line(answer) = wait(screen, &mut input, Wait::Line)? else { return Ok(None) };
+    let answer = answer.trim();
+    let task = match answer.parse::<usize>() {
+        Ok(number @ 1..=2) => Some(TASKS[number - 1].to_string()),
+        _ => (!answer.is_empty()).then(|| answer.to_string()),
+    };
+
+    state = scheduler::step(state, scheduler::Event::Start);
+    let Some((ended, record)) = session(screen, &mut input, state, &LESSONS)? else { return Ok(None) };
+    records.push(SessionRecord { task, ..record });
+    state = ended;
+
+    screen.set_cycle(state.cycle_position());
+    screen.prompt(&state.session_name().to_lowercase(), Some(TAKE_BREAK))?;
+    let Input::Line(answer) = wait(screen, &mut input, Wait::Line)? else { return Ok(None) };
+    if ui::prompt_accepted(&answer) {
+        state = scheduler::step(state, scheduler::Event::Start);
+        let Some((_, record)) = session(screen, &mut input, state, &[])? else { return Ok(None) };
+        records.push(record);
+    }
+
+    // The report can run past the bottom of a small terminal, so the way
+    // out comes first
+    screen.message(&finale(FINISH, &records))?;
+    wait(screen, &mut input, Wait::Key)?;
+    Ok(Some(records))
+}
+
+// Waits for something other than a resize, redrawing for those
+fn wait<W: Write, F: FnMut(Wait) -> io::Result<Input>>(screen: &mut Screen<W>, input: &mut F, what: Wait) -> io::Result<Input> {
+    loop {
+        match input(what)? {
+            Input::Resize => screen.redraw()?,
+            input => return Ok(input),
+        }
+    }
+}
+
+fn left(input: &Input) -> bool {
+    matches!(input, Input::Key(KeyCode::Char('q' | 'Q') | KeyCode::Esc))
+}
+
+// Runs a started session until it ends, slowly until each lesson has been
+// tried; None if the user left the tour instead
+fn session<W: Write, F: FnMut(Wait) -> io::Result<Input>>(
+    screen: &mut Screen<W>,
+    input: &mut F,
+    mut state: State,
+    lessons: &[Lesson],
+) -> io::Result<Option<(State, SessionRecord)>> {
+    let phase = state.phase;
+    let mut lessons = lessons.iter().copied().peekable();
+    let mut interruptions = Interruptions::default();
+    screen.set_interruptions(interruptions);
+    screen.show_cursor(false)?;
+    let result = 'session: loop {
+        let message = match lessons.peek() {
+            Some(lesson) => lesson.text(),
+            None if phase == Phase::Work => SPED_UP,
+            None => BREAK,
+        };
+        screen.progress(&state.timer, message, state.paused)?;
+        let (event, ticks) = match wait(screen, input, Wait::Frame)? {
+            key if left(&key) => return Ok(None),
+            Input::Key(code) => match lessons.peek() {
+                Some(Lesson::Interruption) if Lesson::Interruption.tried(code) => {
+                    interruptions.add(if code == KeyCode::Char('i') { Interruption::Internal } else { Interruption::External });
+                    screen.set_interruptions(interruptions);
+                    lessons.next();
+                    continue;
+                }
+                Some(lesson) if lesson.tried(code) => {
+                    lessons.next();
+                    (Command::from_key(code).and_then(|command| command.session_event(state.paused)), 0)
+                }
+                None if Command::from_key(code) == Some(Command::Skip) => (Some(scheduler::Event::Skip), 0),
+                // Anything else waits for the key being taught
+                _ => continue,
+            },
+            _ => (None, if lessons.peek().is_some() { 1 } else { TICKS_PER_FRAME }),
+        };
+        let ticks = std::iter::repeat_n(scheduler::Event::Tick, ticks as usize);
+        for event in event.into_iter().chain(ticks) {
+            state = scheduler::step(state, event);
+            if state.phase != phase {
+                break 'session scheduler::session_result(event);
+            }
+        }
+    };
+    screen.session_end(&result)?;
+    let record = SessionRecord {
+        session: phase.name().to_lowercase(),
+        started: Local::now().timestamp(),
+        duration: state.timer.elapsed,
+        paused: state.paused_time,
+        outcome: (&result).into(),
+        planned: Some(state.timer.duration),
+        interruptions,
+        ..Default::default()
+    };
+    Ok(Some((state, record)))
+}
+
+fn finale(first: &str, records: &[SessionRecord]) -> String {
+    let summary = stats::summarize(records, &Rollups::default(), &Schedule::default(), Local::now());
+    format!(
+        "{} Run 'timeadair' for real sessions, 'timeadair task add <title>' to fill the task list, \
+         and 'timeadair --help' for the rest.\n\nHere's what 'timeadair stats' would make of it:\n\n{}",
+        first,
+        stats::report(&summary, &ENGLISH).trim_end()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Outcome;
+
+    #[test]
+    fn tour_waits_for_each_key() {
+        let mut script = vec![
+            Input::Key(KeyCode::Enter),
+            Input::Line("n\n".to_string()),
+            Input::Line("y\n".to_string()),
+            Input::Line("1\n".to_string()),
+            // Not what's being taught, so it's ignored
+            Input::Key(KeyCode::Char('s')),
+            Input::Key(KeyCode::Char('p')),
+            Input::Idle,
+            Input::Key(KeyCode::Char(' ')),
+            Input::Key(KeyCode::Char('+')),
+            Input::Key(KeyCode::Char('I')),
+        ];
+        script.extend(std::iter::repeat_with(|| Input::Idle).take(26));
+        script.extend([Input::Line("y\n".to_string()), Input::Key(KeyCode::Char('s')), Input::Key(KeyCode::Enter)]);
+        let mut script = script.into_iter();
+        let mut screen = Screen::fixed(io::sink(), 80, 24).unwrap();
+        let records = play(&mut screen, |_| Ok(script.next().expect("tour wanted more input"))).unwrap().unwrap();
+        assert_eq!(script.len(), 0);
+
+        assert_eq!(records.len(), 2);
+        let work = &records[0];
+        assert_eq!((work.outcome, work.task.as_deref()), (Outcome::Completed, Some(TASKS[0])));
+        assert_eq!((work.duration, work.paused, work.interruptions.external), (26 * 60, 1, 1));
+        assert_eq!((records[1].session.as_str(), records[1].outcome), ("break", Outcome::Skipped));
+        assert!(matches!(screen.view(), ui::View::Message(text) if text.starts_with(FINISH)));
+    }
+}
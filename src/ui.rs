@@ -0,0 +1,1514 @@
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal, Stdout, Write};
+use std::iter;
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    cursor, execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Margin, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Clear, Gauge, Paragraph, Row, Table, Wrap},
+    Frame, Terminal, TerminalOptions, Viewport,
+};
+
+use crate::art::{self, Art};
+use crate::digits;
+use crate::history::Interruptions;
+use crate::locale::{self, Locale, Text};
+use crate::outputs::Integration;
+use crate::scheduler::{Phase, Planned};
+use crate::stats::{self, Today};
+use crate::stopwatch;
+use crate::suggestions::Suggestions;
+use crate::theme::Theme;
+use crate::timer::{self, Timer, TimerResult};
+
+const FLASHES: usize = 2;
+const FLASH_TIME: Duration = Duration::from_millis(120);
+
+// Rows the progress view needs to show everything, header included
+const FULL_HEIGHT: u16 = 11;
+
+// What a running session can show in place of its progress
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pane {
+    Integrations,
+    Dashboard,
+}
+
+// What the interactive frontend shows, independent of how it gets drawn
+pub trait Renderer {
+    fn prompt(&mut self, session_type: &str, notice: Option<&str>) -> io::Result<()>;
+    // Echoes what was typed at a prompt, or the key that answered it
+    fn answer(&mut self, text: &str) -> io::Result<()>;
+    // Asks what the coming work session is for, offering the open tasks
+    fn task_prompt(&mut self, choices: &[String]) -> io::Result<()>;
+    fn progress(&mut self, timer: &Timer, message: &str, paused: bool) -> io::Result<()>;
+    // Switches the time left between a line of text and block numerals
+    fn toggle_big_digits(&mut self) -> io::Result<()>;
+    // How far into the next second a running session is, from 0 to 1, for
+    // a bar that moves in between ticks
+    fn between_ticks(&mut self, fraction: f32) -> io::Result<()>;
+    // `timeadair stopwatch`, with the length of each lap so far
+    fn stopwatch(&mut self, elapsed: u64, laps: &[u64], paused: bool) -> io::Result<()>;
+    // The task board, with the selected column and row
+    fn board(&mut self, columns: &[Vec<String>; 3], selected: (usize, usize), search: Option<&str>) -> io::Result<()>;
+    // Asks whether a task that has had all its estimated pomodoros is done
+    fn done_prompt(&mut self, task: &str, pomodoros: u32) -> io::Result<()>;
+    // Offers to end a work session before a meeting that starts during it
+    fn shorten_prompt(&mut self, minutes: u64, meeting: &str) -> io::Result<()>;
+    // Under --strict, asks for the phrase that gives up on a work session
+    fn abandon_prompt(&mut self) -> io::Result<()>;
+    // Offers to pick up a session the last run never finished
+    fn resume_prompt(&mut self, summary: &str) -> io::Result<()>;
+    // Asks for a 1-5 energy rating once a work session is done
+    fn rating_prompt(&mut self) -> io::Result<()>;
+    // Then for a line on what it got done
+    fn note_prompt(&mut self) -> io::Result<()>;
+    // Asks for a side timer's name and length, see `timers`, again with
+    // what was wrong with the last answer
+    fn timer_prompt(&mut self, error: Option<&str>) -> io::Result<()>;
+    // A side timer ran out
+    fn timer_done(&mut self, name: &str) -> io::Result<()>;
+    fn session_end(&mut self, result: &TimerResult) -> io::Result<()>;
+    // Before the next session starts by itself, `length` seconds long
+    fn countdown(&mut self, session_type: &str, length: u64, seconds: u64) -> io::Result<()>;
+    // A break activity the roulette is passing, or the one it `landed` on
+    fn roulette(&mut self, activity: &str, landed: bool) -> io::Result<()>;
+    // One pre-session checklist item, numbered from 1
+    fn checklist_item(&mut self, item: &str, number: usize, total: usize) -> io::Result<()>;
+    fn message(&mut self, text: &str) -> io::Result<()>;
+    // The status panel: every output and how its deliveries are going
+    fn integrations(&mut self, integrations: &[Integration]) -> io::Result<()>;
+    // Today so far from the history, with focus by the hour
+    fn dashboard(&mut self, today: &Today) -> io::Result<()>;
+    // Something failed; `hint` says what the user can do about it
+    fn error(&mut self, message: &str, hint: Option<&str>) -> io::Result<()>;
+    fn show_cursor(&mut self, visible: bool) -> io::Result<()>;
+    // Position within the long-break cycle, for frontends with a header
+    fn set_cycle(&mut self, cycle: Option<(u32, u32)>);
+    // Pomodoros done today and the daily goal
+    fn set_goal(&mut self, goal: Option<(u32, u32)>);
+    // Logged so far in the running session, shown with its progress
+    fn set_interruptions(&mut self, interruptions: Interruptions);
+    // The sessions a plan has after the current one, soonest first
+    fn set_upcoming(&mut self, upcoming: &[Planned]);
+    // Side timers, each shown with its name under the session's progress
+    fn set_timers(&mut self, timers: &[(String, Timer)]);
+    // Things to do on the coming break, shown in turn; empty for work
+    fn set_suggestions(&mut self, suggestions: &[String]);
+    // The session running or coming up next, for a header that follows it
+    fn set_phase(&mut self, phase: Phase);
+    // Draws the current view again, e.g. after the terminal was resized
+    fn redraw(&mut self) -> io::Result<()>;
+    // An optional subsystem failed and the timer carries on without it
+    fn warning(&mut self, subsystem: &'static str, message: &str) -> io::Result<()>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum View {
+    Blank,
+    // Choices are listed between the notice and the question
+    Prompt { question: String, notice: Option<String>, choices: Vec<String>, input: String },
+    // `label` goes on the bar and `counts` tallies the interruptions, empty
+    // when there are none; `big` shows the time in block numerals,
+    // `suggestion` is something to do with a break and `dim` is the off
+    // half of a second in a flashing final countdown; `timers` are the
+    // side timers' bars, labelled and from 0 to 1
+    Progress {
+        progress: f32,
+        time: String,
+        label: String,
+        message: String,
+        paused: bool,
+        counts: String,
+        upcoming: Option<String>,
+        suggestion: Option<String>,
+        big: bool,
+        dim: bool,
+        timers: Vec<(String, f64)>,
+    },
+    // Laps newest first
+    Stopwatch { time: String, laps: Vec<String>, paused: bool },
+    Checklist { item: String, number: usize, total: usize },
+    Board { columns: [Vec<String>; 3], selected: (usize, usize), search: Option<String> },
+    Message(String),
+    // Drawn over whatever was showing when the countdown started
+    Countdown { text: String, below: Box<View> },
+    // Name, last delivery, queue depth and last error for each integration
+    Integrations(Vec<[String; 4]>),
+    // Today's tally, streak and hourly bars, a line each
+    Dashboard(Vec<String>),
+    Error { message: String, hint: Option<String> },
+}
+
+// The parts of the header and footer that only change now and then,
+// formatted when they do rather than on every frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Chrome {
+    // ` (2/4)`
+    cycle: String,
+    // `  3/8 today`
+    goal: String,
+    goal_reached: bool,
+    // ` ⚠ serial unavailable`
+    warning: String,
+    // Drawn in place of the title, with the title on the border instead
+    art: Option<Art>,
+}
+
+impl Chrome {
+    pub fn new(cycle: Option<(u32, u32)>, goal: Option<(u32, u32)>, warnings: &[&str], theme: &Theme, text: &Text) -> Chrome {
+        let mut chrome = Chrome::default();
+        if let Some((position, length)) = cycle {
+            chrome.cycle = format!(" ({}/{})", position, length);
+        }
+        if let Some((done, goal)) = goal {
+            chrome.goal = format!("  {}", locale::fill(text.goal_today, &[&done, &goal]));
+            chrome.goal_reached = done >= goal;
+        }
+        if !warnings.is_empty() {
+            chrome.warning = format!("{} {} unavailable", warning_sign(theme), warnings.join(", "));
+        }
+        chrome
+    }
+}
+
+// The off half of a flashing second
+fn dimmed(theme: &Theme) -> Style {
+    Style::new().fg(theme.bar_empty).add_modifier(Modifier::DIM)
+}
+
+fn warning_sign(theme: &Theme) -> &'static str {
+    if theme.color {
+        " ⚠"
+    } else {
+        " !"
+    }
+}
+
+// The full-screen layout, drawn with ratatui on the alternate screen
+pub struct Screen<W: Write> {
+    terminal: Terminal<CrosstermBackend<W>>,
+    view: View,
+    cycle: Option<(u32, u32)>,
+    goal: Option<(u32, u32)>,
+    interruptions: Interruptions,
+    upcoming: Option<String>,
+    timers: Vec<(String, f64)>,
+    suggestions: Suggestions,
+    // Failed subsystems for the footer, with the full message for exit
+    warnings: Vec<(&'static str, String)>,
+    chrome: Chrome,
+    // Flash the screen on completion instead of ringing the bell
+    visual_bell: bool,
+    // Move the bar between ticks, from the last timer shown
+    smooth: bool,
+    timer: Option<Timer>,
+    big: bool,
+    // Flash the time in the last few seconds
+    flash: bool,
+    // Show tenths of a second in the final minute
+    tenths: bool,
+    // Header art for work and for breaks, and which the header shows
+    work_art: Option<Art>,
+    break_art: Option<Art>,
+    phase: Phase,
+    theme: Theme,
+    text: &'static Text,
+    alternate: bool,
+}
+
+impl Screen<Stdout> {
+    pub fn stdout() -> io::Result<Screen<Stdout>> {
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let mut screen = Screen::with_terminal(Terminal::new(CrosstermBackend::new(stdout))?);
+        screen.alternate = true;
+        Ok(screen)
+    }
+}
+
+impl<W: Write> Screen<W> {
+    // A screen of a fixed size that never asks the terminal how big it is,
+    // so the demo renders the same frames everywhere
+    pub fn fixed(out: W, width: u16, height: u16) -> io::Result<Screen<W>> {
+        let options = TerminalOptions {
+            viewport: Viewport::Fixed(Rect::new(0, 0, width, height)),
+        };
+        Ok(Screen::with_terminal(Terminal::with_options(CrosstermBackend::new(out), options)?))
+    }
+
+    fn with_terminal(terminal: Terminal<CrosstermBackend<W>>) -> Screen<W> {
+        Screen {
+            terminal,
+            view: View::Blank,
+            cycle: None,
+            goal: None,
+            interruptions: Interruptions::default(),
+            upcoming: None,
+            timers: Vec::new(),
+            suggestions: Suggestions::default(),
+            warnings: Vec::new(),
+            chrome: Chrome::default(),
+            visual_bell: false,
+            smooth: true,
+            timer: None,
+            big: false,
+            flash: false,
+            tenths: false,
+            work_art: None,
+            break_art: None,
+            phase: Phase::AwaitingWork,
+            theme: Theme::default(),
+            text: Locale::default().text(),
+            alternate: false,
+        }
+    }
+
+    pub fn with_visual_bell(mut self, visual_bell: bool) -> Screen<W> {
+        self.visual_bell = visual_bell;
+        self
+    }
+
+    pub fn with_smooth_bar(mut self, smooth: bool) -> Screen<W> {
+        self.smooth = smooth;
+        self
+    }
+
+    pub fn with_big_digits(mut self, big: bool) -> Screen<W> {
+        self.big = big;
+        self
+    }
+
+    pub fn with_flashing_countdown(mut self, flash: bool) -> Screen<W> {
+        self.flash = flash;
+        self
+    }
+
+    pub fn with_tenths(mut self, tenths: bool) -> Screen<W> {
+        self.tenths = tenths;
+        self
+    }
+
+    pub fn with_art(mut self, work: Option<Art>, rest: Option<Art>) -> Screen<W> {
+        self.work_art = work;
+        self.break_art = rest;
+        self.update_chrome();
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Screen<W> {
+        self.theme = theme;
+        self.update_chrome();
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Screen<W> {
+        self.text = locale.text();
+        self.update_chrome();
+        self
+    }
+
+    fn update_chrome(&mut self) {
+        let warnings: Vec<_> = self.warnings.iter().map(|(subsystem, _)| *subsystem).collect();
+        self.chrome = Chrome::new(self.cycle, self.goal, &warnings, &self.theme, self.text);
+        self.chrome.art = match self.phase {
+            Phase::Work | Phase::AwaitingWork => self.work_art.clone(),
+            Phase::Break | Phase::AwaitingBreak => self.break_art.clone(),
+            Phase::Finished => None,
+        };
+    }
+
+    fn show(&mut self, view: View) -> io::Result<()> {
+        self.leave_prompt()?;
+        self.view = view;
+        self.draw()
+    }
+
+    // Input typed at a prompt is echoed by the terminal behind ratatui's
+    // back, so start from a clean screen once the prompt is answered
+    fn leave_prompt(&mut self) -> io::Result<()> {
+        if matches!(self.view, View::Prompt { .. }) {
+            self.terminal.clear()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        self.draw_with(Style::new())
+    }
+
+    // `highlight` is laid over the whole frame, for the visual bell
+    fn draw_with(&mut self, highlight: Style) -> io::Result<()> {
+        let (view, chrome, theme, text) = (&self.view, &self.chrome, &self.theme, self.text);
+        self.terminal.draw(|frame| {
+            render(frame, view, chrome, theme, text);
+            let area = frame.area();
+            frame.buffer_mut().set_style(area, highlight);
+        })?;
+        Ok(())
+    }
+
+    fn flash(&mut self) -> io::Result<()> {
+        let highlight = if self.theme.color {
+            Style::new().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::new().reversed()
+        };
+        for _ in 0..FLASHES {
+            self.draw_with(highlight)?;
+            thread::sleep(FLASH_TIME);
+            self.draw()?;
+            thread::sleep(FLASH_TIME);
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for Screen<W> {
+    fn drop(&mut self) {
+        if self.alternate {
+            let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen, cursor::Show);
+            // Leave the last word (usually the farewell) in the scrollback
+            if let View::Message(text) = &self.view {
+                let _ = writeln!(self.terminal.backend_mut(), "{}", text);
+            }
+            for (_, message) in &self.warnings {
+                let _ = writeln!(self.terminal.backend_mut(), "Warning: {}", message);
+            }
+        }
+    }
+}
+
+impl<W: Write> Renderer for Screen<W> {
+    fn prompt(&mut self, session_type: &str, notice: Option<&str>) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: prompt_text(self.text, session_type),
+            notice: notice.map(str::to_string),
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn answer(&mut self, text: &str) -> io::Result<()> {
+        if let View::Prompt { input, .. } = &mut self.view {
+            *input = text.to_string();
+        }
+        self.draw()
+    }
+
+    fn task_prompt(&mut self, choices: &[String]) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: if choices.is_empty() { self.text.task_prompt } else { self.text.task_picker_prompt }.to_string(),
+            notice: None,
+            choices: choices.to_vec(),
+            input: String::new(),
+        })
+    }
+
+    fn board(&mut self, columns: &[Vec<String>; 3], selected: (usize, usize), search: Option<&str>) -> io::Result<()> {
+        self.show(View::Board { columns: columns.clone(), selected, search: search.map(str::to_string) })
+    }
+
+    fn done_prompt(&mut self, task: &str, pomodoros: u32) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: done_prompt_text(self.text, task, pomodoros),
+            notice: None,
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn shorten_prompt(&mut self, minutes: u64, meeting: &str) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: locale::fill(self.text.shorten_prompt, &[&minutes, &meeting]),
+            notice: None,
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn abandon_prompt(&mut self) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: locale::fill(self.text.abandon_prompt, &[&self.text.abandon_phrase]),
+            notice: None,
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn resume_prompt(&mut self, summary: &str) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: self.text.resume_prompt.to_string(),
+            notice: Some(summary.to_string()),
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn rating_prompt(&mut self) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: self.text.rating_prompt.to_string(),
+            notice: None,
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn note_prompt(&mut self) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: self.text.note_prompt.to_string(),
+            notice: None,
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    fn timer_prompt(&mut self, error: Option<&str>) -> io::Result<()> {
+        self.show(View::Prompt {
+            question: self.text.timer_prompt.to_string(),
+            notice: error.map(str::to_string),
+            choices: Vec::new(),
+            input: String::new(),
+        })
+    }
+
+    // The timer's bar is full for a while after, which says which one
+    fn timer_done(&mut self, _name: &str) -> io::Result<()> {
+        if self.visual_bell {
+            return self.flash();
+        }
+        let out = self.terminal.backend_mut();
+        out.write_all(b"\x07")?;
+        out.flush()
+    }
+
+    // Called every tick, so the last frame's strings are written over
+    // rather than made anew
+    fn progress(&mut self, timer: &Timer, message: &str, paused: bool) -> io::Result<()> {
+        if !matches!(self.view, View::Progress { .. }) {
+            self.leave_prompt()?;
+            self.view = View::Progress {
+                progress: 0.0,
+                time: String::new(),
+                label: String::new(),
+                message: String::new(),
+                paused,
+                counts: String::new(),
+                upcoming: None,
+                suggestion: None,
+                big: self.big,
+                dim: false,
+                timers: Vec::new(),
+            };
+        }
+        let View::Progress { progress, time, label, message: shown, paused: was_paused, counts, upcoming, suggestion, dim, timers, .. } =
+            &mut self.view
+        else {
+            unreachable!()
+        };
+        *progress = timer.get_progress();
+        self.timer = Some(*timer);
+        time.clear();
+        timer.write_time(time);
+        label.clear();
+        let _ = write!(label, "{}% {}", *progress as u32, time);
+        if self.tenths && (1..=timer::TENTHS_FROM).contains(&timer.remaining()) {
+            time.clear();
+            timer.write_tenths(0.0, time);
+        }
+        if paused {
+            label.push(' ');
+            label.push_str(self.text.paused_label);
+        }
+        shown.clear();
+        shown.push_str(message);
+        *was_paused = paused;
+        // Each second of a countdown starts lit
+        *dim = false;
+        counts.clear();
+        if !self.interruptions.is_empty() {
+            locale::fill_into(counts, self.text.interruptions, &[&self.interruptions.internal, &self.interruptions.external]);
+        }
+        upcoming.clone_from(&self.upcoming);
+        timers.clone_from(&self.timers);
+        match (self.suggestions.at(timer.elapsed), suggestion.as_mut()) {
+            (Some((_, text)), Some(shown)) => {
+                shown.clear();
+                shown.push_str(text);
+            }
+            (current, _) => *suggestion = current.map(|(_, text)| text.to_string()),
+        }
+        self.draw()
+    }
+
+    fn toggle_big_digits(&mut self) -> io::Result<()> {
+        self.big = !self.big;
+        if let View::Progress { big, .. } = &mut self.view {
+            *big = self.big;
+        }
+        self.draw()
+    }
+
+    // Only the bar moves, the time in a flashing countdown and the tenths
+    // in the final minute; otherwise the time and percentage wait for the
+    // tick, so nothing is drawn between ticks with the smooth bar off
+    fn between_ticks(&mut self, fraction: f32) -> io::Result<()> {
+        let (Some(timer), View::Progress { progress, time, paused: false, dim, .. }) = (self.timer, &mut self.view) else { return Ok(()) };
+        let flashing = if self.flash { timer.countdown(fraction, timer::FINAL_SECONDS) } else { None };
+        let flashed = *dim != flashing.is_some_and(|countdown| !countdown.lit);
+        *dim ^= flashed;
+        let tenths = self.tenths && (1..=timer::TENTHS_FROM).contains(&timer.remaining());
+        if tenths {
+            time.clear();
+            timer.write_tenths(fraction, time);
+        }
+        if !self.smooth || timer.duration == 0 {
+            return if flashed || tenths { self.draw() } else { Ok(()) };
+        }
+        *progress = ((timer.elapsed as f32 + fraction.clamp(0.0, 1.0)) / timer.duration as f32 * 100.0).min(100.0);
+        self.draw()
+    }
+
+    fn stopwatch(&mut self, elapsed: u64, laps: &[u64], paused: bool) -> io::Result<()> {
+        let lap = |(i, lap): (usize, &u64)| locale::fill(self.text.lap, &[&(i + 1), &stopwatch::format_elapsed(*lap)]);
+        self.show(View::Stopwatch {
+            time: stopwatch::format_elapsed(elapsed),
+            laps: laps.iter().enumerate().rev().map(lap).collect(),
+            paused,
+        })
+    }
+
+    fn session_end(&mut self, result: &TimerResult) -> io::Result<()> {
+        match result {
+            TimerResult::Quit | TimerResult::Interrupted => self.show(View::Message(self.text.farewell.to_string())),
+            TimerResult::Reset => self.show(View::Message(self.text.timer_reset.to_string())),
+            // The next prompt follows straight away
+            TimerResult::Skipped => Ok(()),
+            // The bell is rung by its notifier, see `notify`
+            TimerResult::Completed if self.visual_bell => self.flash(),
+            TimerResult::Completed => Ok(()),
+        }
+    }
+
+    fn countdown(&mut self, session_type: &str, length: u64, seconds: u64) -> io::Result<()> {
+        let below = match mem::replace(&mut self.view, View::Blank) {
+            View::Countdown { below, .. } => below,
+            // A prompt's echoed input would be cleared away underneath
+            View::Prompt { .. } => Box::new(View::Blank),
+            view => Box::new(view),
+        };
+        self.show(View::Countdown {
+            text: countdown_text(self.text, session_type, length, seconds),
+            below,
+        })
+    }
+
+    fn checklist_item(&mut self, item: &str, number: usize, total: usize) -> io::Result<()> {
+        self.show(View::Checklist {
+            item: item.to_string(),
+            number,
+            total,
+        })
+    }
+
+    fn roulette(&mut self, activity: &str, landed: bool) -> io::Result<()> {
+        let template = if landed { self.text.roulette_landed } else { self.text.roulette_spin };
+        self.show(View::Message(locale::fill(template, &[&activity])))
+    }
+
+    fn message(&mut self, text: &str) -> io::Result<()> {
+        self.show(View::Message(text.to_string()))
+    }
+
+    fn integrations(&mut self, integrations: &[Integration]) -> io::Result<()> {
+        self.show(View::Integrations(integration_rows(integrations, Instant::now())))
+    }
+
+    fn dashboard(&mut self, today: &Today) -> io::Result<()> {
+        self.show(View::Dashboard(stats::dashboard(today, self.text, !self.theme.color)))
+    }
+
+    fn error(&mut self, message: &str, hint: Option<&str>) -> io::Result<()> {
+        self.show(View::Error {
+            message: message.to_string(),
+            hint: hint.map(str::to_string),
+        })
+    }
+
+    fn show_cursor(&mut self, visible: bool) -> io::Result<()> {
+        if visible {
+            self.terminal.show_cursor()
+        } else {
+            self.terminal.hide_cursor()
+        }
+    }
+
+    fn set_cycle(&mut self, cycle: Option<(u32, u32)>) {
+        self.cycle = cycle;
+        self.update_chrome();
+    }
+
+    fn set_goal(&mut self, goal: Option<(u32, u32)>) {
+        self.goal = goal;
+        self.update_chrome();
+    }
+
+    fn set_interruptions(&mut self, interruptions: Interruptions) {
+        self.interruptions = interruptions;
+    }
+
+    fn set_upcoming(&mut self, upcoming: &[Planned]) {
+        self.upcoming = upcoming_text(self.text, upcoming);
+    }
+
+    fn set_timers(&mut self, timers: &[(String, Timer)]) {
+        self.timers = timers.iter().map(|(name, timer)| (format!("{} {}", name, timer.format_time()), timer.get_progress() as f64 / 100.0)).collect();
+    }
+
+    fn set_suggestions(&mut self, suggestions: &[String]) {
+        self.suggestions.set(suggestions);
+    }
+
+    fn set_phase(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.update_chrome();
+    }
+
+    fn redraw(&mut self) -> io::Result<()> {
+        self.draw()
+    }
+
+    fn warning(&mut self, subsystem: &'static str, message: &str) -> io::Result<()> {
+        if !self.warnings.iter().any(|(known, _)| *known == subsystem) {
+            self.warnings.push((subsystem, message.to_string()));
+            self.update_chrome();
+        }
+        self.draw()
+    }
+}
+
+// Raw mode for reading single keys. It's left alone when stdin isn't a
+// terminal (piped input, CI), where there are no keys to read and
+// crossterm would go looking for a controlling terminal instead
+pub fn enable_raw_mode() -> io::Result<()> {
+    if !reads_keys() {
+        return Ok(());
+    }
+    terminal::enable_raw_mode()
+}
+
+pub fn disable_raw_mode() -> io::Result<()> {
+    if !reads_keys() {
+        return Ok(());
+    }
+    terminal::disable_raw_mode()
+}
+
+// Raw mode for as long as this is held. Dropping it turns raw mode off and
+// shows the cursor again, so returning early with an error, or unwinding
+// from a panic, can't leave the shell unusable
+pub struct RawMode(());
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        enable_raw_mode()?;
+        Ok(RawMode(()))
+    }
+
+    // Runs `read` with the terminal's own line editing back, e.g. to type
+    // a phrase, then goes back to raw mode
+    pub fn cooked<T>(&self, read: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        restore_mode();
+        let result = read();
+        enable_raw_mode()?;
+        result
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore_mode();
+    }
+}
+
+fn restore_mode() {
+    let _ = disable_raw_mode();
+    // Piped output has no cursor, and shouldn't get the escape code
+    if io::stdout().is_terminal() {
+        let _ = execute!(io::stdout(), cursor::Show);
+    }
+}
+
+pub fn reads_keys() -> bool {
+    io::stdin().is_terminal()
+}
+
+// Puts the terminal back the way it was, for exit paths that can't reach
+// the Screen or RawMode to drop them, like the Ctrl-C handler and the
+// panic hook
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+}
+
+// Plain ASCII for terminals without colour
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+pub fn render(frame: &mut Frame, view: &View, chrome: &Chrome, theme: &Theme, text: &Text) {
+    render_view(frame, view, chrome, theme, text);
+    if !theme.color {
+        let area = frame.area();
+        frame.buffer_mut().set_style(area, Style::reset());
+    }
+}
+
+fn bordered(theme: &Theme) -> Block<'static> {
+    if theme.color {
+        Block::bordered()
+    } else {
+        Block::bordered().border_set(ASCII_BORDER)
+    }
+}
+
+// ` Progress `, borrowed rather than formatted
+fn titled<'a>(theme: &Theme, title: &'a str) -> Block<'a> {
+    bordered(theme).title(Line::from(vec![Span::from(" "), Span::from(title), Span::from(" ")]))
+}
+
+// `locale::fill` for a line of the screen, borrowing the values
+fn filled<'a>(template: &'a str, values: &[&'a str]) -> Line<'a> {
+    let mut parts = template.split("{}");
+    let mut line = Line::from(parts.next().unwrap_or_default());
+    for (part, value) in parts.zip(values.iter().copied().chain(iter::repeat(""))) {
+        line.push_span(value);
+        line.push_span(part);
+    }
+    line
+}
+
+// `[#####-----] 50% 12:30`, for when a coloured gauge can't be shown,
+// written straight into the buffer a cell at a time
+fn ascii_bar(buffer: &mut Buffer, area: Rect, ratio: f64, label: &str) {
+    if area.is_empty() {
+        return;
+    }
+    let inner = usize::from(area.width).saturating_sub(label.len() + 3);
+    let filled = (inner as f64 * ratio).round() as usize;
+    let bar = iter::once("[").chain(iter::repeat_n("#", filled)).chain(iter::repeat_n("-", inner - filled)).chain(["] ", label]);
+    let mut x = area.x;
+    for part in bar {
+        (x, _) = buffer.set_stringn(x, area.y, part, usize::from(area.right().saturating_sub(x)), Style::new());
+    }
+}
+
+fn render_view(frame: &mut Frame, view: &View, chrome: &Chrome, theme: &Theme, text: &Text) {
+    // A short terminal gives the header's rows to the view, and a tall
+    // enough one gives art the rows it needs on top of the usual one
+    let area = frame.area();
+    let art = chrome.art.as_ref().and_then(|art| art.fitting(area.width.saturating_sub(2), (area.height + 1).saturating_sub(FULL_HEIGHT)));
+    let header_height = match art {
+        _ if area.height < FULL_HEIGHT => 0,
+        Some(art) => art.len() as u16 + 2,
+        None => 3,
+    };
+    let [header, body, footer] = Layout::vertical([Constraint::Length(header_height), Constraint::Min(0), Constraint::Length(1)])
+        .areas(frame.area());
+
+    // Keep the right-hand end of the footer for anything that has stopped
+    // working, down to just the sign if the controls wouldn't fit otherwise
+    let footer = if chrome.warning.is_empty() {
+        footer
+    } else {
+        let mut warning = chrome.warning.as_str();
+        if usize::from(footer.width) < text.controls.chars().count() + warning.chars().count() {
+            warning = warning_sign(theme);
+        }
+        let [controls, indicator] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(warning.chars().count() as u16)]).areas(footer);
+        frame.render_widget(Paragraph::new(warning).yellow(), indicator);
+        controls
+    };
+
+    let mut title = Vec::with_capacity(4);
+    if theme.color {
+        title.push(Span::from("🍅 ").bold().fg(theme.accent));
+    }
+    title.push(Span::from(text.header).bold().fg(theme.accent));
+    title.push(Span::from(chrome.cycle.as_str()));
+    let goal = Span::from(chrome.goal.as_str());
+    title.push(if chrome.goal_reached { goal.fg(theme.accent) } else { goal });
+    match art {
+        Some(art) => {
+            let block = bordered(theme).title(Line::from(title));
+            let inner = block.inner(header);
+            let [art_area] = Layout::horizontal([Constraint::Length(art::width_of(art) as u16)]).flex(Flex::Center).areas(inner);
+            frame.render_widget(block, header);
+            frame.render_widget(Paragraph::new(art.iter().map(|line| Line::from(line.as_str())).collect::<Vec<_>>()).fg(theme.accent), art_area);
+        }
+        None => frame.render_widget(Paragraph::new(Line::from(title)).block(bordered(theme)), header),
+    }
+
+    match view {
+        View::Blank => {}
+        View::Prompt { question, notice, choices, input } => {
+            let mut lines = Vec::new();
+            if let Some(notice) = notice {
+                lines.push(Line::from(notice.as_str()).yellow());
+                lines.push(Line::default());
+            }
+            if !choices.is_empty() {
+                lines.extend(choices.iter().map(|choice| Line::from(choice.as_str())));
+                lines.push(Line::default());
+            }
+            let row = body.y + lines.len() as u16;
+            let column = body.x + (question.chars().count() + input.chars().count()) as u16;
+            lines.push(Line::from(format!("{}{}", question, input)));
+            frame.render_widget(Paragraph::new(lines), body);
+            frame.set_cursor_position((column.min(body.right().saturating_sub(1)), row));
+        }
+        View::Progress { progress, time, label, message, paused, counts, upcoming, suggestion, big, dim, timers } => {
+            let ratio = (*progress as f64 / 100.0).clamp(0.0, 1.0);
+            let fill = if *paused { theme.paused } else { theme.bar_fill };
+            let gauge = Gauge::default().gauge_style(Style::new().fg(fill).bg(theme.bar_empty)).ratio(ratio).label(label.as_str()).use_unicode(true);
+            frame.render_widget(Paragraph::new(text.controls).dark_gray(), footer);
+            // A row more in the bar's box for each side timer
+            let [gauge_area, info] = Layout::vertical([Constraint::Length(3 + timers.len() as u16), Constraint::Min(0)]).areas(body);
+            // The session box goes first, then the bar's border
+            let bar = if body.height < 3 {
+                let [bar] = Layout::vertical([Constraint::Length(1)]).areas(body);
+                bar
+            } else {
+                let block = titled(theme, text.progress_title);
+                let inner = block.inner(gauge_area);
+                frame.render_widget(block, gauge_area);
+                inner
+            };
+            let [bar, below] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(bar);
+            if theme.color {
+                frame.render_widget(gauge, bar);
+            } else {
+                ascii_bar(frame.buffer_mut(), bar, ratio, label);
+            }
+            for ((label, ratio), row) in timers.iter().zip(below.rows()) {
+                if theme.color {
+                    let gauge = Gauge::default().gauge_style(Style::new().fg(theme.bar_fill).bg(theme.bar_empty)).ratio(*ratio).label(label.as_str()).use_unicode(true);
+                    frame.render_widget(gauge, row);
+                } else {
+                    ascii_bar(frame.buffer_mut(), row, *ratio, label);
+                }
+            }
+            if info.height < 4 {
+                return;
+            }
+            // The numerals take the rest of the screen, under one line for
+            // everything else
+            let numerals = big.then(|| info.inner(Margin::new(1, 1))).and_then(|inner| {
+                digits::scale(time, inner.width, inner.height.saturating_sub(1)).map(|scale| (inner, scale))
+            });
+            if let Some((inner, scale)) = numerals {
+                let mut line = Line::from(message.as_str());
+                if *paused {
+                    line.push_span(Span::from("  ").fg(theme.paused));
+                    line.push_span(Span::from(text.paused).fg(theme.paused));
+                }
+                if !counts.is_empty() {
+                    line.push_span("  ");
+                    line.push_span(counts.as_str());
+                }
+                frame.render_widget(titled(theme, text.session_title), info);
+                let [top, rest] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+                frame.render_widget(Paragraph::new(line), top);
+                let on = if theme.color { '█' } else { '#' };
+                let numerals: Vec<_> = digits::lines(time, scale, on).into_iter().map(Line::from).collect();
+                let [rest] = Layout::vertical([Constraint::Length(numerals.len() as u16)]).flex(Flex::Center).areas(rest);
+                let style = match (*paused, *dim) {
+                    (true, _) => Style::new().fg(theme.paused),
+                    (false, true) => dimmed(theme),
+                    (false, false) => Style::new().fg(theme.bar_fill),
+                };
+                frame.render_widget(Paragraph::new(numerals).style(style).centered(), rest);
+                return;
+            }
+            let mut lines = vec![Line::from(message.as_str()), filled(text.remaining, &[time])];
+            // The time is the span after whatever text comes before it
+            if let Some(span) = lines[1].spans.get_mut(1).filter(|_| *dim) {
+                span.style = dimmed(theme);
+            }
+            if *paused {
+                lines[1].push_span(Span::from("  ").fg(theme.paused));
+                lines[1].push_span(Span::from(text.paused).fg(theme.paused));
+            }
+            if !counts.is_empty() {
+                lines[1].push_span("  ");
+                lines[1].push_span(counts.as_str());
+            }
+            // Each only if the box has room for it
+            if let Some(suggestion) = suggestion.as_deref().filter(|_| usize::from(info.height) > lines.len() + 2) {
+                lines.push(Line::from(suggestion).italic());
+            }
+            if let Some(upcoming) = upcoming.as_deref().filter(|_| usize::from(info.height) > lines.len() + 2) {
+                lines.push(Line::from(upcoming).dark_gray());
+            }
+            let [info] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)]).areas(info);
+            frame.render_widget(Paragraph::new(lines).block(titled(theme, text.session_title)), info);
+        }
+        View::Stopwatch { time, laps, paused } => {
+            frame.render_widget(Paragraph::new(text.stopwatch_controls).dark_gray(), footer);
+            let [clock, lap_area] = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(body);
+            let mut line = Line::from(Span::from(time.as_str()).bold());
+            if *paused {
+                line.push_span(Span::from("  ").fg(theme.paused));
+                line.push_span(Span::from(text.paused).fg(theme.paused));
+            }
+            frame.render_widget(Paragraph::new(line).block(titled(theme, text.stopwatch_title)), clock);
+            if !laps.is_empty() && lap_area.height > 2 {
+                let lines: Vec<_> = laps.iter().map(|lap| Line::from(lap.as_str())).collect();
+                frame.render_widget(Paragraph::new(lines).block(titled(theme, text.laps_title)), lap_area);
+            }
+        }
+        View::Checklist { item, number, total } => {
+            let block = bordered(theme).title(format!(" {} ", locale::fill(text.checklist_title, &[number, total])));
+            let [item_area] = Layout::vertical([Constraint::Length(3)]).areas(body);
+            frame.render_widget(Paragraph::new(item.as_str()).block(block), item_area);
+            frame.render_widget(Paragraph::new(text.checklist_controls).dark_gray(), footer);
+        }
+        View::Board { columns, selected, search } => {
+            let areas = Layout::horizontal([Constraint::Fill(1); 3]).split(body);
+            for (i, (cards, area)) in columns.iter().zip(areas.iter()).enumerate() {
+                let lines: Vec<_> = cards
+                    .iter()
+                    .enumerate()
+                    .map(|(row, card)| match (i, row) == *selected {
+                        true => Line::from(card.as_str()).reversed(),
+                        false => Line::from(card.as_str()),
+                    })
+                    .collect();
+                let mut block = bordered(theme).title(format!(" {} ({}) ", text.board_columns[i], cards.len()));
+                if i == selected.0 {
+                    block = block.fg(theme.accent).bold();
+                }
+                frame.render_widget(Paragraph::new(lines).block(block), *area);
+            }
+            match search {
+                Some(search) => frame.render_widget(Paragraph::new(locale::fill(text.board_search, &[search])), footer),
+                None => frame.render_widget(Paragraph::new(text.board_controls).dark_gray(), footer),
+            }
+        }
+        View::Message(message) => {
+            frame.render_widget(Paragraph::new(message.as_str()).wrap(Wrap { trim: false }), body);
+        }
+        View::Countdown { text: countdown, below } => {
+            render_view(frame, below, chrome, theme, text);
+            let width = (countdown.chars().count() as u16 + 4).min(body.width);
+            let [row] = Layout::vertical([Constraint::Length(3)]).flex(Flex::Center).areas(body);
+            let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(row);
+            frame.render_widget(Clear, popup);
+            frame.render_widget(Paragraph::new(countdown.as_str()).centered().block(bordered(theme).yellow()), popup);
+        }
+        View::Integrations(rows) => {
+            let table = if rows.is_empty() {
+                Table::new(vec![Row::new(["No integrations are set up"])], [Constraint::Fill(1)])
+            } else {
+                let widths = [Constraint::Length(12), Constraint::Length(14), Constraint::Length(6), Constraint::Fill(1)];
+                Table::new(rows.iter().map(|row| Row::new(row.clone())), widths)
+                    .header(Row::new(["Integration", "Last delivery", "Queue", "Last error"]).bold())
+            };
+            frame.render_widget(table.block(bordered(theme).title(" Integrations ")), body);
+            frame.render_widget(Paragraph::new(text.integrations_controls).dark_gray(), footer);
+        }
+        View::Dashboard(lines) => {
+            let title = format!(" {} ", text.today.trim_end_matches(':'));
+            let lines: Vec<Line> = lines.iter().map(|line| Line::from(line.as_str())).collect();
+            frame.render_widget(Paragraph::new(lines).block(bordered(theme).title(title)), body);
+            frame.render_widget(Paragraph::new(text.dashboard_controls).dark_gray(), footer);
+        }
+        View::Error { message, hint } => {
+            let mut lines = vec![Line::from(message.as_str()).red()];
+            if let Some(hint) = hint {
+                lines.push(Line::default());
+                lines.push(Line::from(hint.as_str()));
+            }
+            let block = bordered(theme).title(" Something went wrong ").red();
+            let [error_area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)]).areas(body);
+            frame.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), error_area);
+            frame.render_widget(Paragraph::new(text.exit).dark_gray(), footer);
+        }
+    }
+}
+
+pub fn integration_rows(integrations: &[Integration], now: Instant) -> Vec<[String; 4]> {
+    integrations
+        .iter()
+        .map(|integration| {
+            let delivery = &integration.delivery;
+            [
+                integration.name.to_string(),
+                delivery.last.map_or("never".to_string(), |last| ago(now.saturating_duration_since(last))),
+                integration.queued.map_or("-".to_string(), |queued| queued.to_string()),
+                delivery.error.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect()
+}
+
+fn ago(elapsed: Duration) -> String {
+    match elapsed.as_secs() {
+        seconds @ 0..=59 => format!("{}s ago", seconds),
+        seconds @ 60..=3599 => format!("{}m ago", seconds / 60),
+        seconds => format!("{}h ago", seconds / 3600),
+    }
+}
+
+pub fn done_prompt_text(text: &Text, task: &str, pomodoros: u32) -> String {
+    locale::fill(text.done_prompt, &[&task, &pomodoros])
+}
+
+// None when there's no plan to look ahead in
+pub fn upcoming_text(text: &Text, upcoming: &[Planned]) -> Option<String> {
+    if upcoming.is_empty() {
+        return None;
+    }
+    let sessions: Vec<_> =
+        upcoming.iter().map(|planned| format!("{} {}m", text.session_name(planned.name()), planned.seconds() / 60)).collect();
+    Some(locale::fill(text.up_next, &[&sessions.join(", ")]))
+}
+
+pub fn prompt_text(text: &Text, session_type: &str) -> String {
+    locale::fill(text.start_prompt, &[&text.session_type(session_type)])
+}
+
+pub fn countdown_text(text: &Text, session_type: &str, length: u64, seconds: u64) -> String {
+    locale::fill(text.countdown, &[&text.session_name(session_type), &Timer::new(length).format_time(), &seconds])
+}
+
+pub fn prompt_accepted(input: &str) -> bool {
+    input.trim().is_empty() || input.trim().to_lowercase().starts_with('y')
+}
+
+// Whatever the case, and however many spaces, but every word of it
+pub fn phrase_typed(phrase: &str, input: &str) -> bool {
+    let words = |text: &str| text.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+    words(input) == words(phrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::ENGLISH;
+    use crate::outputs::Delivery;
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+    use ratatui::style::Modifier;
+
+    fn draw(view: &View, cycle: Option<(u32, u32)>) -> Buffer {
+        draw_sized(view, cycle, 60, 12)
+    }
+
+    fn draw_sized(view: &View, cycle: Option<(u32, u32)>, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let chrome = Chrome::new(cycle, None, &[], &Theme::default(), &ENGLISH);
+        terminal.draw(|frame| render(frame, view, &chrome, &Theme::default(), &ENGLISH)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    fn row(buffer: &Buffer, y: u16) -> String {
+        (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>().trim_end().to_string()
+    }
+
+    fn progress(progress: f32, time: &str, paused: bool) -> View {
+        View::Progress {
+            progress,
+            time: time.to_string(),
+            label: format!("{}% {}{}", progress as u32, time, if paused { " PAUSED" } else { "" }),
+            message: "Current session: Work".to_string(),
+            paused,
+            counts: String::new(),
+            upcoming: None,
+            suggestion: None,
+            big: false,
+            dim: false,
+            timers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn progress_frame() {
+        let buffer = draw(&progress(50.0, "12:30", false), None);
+        assert!(row(&buffer, 1).contains("Tìmeadair - Pomodoro Timer"));
+        assert!(row(&buffer, 4).contains("50% 12:30"));
+        assert_eq!(row(&buffer, 7), "│Current session: Work                                     │");
+        assert_eq!(row(&buffer, 11), ENGLISH.controls);
+        assert_eq!((buffer[(1, 4)].symbol(), buffer[(1, 4)].fg), ("█", Color::Green));
+        assert_eq!((buffer[(58, 4)].symbol(), buffer[(58, 4)].bg), (" ", Color::DarkGray));
+    }
+
+    #[test]
+    fn side_timers_go_under_the_bar() {
+        let mut view = progress(50.0, "12:30", false);
+        if let View::Progress { timers, .. } = &mut view {
+            *timers = vec![("tea 03:00".to_string(), 0.25)];
+        }
+        let buffer = draw_sized(&view, None, 60, 13);
+        assert!(row(&buffer, 4).contains("50% 12:30"));
+        assert!(row(&buffer, 5).contains("tea 03:00"));
+        assert_eq!((buffer[(1, 5)].symbol(), buffer[(1, 5)].fg), ("█", Color::Green));
+        assert_eq!(row(&buffer, 8), "│Current session: Work                                     │");
+    }
+
+    #[test]
+    fn big_digits_fill_the_session_box() {
+        let mut big = progress(50.0, "12:30", false);
+        if let View::Progress { big, .. } = &mut big {
+            *big = true;
+        }
+        let buffer = draw_sized(&big, None, 60, 16);
+        assert_eq!(row(&buffer, 7), "│Current session: Work                                     │");
+        // 34 cells of numerals, centred in 58
+        assert_eq!(row(&buffer, 9), "│                ██  ██████      ██████  ██████            │");
+        assert_eq!(buffer[(17, 9)].fg, Color::Green);
+        // Too small for them, so the text comes back
+        let buffer = draw(&big, None);
+        assert!(row(&buffer, 8).contains("Remaining: 12:30"));
+    }
+
+    #[test]
+    fn breaks_show_a_suggestion() {
+        let mut screen = Screen::fixed(Vec::new(), 60, 12).unwrap();
+        screen.set_suggestions(&["Drink some water".to_string()]);
+        screen.progress(&Timer { duration: 300, elapsed: 40 }, "Current session: Break", false).unwrap();
+        let buffer = draw(screen.view(), None);
+        assert_eq!(row(&buffer, 9), "│Drink some water                                          │");
+        assert!(buffer[(1, 9)].modifier.contains(Modifier::ITALIC));
+        screen.set_suggestions(&[]);
+        screen.progress(&Timer { duration: 1500, elapsed: 40 }, "Current session: Work", false).unwrap();
+        assert!(matches!(screen.view(), View::Progress { suggestion: None, .. }));
+    }
+
+    #[test]
+    fn bar_moves_between_ticks() {
+        let bar = |screen: &Screen<Vec<u8>>| match screen.view() {
+            View::Progress { progress, label, .. } => (*progress, label.clone()),
+            _ => unreachable!(),
+        };
+        let mut screen = Screen::fixed(Vec::new(), 60, 12).unwrap();
+        screen.progress(&Timer { duration: 10, elapsed: 2 }, "Current session: Work", false).unwrap();
+        screen.between_ticks(0.5).unwrap();
+        assert_eq!(bar(&screen), (25.0, "20% 00:08".to_string()));
+
+        let buffer = draw(&progress(25.0, "00:08", false), None);
+        // 14.5 of the 58 cells
+        assert_eq!(buffer[(15, 4)].symbol(), "▌");
+
+        screen.progress(&Timer { duration: 10, elapsed: 2 }, "Current session: Work", true).unwrap();
+        screen.between_ticks(0.5).unwrap();
+        assert_eq!(bar(&screen).0, 20.0);
+        let mut steady = Screen::fixed(Vec::new(), 60, 12).unwrap().with_smooth_bar(false);
+        steady.progress(&Timer { duration: 10, elapsed: 2 }, "Current session: Work", false).unwrap();
+        steady.between_ticks(0.5).unwrap();
+        assert_eq!(bar(&steady).0, 20.0);
+    }
+
+    #[test]
+    fn final_seconds_flash() {
+        let dim = |screen: &Screen<Vec<u8>>| matches!(screen.view(), View::Progress { dim: true, .. });
+        let mut screen = Screen::fixed(Vec::new(), 60, 12).unwrap().with_smooth_bar(false).with_flashing_countdown(true);
+        screen.progress(&Timer { duration: 60, elapsed: 45 }, "Current session: Work", false).unwrap();
+        screen.between_ticks(0.7).unwrap();
+        assert!(!dim(&screen));
+        screen.progress(&Timer { duration: 60, elapsed: 52 }, "Current session: Work", false).unwrap();
+        screen.between_ticks(0.2).unwrap();
+        assert!(!dim(&screen));
+        screen.between_ticks(0.7).unwrap();
+        assert!(dim(&screen));
+
+        let mut view = progress(90.0, "00:08", false);
+        if let View::Progress { dim, .. } = &mut view {
+            *dim = true;
+        }
+        let buffer = draw(&view, None);
+        assert!(buffer[(12, 8)].modifier.contains(Modifier::DIM));
+        assert!(!buffer[(2, 8)].modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn final_minute_in_tenths() {
+        let time = |screen: &Screen<Vec<u8>>| match screen.view() {
+            View::Progress { time, .. } => time.clone(),
+            _ => panic!("no progress"),
+        };
+        let mut screen = Screen::fixed(Vec::new(), 60, 12).unwrap().with_smooth_bar(false).with_tenths(true);
+        screen.progress(&Timer { duration: 120, elapsed: 30 }, "Current session: Work", false).unwrap();
+        screen.between_ticks(0.3).unwrap();
+        assert_eq!(time(&screen), "01:30");
+        screen.progress(&Timer { duration: 120, elapsed: 78 }, "Current session: Work", false).unwrap();
+        assert_eq!(time(&screen), "00:42.0");
+        screen.between_ticks(0.3).unwrap();
+        assert_eq!(time(&screen), "00:41.7");
+        assert_eq!(digits::scale("00:41.7", 60, 12), Some(1));
+    }
+
+    #[test]
+    fn draws_in_gaelic() {
+        let mut terminal = Terminal::new(TestBackend::new(90, 12)).unwrap();
+        let text = Locale::Gaelic.text();
+        let mut screen = Screen::fixed(Vec::new(), 90, 12).unwrap().with_locale(Locale::Gaelic);
+        screen.progress(&Timer { duration: 1500, elapsed: 750 }, "Current session: Work", true).unwrap();
+        terminal.draw(|frame| render(frame, screen.view(), &Chrome::default(), &Theme::default(), text)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert!(row(buffer, 1).contains("Tìmeadair Pomodoro"));
+        assert!(row(buffer, 4).contains("50% 12:30 NA STAD"));
+        assert!(row(buffer, 8).contains("Air fhàgail: 12:30  (na stad)"));
+        assert_eq!(row(buffer, 11), text.controls);
+        assert_eq!(prompt_text(text, "work"), "Tòisich seisean obrach? [Y/n]: ");
+    }
+
+    #[test]
+    fn small_terminals_keep_the_bar() {
+        let view = progress(50.0, "12:30", false);
+        let buffer = draw_sized(&view, None, 30, 8);
+        assert!(row(&buffer, 1).contains("50% 12:30"));
+        assert!(row(&buffer, 4).contains("Current session: Work"));
+        let buffer = draw_sized(&view, None, 30, 4);
+        assert!(row(&buffer, 1).contains("50% 12:30"));
+        let buffer = draw_sized(&view, None, 20, 3);
+        assert!(row(&buffer, 0).contains("50% 12:30"));
+        assert_eq!(row(&buffer, 2), "Keys: 'q' quit, 'r'");
+    }
+
+    #[test]
+    fn no_color_falls_back_to_ascii() {
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        let theme = Theme::parse("light").unwrap().without_color();
+        let chrome = Chrome::new(None, None, &["serial"], &theme, &ENGLISH);
+        terminal.draw(|frame| render(frame, &progress(50.0, "12:30", false), &chrome, &theme, &ENGLISH)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(row(buffer, 0), "+--------------------------------------+");
+        assert_eq!(row(buffer, 4), "|[#############-------------] 50% 12:30|");
+        assert!(row(buffer, 11).ends_with(" !"));
+        assert!(buffer.content().iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+    }
+
+    #[test]
+    fn paused_bar_is_marked() {
+        let buffer = draw(&progress(50.0, "12:30", true), None);
+        assert!(row(&buffer, 4).contains("50% 12:30 PAUSED"));
+        assert!(row(&buffer, 8).contains("(paused)"));
+    }
+
+    #[test]
+    fn progress_reuses_its_strings() {
+        let mut screen = Screen::fixed(io::sink(), 80, 12).unwrap();
+        let strings = |view: &View| match view {
+            View::Progress { time, label, message, .. } => [time.as_ptr(), label.as_ptr(), message.as_ptr()],
+            _ => panic!("not the progress view"),
+        };
+        screen.progress(&Timer { duration: 1500, elapsed: 1 }, "Current session: Work", true).unwrap();
+        let first = strings(screen.view());
+        screen.progress(&Timer { duration: 1500, elapsed: 2 }, "Current session: Work", false).unwrap();
+        assert_eq!(strings(screen.view()), first);
+        let View::Progress { label, .. } = screen.view() else { unreachable!() };
+        assert_eq!(label, "0% 24:58");
+    }
+
+    #[test]
+    fn interruptions_are_counted() {
+        let mut screen = Screen::fixed(Vec::new(), 80, 12).unwrap();
+        screen.set_interruptions(Interruptions { internal: 2, external: 1 });
+        screen.progress(&Timer { duration: 1500, elapsed: 750 }, "Current session: Work", false).unwrap();
+        let buffer = draw_sized(screen.view(), None, 80, 12);
+        assert!(row(&buffer, 8).contains("Remaining: 12:30  Interruptions: 2 internal, 1 external"));
+        assert!(!row(&draw(&progress(50.0, "12:30", false), None), 8).contains("Interruptions"));
+    }
+
+    #[test]
+    fn planned_sessions_come_up_next() {
+        let mut screen = Screen::fixed(Vec::new(), 80, 12).unwrap();
+        screen.set_upcoming(&[Planned::Break(600), Planned::LongBreak(1800)]);
+        screen.progress(&Timer { duration: 1500, elapsed: 750 }, "Current session: Work", false).unwrap();
+        let buffer = draw_sized(screen.view(), None, 80, 12);
+        assert!(row(&buffer, 9).contains("Up next: Break 10m, Long break 30m"));
+        // The controls keep their row when there's no room for it
+        let buffer = draw_sized(screen.view(), None, 80, 11);
+        assert!(!(0..11).any(|y| row(&buffer, y).contains("Up next")));
+    }
+
+    #[test]
+    fn stopwatch_lists_laps_newest_first() {
+        let mut screen = Screen::fixed(Vec::new(), 60, 12).unwrap();
+        screen.stopwatch(100, &[70, 30], true).unwrap();
+        let View::Stopwatch { laps, .. } = screen.view().clone() else { panic!("not the stopwatch") };
+        assert_eq!(laps, ["Lap 2  00:30", "Lap 1  01:10"]);
+
+        let buffer = draw(screen.view(), None);
+        assert!(row(&buffer, 3).contains("Stopwatch"));
+        assert!(row(&buffer, 4).contains("01:40  (paused)"));
+        assert!(row(&buffer, 7).contains("Lap 2  00:30"));
+        assert_eq!(row(&buffer, 11), ENGLISH.stopwatch_controls);
+    }
+
+    #[test]
+    fn header_shows_cycle_position() {
+        let buffer = draw(&View::Blank, Some((2, 4)));
+        assert!(row(&buffer, 1).contains("Tìmeadair - Pomodoro Timer (2/4)"));
+    }
+
+    #[test]
+    fn header_shows_the_daily_goal() {
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        let theme = Theme::default();
+        let chrome = Chrome::new(Some((2, 4)), Some((8, 8)), &[], &theme, &ENGLISH);
+        terminal.draw(|frame| render(frame, &View::Blank, &chrome, &theme, &ENGLISH)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert!(row(buffer, 1).contains("Pomodoro Timer (2/4)  8/8 today"));
+        // Picked out once it's reached
+        assert!((0..60).any(|x| buffer[(x, 1)].symbol() == "/" && buffer[(x, 1)].fg == theme.accent));
+    }
+
+    #[test]
+    fn header_art_when_there_is_room() {
+        let path = std::env::temp_dir().join(format!("timeadair-art-{}.txt", std::process::id()));
+        std::fs::write(&path, " ,--.\n(    )\n `--'\n%\n(o)\n").unwrap();
+        let art = Art::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let theme = Theme::default();
+        let mut chrome = Chrome::new(None, None, &[], &theme, &ENGLISH);
+        chrome.art = Some(art);
+        let draw = |height| {
+            let mut terminal = Terminal::new(TestBackend::new(40, height)).unwrap();
+            terminal.draw(|frame| render(frame, &progress(50.0, "12:30", false), &chrome, &theme, &ENGLISH)).unwrap();
+            terminal.backend().buffer().clone()
+        };
+        let buffer = draw(14);
+        assert!(row(&buffer, 0).contains(ENGLISH.header));
+        assert_eq!(row(&buffer, 2), format!("│{:16}(    ){:16}│", "", ""));
+        assert!(row(&buffer, 5).contains("Progress"));
+        // Only the small one fits
+        assert!(row(&draw(12), 1).contains("(o)"));
+    }
+
+    #[test]
+    fn error_screen_shows_the_way_out() {
+        let view = View::Error {
+            message: "unable to open serial device /dev/ttyUSB0: gone".to_string(),
+            hint: Some("Run without --serial.".to_string()),
+        };
+        let buffer = draw(&view, None);
+        assert!(row(&buffer, 3).contains("Something went wrong"));
+        assert!(row(&buffer, 4).contains("/dev/ttyUSB0: gone"));
+        assert!(row(&buffer, 6).contains("Run without --serial."));
+        assert_eq!(row(&buffer, 11), "Press any key to exit");
+    }
+
+    #[test]
+    fn footer_flags_failed_subsystems() {
+        let mut terminal = Terminal::new(TestBackend::new(92, 12)).unwrap();
+        let view = progress(50.0, "12:30", false);
+        let chrome = Chrome::new(None, None, &["serial", "history"], &Theme::default(), &ENGLISH);
+        terminal.draw(|frame| render(frame, &view, &chrome, &Theme::default(), &ENGLISH)).unwrap();
+        let footer = row(terminal.backend().buffer(), 11);
+        assert!(footer.starts_with(ENGLISH.controls));
+        assert!(footer.ends_with("⚠ serial, history unavailable"));
+    }
+
+    #[test]
+    fn integrations_panel_shows_failures() {
+        let now = Instant::now();
+        let integrations = [
+            Integration {
+                name: "serial",
+                delivery: Delivery { last: Some(now - Duration::from_secs(75)), error: Some("gone".to_string()) },
+                queued: None,
+            },
+            Integration { name: "event log", delivery: Delivery::default(), queued: Some(3) },
+        ];
+        let rows = integration_rows(&integrations, now);
+        assert_eq!(rows[0], ["serial", "1m ago", "-", "gone"]);
+        assert_eq!(rows[1], ["event log", "never", "3", "-"]);
+
+        let buffer = draw(&View::Integrations(rows), None);
+        assert!(row(&buffer, 4).contains("Integration"));
+        assert!(row(&buffer, 5).contains("serial       1m ago         -      gone"));
+        assert_eq!(row(&buffer, 11), ENGLISH.integrations_controls);
+    }
+
+    #[test]
+    fn dashboard_shows_today_by_the_hour() {
+        let mut hours = [0; 24];
+        hours[9] = 1500;
+        let today = Today { tally: stats::Tally { pomodoros: 1, focus: 1500, interruptions: 0 }, current_streak: 3, hours };
+        let buffer = draw_sized(&View::Dashboard(stats::dashboard(&today, &ENGLISH, true)), None, 60, 14);
+        assert!(row(&buffer, 3).contains(" Today "));
+        assert!(row(&buffer, 4).contains("Today:   1 pomodoro, 0h 25m focus"));
+        assert!(row(&buffer, 5).contains("Streak:  3 days"));
+        assert!(row(&buffer, 7).contains(&format!("09:00  {}  25m", "#".repeat(30))));
+        assert_eq!(row(&buffer, 13), ENGLISH.dashboard_controls);
+    }
+
+    #[test]
+    fn countdown_is_drawn_over_the_last_view() {
+        let mut screen = Screen::fixed(Vec::new(), 60, 12).unwrap();
+        let timer = Timer::new(300);
+        screen.progress(&timer, "Current session: Work", false).unwrap();
+        screen.countdown("break", 300, 3).unwrap();
+        screen.countdown("break", 300, 2).unwrap();
+        let View::Countdown { text, below } = screen.view() else { panic!("no countdown") };
+        assert_eq!(text, "Next: Break (05:00) - starting in 2s, press any key to start now or 'q' to quit");
+        assert!(matches!(**below, View::Progress { .. }));
+    }
+
+    #[test]
+    fn visual_bell_flashes_instead() {
+        let mut out = Vec::new();
+        {
+            let mut screen = Screen::fixed(&mut out, 60, 12).unwrap().with_visual_bell(true);
+            screen.session_end(&TimerResult::Completed).unwrap();
+        }
+        assert!(!out.contains(&b'\x07'));
+        // Yellow backgrounds, each followed by a normal frame
+        let text = String::from_utf8_lossy(&out);
+        assert_eq!(text.matches("48;5;3m").count(), FLASHES);
+    }
+
+    #[test]
+    fn completion_leaves_the_bell_to_its_notifier() {
+        let mut out = Vec::new();
+        {
+            let mut screen = Screen::fixed(&mut out, 60, 12).unwrap();
+            screen.session_end(&TimerResult::Completed).unwrap();
+        }
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn giving_up_takes_the_whole_phrase() {
+        assert!(phrase_typed(ENGLISH.abandon_phrase, "  i GIVE   up\n"));
+        assert!(!phrase_typed(ENGLISH.abandon_phrase, "\n"));
+        assert!(!phrase_typed(ENGLISH.abandon_phrase, "I give"));
+        assert!(!phrase_typed(ENGLISH.abandon_phrase, "y"));
+    }
+}
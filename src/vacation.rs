@@ -0,0 +1,77 @@
+//! Days off that streaks skip over, the same as the days a `[schedule]`
+//! leaves out, so a holiday doesn't end a run of good days:
+//!
+//! ```toml
+//! vacation = ["2024-12-24", "2024-12-27..2025-01-02"]
+//! vacation_calendar = "https://example.com/holidays.ics"   # or a file
+//! ```
+//!
+//! Every event in the calendar counts, all-day or not, so it's meant for a
+//! feed of holidays or leave rather than someone's whole diary. It's only
+//! read for `timeadair stats`, with `curl` when it's a URL, and a calendar
+//! that can't be read is warned about and left out.
+
+use std::collections::BTreeSet;
+use std::io;
+
+use chrono::{Days, NaiveDate};
+
+use crate::ics;
+
+// A year is as long as any one stretch of days off can be
+const LONGEST: u64 = 366;
+
+// `2024-12-24`, or an inclusive range like `2024-12-27..2025-01-02`
+pub fn parse(entry: &str) -> Result<Vec<NaiveDate>, String> {
+    let invalid = || format!("vacation expects dates like 2024-12-24 or 2024-12-27..2025-01-02, not '{}'", entry);
+    let date = |text: &str| NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").map_err(|_| invalid());
+    let (first, last) = match entry.split_once("..") {
+        Some((first, last)) => (date(first)?, date(last)?),
+        None => (date(entry)?, date(entry)?),
+    };
+    if last < first || first + Days::new(LONGEST) <= last {
+        return Err(invalid());
+    }
+    Ok(first.iter_days().take_while(|day| *day <= last).collect())
+}
+
+// The days covered by a calendar at a path or an http(s) URL
+pub fn calendar(source: &str) -> io::Result<BTreeSet<NaiveDate>> {
+    Ok(days_in(&ics::fetch(source)?))
+}
+
+// Each event's DTSTART up to DTEND, which is the day after for all-day
+// events and the end itself for timed ones
+fn days_in(ics: &str) -> BTreeSet<NaiveDate> {
+    let mut days = BTreeSet::new();
+    for event in ics::events(ics) {
+        let Some(start) = event.start else { continue };
+        let first = start.date();
+        let last = match event.end {
+            Some(end) if end.is_midnight() => end.date().pred_opt().unwrap_or(end.date()),
+            Some(end) => end.date(),
+            None => first,
+        };
+        days.extend(first.iter_days().take_while(|day| *day <= last).take(LONGEST as usize));
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_ranges_and_calendars() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 12, d).unwrap();
+        assert_eq!(parse("2024-12-24").unwrap(), [day(24)]);
+        assert_eq!(parse("2024-12-27..2024-12-29").unwrap(), [day(27), day(28), day(29)]);
+        assert!(parse("2024-12-29..2024-12-27").is_err());
+        assert!(parse("Christmas").is_err());
+
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Christmas\r\n  break\r\nDTSTART;VALUE=DATE:20241224\r\n\
+                   DTEND;VALUE=DATE:20241227\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART:20241230T090000Z\r\n\
+                   DTEND:20241231T000000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(days_in(ics).into_iter().collect::<Vec<_>>(), [day(24), day(25), day(26), day(30)]);
+    }
+}
@@ -0,0 +1,120 @@
+//! `timeadair wait`, which blocks until the running session ends, so that
+//! noisy jobs can be held back until the break:
+//!
+//! ```text
+//! timeadair wait && make deploy
+//! ```
+//!
+//! It watches the status file, so it works with the full-screen timer as
+//! well as the daemon. The exit code says how the session ended:
+//!
+//! - 0 it ran to completion
+//! - 3 there was no session running to wait for
+//! - 4 it was skipped, reset or abandoned under --strict
+//! - 5 the timer was quit, or went away without saying
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::history::Outcome;
+
+const POLL: Duration = Duration::from_millis(250);
+
+// The parts of the status file that matter here
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    pid: u32,
+    phase: String,
+    ended: u32,
+    outcome: Option<Outcome>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Ended {
+    pub session: String,
+    // None when the timer went away before saying
+    pub outcome: Option<Outcome>,
+}
+
+impl Ended {
+    pub fn exit_code(&self) -> i32 {
+        match self.outcome {
+            Some(Outcome::Completed) => 0,
+            Some(Outcome::Skipped | Outcome::Reset | Outcome::Abandoned) => 4,
+            Some(Outcome::Quit | Outcome::Interrupted) | None => 5,
+        }
+    }
+}
+
+// None when no session is running, including at a prompt between two
+pub fn wait(path: &Path) -> io::Result<Option<Ended>> {
+    let Some(start) = read(path)?.filter(|status| status.phase != "waiting" && alive(status.pid)) else {
+        return Ok(None);
+    };
+    loop {
+        thread::sleep(POLL);
+        match read(path)? {
+            Some(status) if status.pid == start.pid && alive(status.pid) => {
+                if status.ended > start.ended {
+                    return Ok(Some(Ended { session: start.phase, outcome: status.outcome }));
+                }
+            }
+            _ => return Ok(Some(Ended { session: start.phase, outcome: None })),
+        }
+    }
+}
+
+fn read(path: &Path) -> io::Result<Option<Snapshot>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(parse(&contents)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn parse(contents: &str) -> Option<Snapshot> {
+    let value = |key: &str| contents.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix('='));
+    Some(Snapshot {
+        pid: value("pid")?.parse().ok()?,
+        phase: value("phase")?.to_string(),
+        ended: value("ended").and_then(|ended| ended.parse().ok()).unwrap_or(0),
+        outcome: value("outcome").and_then(Outcome::parse),
+    })
+}
+
+// A leftover file from a timer that died is not a running timer
+#[cfg(unix)]
+pub fn alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else { return false };
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_status_file() {
+        let status = parse("pid=42\nphase=work\nremaining=12:30\ncompleted=1\nended=3\noutcome=skipped\n").unwrap();
+        assert_eq!(status, Snapshot { pid: 42, phase: "work".to_string(), ended: 3, outcome: Some(Outcome::Skipped) });
+        // Written before any session ended
+        assert_eq!(parse("pid=42\nphase=waiting\nremaining=\ncompleted=0\nended=0\noutcome=\n").unwrap().outcome, None);
+        assert_eq!(parse("phase=work\n"), None);
+    }
+
+    #[test]
+    fn exit_codes_follow_the_outcome() {
+        let ended = |outcome| Ended { session: "work".to_string(), outcome };
+        assert_eq!(ended(Some(Outcome::Completed)).exit_code(), 0);
+        assert_eq!(ended(Some(Outcome::Reset)).exit_code(), 4);
+        assert_eq!(ended(None).exit_code(), 5);
+    }
+}
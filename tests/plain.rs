@@ -0,0 +1,91 @@
+//! The timer run as a child process with its input and output piped, the
+//! way CI and scripts see it, so no terminal is involved: the full-screen
+//! view falls back to plain lines and the keyboard is left alone.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+
+// A home, config and data directory of its own, so nothing real is read
+fn sandbox(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("timeadair-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn timeadair(dir: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = spawn(dir, args);
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn spawn(dir: &Path, args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_timeadair"))
+        .args(["--data-dir", dir.join("data").to_str().unwrap(), "--mute"])
+        .args(args)
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_RUNTIME_DIR", dir)
+        .env_remove("LANG")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+#[test]
+fn declining_the_first_session_says_goodbye() {
+    let dir = sandbox("decline");
+    let output = timeadair(&dir, &[], "n\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Start work session? [Y/n]: Pomodoro session ended. See you next time!\n");
+    assert!(!dir.join("data").join("history.jsonl").exists());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn countdown_prints_a_line_a_second() {
+    let dir = sandbox("countdown");
+    let output = timeadair(&dir, &["countdown", "2s"], "");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.first(), Some(&"Countdown from 00:02 - 00:02 remaining (0%)"));
+    assert_eq!(lines.last(), Some(&"Countdown finished."));
+    assert!(lines.contains(&"Session complete."));
+    // No escape sequences or bell in a log
+    assert!(!stdout.contains(['\x1b', '\x07', '\r']));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bad_arguments_exit_with_usage_code() {
+    let dir = sandbox("usage");
+    let output = timeadair(&dir, &["--work", "0"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("timeadair: "));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn headless_sessions_are_recorded_with_rate_on() {
+    let dir = sandbox("stdio-rate");
+    let mut child = spawn(&dir, &["--stdio", "--rate", "--work", "1", "--adjust-step", "1"]);
+    let mut stdin = child.stdin.take().unwrap();
+    // Cut down to the second already begun, so it completes on the next tick
+    stdin.write_all(b"{\"command\":\"start\"}\n{\"command\":\"subtract-time\"}\n").unwrap();
+    let mut events = BufReader::new(child.stdout.take().unwrap()).lines().map(Result::unwrap);
+    let completed = events.by_ref().take_while(|line| !line.contains("\"awaiting_start\",\"session\":\"break\"")).any(|line| line.contains("session_completed"));
+    // Closing stdin quits, and the rest is read so nothing is left blocked
+    drop(stdin);
+    events.for_each(drop);
+    assert!(child.wait().unwrap().success());
+    assert!(completed);
+    let history = fs::read_to_string(dir.join("data").join("history.jsonl")).unwrap();
+    assert!(history.contains("\"session\":\"work\"") && history.contains("\"outcome\":\"completed\""));
+    let _ = fs::remove_dir_all(&dir);
+}